@@ -0,0 +1,77 @@
+//! Build Script
+//!
+//! Embeds build metadata into the binary as compile-time environment variables,
+//! read by `core::build_info`:
+//! - MCP_BUILD_GIT_SHA: Commit the binary was built from (`GIT_SHA` overrides `git rev-parse`)
+//! - MCP_BUILD_TIMESTAMP: UTC build time in RFC 3339 format
+//! - MCP_BUILD_RUSTC_VERSION: Output of `rustc --version`
+//!
+//! Any value that cannot be determined (e.g. building outside a git checkout)
+//! is left unset, and the server reports it as "unknown".
+
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    // Git commit, preferring an explicit GIT_SHA (e.g. from a Docker build arg)
+    let git_sha = std::env::var("GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.is_empty())
+        .or_else(|| command_output("git", &["rev-parse", "--short=12", "HEAD"]));
+    if let Some(sha) = git_sha {
+        println!("cargo:rustc-env=MCP_BUILD_GIT_SHA={}", sha);
+    }
+
+    // Build time, derived from the system clock
+    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        println!("cargo:rustc-env=MCP_BUILD_TIMESTAMP={}", rfc3339(now.as_secs()));
+    }
+
+    // Compiler version, using the same rustc cargo builds with
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    if let Some(version) = command_output(&rustc, &["--version"]) {
+        println!("cargo:rustc-env=MCP_BUILD_RUSTC_VERSION={}", version);
+    }
+
+    // Refresh the commit when HEAD moves, and honour a changed GIT_SHA
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+}
+
+/// Run a command and return its trimmed stdout, or `None` if it fails.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Format seconds since the Unix epoch as an RFC 3339 UTC timestamp.
+fn rfc3339(secs: u64) -> String {
+    let days = secs / 86_400;
+    let rem = secs % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
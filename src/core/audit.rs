@@ -0,0 +1,99 @@
+//! Tool Execution Audit Log
+//!
+//! When `MCP_AUDIT_LOG_PATH` is set, every executed `tools/call` is appended
+//! to that file as one JSON line, whichever transport it arrived on:
+//!
+//! ```json
+//! {"timestamp":"2026-01-01T12:00:00.000Z","tool":"echo","arguments":{"message":"hi"},"outcome":"ok","duration_ms":3}
+//! ```
+//!
+//! Failed calls have `"outcome": "error"` and an `error` message. Calls
+//! rejected by argument validation or a `validateOnly` check never run and are
+//! not logged.
+//!
+//! Arguments are logged with sensitive values replaced by `"[REDACTED]"`: any
+//! key ending in one of the comma-separated suffixes of `MCP_AUDIT_REDACT_KEYS`
+//! (default `password,_key,_token,_secret`), ignoring case, at any depth.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use crate::core::utils;
+
+/// Key suffixes redacted from logged arguments when `MCP_AUDIT_REDACT_KEYS` is unset.
+const DEFAULT_REDACT_KEYS: &str = "password,_key,_token,_secret";
+
+/// Append-only log of executed tool calls, one JSON line per call.
+pub struct AuditLog {
+    /// Buffered writer of the log file, flushed after every line
+    writer: Mutex<BufWriter<File>>,
+    /// Lowercase key suffixes whose values are redacted from arguments
+    redact_keys: Vec<String>,
+}
+
+impl AuditLog {
+    /// Open the audit log named by `MCP_AUDIT_LOG_PATH`, if it is set.
+    ///
+    /// # Returns
+    /// `None` when auditing is disabled, or an error if the file cannot be opened.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let path = utils::get_env_var("MCP_AUDIT_LOG_PATH", "");
+        if path.is_empty() {
+            return Ok(None);
+        }
+        let redact_keys = utils::get_env_var("MCP_AUDIT_REDACT_KEYS", DEFAULT_REDACT_KEYS)
+            .split(',')
+            .map(|key| key.trim().to_ascii_lowercase())
+            .filter(|key| !key.is_empty())
+            .collect();
+        Self::open(Path::new(&path), redact_keys).map(Some)
+    }
+
+    /// Open an audit log file for appending, creating it if needed.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the log file
+    /// * `redact_keys` - Lowercase key suffixes whose values are redacted from arguments
+    pub fn open(path: &Path, redact_keys: Vec<String>) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("failed to open audit log {}: {}", path.display(), e))?;
+        Ok(Self {
+            writer: Mutex::new(BufWriter::new(file)),
+            redact_keys,
+        })
+    }
+
+    /// Append the record of one tool call.
+    ///
+    /// A failure to write is reported on stderr and does not affect the call.
+    ///
+    /// # Arguments
+    /// * `tool_name` - Name of the called tool
+    /// * `arguments` - Arguments of the call, redacted before they are written
+    /// * `error` - Error message of a failed call, or `None` if it succeeded
+    /// * `elapsed` - How long the call took
+    pub fn record(&self, tool_name: &str, arguments: &serde_json::Value, error: Option<&str>, elapsed: Duration) {
+        let mut record = serde_json::json!({
+            "timestamp": utils::rfc3339(SystemTime::now()),
+            "tool": tool_name,
+            "arguments": utils::redact_keys(arguments, &self.redact_keys),
+            "outcome": if error.is_some() { "error" } else { "ok" },
+            "duration_ms": elapsed.as_millis() as u64,
+        });
+        if let Some(error) = error {
+            record["error"] = serde_json::Value::from(error);
+        }
+
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let written = writeln!(writer, "{}", record).and_then(|()| writer.flush());
+        if let Err(e) = written {
+            utils::log_stderr!("Failed to write audit log record: {}", e);
+        }
+    }
+}
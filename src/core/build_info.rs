@@ -0,0 +1,32 @@
+//! Build Metadata
+//!
+//! Information about the build the server binary came from, embedded at
+//! compile time by `build.rs`. It is served by the `/version` endpoint and
+//! included in the initialize result so operators can confirm what is deployed.
+
+use serde::Serialize;
+
+/// Value reported for metadata that was unavailable at build time.
+const UNKNOWN: &str = "unknown";
+
+/// Compile-time build metadata.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct BuildInfo {
+    /// Commit the binary was built from
+    pub git_sha: &'static str,
+    /// UTC build time in RFC 3339 format
+    pub build_timestamp: &'static str,
+    /// Compiler version, as reported by `rustc --version`
+    pub rustc_version: &'static str,
+}
+
+/// Get the build metadata of this binary.
+///
+/// Fields that could not be determined when building are `"unknown"`.
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        git_sha: option_env!("MCP_BUILD_GIT_SHA").unwrap_or(UNKNOWN),
+        build_timestamp: option_env!("MCP_BUILD_TIMESTAMP").unwrap_or(UNKNOWN),
+        rustc_version: option_env!("MCP_BUILD_RUSTC_VERSION").unwrap_or(UNKNOWN),
+    }
+}
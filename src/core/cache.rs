@@ -0,0 +1,84 @@
+//! Tool Result Cache
+//!
+//! Idempotent tools such as `calc` return the same result for the same
+//! arguments, so repeating a call only wastes work. The cache keeps the most
+//! recently used successful results, keyed by a hash of the tool name and its
+//! canonical arguments, and `tools/call` serves repeated calls from it without
+//! running the handler.
+//!
+//! Caching is disabled unless `TOOL_CACHE_SIZE` is set to a positive number of
+//! entries, and only tools whose definition sets `cacheable` are cached.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use lru::LruCache;
+
+use crate::core::server::ToolOutput;
+use crate::core::utils;
+
+/// Bounded LRU cache of successful tool results.
+pub struct ToolCache {
+    /// Cached outputs by call key, or `None` when caching is disabled
+    entries: Option<Mutex<LruCache<u64, ToolOutput>>>,
+}
+
+impl ToolCache {
+    /// Create a cache holding at most `capacity` results.
+    ///
+    /// # Arguments
+    /// * `capacity` - Maximum number of cached results; 0 disables caching
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: NonZeroUsize::new(capacity).map(|capacity| Mutex::new(LruCache::new(capacity))),
+        }
+    }
+
+    /// Create a cache sized by `TOOL_CACHE_SIZE` (default 0, disabled).
+    pub fn from_env() -> Self {
+        Self::new(utils::parse_env_var("TOOL_CACHE_SIZE", 0))
+    }
+
+    /// Whether results are cached at all.
+    pub fn is_enabled(&self) -> bool {
+        self.entries.is_some()
+    }
+
+    /// Look up the cached result of a call, marking it most recently used.
+    ///
+    /// # Arguments
+    /// * `tool_name` - Name of the tool being called
+    /// * `arguments` - Arguments of the call
+    pub fn get(&self, tool_name: &str, arguments: &serde_json::Value) -> Option<ToolOutput> {
+        let entries = self.entries.as_ref()?;
+        let mut entries = entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.get(&call_key(tool_name, arguments)).cloned()
+    }
+
+    /// Store the successful result of a call, evicting the least recently
+    /// used result when the cache is full.
+    ///
+    /// # Arguments
+    /// * `tool_name` - Name of the tool that was called
+    /// * `arguments` - Arguments of the call
+    /// * `output` - Output the tool returned
+    pub fn put(&self, tool_name: &str, arguments: &serde_json::Value, output: &ToolOutput) {
+        if let Some(entries) = &self.entries {
+            let mut entries = entries.lock().unwrap_or_else(|e| e.into_inner());
+            entries.put(call_key(tool_name, arguments), output.clone());
+        }
+    }
+}
+
+/// Hash a tool name and its arguments into a cache key.
+///
+/// JSON objects keep their keys sorted, so serializing the arguments gives
+/// the same canonical form regardless of the key order the client sent.
+fn call_key(tool_name: &str, arguments: &serde_json::Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    tool_name.hash(&mut hasher);
+    arguments.to_string().hash(&mut hasher);
+    hasher.finish()
+}
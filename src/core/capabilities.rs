@@ -0,0 +1,97 @@
+//! Server Capabilities
+//!
+//! The capabilities a server advertises in its `initialize` result tell the
+//! client which protocol features it may use. They are derived from what is
+//! actually registered rather than written out by hand: a capability is only
+//! advertised when the methods (or tools) that implement it are present, so
+//! the advertisement cannot drift from the server's behaviour.
+
+use serde::Serialize;
+
+use crate::core::router::MethodRegistry;
+use crate::core::server::ToolRegistry;
+
+/// Capabilities advertised in the initialize result and by `capabilities/probe`.
+///
+/// Serializes to the MCP shape: each supported capability is an object, and
+/// unsupported capabilities are omitted.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ServerCapabilities {
+    /// Tool listing and calling (`tools/list`, `tools/call`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<ToolsCapability>,
+    /// Resource listing, reading and subscriptions (`resources/list`,
+    /// `resources/read`, `resources/subscribe`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourcesCapability>,
+    /// Prompt templates (`prompts/list`, `prompts/get`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompts: Option<PromptsCapability>,
+    /// Log messages sent to the client (`logging/setLevel`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logging: Option<EmptyCapability>,
+    /// Argument completion (`completion/complete`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completions: Option<EmptyCapability>,
+}
+
+/// Tools capability.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolsCapability {
+    /// Whether the server notifies clients when the tool list changes
+    #[serde(skip_serializing_if = "is_false")]
+    pub list_changed: bool,
+}
+
+/// Resources capability.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourcesCapability {
+    /// Whether clients can subscribe to resource updates
+    #[serde(skip_serializing_if = "is_false")]
+    pub subscribe: bool,
+    /// Whether the server notifies clients when the resource list changes
+    #[serde(skip_serializing_if = "is_false")]
+    pub list_changed: bool,
+}
+
+/// Prompts capability.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptsCapability {
+    /// Whether the server notifies clients when the prompt list changes
+    #[serde(skip_serializing_if = "is_false")]
+    pub list_changed: bool,
+}
+
+/// Capability without options, serialized as `{}`.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct EmptyCapability {}
+
+impl ServerCapabilities {
+    /// Derive the capabilities from the registered tools and methods.
+    ///
+    /// # Arguments
+    /// * `tools` - Registered tools; the tools capability needs at least one,
+    ///   and is advertised even when `tools/call` is disabled (read-only mode)
+    /// * `methods` - Registered methods, which decide the other capabilities
+    pub fn detect(tools: &ToolRegistry, methods: &MethodRegistry) -> Self {
+        let has = |method: &str| methods.get(method).is_some();
+        Self {
+            tools: (!tools.tools.is_empty() && has("tools/list")).then(ToolsCapability::default),
+            resources: (has("resources/list") || has("resources/subscribe")).then(|| ResourcesCapability {
+                subscribe: has("resources/subscribe"),
+                list_changed: false,
+            }),
+            prompts: has("prompts/list").then(PromptsCapability::default),
+            logging: has("logging/setLevel").then(EmptyCapability::default),
+            completions: has("completion/complete").then(EmptyCapability::default),
+        }
+    }
+}
+
+/// Used to omit capability flags that are off.
+fn is_false(value: &bool) -> bool {
+    !*value
+}
@@ -0,0 +1,172 @@
+//! Argument Completion
+//!
+//! This module implements the registry behind the MCP `completion/complete`
+//! method. Completion providers are registered per argument of a prompt or
+//! resource reference and suggest values for the argument as the user types.
+//! Arguments without a provider complete to an empty list.
+
+use std::collections::HashMap;
+
+/// Maximum number of values returned in one completion response, per the MCP spec.
+pub const MAX_COMPLETION_VALUES: usize = 100;
+
+/// What a completion request refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CompletionRef {
+    /// A prompt, by name (`ref/prompt`)
+    Prompt(String),
+    /// A resource or resource template, by URI (`ref/resource`)
+    Resource(String),
+}
+
+impl CompletionRef {
+    /// Parse the `ref` object of a completion request.
+    ///
+    /// # Arguments
+    /// * `value` - `{"type": "ref/prompt", "name": ...}` or `{"type": "ref/resource", "uri": ...}`
+    pub fn from_json(value: &serde_json::Value) -> Result<Self, String> {
+        let field = |name: &str| {
+            value.get(name)
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+                .ok_or_else(|| format!("ref is missing {}", name))
+        };
+        match value.get("type").and_then(|v| v.as_str()) {
+            Some("ref/prompt") => field("name").map(CompletionRef::Prompt),
+            Some("ref/resource") => field("uri").map(CompletionRef::Resource),
+            Some(other) => Err(format!("unknown ref type: {}", other)),
+            None => Err("ref is missing type".to_string()),
+        }
+    }
+}
+
+/// Source of completion values for one argument.
+///
+/// Any `Fn(&str) -> Vec<String>` closure is a provider: it receives the value
+/// typed so far and returns the matching suggestions, best first.
+pub trait CompletionProvider: Send + Sync {
+    /// Suggest values for an argument.
+    ///
+    /// # Arguments
+    /// * `value` - Current (partial) value of the argument
+    fn complete(&self, value: &str) -> Vec<String>;
+}
+
+impl<F> CompletionProvider for F
+where
+    F: Fn(&str) -> Vec<String> + Send + Sync,
+{
+    fn complete(&self, value: &str) -> Vec<String> {
+        self(value)
+    }
+}
+
+/// Registry of completion providers keyed by reference and argument name.
+#[derive(Default)]
+pub struct CompletionRegistry {
+    providers: HashMap<(CompletionRef, String), Box<dyn CompletionProvider>>,
+}
+
+impl CompletionRegistry {
+    /// Create an empty completion registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the provider for one argument of a prompt or resource.
+    ///
+    /// # Arguments
+    /// * `reference` - Prompt or resource the argument belongs to
+    /// * `argument` - Name of the argument to complete
+    /// * `provider` - Source of suggestions for the argument
+    pub fn register(
+        &mut self,
+        reference: CompletionRef,
+        argument: impl Into<String>,
+        provider: impl CompletionProvider + 'static,
+    ) {
+        self.providers.insert((reference, argument.into()), Box::new(provider));
+    }
+
+    /// Complete an argument value.
+    ///
+    /// # Arguments
+    /// * `reference` - Prompt or resource the argument belongs to
+    /// * `argument` - Name of the argument being completed
+    /// * `value` - Current (partial) value of the argument
+    ///
+    /// # Returns
+    /// The MCP `completion` object: at most `MAX_COMPLETION_VALUES` values, the
+    /// total number of matches and whether more matches exist. Arguments without
+    /// a provider have no suggestions.
+    pub fn complete(&self, reference: &CompletionRef, argument: &str, value: &str) -> serde_json::Value {
+        let mut values = self.providers.get(&(reference.clone(), argument.to_string()))
+            .map(|provider| provider.complete(value))
+            .unwrap_or_default();
+        let total = values.len();
+        values.truncate(MAX_COMPLETION_VALUES);
+        
+        serde_json::json!({
+            "values": values,
+            "total": total,
+            "hasMore": total > MAX_COMPLETION_VALUES
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLORS: [&str; 4] = ["red", "green", "blue", "rose"];
+
+    fn registry() -> CompletionRegistry {
+        let mut registry = CompletionRegistry::new();
+        registry.register(
+            CompletionRef::Prompt("paint".to_string()),
+            "color",
+            |value: &str| COLORS.iter()
+                .filter(|color| color.starts_with(value))
+                .map(|color| color.to_string())
+                .collect(),
+        );
+        registry
+    }
+
+    #[test]
+    fn prefixes_complete_to_matching_values() {
+        let completion = registry().complete(&CompletionRef::Prompt("paint".to_string()), "color", "r");
+
+        assert_eq!(completion, serde_json::json!({
+            "values": ["red", "rose"],
+            "total": 2,
+            "hasMore": false
+        }));
+    }
+
+    #[test]
+    fn arguments_without_a_provider_complete_to_nothing() {
+        let registry = registry();
+
+        for (reference, argument) in [
+            (CompletionRef::Prompt("paint".to_string()), "size"),
+            (CompletionRef::Prompt("draw".to_string()), "color"),
+            (CompletionRef::Resource("file:///paint".to_string()), "color"),
+        ] {
+            let completion = registry.complete(&reference, argument, "r");
+            assert_eq!(completion["values"], serde_json::json!([]));
+            assert_eq!(completion["total"], 0);
+        }
+    }
+
+    #[test]
+    fn refs_are_parsed_by_type() {
+        let prompt = serde_json::json!({ "type": "ref/prompt", "name": "paint" });
+        let resource = serde_json::json!({ "type": "ref/resource", "uri": "file:///a" });
+
+        assert_eq!(CompletionRef::from_json(&prompt), Ok(CompletionRef::Prompt("paint".to_string())));
+        assert_eq!(CompletionRef::from_json(&resource), Ok(CompletionRef::Resource("file:///a".to_string())));
+        assert!(CompletionRef::from_json(&serde_json::json!({ "type": "ref/prompt" })).is_err());
+        assert!(CompletionRef::from_json(&serde_json::json!({ "type": "ref/tool", "name": "x" })).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+//! Message Framing for Stream Transports
+//!
+//! STDIO and the Unix socket transport exchange JSON-RPC messages over a byte
+//! stream, which needs a way to tell where one message ends and the next begins.
+//! Two framings are supported:
+//! - `line`: one JSON message per line (newline-delimited JSON), the default
+//! - `content-length`: LSP-style `Content-Length: N\r\n\r\n<body>` frames, where
+//!   N is the body length in bytes
+//!
+//! Messages are read with a size limit, so a client cannot make the server
+//! buffer an unbounded amount of input. An oversized message is skipped and
+//! reported to the caller, and reading continues with the next message.
+
+use std::fmt;
+use std::str::FromStr;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Default maximum size of one incoming message (10 MiB).
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// A message read from the stream.
+#[derive(Debug)]
+pub enum Message {
+    /// The complete message body
+    Complete(Vec<u8>),
+    /// A message over the size limit, which was skipped
+    TooLarge,
+}
+
+/// How messages are delimited on a stream transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Framing {
+    /// Newline-delimited JSON
+    #[default]
+    Line,
+    /// LSP-style `Content-Length` headers followed by the body
+    ContentLength,
+}
+
+impl fmt::Display for Framing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Framing::Line => write!(f, "line"),
+            Framing::ContentLength => write!(f, "content-length"),
+        }
+    }
+}
+
+impl FromStr for Framing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "line" => Ok(Framing::Line),
+            "content-length" => Ok(Framing::ContentLength),
+            other => Err(format!("Unknown framing: {}", other)),
+        }
+    }
+}
+
+/// Read the next message from the stream.
+///
+/// In line framing, blank lines are returned as empty messages for the caller
+/// to skip. In content-length framing, headers other than `Content-Length` are
+/// ignored and exactly the announced number of bytes is read as the body.
+///
+/// A message larger than `max_bytes` is consumed without being buffered (up to
+/// the next newline, or the announced length) and returned as `TooLarge`.
+///
+/// # Arguments
+/// * `reader` - Buffered stream to read from
+/// * `framing` - Framing used on the stream
+/// * `max_bytes` - Largest message body accepted
+///
+/// # Returns
+/// The next message, `None` at the end of the stream, or an error if the
+/// stream ends mid-message or a frame header is malformed
+pub async fn read_message<R>(
+    reader: &mut R,
+    framing: Framing,
+    max_bytes: usize,
+) -> std::io::Result<Option<Message>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    match framing {
+        Framing::Line => read_line_message(reader, max_bytes).await,
+        Framing::ContentLength => {
+            let mut content_length = None;
+            let mut header = String::new();
+            
+            // Headers end with an empty line
+            loop {
+                header.clear();
+                if reader.read_line(&mut header).await? == 0 {
+                    if content_length.is_none() {
+                        return Ok(None);
+                    }
+                    return Err(invalid_frame("stream ended inside frame headers"));
+                }
+                let header = header.trim_end_matches(['\r', '\n']);
+                if header.is_empty() {
+                    // Stray blank lines between frames are skipped
+                    if content_length.is_none() {
+                        continue;
+                    }
+                    break;
+                }
+                let (name, value) = header.split_once(':')
+                    .ok_or_else(|| invalid_frame(format!("malformed header: {}", header)))?;
+                if name.trim().eq_ignore_ascii_case("Content-Length") {
+                    let length = value.trim().parse::<usize>()
+                        .map_err(|_| invalid_frame(format!("invalid Content-Length: {}", value.trim())))?;
+                    content_length = Some(length);
+                }
+            }
+            
+            let length = content_length.unwrap_or_default();
+            if length > max_bytes {
+                // Skip the body without buffering it
+                let skipped = tokio::io::copy(&mut reader.take(length as u64), &mut tokio::io::sink()).await?;
+                if skipped < length as u64 {
+                    return Err(std::io::ErrorKind::UnexpectedEof.into());
+                }
+                return Ok(Some(Message::TooLarge));
+            }
+            
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).await?;
+            Ok(Some(Message::Complete(body)))
+        }
+    }
+}
+
+/// Read one newline-terminated message of at most `max_bytes`.
+///
+/// The line is read from the reader's buffer chunk by chunk. Once it exceeds
+/// the limit, the rest of it is discarded up to and including the newline, so
+/// the next read starts at the following message.
+async fn read_line_message<R>(reader: &mut R, max_bytes: usize) -> std::io::Result<Option<Message>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut line = Vec::new();
+    let mut read_any = false;
+    let mut too_large = false;
+    
+    loop {
+        let chunk = reader.fill_buf().await?;
+        if chunk.is_empty() {
+            // End of stream; a final line without a newline still counts
+            if !read_any {
+                return Ok(None);
+            }
+            break;
+        }
+        read_any = true;
+        
+        let (part, found_newline) = match chunk.iter().position(|&b| b == b'\n') {
+            Some(pos) => (&chunk[..pos], true),
+            None => (chunk, false),
+        };
+        if !too_large {
+            if line.len() + part.len() > max_bytes {
+                too_large = true;
+                line = Vec::new();
+            } else {
+                line.extend_from_slice(part);
+            }
+        }
+        
+        let consumed = part.len() + usize::from(found_newline);
+        reader.consume(consumed);
+        if found_newline {
+            break;
+        }
+    }
+    
+    if too_large {
+        return Ok(Some(Message::TooLarge));
+    }
+    // Strip the "\r" of a "\r\n" terminator
+    if line.ends_with(b"\r") {
+        line.pop();
+    }
+    Ok(Some(Message::Complete(line)))
+}
+
+/// Write one message to the stream and flush it.
+///
+/// # Arguments
+/// * `writer` - Stream to write to
+/// * `framing` - Framing used on the stream
+/// * `message` - Serialized JSON message
+pub async fn write_message<W>(writer: &mut W, framing: Framing, message: &str) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    match framing {
+        Framing::Line => {
+            writer.write_all(message.as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+        }
+        Framing::ContentLength => {
+            // The length is in bytes, not characters
+            let header = format!("Content-Length: {}\r\n\r\n", message.len());
+            writer.write_all(header.as_bytes()).await?;
+            writer.write_all(message.as_bytes()).await?;
+        }
+    }
+    // Flush after each message for low latency
+    writer.flush().await
+}
+
+/// Build the error for a malformed frame.
+fn invalid_frame(message: impl Into<String>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Read every message from `input` with content-length framing.
+    async fn read_all(input: &[u8], max_bytes: usize) -> Vec<Message> {
+        let mut reader = input;
+        let mut messages = Vec::new();
+        while let Some(message) = read_message(&mut reader, Framing::ContentLength, max_bytes).await.unwrap() {
+            messages.push(message);
+        }
+        messages
+    }
+
+    #[tokio::test]
+    async fn content_length_counts_bytes_not_characters() {
+        let body = r#"{"text":"héllo → wörld"}"#;
+        let mut framed = Vec::new();
+        write_message(&mut framed, Framing::ContentLength, body).await.unwrap();
+        assert!(framed.starts_with(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes()));
+
+        // A second frame with an extra header directly follows the first
+        framed.extend_from_slice(b"Content-Type: application/json\r\nContent-Length: 2\r\n\r\n{}");
+        let messages = read_all(&framed, DEFAULT_MAX_MESSAGE_BYTES).await;
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(&messages[0], Message::Complete(read) if read == body.as_bytes()));
+        assert!(matches!(&messages[1], Message::Complete(read) if read == b"{}"));
+    }
+
+    #[tokio::test]
+    async fn oversized_frames_are_skipped() {
+        let framed = b"Content-Length: 10\r\n\r\n0123456789Content-Length: 2\r\n\r\n{}";
+
+        let messages = read_all(framed, 5).await;
+        assert!(matches!(messages[0], Message::TooLarge));
+        assert!(matches!(&messages[1], Message::Complete(read) if read == b"{}"));
+    }
+
+    #[tokio::test]
+    async fn a_frame_cut_short_is_an_error() {
+        let mut reader: &[u8] = b"Content-Length: 10\r\n\r\n{}";
+
+        assert!(read_message(&mut reader, Framing::ContentLength, DEFAULT_MAX_MESSAGE_BYTES).await.is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! MCP Logging Capability
+//!
+//! Clients opt into server log messages by calling `logging/setLevel`; the server
+//! then sends `notifications/message` notifications for every log at or above
+//! that level over the client's session. Levels follow RFC 5424 severities, as
+//! in the MCP specification.
+//!
+//! Tool handlers can log to the client that called them with `log_to_client`,
+//! which finds the caller's session from the tool call in progress.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::core::server::MCPNotification;
+use crate::core::session::Session;
+
+/// Severity of a log message, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+impl LogLevel {
+    /// Every level, from least to most severe.
+    pub const ALL: [LogLevel; 8] = [
+        LogLevel::Debug,
+        LogLevel::Info,
+        LogLevel::Notice,
+        LogLevel::Warning,
+        LogLevel::Error,
+        LogLevel::Critical,
+        LogLevel::Alert,
+        LogLevel::Emergency,
+    ];
+
+    /// Name of the level as used on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Notice => "notice",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+            LogLevel::Critical => "critical",
+            LogLevel::Alert => "alert",
+            LogLevel::Emergency => "emergency",
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LogLevel::ALL.into_iter()
+            .find(|level| level.as_str() == s)
+            .ok_or_else(|| format!("Unknown log level: {}", s))
+    }
+}
+
+/// Send a log message to a session's client if it meets the session's level.
+///
+/// # Arguments
+/// * `session` - Session of the client to notify
+/// * `level` - Severity of the message
+/// * `logger` - Optional name of the component logging the message
+/// * `data` - Message payload (a string or any JSON value)
+///
+/// # Returns
+/// Whether the notification was delivered. Messages below the session's level,
+/// or for sessions without a notification channel, are dropped.
+pub fn send_log(session: &Session, level: LogLevel, logger: Option<&str>, data: serde_json::Value) -> bool {
+    if level < session.log_level() {
+        return false;
+    }
+    
+    let mut params = serde_json::json!({
+        "level": level.as_str(),
+        "data": data
+    });
+    if let Some(logger) = logger {
+        params["logger"] = serde_json::Value::String(logger.to_string());
+    }
+    session.notify(&MCPNotification::new("notifications/message", params))
+}
+
+tokio::task_local! {
+    /// Session of the client whose tool call is running on this task.
+    static CALL_SESSION: Arc<Session>;
+}
+
+/// Run a future with `session` as the session of the current tool call.
+pub async fn scope_call<F: std::future::Future>(session: Arc<Session>, future: F) -> F::Output {
+    CALL_SESSION.scope(session, future).await
+}
+
+/// Run a blocking closure with `session` as the session of the current tool call.
+pub fn scope_call_blocking<R>(session: Arc<Session>, f: impl FnOnce() -> R) -> R {
+    CALL_SESSION.sync_scope(session, f)
+}
+
+/// Log a message to the client whose tool call is in progress.
+///
+/// Intended for tool handlers. Outside of a tool call this does nothing.
+///
+/// # Arguments
+/// * `level` - Severity of the message
+/// * `message` - Text of the message
+///
+/// # Returns
+/// Whether the message was delivered to the client.
+pub fn log_to_client(level: LogLevel, message: impl Into<String>) -> bool {
+    let message = message.into();
+    CALL_SESSION.try_with(|session| {
+        send_log(session, level, None, serde_json::Value::String(message))
+    }).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use crate::core::completion::CompletionRegistry;
+    use crate::core::router::MethodContext;
+    use crate::core::server::{initialize_methods, AppState, ToolRegistry};
+
+    #[tokio::test]
+    async fn messages_below_the_set_level_are_suppressed() {
+        let sent = Arc::new(Mutex::new(Vec::<serde_json::Value>::new()));
+        let received = sent.clone();
+        let session = Arc::new(Session::new());
+        session.set_notifier(Arc::new(move |json: String| {
+            received.lock().unwrap().push(serde_json::from_str(&json).unwrap());
+            true
+        }));
+
+        let state = AppState {
+            server_name: "test".to_string(),
+            server_version: "0.0.0".to_string(),
+            instructions: None,
+        };
+        let ctx = MethodContext::new(state, Arc::new(ToolRegistry::new()), initialize_methods(), Arc::new(CompletionRegistry::new()))
+            .with_session(session.clone());
+        let set_level = ctx.methods.get("logging/setLevel").expect("logging/setLevel is registered");
+        set_level(ctx.clone(), Some(serde_json::json!({ "level": "error" }))).await.unwrap();
+
+        // Tool handlers log through the session of the call in progress
+        let delivered = scope_call(session, async {
+            (log_to_client(LogLevel::Info, "routine"), log_to_client(LogLevel::Error, "broken"))
+        }).await;
+        assert_eq!(delivered, (false, true));
+
+        let sent = sent.lock().unwrap();
+        assert_eq!(*sent, [serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/message",
+            "params": { "level": "error", "data": "broken" }
+        })]);
+    }
+
+    #[test]
+    fn logging_outside_a_tool_call_is_dropped() {
+        assert!(!log_to_client(LogLevel::Emergency, "nobody is listening"));
+    }
+}
@@ -0,0 +1,201 @@
+//! Server Metrics
+//!
+//! Counters collected across every transport and reported by `GET /metrics`,
+//! either as JSON or in the Prometheus text exposition format. This tracks the
+//! client applications that initialized a session, by the name and version
+//! from `initialize`'s `clientInfo`, and histograms of how long requests take
+//! by method and tool calls take by tool. The same counters back the
+//! `server/stats` JSON-RPC method, along with the server's uptime.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::core::session::ClientInfo;
+
+/// Upper bounds of the duration histogram buckets, in seconds.
+///
+/// Spans quick methods such as `ping` to tool calls near the default timeout.
+pub const DURATION_BUCKETS: [f64; 14] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+
+/// Histogram of durations over `DURATION_BUCKETS`.
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    /// Observations per bucket, not cumulative; the last counts those beyond
+    /// every bound
+    buckets: [u64; DURATION_BUCKETS.len() + 1],
+    /// Sum of every observation, in seconds
+    sum: f64,
+}
+
+impl Histogram {
+    /// Record one duration.
+    pub fn observe(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let bucket = DURATION_BUCKETS.iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.sum += seconds;
+    }
+
+    /// Number of recorded durations.
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Sum of the recorded durations, in seconds.
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// Cumulative count of durations at most each bound of `DURATION_BUCKETS`.
+    pub fn cumulative(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        DURATION_BUCKETS.iter().zip(self.buckets.iter()).scan(0, |total, (&bound, &count)| {
+            *total += count;
+            Some((bound, *total))
+        })
+    }
+}
+
+/// Metrics shared by every transport and session.
+pub struct Metrics {
+    /// Sessions initialized by each client name and version
+    clients: Mutex<HashMap<(String, String), u64>>,
+    /// Durations of requests by JSON-RPC method
+    methods: Mutex<HashMap<String, Histogram>>,
+    /// Durations of tool calls by tool
+    tools: Mutex<HashMap<String, Histogram>>,
+    /// When the server started
+    started: Instant,
+}
+
+impl Metrics {
+    /// Create an empty set of metrics.
+    pub fn new() -> Self {
+        Self {
+            clients: Mutex::new(HashMap::new()),
+            methods: Mutex::new(HashMap::new()),
+            tools: Mutex::new(HashMap::new()),
+            started: Instant::now(),
+        }
+    }
+
+    /// Time since the server started.
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Record how long a request to a registered `method` took.
+    pub fn record_method(&self, method: &str, elapsed: Duration) {
+        observe(&self.methods, method, elapsed);
+    }
+
+    /// Record how long a call to a registered `tool` took.
+    pub fn record_tool(&self, tool: &str, elapsed: Duration) {
+        observe(&self.tools, tool, elapsed);
+    }
+
+    /// Request duration histograms, sorted by method.
+    pub fn method_durations(&self) -> Vec<(String, Histogram)> {
+        snapshot(&self.methods)
+    }
+
+    /// Tool call duration histograms, sorted by tool.
+    pub fn tool_durations(&self) -> Vec<(String, Histogram)> {
+        snapshot(&self.tools)
+    }
+
+    /// Count a session initialized by `client`.
+    pub fn record_client(&self, client: &ClientInfo) {
+        let mut clients = self.clients.lock().unwrap();
+        *clients.entry((client.name.clone(), client.version.clone())).or_default() += 1;
+    }
+
+    /// Sessions initialized per client, sorted by name and version.
+    ///
+    /// # Returns
+    /// `(name, version, sessions)` for every client seen.
+    pub fn clients(&self) -> Vec<(String, String, u64)> {
+        let clients = self.clients.lock().unwrap();
+        let mut clients: Vec<_> = clients.iter()
+            .map(|((name, version), count)| (name.clone(), version.clone(), *count))
+            .collect();
+        clients.sort();
+        clients
+    }
+
+    /// Render the metrics in the Prometheus text exposition format.
+    ///
+    /// # Arguments
+    /// * `requests_total` - JSON-RPC requests received over HTTP
+    pub fn prometheus(&self, requests_total: u64) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP mcp_requests_total JSON-RPC requests received over HTTP.\n");
+        out.push_str("# TYPE mcp_requests_total counter\n");
+        let _ = writeln!(out, "mcp_requests_total {}", requests_total);
+
+        out.push_str("# HELP mcp_clients Sessions initialized by each client name and version.\n");
+        out.push_str("# TYPE mcp_clients gauge\n");
+        for (name, version, count) in self.clients() {
+            let _ = writeln!(
+                out,
+                "mcp_clients{{name=\"{}\",version=\"{}\"}} {}",
+                escape_label(&name),
+                escape_label(&version),
+                count
+            );
+        }
+
+        out.push_str("# HELP mcp_request_duration_seconds Duration of JSON-RPC requests by method.\n");
+        out.push_str("# TYPE mcp_request_duration_seconds histogram\n");
+        for (method, histogram) in self.method_durations() {
+            write_histogram(&mut out, "mcp_request_duration_seconds", "method", &method, &histogram);
+        }
+
+        out.push_str("# HELP mcp_tool_duration_seconds Duration of tool calls by tool.\n");
+        out.push_str("# TYPE mcp_tool_duration_seconds histogram\n");
+        for (tool, histogram) in self.tool_durations() {
+            write_histogram(&mut out, "mcp_tool_duration_seconds", "tool", &tool, &histogram);
+        }
+        out
+    }
+}
+
+/// Record `elapsed` in the histogram of `key`.
+fn observe(histograms: &Mutex<HashMap<String, Histogram>>, key: &str, elapsed: Duration) {
+    let mut histograms = histograms.lock().unwrap();
+    match histograms.get_mut(key) {
+        Some(histogram) => histogram.observe(elapsed),
+        None => histograms.entry(key.to_string()).or_default().observe(elapsed),
+    }
+}
+
+/// Copy of every histogram, sorted by key.
+fn snapshot(histograms: &Mutex<HashMap<String, Histogram>>) -> Vec<(String, Histogram)> {
+    let histograms = histograms.lock().unwrap();
+    let mut histograms: Vec<_> = histograms.iter()
+        .map(|(key, histogram)| (key.clone(), histogram.clone()))
+        .collect();
+    histograms.sort_by(|a, b| a.0.cmp(&b.0));
+    histograms
+}
+
+/// Write the `_bucket`, `_sum` and `_count` series of one labelled histogram.
+fn write_histogram(out: &mut String, name: &str, label: &str, value: &str, histogram: &Histogram) {
+    let value = escape_label(value);
+    for (bound, count) in histogram.cumulative() {
+        let _ = writeln!(out, "{}_bucket{{{}=\"{}\",le=\"{}\"}} {}", name, label, value, bound, count);
+    }
+    let _ = writeln!(out, "{}_bucket{{{}=\"{}\",le=\"+Inf\"}} {}", name, label, value, histogram.count());
+    let _ = writeln!(out, "{}_sum{{{}=\"{}\"}} {}", name, label, value, histogram.sum());
+    let _ = writeln!(out, "{}_count{{{}=\"{}\"}} {}", name, label, value, histogram.count());
+}
+
+/// Escape a Prometheus label value: backslash, double quote and newline.
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,995 @@
+//! HTTP Middleware
+//!
+//! This module contains the Actix Web middleware used by the HTTP transport:
+//! - BearerAuth: Optional bearer-token authentication for the MCP endpoints
+//! - RateLimit: Optional per-client token-bucket rate limiting for the MCP endpoints
+//! - CORS: Optional cross-origin access for browser-based clients
+//! - Compression: Response compression with a size threshold and selectable algorithms
+//! - RequestDecompression: Decoding of gzip and zstd request bodies, capped in size
+//! - RequestLog: Request logging that samples successful requests and always logs errors
+
+use actix_cors::Cors;
+use actix_http::encoding::Encoder;
+use actix_web::{
+    body::{BodySize, EitherBody, MessageBody},
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{self, AcceptEncoding, ContentEncoding, Encoding, HeaderMap},
+        Method, StatusCode, Uri,
+    },
+    middleware::Condition,
+    Error, HttpMessage, HttpResponse,
+};
+use bytes::{Bytes, BytesMut};
+use dashmap::DashMap;
+use futures_util::{future::LocalBoxFuture, StreamExt};
+use std::future::{ready, Ready};
+use std::io::Read;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::core::server::{self, MCPError, MCPResponse};
+use crate::core::utils;
+
+/// Bearer-token authentication middleware.
+///
+/// When a token is configured, requests to the MCP JSON-RPC endpoints must carry
+/// an `Authorization: Bearer <token>` header. Requests without it, or with the
+/// wrong token, receive a 401 response with a JSON-RPC -32001 error. Liveness
+/// and metrics endpoints (`/health`, `GET /`, `/metrics`) are never checked, and
+/// neither are CORS preflight requests. Without a token the middleware is a no-op.
+#[derive(Clone)]
+pub struct BearerAuth {
+    token: Option<Rc<str>>,
+}
+
+impl BearerAuth {
+    /// Create the middleware. `None` disables authentication.
+    pub fn new(token: Option<String>) -> Self {
+        Self {
+            token: token.map(Rc::from),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for BearerAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = BearerAuthMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(BearerAuthMiddleware {
+            service,
+            token: self.token.clone(),
+        }))
+    }
+}
+
+/// Service produced by `BearerAuth`.
+pub struct BearerAuthMiddleware<S> {
+    service: S,
+    token: Option<Rc<str>>,
+}
+
+impl<S, B> Service<ServiceRequest> for BearerAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Some(token) = &self.token
+            && is_mcp_endpoint(&req)
+            && !has_valid_token(req.headers(), token) {
+            let response = HttpResponse::Unauthorized()
+                .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+                .json(MCPResponse::error(
+                    None,
+                    MCPError::new(-32001, "Unauthorized: missing or invalid bearer token"),
+                ));
+            return Box::pin(ready(Ok(req.into_response(response).map_into_right_body())));
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { Ok(fut.await?.map_into_left_body()) })
+    }
+}
+
+/// Whether a request targets an MCP endpoint guarded by authentication and rate limiting.
+fn is_mcp_endpoint(req: &ServiceRequest) -> bool {
+    if req.method() == Method::OPTIONS {
+        return false;
+    }
+    match req.path() {
+        "/mcp" | "/sse" | "/messages" => true,
+        // Exposes configuration, so it is guarded like the MCP endpoints
+        "/debug/state" => true,
+        // GET / is a health check alias; only JSON-RPC POSTs are guarded
+        "/" => req.method() == Method::POST,
+        _ => false,
+    }
+}
+
+/// The token from a request's `Authorization: Bearer` header, if any.
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// Check a request's `Authorization: Bearer` header against the expected token.
+///
+/// The comparison takes constant time, so it can also guard endpoints outside
+/// this middleware, such as the admin endpoints.
+pub fn has_valid_token(headers: &HeaderMap, expected: &str) -> bool {
+    bearer_token(headers).is_some_and(|provided| constant_time_eq(provided.as_bytes(), expected.as_bytes()))
+}
+
+/// Compare two byte strings in time independent of where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// How often idle buckets are removed from the rate limiter.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Per-client token bucket.
+///
+/// Holds up to `capacity` tokens and refills continuously at `capacity` tokens
+/// per minute; each request consumes one token.
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token-bucket rate limiter shared by every worker.
+///
+/// Buckets are keyed by client (bearer token when present, otherwise peer IP)
+/// and idle buckets are pruned periodically so the map cannot grow without bound.
+pub struct RateLimiter {
+    /// Requests allowed per client per minute (also the burst size)
+    per_minute: u32,
+    /// Buckets keyed by client
+    buckets: DashMap<String, TokenBucket>,
+    /// When idle buckets were last pruned
+    last_prune: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Create a limiter allowing `per_minute` requests per client per minute.
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            buckets: DashMap::new(),
+            last_prune: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Tokens regained per second.
+    fn refill_rate(&self) -> f64 {
+        self.per_minute as f64 / 60.0
+    }
+
+    /// Take a token for `key`.
+    ///
+    /// # Returns
+    /// `Ok(())` if the request may proceed, or `Err(wait)` with the time until
+    /// the next token becomes available.
+    fn acquire(&self, key: &str) -> Result<(), Duration> {
+        self.prune_if_due();
+        
+        let now = Instant::now();
+        let capacity = self.per_minute as f64;
+        let mut bucket = self.buckets.entry(key.to_string()).or_insert_with(|| TokenBucket {
+            tokens: capacity,
+            last_refill: now,
+        });
+        
+        // Refill for the time elapsed since the last request
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_rate()).min(capacity);
+        bucket.last_refill = now;
+        
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill_rate()))
+        }
+    }
+
+    /// Drop buckets that have been idle long enough to be full again.
+    ///
+    /// A full bucket is indistinguishable from a fresh one, so removing it loses
+    /// nothing. Runs at most once per `PRUNE_INTERVAL`, from whichever request
+    /// notices it is due.
+    fn prune_if_due(&self) {
+        let Ok(mut last_prune) = self.last_prune.try_lock() else {
+            // Another request is already pruning
+            return;
+        };
+        if last_prune.elapsed() < PRUNE_INTERVAL {
+            return;
+        }
+        *last_prune = Instant::now();
+        drop(last_prune);
+        
+        let refill_time = Duration::from_secs(60);
+        self.buckets.retain(|_, bucket| bucket.last_refill.elapsed() < refill_time);
+    }
+}
+
+/// Per-client rate limiting middleware.
+///
+/// Requests to the MCP JSON-RPC endpoints are counted against a token bucket
+/// keyed by the client's bearer token, or its peer IP when no token is sent.
+/// Clients over the limit receive a 429 response with a `Retry-After` header and
+/// a JSON-RPC -32000 error. Without a limiter the middleware is a no-op.
+#[derive(Clone)]
+pub struct RateLimit {
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl RateLimit {
+    /// Create the middleware. `None` disables rate limiting.
+    pub fn new(limiter: Option<Arc<RateLimiter>>) -> Self {
+        Self { limiter }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service,
+            limiter: self.limiter.clone(),
+        }))
+    }
+}
+
+/// Service produced by `RateLimit`.
+pub struct RateLimitMiddleware<S> {
+    service: S,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Some(limiter) = &self.limiter
+            && is_mcp_endpoint(&req)
+            && let Err(wait) = limiter.acquire(&client_key(&req)) {
+            let response = HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, wait.as_secs_f64().ceil().max(1.0).to_string()))
+                .json(MCPResponse::error(
+                    None,
+                    MCPError::new(-32000, "Rate limit exceeded"),
+                ));
+            return Box::pin(ready(Ok(req.into_response(response).map_into_right_body())));
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { Ok(fut.await?.map_into_left_body()) })
+    }
+}
+
+/// Rate limiting key for a request: its bearer token, or its peer IP.
+fn client_key(req: &ServiceRequest) -> String {
+    match bearer_token(req.headers()) {
+        Some(token) => format!("token:{}", token),
+        None => format!(
+            "ip:{}",
+            req.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_default()
+        ),
+    }
+}
+
+/// Destination of request log lines.
+type LogSink = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Sampled request logging middleware.
+///
+/// Logs one line per request to stderr with the request line, status and
+/// duration. Successful (2xx) requests are sampled: with a rate of 0.25 every
+/// fourth one is logged. Every other status is always logged, so errors stay
+/// visible however low the rate is.
+#[derive(Clone)]
+pub struct RequestLog {
+    /// Fraction of successful requests logged, from 0.0 to 1.0
+    sample_rate: f64,
+    /// Successful requests seen so far, shared by every worker
+    seen: Arc<AtomicU64>,
+    /// Where log lines are written
+    sink: LogSink,
+}
+
+impl RequestLog {
+    /// Create the middleware, logging to stderr.
+    ///
+    /// # Arguments
+    /// * `sample_rate` - Fraction of successful requests to log; clamped to 0.0–1.0
+    pub fn new(sample_rate: f64) -> Self {
+        Self::with_sink(sample_rate, Arc::new(|line: &str| utils::log_stderr!("{}", line)))
+    }
+
+    /// Create the middleware with the sampling rate in `LOG_SAMPLE_RATE` (default 1.0).
+    pub fn from_env() -> Self {
+        Self::new(utils::parse_env_var("LOG_SAMPLE_RATE", 1.0))
+    }
+
+    /// Fraction of successful requests logged.
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Create the middleware with a custom destination for log lines.
+    fn with_sink(sample_rate: f64, sink: LogSink) -> Self {
+        Self {
+            sample_rate: if sample_rate.is_nan() { 1.0 } else { sample_rate.clamp(0.0, 1.0) },
+            seen: Arc::new(AtomicU64::new(0)),
+            sink,
+        }
+    }
+
+    /// Whether a request that finished with `status` is logged.
+    ///
+    /// Successful requests are sampled deterministically: a request is logged
+    /// whenever the running count of successes times the rate crosses the next
+    /// whole number, which logs exactly the configured fraction over time.
+    fn should_log(&self, status: StatusCode) -> bool {
+        if !status.is_success() {
+            return true;
+        }
+        let n = self.seen.fetch_add(1, Ordering::Relaxed) as f64;
+        ((n + 1.0) * self.sample_rate).floor() > (n * self.sample_rate).floor()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestLogMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestLogMiddleware {
+            service,
+            log: self.clone(),
+        }))
+    }
+}
+
+/// Service produced by `RequestLog`.
+pub struct RequestLogMiddleware<S> {
+    service: S,
+    log: RequestLog,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestLogMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        // Capture the request line before the request is consumed
+        let request_line = format!(
+            "{} {} {:?}",
+            req.method(),
+            req.uri().path_and_query().map_or(req.path(), |pq| pq.as_str()),
+            req.version()
+        );
+        let started = Instant::now();
+        let log = self.log.clone();
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let result = fut.await;
+            let status = match &result {
+                Ok(res) => res.status(),
+                Err(e) => e.as_response_error().status_code(),
+            };
+            if log.should_log(status) {
+                (log.sink)(&format!(
+                    "{} {} {:.3}ms",
+                    request_line,
+                    status.as_u16(),
+                    started.elapsed().as_secs_f64() * 1000.0
+                ));
+            }
+            result
+        })
+    }
+}
+
+/// Origins allowed to make cross-origin requests.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CorsOrigins {
+    /// Any origin (`*`)
+    Any,
+    /// Only the listed origins, e.g. `https://app.example.com`
+    List(Vec<String>),
+}
+
+impl std::str::FromStr for CorsOrigins {
+    type Err = String;
+
+    /// Parse a comma-separated origin list, or `*` for any origin.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let origins: Vec<String> = s
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        if origins.iter().any(|origin| origin == "*") {
+            return Ok(CorsOrigins::Any);
+        }
+        if origins.is_empty() {
+            return Err("no origins given".to_string());
+        }
+
+        // Reject malformed origins up front; actix-cors would otherwise fail
+        // every worker at startup with a less helpful error
+        for origin in &origins {
+            let uri: Uri = origin
+                .parse()
+                .map_err(|_| format!("invalid origin '{}'", origin))?;
+            if uri.scheme().is_none() || uri.host().is_none() {
+                return Err(format!("invalid origin '{}': expected scheme://host[:port]", origin));
+            }
+        }
+
+        Ok(CorsOrigins::List(origins))
+    }
+}
+
+/// Read the allowed CORS origins from `MCP_CORS_ALLOWED_ORIGINS`.
+///
+/// # Returns
+/// * `Ok(None)` - The variable is unset or empty, so CORS is disabled
+/// * `Ok(Some(origins))` - The parsed origin list
+/// * `Err(msg)` - The variable is set but contains an invalid origin
+pub fn cors_origins_from_env() -> Result<Option<CorsOrigins>, String> {
+    match std::env::var("MCP_CORS_ALLOWED_ORIGINS") {
+        Ok(value) if !value.trim().is_empty() => value
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("MCP_CORS_ALLOWED_ORIGINS: {}", e)),
+        _ => Ok(None),
+    }
+}
+
+/// Build the CORS middleware for the configured origins.
+///
+/// Allowed origins get `Access-Control-Allow-*` headers on responses, and
+/// preflight `OPTIONS` requests are answered directly. Requests from other
+/// origins are still processed but receive no CORS headers, so browsers block
+/// them. Without origins the middleware is a no-op and no CORS headers are sent.
+///
+/// # Arguments
+/// * `origins` - Allowed origins, or `None` to disable CORS
+pub fn cors(origins: Option<&CorsOrigins>) -> Condition<Cors> {
+    let Some(origins) = origins else {
+        return Condition::new(false, Cors::default());
+    };
+
+    let mut cors = Cors::default()
+        .allowed_methods(["GET", "POST", "DELETE", "OPTIONS"])
+        .allowed_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::ACCEPT,
+            header::HeaderName::from_static("x-request-deadline-ms"),
+            header::HeaderName::from_static("mcp-session-id"),
+        ])
+        // Browser clients must read the session id returned by initialize
+        .expose_headers([header::HeaderName::from_static("mcp-session-id")])
+        .max_age(3600);
+
+    cors = match origins {
+        CorsOrigins::Any => cors.allow_any_origin().send_wildcard(),
+        CorsOrigins::List(list) => list
+            .iter()
+            .fold(cors, |cors, origin| cors.allowed_origin(origin)),
+    };
+
+    Condition::new(true, cors)
+}
+
+/// Default smallest response body, in bytes, that is compressed.
+pub const DEFAULT_COMPRESSION_MIN_BYTES: usize = 1024;
+
+/// Response compression settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompressionConfig {
+    /// Smallest response body, in bytes, that is compressed; smaller bodies
+    /// are sent as is because compressing them costs more than it saves
+    pub min_bytes: usize,
+    /// Encodings offered to clients; empty disables compression
+    pub encodings: Vec<ContentEncoding>,
+}
+
+impl CompressionConfig {
+    /// Read the compression settings from the environment.
+    ///
+    /// `COMPRESSION_MIN_BYTES` sets the size threshold (default 1024), and
+    /// `COMPRESSION_ALGORITHMS` is a comma-separated list of `br`, `gzip` and
+    /// `deflate`, or `none` to disable compression (default all three).
+    ///
+    /// # Returns
+    /// * `Ok(config)` - The compression settings
+    /// * `Err(msg)` - `COMPRESSION_ALGORITHMS` names an unknown algorithm
+    pub fn from_env() -> Result<Self, String> {
+        let min_bytes = utils::parse_env_var("COMPRESSION_MIN_BYTES", DEFAULT_COMPRESSION_MIN_BYTES);
+        let encodings = match std::env::var("COMPRESSION_ALGORITHMS") {
+            Ok(value) if !value.trim().is_empty() => parse_compression_algorithms(&value)
+                .map_err(|e| format!("COMPRESSION_ALGORITHMS: {}", e))?,
+            _ => vec![ContentEncoding::Brotli, ContentEncoding::Gzip, ContentEncoding::Deflate],
+        };
+        Ok(Self { min_bytes, encodings })
+    }
+}
+
+/// Parse a comma-separated list of compression algorithms.
+fn parse_compression_algorithms(value: &str) -> Result<Vec<ContentEncoding>, String> {
+    let mut encodings = Vec::new();
+    for name in value.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        let encoding = match name.to_ascii_lowercase().as_str() {
+            "none" => continue,
+            "br" | "brotli" => ContentEncoding::Brotli,
+            "gzip" => ContentEncoding::Gzip,
+            "deflate" => ContentEncoding::Deflate,
+            _ => {
+                return Err(format!(
+                    "unknown algorithm '{}'. Must be 'br', 'gzip', 'deflate' or 'none'",
+                    name
+                ));
+            }
+        };
+        if !encodings.contains(&encoding) {
+            encodings.push(encoding);
+        }
+    }
+    Ok(encodings)
+}
+
+/// Response compression middleware.
+///
+/// Works like Actix's `Compress`, negotiating an encoding from the client's
+/// `Accept-Encoding` header, but only offers the configured algorithms and
+/// leaves bodies smaller than the threshold uncompressed. Streamed bodies of
+/// unknown size are always compressed when an encoding was negotiated.
+#[derive(Clone)]
+pub struct Compression {
+    config: Rc<CompressionConfig>,
+}
+
+impl Compression {
+    /// Create the middleware from compression settings.
+    pub fn new(config: CompressionConfig) -> Self {
+        Self {
+            config: Rc::new(config),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Compression
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<Encoder<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = CompressionMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CompressionMiddleware {
+            service,
+            config: self.config.clone(),
+        }))
+    }
+}
+
+/// Service produced by `Compression`.
+pub struct CompressionMiddleware<S> {
+    service: S,
+    config: Rc<CompressionConfig>,
+}
+
+impl<S, B> Service<ServiceRequest> for CompressionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<Encoder<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        // Pick the encoding before the request is consumed by the inner service
+        let encoding = negotiate_encoding(&req, &self.config.encodings);
+        let min_bytes = self.config.min_bytes as u64;
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            Ok(res.map_body(move |head, body| {
+                // Small bodies are sent as is; Identity makes the encoder a pass-through
+                let encoding = match body.size() {
+                    BodySize::Sized(len) if len < min_bytes => ContentEncoding::Identity,
+                    _ => encoding,
+                };
+                Encoder::response(encoding, head, body)
+            }))
+        })
+    }
+}
+
+/// The client's preferred encoding among the enabled ones, or `Identity`.
+fn negotiate_encoding(req: &ServiceRequest, encodings: &[ContentEncoding]) -> ContentEncoding {
+    if encodings.is_empty() {
+        return ContentEncoding::Identity;
+    }
+    let Some(accept) = req.get_header::<AcceptEncoding>() else {
+        return ContentEncoding::Identity;
+    };
+
+    let supported: Vec<Encoding> = std::iter::once(Encoding::identity())
+        .chain(encodings.iter().map(|&encoding| Encoding::Known(encoding)))
+        .collect();
+    match accept.negotiate(supported.iter()) {
+        Some(Encoding::Known(encoding)) => encoding,
+        _ => ContentEncoding::Identity,
+    }
+}
+
+/// Request body decompression settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecompressionConfig {
+    /// Largest request body, in bytes, both as sent and once decoded
+    pub max_bytes: usize,
+    /// Encodings accepted in `Content-Encoding`; empty accepts none
+    pub encodings: Vec<ContentEncoding>,
+}
+
+impl DecompressionConfig {
+    /// Read the decompression settings from the environment.
+    ///
+    /// `REQUEST_DECOMPRESSION_ALGORITHMS` is a comma-separated list of `gzip`
+    /// and `zstd`, or `none` to reject compressed bodies (default both).
+    ///
+    /// # Arguments
+    /// * `max_bytes` - Largest request body, as sent and once decoded (`MAX_REQUEST_BYTES`)
+    ///
+    /// # Returns
+    /// * `Ok(config)` - The decompression settings
+    /// * `Err(msg)` - `REQUEST_DECOMPRESSION_ALGORITHMS` names an unknown algorithm
+    pub fn from_env(max_bytes: usize) -> Result<Self, String> {
+        let encodings = match std::env::var("REQUEST_DECOMPRESSION_ALGORITHMS") {
+            Ok(value) if !value.trim().is_empty() => parse_decompression_algorithms(&value)
+                .map_err(|e| format!("REQUEST_DECOMPRESSION_ALGORITHMS: {}", e))?,
+            _ => vec![ContentEncoding::Gzip, ContentEncoding::Zstd],
+        };
+        Ok(Self { max_bytes, encodings })
+    }
+}
+
+/// Parse a comma-separated list of request decompression algorithms.
+fn parse_decompression_algorithms(value: &str) -> Result<Vec<ContentEncoding>, String> {
+    let mut encodings = Vec::new();
+    for name in value.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        let encoding = match name.to_ascii_lowercase().as_str() {
+            "none" => continue,
+            "gzip" => ContentEncoding::Gzip,
+            "zstd" => ContentEncoding::Zstd,
+            _ => {
+                return Err(format!(
+                    "unknown algorithm '{}'. Must be 'gzip', 'zstd' or 'none'",
+                    name
+                ));
+            }
+        };
+        if !encodings.contains(&encoding) {
+            encodings.push(encoding);
+        }
+    }
+    Ok(encodings)
+}
+
+/// Request body decompression middleware.
+///
+/// Decodes request bodies sent with `Content-Encoding: gzip` (or `x-gzip`) or
+/// `zstd` before they reach the handlers, which then see a plain body. Bodies
+/// are capped at `max_bytes` both as sent and once decoded, so a small body
+/// that expands enormously (a zip bomb) is rejected with a 413 response and a
+/// JSON-RPC -32600 error once decoding passes the cap, without decoding the
+/// rest. Bodies that fail to decode get a 400 response, and encodings that are
+/// not enabled a 415 response. Bodies without `Content-Encoding`, or with
+/// `identity`, are passed through untouched.
+#[derive(Clone)]
+pub struct RequestDecompression {
+    config: Rc<DecompressionConfig>,
+}
+
+impl RequestDecompression {
+    /// Create the middleware from decompression settings.
+    pub fn new(config: DecompressionConfig) -> Self {
+        Self {
+            config: Rc::new(config),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestDecompression
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestDecompressionMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestDecompressionMiddleware {
+            service: Rc::new(service),
+            config: self.config.clone(),
+        }))
+    }
+}
+
+/// Service produced by `RequestDecompression`.
+pub struct RequestDecompressionMiddleware<S> {
+    service: Rc<S>,
+    config: Rc<DecompressionConfig>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestDecompressionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let encoding = match request_encoding(&req, &self.config.encodings) {
+            Ok(Some(encoding)) => encoding,
+            Ok(None) => {
+                let fut = self.service.call(req);
+                return Box::pin(async move { Ok(fut.await?.map_into_left_body()) });
+            }
+            Err(name) => {
+                let response = HttpResponse::UnsupportedMediaType().json(MCPResponse::error(
+                    None,
+                    MCPError::new(-32600, format!("Unsupported Content-Encoding: {}", name)),
+                ));
+                return Box::pin(ready(Ok(req.into_response(response).map_into_right_body())));
+            }
+        };
+
+        let service = self.service.clone();
+        let max_bytes = self.config.max_bytes;
+        Box::pin(async move {
+            let body = match decompress_body(req.take_payload(), encoding, max_bytes).await {
+                Ok(body) => body,
+                Err(response) => return Ok(req.into_response(response).map_into_right_body()),
+            };
+            // Handlers see the decoded body as if it had been sent uncompressed
+            let headers = req.headers_mut();
+            headers.remove(header::CONTENT_ENCODING);
+            headers.insert(header::CONTENT_LENGTH, header::HeaderValue::from(body.len()));
+            req.set_payload(Payload::from(body));
+            Ok(service.call(req).await?.map_into_left_body())
+        })
+    }
+}
+
+/// The enabled encoding a request body is sent with, if it is encoded.
+///
+/// # Returns
+/// * `Ok(Some(encoding))` - The body is gzip or zstd encoded
+/// * `Ok(None)` - The body is not encoded
+/// * `Err(name)` - The body is sent with an encoding that is not enabled
+fn request_encoding(
+    req: &ServiceRequest,
+    encodings: &[ContentEncoding],
+) -> Result<Option<ContentEncoding>, String> {
+    let Some(value) = req.headers().get(header::CONTENT_ENCODING) else {
+        return Ok(None);
+    };
+    let name = value.to_str().unwrap_or_default().trim().to_ascii_lowercase();
+    let encoding = match name.as_str() {
+        "" | "identity" => return Ok(None),
+        "gzip" | "x-gzip" => Some(ContentEncoding::Gzip),
+        "zstd" => Some(ContentEncoding::Zstd),
+        _ => None,
+    };
+    match encoding {
+        Some(encoding) if encodings.contains(&encoding) => Ok(Some(encoding)),
+        _ => Err(name),
+    }
+}
+
+/// Read and decode an encoded request body.
+///
+/// Decoding stops one byte past `max_bytes`, so the memory a body can take is
+/// bounded however far it would expand.
+///
+/// # Returns
+/// The decoded body, or the error response to send back.
+async fn decompress_body(
+    mut payload: Payload,
+    encoding: ContentEncoding,
+    max_bytes: usize,
+) -> Result<Bytes, HttpResponse> {
+    let mut compressed = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| bad_encoded_body(format!("Failed to read request body: {}", e)))?;
+        if compressed.len() + chunk.len() > max_bytes {
+            return Err(server::payload_too_large(max_bytes));
+        }
+        compressed.extend_from_slice(&chunk);
+    }
+
+    let decoder: Box<dyn Read + '_> = match encoding {
+        ContentEncoding::Zstd => Box::new(
+            zstd::stream::read::Decoder::new(&compressed[..])
+                .map_err(|e| bad_encoded_body(format!("Invalid zstd request body: {}", e)))?,
+        ),
+        _ => Box::new(flate2::read::MultiGzDecoder::new(&compressed[..])),
+    };
+    let mut body = Vec::new();
+    decoder.take(max_bytes as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| bad_encoded_body(format!("Invalid {} request body: {}", encoding.as_str(), e)))?;
+    if body.len() > max_bytes {
+        return Err(server::payload_too_large(max_bytes));
+    }
+    Ok(Bytes::from(body))
+}
+
+/// 400 response for a body that cannot be read or decoded.
+fn bad_encoded_body(message: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(MCPResponse::error(None, MCPError::new(-32600, message)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App};
+
+    #[actix_rt::test]
+    async fn zero_sample_rate_logs_only_errors() {
+        let lines = Arc::new(Mutex::new(Vec::<String>::new()));
+        let sink_lines = lines.clone();
+        let log = RequestLog::with_sink(0.0, Arc::new(move |line: &str| {
+            sink_lines.lock().unwrap().push(line.to_string());
+        }));
+        let app = test::init_service(
+            App::new()
+                .wrap(log)
+                .route("/ok", web::get().to(HttpResponse::Ok))
+                .route("/fail", web::get().to(HttpResponse::InternalServerError)),
+        ).await;
+
+        for _ in 0..10 {
+            test::call_service(&app, test::TestRequest::get().uri("/ok").to_request()).await;
+        }
+        test::call_service(&app, test::TestRequest::get().uri("/fail").to_request()).await;
+
+        let lines = lines.lock().unwrap();
+        assert_eq!(lines.len(), 1, "unexpected log lines: {:?}", lines);
+        assert!(lines[0].starts_with("GET /fail"), "unexpected log line: {}", lines[0]);
+        assert!(lines[0].contains(" 500 "), "unexpected log line: {}", lines[0]);
+    }
+
+    #[actix_rt::test]
+    async fn clients_over_the_limit_wait_for_a_refill() {
+        let limiter = Arc::new(RateLimiter::new(60));
+        let app = test::init_service(
+            App::new()
+                .wrap(RateLimit::new(Some(limiter)))
+                .route("/mcp", web::post().to(HttpResponse::Ok)),
+        ).await;
+        let post = |token: &str| test::TestRequest::post()
+            .uri("/mcp")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request();
+
+        // The full bucket allows a burst of one minute's requests
+        for _ in 0..60 {
+            let response = test::call_service(&app, post("a")).await;
+            assert_eq!(response.status(), 200);
+        }
+        let response = test::call_service(&app, post("a")).await;
+        assert_eq!(response.status(), 429);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "1");
+        let body: serde_json::Value = test::read_body_json(response).await;
+        assert_eq!(body["error"]["code"], -32000);
+        assert_eq!(body["error"]["message"], "Rate limit exceeded");
+
+        // Other clients have their own bucket
+        let response = test::call_service(&app, post("b")).await;
+        assert_eq!(response.status(), 200);
+
+        // At 60 per minute a token is regained every second
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let response = test::call_service(&app, post("a")).await;
+        assert_eq!(response.status(), 200);
+        let response = test::call_service(&app, post("a")).await;
+        assert_eq!(response.status(), 429);
+    }
+
+    #[test]
+    fn idle_buckets_are_pruned() {
+        let limiter = RateLimiter::new(60);
+        limiter.acquire("idle").unwrap();
+        limiter.acquire("active").unwrap();
+
+        // Make "idle" full again and the prune due
+        let long_ago = Instant::now() - Duration::from_secs(61);
+        limiter.buckets.get_mut("idle").unwrap().last_refill = long_ago;
+        *limiter.last_prune.lock().unwrap() = long_ago;
+
+        limiter.acquire("active").unwrap();
+        assert!(!limiter.buckets.contains_key("idle"));
+        assert!(limiter.buckets.contains_key("active"));
+    }
+}
@@ -1,9 +1,51 @@
-/// Core Server Framework Module
-///
-/// This module contains the core server implementation including:
-/// - server.rs: MCP server implementation with HTTP and STDIO transport
-/// - utils.rs: Configuration and utility functions
+//! Core Server Framework Module
+//!
+//! This module contains the core server implementation including:
+//! - server.rs: MCP server implementation with HTTP and STDIO transport
+//! - audit.rs: Optional JSON-lines audit log of executed tool calls
+//! - build_info.rs: Build metadata (git commit, build time, compiler) embedded at compile time
+//! - cache.rs: LRU cache of tool results for cacheable tools
+//! - capabilities.rs: Server capabilities derived from the registered tools and methods
+//! - completion.rs: Argument completion providers for completion/complete
+//! - framing.rs: Message framing (line or Content-Length) for stream transports
+//! - logging.rs: MCP logging capability (log levels and client log notifications)
+//! - metrics.rs: Counters reported by /metrics, as JSON or in Prometheus format
+//! - middleware.rs: Actix Web middleware for the HTTP transport
+//! - openapi.rs: OpenAPI description of the HTTP API
+//! - plugin.rs: Tool plugins discovered and registered automatically at startup
+//! - progress.rs: Progress notifications for long-running tool calls
+//! - resources.rs: Per-session subscriptions to resource update notifications
+//! - router.rs: Registry of JSON-RPC methods shared by every transport
+//! - self_test.rs: Optional startup check of every method's response shape
+//! - session.rs: Per-connection client session state
+//! - sse.rs: Session-based MCP SSE transport
+//! - streaming.rs: Chunked HTTP responses for streaming tool handlers
+//! - tls.rs: Optional TLS (HTTPS) configuration for HTTP mode
+//! - tool_middleware.rs: Middleware hooks run around every tool call
+//! - utils.rs: Configuration and utility functions
+//! - validation.rs: Tool argument validation against input schemas
 
+pub mod audit;
+pub mod build_info;
+pub mod cache;
+pub mod capabilities;
+pub mod completion;
+pub mod framing;
+pub mod logging;
+pub mod metrics;
+pub mod middleware;
+pub mod openapi;
+pub mod plugin;
+pub mod progress;
+pub mod resources;
+pub mod router;
+pub mod self_test;
 pub mod server;
+pub mod session;
+pub mod sse;
+pub mod streaming;
+pub mod tls;
+pub mod tool_middleware;
 pub mod utils;
+pub mod validation;
 
@@ -0,0 +1,260 @@
+//! OpenAPI Description of the HTTP API
+//!
+//! Builds the OpenAPI 3.1 document served at `GET /openapi.json`. The routes,
+//! the JSON-RPC envelope and the error codes are described by hand; the
+//! `tools/call` params are generated from the tool registry, so every tool's
+//! input schema is reflected in the document.
+
+use serde_json::{json, Map, Value};
+
+use crate::core::server::{AppState, ToolRegistry};
+
+/// JSON-RPC error codes returned by the server, with their meaning.
+const ERROR_CODES: &[(i32, &str)] = &[
+    (-32700, "Parse error: the body is not valid JSON"),
+    (-32600, "Invalid Request: the body is not a valid JSON-RPC request, or is too large"),
+    (-32601, "Method not found, or unknown tool"),
+    (-32602, "Invalid params, including tool arguments that fail schema validation"),
+    (-32603, "Internal error, including tool output that does not match its outputSchema"),
+    (-32002, "Server not initialized"),
+    (-32000, "Server error: rate limit exceeded or tool execution timed out"),
+    (-32001, "Unauthorized: missing or invalid bearer token"),
+    (-32800, "Request cancelled"),
+];
+
+/// Build the OpenAPI document for the server's HTTP API.
+///
+/// # Arguments
+/// * `state` - Server metadata, used for the document's title and version
+/// * `tools` - Registry whose tools are described in the `tools/call` params
+pub fn openapi_document(state: &AppState, tools: &ToolRegistry) -> Value {
+    let mut schemas = Map::new();
+    let mut tool_calls = Vec::new();
+    for tool in &tools.tools {
+        let schema_name = format!("ToolArguments.{}", schema_key(&tool.name));
+        schemas.insert(schema_name.clone(), tool.input_schema.clone());
+        tool_calls.push(json!({
+            "type": "object",
+            "description": tool.description,
+            "properties": {
+                "name": { "const": tool.name },
+                "arguments": { "$ref": format!("#/components/schemas/{}", schema_name) }
+            },
+            "required": ["name"]
+        }));
+    }
+    
+    let error_codes: Map<String, Value> = ERROR_CODES.iter()
+        .map(|(code, meaning)| (code.to_string(), json!(meaning)))
+        .collect();
+    
+    schemas.insert("MCPRequest".to_string(), json!({
+        "type": "object",
+        "description": "JSON-RPC 2.0 request. Requests without an id are notifications and get no response body.",
+        "properties": {
+            "jsonrpc": { "const": "2.0" },
+            "id": { "type": ["string", "number", "null"] },
+            "method": { "type": "string", "examples": ["initialize", "tools/list", "tools/call", "ping"] },
+            "params": { "type": "object" }
+        },
+        "required": ["jsonrpc", "method"]
+    }));
+    schemas.insert("MCPResponse".to_string(), json!({
+        "type": "object",
+        "description": "JSON-RPC 2.0 response carrying either a result or an error.",
+        "properties": {
+            "jsonrpc": { "const": "2.0" },
+            "id": { "type": ["string", "number", "null"] },
+            "result": {},
+            "error": { "$ref": "#/components/schemas/MCPError" }
+        },
+        "required": ["jsonrpc", "id"]
+    }));
+    schemas.insert("MCPError".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "code": {
+                "type": "integer",
+                "enum": ERROR_CODES.iter().map(|(code, _)| *code).collect::<Vec<_>>()
+            },
+            "message": { "type": "string" },
+            "data": {}
+        },
+        "required": ["code", "message"],
+        "x-error-codes": error_codes
+    }));
+    schemas.insert("ToolCallParams".to_string(), json!({
+        "description": "Params of a tools/call request, one variant per registered tool.",
+        "oneOf": tool_calls
+    }));
+    
+    let jsonrpc_operation = |summary: &str| json!({
+        "summary": summary,
+        "requestBody": {
+            "required": true,
+            "content": {
+                "application/json": { "schema": { "$ref": "#/components/schemas/MCPRequest" } }
+            }
+        },
+        "responses": {
+            "200": {
+                "description": "JSON-RPC response",
+                "content": {
+                    "application/json": { "schema": { "$ref": "#/components/schemas/MCPResponse" } }
+                }
+            },
+            "202": { "description": "Notification accepted; no response body" },
+            "400": {
+                "description": "Parse error or invalid request",
+                "content": {
+                    "application/json": { "schema": { "$ref": "#/components/schemas/MCPResponse" } }
+                }
+            },
+            "401": { "description": "Missing or invalid bearer token (when MCP_AUTH_TOKEN is set)" },
+            "413": { "description": "Request body larger than MAX_REQUEST_BYTES" },
+            "429": { "description": "Rate limit exceeded (when RATE_LIMIT_PER_MIN is set)" }
+        }
+    });
+    let json_get = |summary: &str, description: &str| json!({
+        "get": {
+            "summary": summary,
+            "responses": {
+                "200": {
+                    "description": description,
+                    "content": { "application/json": { "schema": { "type": "object" } } }
+                }
+            }
+        }
+    });
+    
+    // REST endpoints wrap their JSON in {data, error, meta} on request
+    let envelope = json!({
+        "name": "envelope",
+        "in": "query",
+        "required": false,
+        "schema": { "type": "string", "enum": ["0", "1"] },
+        "description": "Wrap the response in {data, error, meta}; defaults to MCP_REST_ENVELOPE"
+    });
+    let mut tools_list = json_get("List tools", "Every registered tool, as returned by tools/list");
+    tools_list["get"]["parameters"] = json!([envelope]);
+    
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": state.server_name,
+            "version": state.server_version,
+            "description": "Model Context Protocol server. MCP methods are called as JSON-RPC 2.0 requests POSTed to /mcp."
+        },
+        "paths": {
+            "/mcp": {
+                "post": jsonrpc_operation("Call an MCP method (JSON-RPC 2.0)"),
+                "get": {
+                    "summary": "Call a read-only MCP method with query parameters",
+                    "description": "Builds a JSON-RPC request from the query. tools/call is only accepted when MCP_HTTP_GET_TOOL_CALLS is set.",
+                    "parameters": [
+                        { "name": "method", "in": "query", "required": true, "schema": { "type": "string" } },
+                        { "name": "id", "in": "query", "required": false, "schema": { "type": "string" } },
+                        { "name": "params", "in": "query", "required": false, "schema": { "type": "string" }, "description": "JSON object of params" },
+                        { "name": "name", "in": "query", "required": false, "schema": { "type": "string" }, "description": "Sets params.name" },
+                        { "name": "args", "in": "query", "required": false, "schema": { "type": "string" }, "description": "JSON object setting params.arguments" }
+                    ],
+                    "responses": {
+                        "200": { "description": "JSON-RPC response" },
+                        "400": { "description": "Missing method or malformed params" },
+                        "405": { "description": "The method is not allowed over GET" }
+                    }
+                }
+            },
+            "/": { "post": jsonrpc_operation("Call an MCP method (JSON-RPC 2.0), alias of /mcp") },
+            "/health": {
+                "get": {
+                    "summary": "Liveness check",
+                    "parameters": [
+                        { "name": "deep", "in": "query", "required": false, "schema": { "type": "boolean" } },
+                        { "name": "format", "in": "query", "required": false, "schema": { "type": "string", "enum": ["json", "text", "empty"] } }
+                    ],
+                    "responses": {
+                        "200": { "description": "The server is running (and, with deep=true, the canary tool succeeded)" },
+                        "204": { "description": "The server is running; requested with format=empty" },
+                        "503": { "description": "The deep check's canary tool failed; the body gives the error" }
+                    }
+                }
+            },
+            "/readyz": {
+                "get": {
+                    "summary": "Readiness check",
+                    "responses": {
+                        "200": { "description": "The server is ready to serve traffic" },
+                        "503": { "description": "The server is not ready; the body gives the reason" }
+                    }
+                }
+            },
+            "/version": json_get("Build information", "Server name, version, git commit, build time and rustc version"),
+            "/metrics": {
+                "get": {
+                    "summary": "Request metrics",
+                    "parameters": [
+                        { "name": "format", "in": "query", "required": false, "schema": { "type": "string", "enum": ["json", "prometheus"] } },
+                        envelope
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Request counters and sessions per client, as JSON, or with duration histograms in Prometheus text format",
+                            "content": {
+                                "application/json": { "schema": { "type": "object" } },
+                                "text/plain": { "schema": { "type": "string" } }
+                            }
+                        }
+                    }
+                }
+            },
+            "/tools": tools_list,
+            "/tools/{name}/schema": {
+                "get": {
+                    "summary": "Get one tool's schema",
+                    "parameters": [
+                        { "name": "name", "in": "path", "required": true, "schema": { "type": "string" } },
+                        envelope
+                    ],
+                    "responses": {
+                        "200": { "description": "The tool, as listed by tools/list" },
+                        "404": { "description": "No tool with that name" }
+                    }
+                }
+            },
+            "/.well-known/mcp.json": json_get("Capability manifest", "Server name and version, protocol version, capabilities and tools, for discovery before connecting"),
+            "/openapi.json": json_get("This document", "OpenAPI 3.1 description of the HTTP API"),
+            "/admin/shutdown": {
+                "post": {
+                    "summary": "Shut the server down gracefully",
+                    "description": "Requires `Authorization: Bearer` with the MCP_ADMIN_TOKEN. In-flight requests complete within SHUTDOWN_TIMEOUT_SECS.",
+                    "responses": {
+                        "202": { "description": "Shutdown started; the body is {\"status\": \"shutting_down\"}" },
+                        "401": { "description": "Missing or invalid admin token" },
+                        "404": { "description": "Admin endpoints are disabled (MCP_ADMIN_TOKEN is not set)" }
+                    }
+                }
+            },
+            "/debug/state": {
+                "get": {
+                    "summary": "Runtime state snapshot",
+                    "description": "Registered tools, request counter, configuration with secrets redacted, and open SSE sessions. Development only.",
+                    "responses": {
+                        "200": { "description": "The snapshot" },
+                        "404": { "description": "Debug endpoints are disabled (MCP_DEBUG_ENDPOINTS is not set)" }
+                    }
+                }
+            }
+        },
+        "components": {
+            "schemas": schemas
+        }
+    })
+}
+
+/// Make a tool name usable as an OpenAPI component key (`[A-Za-z0-9._-]`).
+fn schema_key(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,46 @@
+//! Tool Plugins
+//!
+//! A tool plugin adds one or more tools to the registry. Plugins are submitted
+//! with the `submit_tool_plugin!` macro (see `tools/macros.rs`) from any tool module,
+//! collected at link time, and registered by `initialize_tools` at startup, so
+//! a new tool needs no edit to `initialize_tools`.
+//!
+//! # Example
+//! ```rust,ignore
+//! struct WeatherPlugin;
+//!
+//! impl ToolPlugin for WeatherPlugin {
+//!     fn register(&self, registry: &mut ToolRegistry) {
+//!         weather::register(registry);
+//!     }
+//! }
+//!
+//! submit_tool_plugin!(WeatherPlugin);
+//! ```
+
+use crate::core::server::ToolRegistry;
+
+/// A source of tools that registers itself at startup.
+pub trait ToolPlugin: Sync {
+    /// Register the plugin's tools with the registry.
+    ///
+    /// # Arguments
+    /// * `registry` - Mutable reference to the tool registry being built
+    fn register(&self, registry: &mut ToolRegistry);
+}
+
+/// A submitted plugin, as collected by `inventory`.
+pub struct PluginRegistration(pub &'static dyn ToolPlugin);
+
+inventory::collect!(PluginRegistration);
+
+/// Every submitted tool plugin.
+///
+/// Plugins are returned in link order, which is unspecified; tools/list lists
+/// the tools of each plugin in the order the plugin registers them.
+pub fn plugins() -> Vec<&'static dyn ToolPlugin> {
+    inventory::iter::<PluginRegistration>
+        .into_iter()
+        .map(|registration| registration.0)
+        .collect()
+}
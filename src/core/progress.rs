@@ -0,0 +1,74 @@
+//! MCP Progress Notifications
+//!
+//! A client can ask to be told how a long tool call is going by sending a
+//! `progressToken` in the `_meta` of its `tools/call` params. While the call
+//! runs, the server then sends `notifications/progress` notifications carrying
+//! that token over the client's session, ahead of the call's response.
+//!
+//! Tool handlers report progress through the `ProgressReporter` in their
+//! `CallContext`, which can be cloned into other tasks. Without a token,
+//! reports are silently dropped, so tools can report unconditionally.
+
+use std::sync::Arc;
+
+use crate::core::server::MCPNotification;
+use crate::core::session::Session;
+
+/// Handle for reporting the progress of one tool call to its client.
+///
+/// Cloning the handle is cheap, so it can be moved into spawned tasks.
+#[derive(Clone, Default)]
+pub struct ProgressReporter {
+    /// Session of the calling client, and the token it asked progress for
+    target: Option<(Arc<Session>, serde_json::Value)>,
+}
+
+impl ProgressReporter {
+    /// Create a reporter for a tool call.
+    ///
+    /// # Arguments
+    /// * `session` - Session of the calling client
+    /// * `token` - The call's `_meta.progressToken`, or `None` if the client
+    ///   did not ask for progress
+    pub fn new(session: Arc<Session>, token: Option<serde_json::Value>) -> Self {
+        Self {
+            target: token.map(|token| (session, token)),
+        }
+    }
+
+    /// Whether the client asked for progress notifications.
+    #[allow(dead_code)] // Available for tools that skip costly progress bookkeeping
+    pub fn is_enabled(&self) -> bool {
+        self.target.is_some()
+    }
+
+    /// Send a `notifications/progress` notification.
+    ///
+    /// Progress should increase with every report, as the MCP specification
+    /// requires.
+    ///
+    /// # Arguments
+    /// * `progress` - Work done so far
+    /// * `total` - Total amount of work, if known
+    /// * `message` - Optional human-readable status
+    ///
+    /// # Returns
+    /// Whether the notification was delivered to the client.
+    pub fn report(&self, progress: f64, total: Option<f64>, message: Option<&str>) -> bool {
+        let Some((session, token)) = &self.target else {
+            return false;
+        };
+
+        let mut params = serde_json::json!({
+            "progressToken": token,
+            "progress": progress
+        });
+        if let Some(total) = total {
+            params["total"] = serde_json::json!(total);
+        }
+        if let Some(message) = message {
+            params["message"] = serde_json::json!(message);
+        }
+        session.notify(&MCPNotification::new("notifications/progress", params))
+    }
+}
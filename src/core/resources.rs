@@ -0,0 +1,106 @@
+//! Resource Subscriptions
+//!
+//! Clients subscribe to a resource URI with `resources/subscribe` and are then
+//! sent a `notifications/resources/updated` notification whenever the server
+//! reports a change to that resource with `notify_resource_updated`.
+//!
+//! Subscriptions belong to a session, so they are only useful on transports
+//! that can deliver notifications (SSE sessions, STDIO and Unix sockets).
+//! Sessions are held weakly: a session that has disconnected is dropped from
+//! the subscriptions the next time one of its resources is updated.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::core::server::MCPNotification;
+use crate::core::session::Session;
+
+/// Sessions subscribed to each resource URI.
+pub struct ResourceSubscriptions {
+    /// Subscribed sessions by resource URI
+    subscribers: Mutex<HashMap<String, Vec<Weak<Session>>>>,
+}
+
+impl ResourceSubscriptions {
+    /// Create a registry without subscriptions.
+    pub fn new() -> Self {
+        Self {
+            subscribers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Subscribe a session to updates of a resource.
+    ///
+    /// Subscribing again to the same URI has no further effect.
+    ///
+    /// # Arguments
+    /// * `uri` - URI of the resource
+    /// * `session` - Session to notify when the resource changes
+    pub fn subscribe(&self, uri: &str, session: &Arc<Session>) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let sessions = subscribers.entry(uri.to_string()).or_default();
+        if !sessions.iter().any(|subscribed| is_session(subscribed, session)) {
+            sessions.push(Arc::downgrade(session));
+        }
+    }
+
+    /// Remove a session's subscription to a resource.
+    ///
+    /// # Returns
+    /// Whether the session was subscribed.
+    pub fn unsubscribe(&self, uri: &str, session: &Arc<Session>) -> bool {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let Some(sessions) = subscribers.get_mut(uri) else {
+            return false;
+        };
+        let before = sessions.len();
+        sessions.retain(|subscribed| !is_session(subscribed, session));
+        let removed = sessions.len() < before;
+        if sessions.is_empty() {
+            subscribers.remove(uri);
+        }
+        removed
+    }
+
+    /// Send `notifications/resources/updated` to every session subscribed to `uri`.
+    ///
+    /// Sessions that have closed, or whose client can no longer be reached,
+    /// are unsubscribed.
+    ///
+    /// # Arguments
+    /// * `uri` - URI of the resource that changed
+    ///
+    /// # Returns
+    /// The number of sessions notified.
+    #[allow(dead_code)] // Called by tools and resources that change server-side state
+    pub fn notify_resource_updated(&self, uri: &str) -> usize {
+        // Collect the live sessions first so the lock is not held while sending
+        let sessions: Vec<Arc<Session>> = {
+            let mut subscribers = self.subscribers.lock().unwrap();
+            let Some(sessions) = subscribers.get_mut(uri) else {
+                return 0;
+            };
+            sessions.retain(|session| session.strong_count() > 0);
+            sessions.iter().filter_map(Weak::upgrade).collect()
+        };
+
+        let notification = MCPNotification::new(
+            "notifications/resources/updated",
+            serde_json::json!({ "uri": uri }),
+        );
+        let mut delivered = 0;
+        for session in sessions {
+            if session.notify(&notification) {
+                delivered += 1;
+            } else {
+                self.unsubscribe(uri, &session);
+            }
+        }
+        delivered
+    }
+}
+
+/// Whether a weak session reference points at `session`.
+fn is_session(subscribed: &Weak<Session>, session: &Arc<Session>) -> bool {
+    std::ptr::eq(subscribed.as_ptr(), Arc::as_ptr(session))
+}
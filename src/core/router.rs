@@ -0,0 +1,183 @@
+//! JSON-RPC Method Router
+//!
+//! This module contains the registry of MCP methods the server handles. Every
+//! transport (HTTP, SSE and STDIO) dispatches through the same registry, so a
+//! method registered here is available everywhere and shows up in the
+//! `capabilities/probe` description of the server's API.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+use crate::core::capabilities::ServerCapabilities;
+use crate::core::completion::CompletionRegistry;
+use crate::core::metrics::Metrics;
+use crate::core::resources::ResourceSubscriptions;
+use crate::core::server::{AppState, MCPError, ToolRegistry};
+use crate::core::session::Session;
+
+/// Result of a method handler: the JSON-RPC `result` value or an error.
+pub type MethodResult = Result<serde_json::Value, MCPError>;
+
+/// Boxed future returned by method handlers.
+pub type MethodFuture = Pin<Box<dyn Future<Output = MethodResult> + Send>>;
+
+/// Method handler function type definition.
+///
+/// Method handlers receive the shared server context and the request params,
+/// and resolve to a result or a JSON-RPC error. The request id is handled by
+/// the dispatcher, so handlers never need to see it.
+pub type MethodHandler = Box<dyn Fn(MethodContext, Option<serde_json::Value>) -> MethodFuture + Send + Sync>;
+
+/// Shared state made available to every method handler.
+///
+/// Cloning is cheap: every field is reference counted.
+#[derive(Clone)]
+pub struct MethodContext {
+    /// Server metadata
+    pub state: Arc<AppState>,
+    /// Registered tools
+    pub tools: Arc<ToolRegistry>,
+    /// Registered methods
+    pub methods: Arc<MethodRegistry>,
+    /// Registered argument completion providers
+    pub completions: Arc<CompletionRegistry>,
+    /// Capabilities advertised to clients, derived from the registries
+    pub capabilities: Arc<ServerCapabilities>,
+    /// Sessions subscribed to resource updates
+    pub subscriptions: Arc<ResourceSubscriptions>,
+    /// Counters reported by `/metrics`
+    pub metrics: Arc<Metrics>,
+    /// Session of the client that sent the request
+    pub session: Arc<Session>,
+    /// Fires when the client cancels the request being handled
+    pub cancellation: CancellationToken,
+}
+
+impl MethodContext {
+    /// Create a context from the server's shared components with a fresh session.
+    ///
+    /// The server's capabilities are derived from the tool and method registries here.
+    pub fn new(
+        state: AppState,
+        tools: Arc<ToolRegistry>,
+        methods: Arc<MethodRegistry>,
+        completions: Arc<CompletionRegistry>,
+    ) -> Self {
+        let capabilities = Arc::new(ServerCapabilities::detect(&tools, &methods));
+        Self {
+            state: Arc::new(state),
+            tools,
+            methods,
+            completions,
+            capabilities,
+            subscriptions: Arc::new(ResourceSubscriptions::new()),
+            metrics: Arc::new(Metrics::new()),
+            session: Arc::new(Session::new()),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// Clone this context for use with a different client session.
+    pub fn with_session(&self, session: Arc<Session>) -> Self {
+        Self {
+            session,
+            ..self.clone()
+        }
+    }
+
+    /// Clone this context for handling a request cancelled through `cancellation`.
+    pub fn with_cancellation(&self, cancellation: CancellationToken) -> Self {
+        Self {
+            cancellation,
+            ..self.clone()
+        }
+    }
+}
+
+/// Description of a registered method.
+///
+/// This is what `capabilities/probe` reports for each method.
+#[derive(Serialize, Debug, Clone)]
+pub struct MethodInfo {
+    /// JSON-RPC method name (e.g., "tools/call")
+    pub name: String,
+    /// Human-readable description of what the method does
+    pub description: String,
+    /// JSON Schema describing the method's params
+    #[serde(rename = "paramsSchema")]
+    pub params_schema: serde_json::Value,
+}
+
+/// Registry of JSON-RPC methods.
+///
+/// Mirrors `ToolRegistry`: a list of method descriptions for discovery and a
+/// HashMap of method names to their handlers for dispatch.
+pub struct MethodRegistry {
+    /// List of all registered methods (for capabilities/probe)
+    pub methods: Vec<MethodInfo>,
+    /// Map of method names to their handler functions
+    pub handlers: HashMap<String, MethodHandler>,
+    /// Methods switched off by configuration, with the error message sent to
+    /// clients that call them
+    disabled: HashMap<String, String>,
+}
+
+impl MethodRegistry {
+    /// Create a new empty method registry.
+    pub fn new() -> Self {
+        Self {
+            methods: Vec::new(),
+            handlers: HashMap::new(),
+            disabled: HashMap::new(),
+        }
+    }
+
+    /// Register a method with the registry.
+    ///
+    /// Registering a name that already exists replaces its handler and
+    /// description, which allows the built-in methods to be overridden.
+    ///
+    /// # Arguments
+    /// * `info` - Method description with name, description and params schema
+    /// * `handler` - Async function that handles the method
+    pub fn register<F, Fut>(&mut self, info: MethodInfo, handler: F)
+    where
+        F: Fn(MethodContext, Option<serde_json::Value>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = MethodResult> + Send + 'static,
+    {
+        let name = info.name.clone();
+        self.methods.retain(|method| method.name != name);
+        self.disabled.remove(&name);
+        self.methods.push(info);
+        self.handlers.insert(name, Box::new(move |ctx, params| Box::pin(handler(ctx, params))));
+    }
+
+    /// Look up the handler for a method.
+    pub fn get(&self, method: &str) -> Option<&MethodHandler> {
+        self.handlers.get(method)
+    }
+
+    /// Remove a method, answering calls to it with `reason` instead of the
+    /// usual "Method not found" message.
+    ///
+    /// A disabled method is no longer listed or advertised, as if it had never
+    /// been registered.
+    ///
+    /// # Arguments
+    /// * `method` - Name of the method to disable
+    /// * `reason` - Message of the -32601 error returned to callers
+    pub fn disable(&mut self, method: &str, reason: impl Into<String>) {
+        self.methods.retain(|info| info.name != method);
+        self.handlers.remove(method);
+        self.disabled.insert(method.to_string(), reason.into());
+    }
+
+    /// The error message for a disabled method, if `method` was disabled.
+    pub fn disabled_reason(&self, method: &str) -> Option<&str> {
+        self.disabled.get(method).map(String::as_str)
+    }
+}
@@ -0,0 +1,306 @@
+//! Startup Self-Test
+//!
+//! With `MCP_SELF_TEST=1`, the server sends a sample request for each of its
+//! JSON-RPC methods through the normal dispatcher before it starts serving,
+//! serializes each response as it would go on the wire, and checks that it
+//! parses back into the shape clients expect. The outcome is logged; with
+//! `MCP_SELF_TEST_ABORT=1` a failure also stops the server from starting.
+//!
+//! This is a smoke test for production containers: it catches a response
+//! serialization regression before the first client does. Requests run in a
+//! throwaway session that is not counted in the metrics, and `tools/call` only
+//! calls the harmless `echo` tool.
+
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+use crate::core::metrics::Metrics;
+use crate::core::router::MethodContext;
+use crate::core::server::{self, MCPRequest};
+use crate::core::session::Session;
+use crate::core::utils;
+
+/// Outcome of a self-test run, by method name.
+#[derive(Debug, Default)]
+pub struct SelfTestReport {
+    /// Methods whose response had the expected shape
+    pub passed: Vec<String>,
+    /// Methods whose response did not, with the reason
+    pub failed: Vec<(String, String)>,
+    /// Registered methods without a sample request, or whose sample could not be built
+    pub skipped: Vec<String>,
+}
+
+impl SelfTestReport {
+    /// Whether every checked method passed.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Check that a result deserializes into `T`.
+type ShapeCheck = fn(serde_json::Value) -> Result<(), String>;
+
+/// Deserialize `value` into `T`, discarding it.
+fn shape<T: DeserializeOwned>(value: serde_json::Value) -> Result<(), String> {
+    serde_json::from_value::<T>(value).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Whether `MCP_SELF_TEST` is enabled.
+pub fn enabled() -> bool {
+    utils::env_flag("MCP_SELF_TEST")
+}
+
+/// Whether a failed self-test should stop the server (`MCP_SELF_TEST_ABORT`).
+pub fn abort_on_failure() -> bool {
+    utils::env_flag("MCP_SELF_TEST_ABORT")
+}
+
+/// Run a sample request for every registered method and check the responses.
+///
+/// # Arguments
+/// * `ctx` - Method context of the server; requests run in a new session of it
+pub async fn run(ctx: &MethodContext) -> SelfTestReport {
+    // A notifier lets resources/subscribe succeed; notifications go nowhere
+    let session = Arc::new(Session::new());
+    session.set_notifier(Arc::new(|_| true));
+    let mut ctx = ctx.with_session(session);
+    // Sample requests are not client traffic, so they are not counted in /metrics
+    ctx.metrics = Arc::new(Metrics::new());
+
+    let first_tool = ctx.tools.tools.iter().map(|tool| tool.name.clone()).min();
+    let has_echo = ctx.tools.get_tool("echo").is_some();
+    let uri = serde_json::json!({ "uri": "self-test://resource" });
+
+    // Run in order: initialize first, as a client would. Samples that need a
+    // tool to exist have no params when it does not, and are skipped
+    let samples: Vec<(&str, Option<serde_json::Value>, ShapeCheck)> = vec![
+        ("initialize", Some(serde_json::json!({
+            "protocolVersion": crate::core::session::SUPPORTED_PROTOCOL_VERSIONS[0],
+            "capabilities": {},
+            "clientInfo": { "name": "self-test", "version": env!("CARGO_PKG_VERSION") }
+        })), shape::<shapes::Initialize>),
+        ("ping", Some(serde_json::json!({})), shape::<shapes::Empty>),
+        ("tools/list", Some(serde_json::json!({})), shape::<shapes::ToolsList>),
+        ("tools/describe", first_tool.map(|name| serde_json::json!({ "name": name })), shape::<shapes::Tool>),
+        ("tools/call", has_echo.then(|| serde_json::json!({
+            "name": "echo",
+            "arguments": { "message": "self-test" }
+        })), shape::<shapes::ToolCall>),
+        ("logging/setLevel", Some(serde_json::json!({ "level": "info" })), shape::<shapes::Empty>),
+        ("resources/subscribe", Some(uri.clone()), shape::<shapes::Empty>),
+        ("resources/unsubscribe", Some(uri), shape::<shapes::Empty>),
+        ("completion/complete", Some(serde_json::json!({
+            "ref": { "type": "ref/prompt", "name": "self-test" },
+            "argument": { "name": "argument", "value": "" }
+        })), shape::<shapes::Completion>),
+        ("capabilities/probe", Some(serde_json::json!({})), shape::<shapes::Probe>),
+        ("server/stats", Some(serde_json::json!({})), shape::<shapes::Stats>),
+    ];
+
+    let mut report = SelfTestReport::default();
+    // Methods registered later, e.g. by an embedding binary, have no sample
+    for method in &ctx.methods.methods {
+        if !samples.iter().any(|(name, _, _)| *name == method.name) {
+            report.skipped.push(method.name.clone());
+        }
+    }
+    for (id, (method, params, check)) in samples.into_iter().enumerate() {
+        // Methods that are not registered (e.g. tools/call in read-only mode) are not checked
+        if ctx.methods.get(method).is_none() {
+            continue;
+        }
+        let Some(params) = params else {
+            report.skipped.push(method.to_string());
+            continue;
+        };
+        match check_method(&ctx, id, method, params, check).await {
+            Ok(()) => report.passed.push(method.to_string()),
+            Err(reason) => report.failed.push((method.to_string(), reason)),
+        }
+    }
+    report
+}
+
+/// Dispatch one sample request and check its serialized response.
+///
+/// # Arguments
+/// * `ctx` - Method context of the self-test session
+/// * `id` - Id of the request
+/// * `method` - Method to call
+/// * `params` - Sample params
+/// * `check` - Check of the response's `result`
+async fn check_method(
+    ctx: &MethodContext,
+    id: usize,
+    method: &str,
+    params: serde_json::Value,
+    check: ShapeCheck,
+) -> Result<(), String> {
+    let request: MCPRequest = serde_json::from_value(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": method,
+        "params": params
+    }))
+    .map_err(|e| format!("invalid sample request: {}", e))?;
+
+    let response = server::dispatch_request(ctx.clone(), request).await
+        .ok_or("no response")?;
+    let wire = serde_json::to_string(&response).map_err(|e| format!("response does not serialize: {}", e))?;
+    let response: shapes::Response = serde_json::from_str(&wire)
+        .map_err(|e| format!("response is not a JSON-RPC response: {}", e))?;
+
+    if response.jsonrpc != "2.0" {
+        return Err(format!("jsonrpc is {:?}", response.jsonrpc));
+    }
+    if response.id != serde_json::json!(id) {
+        return Err(format!("id {} does not match request id {}", response.id, id));
+    }
+    match (response.result, response.error) {
+        (Some(result), None) => check(result).map_err(|e| format!("unexpected result: {}", e)),
+        (None, Some(error)) => Err(format!("error {}: {}", error.code, error.message)),
+        _ => Err("response must have exactly one of result and error".to_string()),
+    }
+}
+
+/// Run the self-test and log its outcome to stderr.
+///
+/// # Returns
+/// Whether every checked method passed.
+pub async fn run_and_log(ctx: &MethodContext) -> bool {
+    let report = run(ctx).await;
+    for (method, reason) in &report.failed {
+        utils::log_stderr!("Self-test failed: {}: {}", method, reason);
+    }
+    utils::log_stderr!(
+        "Self-test {}: {} passed, {} failed, {} skipped{}",
+        if report.is_success() { "passed" } else { "failed" },
+        report.passed.len(),
+        report.failed.len(),
+        report.skipped.len(),
+        if report.skipped.is_empty() { String::new() } else { format!(" ({})", report.skipped.join(", ")) }
+    );
+    report.is_success()
+}
+
+/// Shapes responses must deserialize into.
+///
+/// Only the fields every client relies on are required; others are ignored.
+#[allow(dead_code)] // Fields are only deserialized, to check the shape
+mod shapes {
+    use serde::Deserialize;
+
+    /// A JSON-RPC response envelope
+    #[derive(Deserialize)]
+    pub struct Response {
+        pub jsonrpc: String,
+        pub id: serde_json::Value,
+        pub result: Option<serde_json::Value>,
+        pub error: Option<Error>,
+    }
+
+    /// A JSON-RPC error object
+    #[derive(Deserialize)]
+    pub struct Error {
+        pub code: i64,
+        pub message: String,
+    }
+
+    /// An object result without required members
+    #[derive(Deserialize)]
+    pub struct Empty {}
+
+    /// Result of `initialize`
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Initialize {
+        pub protocol_version: String,
+        pub capabilities: serde_json::Map<String, serde_json::Value>,
+        pub server_info: ServerInfo,
+    }
+
+    /// `serverInfo` of `initialize`
+    #[derive(Deserialize)]
+    pub struct ServerInfo {
+        pub name: String,
+        pub version: String,
+    }
+
+    /// Result of `tools/list`
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ToolsList {
+        pub tools: Vec<Tool>,
+        pub next_cursor: Option<String>,
+    }
+
+    /// A tool, as listed and described
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Tool {
+        pub name: String,
+        pub description: String,
+        pub input_schema: serde_json::Map<String, serde_json::Value>,
+    }
+
+    /// Result of `tools/call`
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ToolCall {
+        pub content: Vec<ContentBlock>,
+        pub is_error: bool,
+    }
+
+    /// A content block of a tool result
+    #[derive(Deserialize)]
+    pub struct ContentBlock {
+        #[serde(rename = "type")]
+        pub kind: String,
+    }
+
+    /// Result of `completion/complete`
+    #[derive(Deserialize)]
+    pub struct Completion {
+        pub completion: CompletionValues,
+    }
+
+    /// Suggestions of `completion/complete`
+    #[derive(Deserialize)]
+    pub struct CompletionValues {
+        pub values: Vec<String>,
+    }
+
+    /// Result of `capabilities/probe`
+    #[derive(Deserialize)]
+    pub struct Probe {
+        pub methods: Vec<Method>,
+        pub capabilities: serde_json::Map<String, serde_json::Value>,
+        pub tools: Vec<String>,
+    }
+
+    /// Result of `server/stats`
+    #[derive(Deserialize)]
+    pub struct Stats {
+        pub requests_total: u64,
+        pub per_method: std::collections::HashMap<String, Count>,
+        pub per_tool: std::collections::HashMap<String, Count>,
+        pub uptime_secs: u64,
+    }
+
+    /// Requests or calls counted by `server/stats`
+    #[derive(Deserialize)]
+    pub struct Count {
+        pub count: u64,
+        pub total_secs: f64,
+    }
+
+    /// A method described by `capabilities/probe`
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Method {
+        pub name: String,
+        pub description: String,
+        pub params_schema: serde_json::Value,
+    }
+}
@@ -1,27 +1,53 @@
-/// MCP Server Implementation
-///
-/// This module contains the core MCP server implementation including:
-/// - JSON-RPC 2.0 request/response structures
-/// - Tool registry for managing available tools
-/// - HTTP server setup with Actix Web
-/// - STDIO server implementation for line-based communication
-/// - Request handlers for MCP protocol methods
+//! MCP Server Implementation
+//!
+//! This module contains the core MCP server implementation including:
+//! - JSON-RPC 2.0 request/response structures
+//! - Tool registry for managing available tools
+//! - HTTP server setup with Actix Web
+//! - STDIO server implementation for line-based communication
+//! - Request handlers for MCP protocol methods
 
 use actix_web::{
     web, App, HttpServer, HttpResponse, Result,
-    middleware::{Compress, Logger, DefaultHeaders},
-    HttpRequest,
+    middleware::DefaultHeaders,
+    HttpMessage, HttpRequest,
+    dev::ServerHandle,
     http,
 };
-use futures_util::{StreamExt, TryStreamExt};
-use bytes::BytesMut;
-use actix_web::web::Bytes;
-use std::time::Duration;
-use tokio::sync::mpsc;
+use futures_util::{FutureExt, Stream, StreamExt, TryStreamExt};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use bytes::{Bytes, BytesMut};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio_util::sync::CancellationToken;
 
+use crate::core::build_info;
+use crate::core::audit::AuditLog;
+use crate::core::cache::ToolCache;
+use crate::core::completion::{CompletionProvider, CompletionRef, CompletionRegistry};
+use crate::core::framing::{self, Framing, Message, DEFAULT_MAX_MESSAGE_BYTES};
+use crate::core::logging::{self, LogLevel};
+use crate::core::metrics::Histogram;
+use crate::core::middleware::{self, BearerAuth, Compression, CompressionConfig, CorsOrigins, DecompressionConfig, RateLimit, RateLimiter, RequestDecompression, RequestLog};
+use crate::core::openapi;
+use crate::core::plugin;
+use crate::core::progress::ProgressReporter;
+use crate::core::router::{MethodContext, MethodInfo, MethodRegistry, MethodResult};
+use crate::core::self_test;
+use crate::core::session::{ClientInfo, HttpSessions, Session, SESSION_HEADER, SUPPORTED_PROTOCOL_VERSIONS};
+use crate::core::sse::{self, SseSessions};
+use crate::core::streaming;
+use crate::core::tls;
+use crate::core::tool_middleware::{TimingMiddleware, ToolMiddleware};
+use crate::core::utils::{self, LogFormat};
+use crate::core::validation;
 use crate::tools;
 
 /// Application state shared across all worker threads in HTTP mode.
@@ -34,6 +60,42 @@ pub struct AppState {
     pub server_name: String,
     /// Server version string as reported in MCP initialize responses
     pub server_version: String,
+    /// Usage instructions for the model, included in initialize responses when set
+    pub instructions: Option<String>,
+}
+
+/// Longest `instructions` string sent to clients, in bytes.
+const MAX_INSTRUCTIONS_BYTES: usize = 4096;
+
+/// Read the server instructions from `MCP_INSTRUCTIONS`.
+///
+/// The value is trimmed, and values longer than 4 KiB are truncated (at a
+/// character boundary) with a warning, so a misconfigured deployment cannot
+/// flood every client's context.
+///
+/// # Returns
+/// The instructions, or `None` if the variable is unset or blank.
+fn server_instructions() -> Option<String> {
+    let instructions = utils::get_env_var("MCP_INSTRUCTIONS", "");
+    let instructions = instructions.trim();
+    if instructions.is_empty() {
+        return None;
+    }
+    
+    if instructions.len() <= MAX_INSTRUCTIONS_BYTES {
+        return Some(instructions.to_string());
+    }
+    
+    let mut end = MAX_INSTRUCTIONS_BYTES;
+    while !instructions.is_char_boundary(end) {
+        end -= 1;
+    }
+    utils::log_stderr!(
+        "Warning: MCP_INSTRUCTIONS is {} bytes; truncated to {} bytes",
+        instructions.len(),
+        end
+    );
+    Some(instructions[..end].trim_end().to_string())
 }
 
 /// JSON-RPC 2.0 request structure for MCP protocol.
@@ -44,7 +106,6 @@ pub struct AppState {
 #[derive(Deserialize, Debug)]
 pub struct MCPRequest {
     /// JSON-RPC version identifier, must be "2.0"
-    #[allow(dead_code)]
     jsonrpc: String,
     /// Request ID for correlating responses. None indicates a notification.
     id: Option<serde_json::Value>,
@@ -72,6 +133,18 @@ pub struct MCPResponse {
     error: Option<MCPError>,
 }
 
+impl MCPResponse {
+    /// Create an error response for the given request id.
+    pub fn error(id: Option<serde_json::Value>, error: MCPError) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
 /// JSON-RPC 2.0 error structure.
 ///
 /// Errors follow the JSON-RPC 2.0 error format with a numeric code, message,
@@ -87,6 +160,48 @@ pub struct MCPError {
     data: Option<serde_json::Value>,
 }
 
+impl MCPError {
+    /// Create an error with the given code and message and no additional data.
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+    
+    /// Attach machine-readable context to the error as its `data` member.
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+/// JSON-RPC 2.0 notification structure.
+///
+/// Notifications are server-originated messages that carry no id and expect
+/// no response (e.g., "notifications/message").
+#[derive(Serialize, Debug)]
+pub struct MCPNotification {
+    /// JSON-RPC version identifier, always "2.0"
+    jsonrpc: String,
+    /// Notification method name
+    method: String,
+    /// Notification parameters
+    params: serde_json::Value,
+}
+
+impl MCPNotification {
+    /// Create a new notification for the given method and parameters.
+    pub fn new(method: &str, params: serde_json::Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+        }
+    }
+}
+
 /// MCP tool definition structure.
 ///
 /// Each tool must have a unique name, description, and JSON schema defining
@@ -99,14 +214,291 @@ pub struct MCPTool {
     pub description: String,
     /// JSON Schema defining the tool's input parameters
     pub input_schema: serde_json::Value,
+    /// Optional JSON Schema describing the tool's structured result.
+    /// Tools that declare one return `structuredContent` from tools/call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
+    /// Whether results may be served from the tool result cache.
+    /// Only set this for tools whose result depends on nothing but their arguments.
+    #[serde(skip)]
+    pub cacheable: bool,
+}
+
+/// Error returned by a tool handler.
+///
+/// Invalid arguments are reported to the client as a JSON-RPC -32602 error,
+/// while execution failures are reported as a tool result with `isError: true`
+/// so the model can see and react to them.
+#[derive(Debug, Clone)]
+pub enum ToolError {
+    /// The arguments were missing, mistyped, or otherwise unusable
+    InvalidArguments(String),
+    /// The tool ran but failed to produce a result
+    ExecutionFailed(String),
+}
+
+impl std::fmt::Display for ToolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToolError::InvalidArguments(msg) => write!(f, "Invalid arguments: {}", msg),
+            ToolError::ExecutionFailed(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<String> for ToolError {
+    /// Plain error strings are treated as execution failures.
+    fn from(msg: String) -> Self {
+        ToolError::ExecutionFailed(msg)
+    }
+}
+
+/// A single item in a tool result's `content` array.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ContentBlock {
+    /// Plain text for the model to read
+    Text { text: String },
+    /// Base64-encoded image data
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    /// Resource contents embedded directly in the result
+    Resource { resource: EmbeddedResource },
+}
+
+impl ContentBlock {
+    /// Create an image block from raw image bytes.
+    ///
+    /// # Arguments
+    /// * `bytes` - Encoded image data (e.g. the contents of a PNG file)
+    /// * `mime_type` - MIME type of the image, such as `image/png`
+    pub fn image(bytes: &[u8], mime_type: impl Into<String>) -> Self {
+        ContentBlock::Image {
+            data: BASE64.encode(bytes),
+            mime_type: mime_type.into(),
+        }
+    }
+
+    /// Check that the block is well-formed before it is sent to the client.
+    ///
+    /// Image blocks must name a MIME type, and embedded resources must carry
+    /// exactly one of `text` or `blob`.
+    fn validate(&self) -> std::result::Result<(), String> {
+        match self {
+            ContentBlock::Text { .. } => Ok(()),
+            ContentBlock::Image { mime_type, .. } if mime_type.is_empty() => {
+                Err("image content block is missing a mimeType".to_string())
+            }
+            ContentBlock::Image { .. } => Ok(()),
+            ContentBlock::Resource { resource } => {
+                if resource.text.is_some() == resource.blob.is_some() {
+                    Err(format!(
+                        "resource content block '{}' must have exactly one of text or blob",
+                        resource.uri
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// Contents of a resource embedded in a tool result.
+///
+/// Textual resources set `text`; binary resources set `blob` to base64 data.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddedResource {
+    /// URI identifying the resource
+    pub uri: String,
+    /// MIME type of the resource contents
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// Text contents of a textual resource
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Base64-encoded contents of a binary resource
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+}
+
+impl EmbeddedResource {
+    /// Create a binary resource from raw bytes.
+    ///
+    /// # Arguments
+    /// * `uri` - URI identifying the resource
+    /// * `mime_type` - MIME type of the contents
+    /// * `bytes` - Raw resource contents, stored base64-encoded
+    pub fn blob(uri: impl Into<String>, mime_type: impl Into<String>, bytes: &[u8]) -> Self {
+        Self {
+            uri: uri.into(),
+            mime_type: Some(mime_type.into()),
+            text: None,
+            blob: Some(BASE64.encode(bytes)),
+        }
+    }
+}
+
+/// Result produced by a tool handler.
+///
+/// Handlers may simply return a JSON value, which converts into a `ToolOutput`
+/// rendered as a single text block holding the serialized value, plus
+/// `structuredContent` when the tool declares an output schema. Handlers that
+/// need more control build a `ToolOutput` explicitly, choosing their own
+/// content blocks and structured content. Tools that only partly succeed can
+/// attach warnings, returned in `_meta.warnings` of a successful result.
+#[derive(Debug, Clone, Default)]
+pub struct ToolOutput {
+    /// Content blocks returned to the client, in order
+    pub content: Vec<ContentBlock>,
+    /// Machine-readable result returned as `structuredContent`
+    pub structured_content: Option<serde_json::Value>,
+    /// Non-fatal problems returned in `_meta.warnings`
+    pub warnings: Vec<String>,
+    /// Plain JSON result, set when the output was converted from a value
+    value: Option<serde_json::Value>,
+}
+
+impl ToolOutput {
+    /// Create an output holding a single text block.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            content: vec![ContentBlock::Text { text: text.into() }],
+            ..Self::default()
+        }
+    }
+
+    /// Append a content block to the output.
+    ///
+    /// # Arguments
+    /// * `block` - Text, image or resource block to add after existing blocks
+    pub fn with_content(mut self, block: ContentBlock) -> Self {
+        self.content.push(block);
+        self
+    }
+
+    /// Attach structured content to the output.
+    ///
+    /// # Arguments
+    /// * `structured` - Machine-readable result, usually a JSON object
+    pub fn with_structured(mut self, structured: serde_json::Value) -> Self {
+        self.structured_content = Some(structured);
+        self
+    }
+
+    /// Add a warning to the output.
+    ///
+    /// # Arguments
+    /// * `warning` - Description of a non-fatal problem, such as one item of a
+    ///   batch that could not be processed
+    pub fn with_warning(mut self, warning: impl Into<String>) -> Self {
+        self.warnings.push(warning.into());
+        self
+    }
+}
+
+impl From<serde_json::Value> for ToolOutput {
+    /// Plain JSON results are rendered as serialized text.
+    fn from(value: serde_json::Value) -> Self {
+        Self {
+            value: Some(value),
+            ..Self::default()
+        }
+    }
+}
+
+/// Everything a tool handler receives for one call.
+///
+/// Besides the validated arguments, the context identifies the calling
+/// client's session and carries the call's cancellation token, progress
+/// reporter and deadline, so handlers need not reach for task-local state.
+/// Tools that only need their arguments can convert the context into them,
+/// and a context holding just arguments can be built from a JSON value.
+#[derive(Clone)]
+pub struct CallContext {
+    /// Validated arguments of the call
+    pub arguments: serde_json::Value,
+    /// Id of the calling client's session, for tools that keep state per
+    /// session. STDIO and Unix socket connections and SSE streams keep one
+    /// session for their whole lifetime, while every plain HTTP request gets
+    /// a new one
+    pub session_id: Option<String>,
+    /// Cancelled when the client cancels the call
+    pub cancellation: CancellationToken,
+    /// Reports progress to the client, if it asked for progress
+    pub progress: ProgressReporter,
+    /// Time by which the client needs the result, from `_meta.deadlineMs`
+    pub deadline: Option<SystemTime>,
+}
+
+impl From<serde_json::Value> for CallContext {
+    /// A context holding only arguments: no session, progress or deadline.
+    fn from(arguments: serde_json::Value) -> Self {
+        Self {
+            arguments,
+            session_id: None,
+            cancellation: CancellationToken::new(),
+            progress: ProgressReporter::default(),
+            deadline: None,
+        }
+    }
+}
+
+impl From<CallContext> for serde_json::Value {
+    /// The call's arguments, for handlers that need nothing else.
+    fn from(context: CallContext) -> Self {
+        context.arguments
+    }
 }
 
 /// Tool handler function type definition.
 ///
-/// Tool handlers are boxed closures that take JSON arguments and
-/// return either a JSON result or an error string. The handler must be
+/// Tool handlers are boxed closures that take the `CallContext` of a call and
+/// return either a `ToolOutput` or a `ToolError`. The handler must be
 /// Send + Sync to work across threads in the HTTP server.
-pub type ToolHandler = Box<dyn Fn(serde_json::Value) -> Result<serde_json::Value, String> + Send + Sync>;
+pub type ToolHandler = Box<dyn Fn(CallContext) -> Result<ToolOutput, ToolError> + Send + Sync>;
+
+/// Future returned by an async tool handler.
+pub type ToolFuture = Pin<Box<dyn Future<Output = Result<ToolOutput, ToolError>> + Send>>;
+
+/// Async tool handler function type definition.
+///
+/// Async handlers return a future instead of blocking, which suits tools that
+/// wait on I/O (network requests, timers) rather than compute. They receive the
+/// same `CallContext` as blocking handlers, whose cancellation token fires if
+/// the client cancels the call so they can stop early; the result of a
+/// cancelled call is discarded either way.
+pub type AsyncToolHandler = Box<dyn Fn(CallContext) -> ToolFuture + Send + Sync>;
+
+/// Stream of output chunks produced by a streaming tool handler.
+pub type ToolStream = Pin<Box<dyn Stream<Item = Result<Bytes, ToolError>> + Send>>;
+
+/// Streaming tool handler function type definition.
+///
+/// Streaming handlers suit tools producing large text output: instead of one
+/// result they return a stream of byte chunks, which together form the text of
+/// the call's single text block. Over HTTP the chunks are sent to the client
+/// as they are produced (see `core::streaming`). Like the other handlers, they
+/// receive the `CallContext` of the call.
+pub type StreamToolHandler = Box<dyn Fn(CallContext) -> ToolStream + Send + Sync>;
+
+/// A registered tool handler: blocking, async or streaming.
+///
+/// Handlers are reference counted so a call can move its handler onto a
+/// blocking thread or task without holding a borrow of the registry.
+#[derive(Clone)]
+pub enum RegisteredHandler {
+    /// Blocking handler, run on tokio's blocking thread pool
+    Sync(Arc<dyn Fn(CallContext) -> Result<ToolOutput, ToolError> + Send + Sync>),
+    /// Async handler, awaited on the calling task
+    Async(Arc<dyn Fn(CallContext) -> ToolFuture + Send + Sync>),
+    /// Streaming handler, whose output is forwarded or collected as it arrives
+    Stream(Arc<dyn Fn(CallContext) -> ToolStream + Send + Sync>),
+}
 
 /// Registry of available MCP tools.
 ///
@@ -116,7 +508,24 @@ pub struct ToolRegistry {
     /// List of all registered tools (for tools/list method)
     pub tools: Vec<MCPTool>,
     /// Map of tool names to their handler functions (for tools/call method)
-    pub handlers: HashMap<String, ToolHandler>,
+    pub handlers: HashMap<String, RegisteredHandler>,
+    /// Middleware run around every tool call, in order
+    pub middleware: Vec<Box<dyn ToolMiddleware>>,
+    /// Results of cacheable tools, sized by `TOOL_CACHE_SIZE`
+    pub cache: ToolCache,
+    /// Whether string arguments are converted to the types their schema
+    /// expects before validation (`MCP_COERCE_ARGS`)
+    pub coerce_arguments: bool,
+    /// Whether integers beyond JavaScript's safe range are returned as
+    /// strings in tool results (`MCP_BIG_INT_AS_STRING`)
+    pub big_int_as_string: bool,
+    /// Log of executed tool calls, opened from `MCP_AUDIT_LOG_PATH`
+    pub audit: Option<AuditLog>,
+    /// Alternative names tools can be called by, mapped to their canonical
+    /// names. Aliases are not listed by tools/list
+    pub aliases: HashMap<String, String>,
+    /// Schemas of the configuration sections tools declared, by tool name
+    pub config_schemas: HashMap<String, serde_json::Value>,
 }
 
 impl ToolRegistry {
@@ -127,6 +536,13 @@ impl ToolRegistry {
         Self {
             tools: Vec::new(),
             handlers: HashMap::new(),
+            middleware: Vec::new(),
+            cache: ToolCache::from_env(),
+            coerce_arguments: utils::env_flag("MCP_COERCE_ARGS"),
+            big_int_as_string: utils::env_flag("MCP_BIG_INT_AS_STRING"),
+            audit: None,
+            aliases: HashMap::new(),
+            config_schemas: HashMap::new(),
         }
     }
 
@@ -134,853 +550,5161 @@ impl ToolRegistry {
     ///
     /// This method adds the tool definition to the tools list and stores
     /// the handler function in the handlers map for later execution.
+    /// Registering a name that already exists replaces that tool, keeping
+    /// its place in the list, so every name is listed once.
     ///
     /// # Arguments
     /// * `tool` - Tool definition with name, description, and input schema
     /// * `handler` - Function that executes the tool when called
     pub fn register(&mut self, tool: MCPTool, handler: ToolHandler) {
+        self.insert(tool, RegisteredHandler::Sync(Arc::from(handler)));
+    }
+
+    /// Register a tool whose handler is async.
+    ///
+    /// # Arguments
+    /// * `tool` - Tool definition with name, description, and input schema
+    /// * `handler` - Async function that executes the tool when called
+    pub fn register_async(&mut self, tool: MCPTool, handler: AsyncToolHandler) {
+        self.insert(tool, RegisteredHandler::Async(Arc::from(handler)));
+    }
+
+    /// Register a tool whose handler streams its output.
+    ///
+    /// Streamed output is plain text, so the tool should not declare an output
+    /// schema, and output templates are not applied to it.
+    ///
+    /// # Arguments
+    /// * `tool` - Tool definition with name, description, and input schema
+    /// * `handler` - Function returning the stream of output chunks for a call
+    pub fn register_stream(&mut self, tool: MCPTool, handler: StreamToolHandler) {
+        self.insert(tool, RegisteredHandler::Stream(Arc::from(handler)));
+    }
+
+    /// Add a tool definition and its handler, replacing any tool of the same name.
+    fn insert(&mut self, tool: MCPTool, handler: RegisteredHandler) {
         let name = tool.name.clone();
-        self.tools.push(tool);
+        match self.tools.iter_mut().find(|existing| existing.name == name) {
+            Some(existing) => {
+                utils::log_stderr!("Tool {} registered twice; replacing the earlier registration", name);
+                *existing = tool;
+            }
+            None => self.tools.push(tool),
+        }
+        // A tool's own name takes precedence over an alias of another tool
+        self.aliases.remove(&name);
         self.handlers.insert(name, handler);
     }
+
+    /// Add middleware to run around every tool call, after any added before it.
+    ///
+    /// # Arguments
+    /// * `middleware` - Hooks to run before and after each call
+    pub fn add_middleware(&mut self, middleware: impl ToolMiddleware + 'static) {
+        self.middleware.push(Box::new(middleware));
+    }
+
+    /// Remove a tool and its handler from the registry.
+    ///
+    /// # Returns
+    /// `true` if the tool was registered
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.tools.retain(|tool| tool.name != name);
+        self.aliases.retain(|_, canonical| canonical != name);
+        self.config_schemas.remove(name);
+        self.handlers.remove(name).is_some()
+    }
+
+    /// Declare the schema of a tool's configuration section.
+    ///
+    /// The tool's section of the configuration file (`tools.<name>`) is
+    /// checked against it at startup, so unknown or mistyped keys are
+    /// reported instead of silently falling back to defaults. The keys the
+    /// server reads for every tool (`timeout_secs`, `output_template`) are
+    /// always accepted.
+    ///
+    /// # Arguments
+    /// * `tool` - Name of the registered tool
+    /// * `schema` - JSON Schema of the section, an object with `properties`
+    pub fn set_config_schema(&mut self, tool: &str, schema: serde_json::Value) {
+        self.config_schemas.insert(tool.to_string(), schema);
+    }
+
+    /// Let a registered tool also be called by another name.
+    ///
+    /// Calls to the alias behave exactly like calls to the canonical name,
+    /// which is the only name listed by tools/list. This keeps clients of a
+    /// renamed tool working.
+    ///
+    /// # Arguments
+    /// * `alias` - Alternative name, e.g. the tool's former name
+    /// * `canonical` - Name the tool is registered under
+    ///
+    /// # Returns
+    /// `false` if `canonical` is not a registered tool, or `alias` already
+    /// names one
+    #[allow(dead_code)] // Used by tools declaring `aliases` in `register_tool!`
+    pub fn add_alias(&mut self, alias: &str, canonical: &str) -> bool {
+        if !self.handlers.contains_key(canonical) || self.handlers.contains_key(alias) {
+            return false;
+        }
+        self.aliases.insert(alias.to_string(), canonical.to_string());
+        true
+    }
+
+    /// Canonical name of the tool called `name`, which may be an alias.
+    ///
+    /// Names that are not aliases are returned unchanged.
+    pub fn resolve_alias<'a>(&'a self, name: &'a str) -> &'a str {
+        self.aliases.get(name).map(String::as_str).unwrap_or(name)
+    }
+
+    /// Look up a registered tool definition by name.
+    pub fn get_tool(&self, name: &str) -> Option<&MCPTool> {
+        self.tools.iter().find(|tool| tool.name == name)
+    }
+}
+
+/// Query parameters accepted by the health endpoint.
+#[derive(Deserialize)]
+struct HealthQuery {
+    /// Also invoke the canary tool (`?deep=true`)
+    #[serde(default)]
+    deep: bool,
+    /// Response format (`?format=json|text|empty`), overriding the `Accept` header
+    format: Option<HealthFormat>,
+}
+
+/// Format of a health check response.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum HealthFormat {
+    /// JSON status object (the default)
+    Json,
+    /// `text/plain` body: `OK`, or `UNHEALTHY` when a deep check fails
+    Text,
+    /// No body: 204 when healthy, 503 otherwise
+    Empty,
+}
+
+impl HealthFormat {
+    /// Format chosen by the request: `?format=` if given, else text if the
+    /// client prefers it (see `prefers_plain_text`), else JSON.
+    fn negotiate(req: &HttpRequest, format: Option<Self>) -> Self {
+        match format {
+            Some(format) => format,
+            None if prefers_plain_text(req) => Self::Text,
+            None => Self::Json,
+        }
+    }
+}
+
+/// Whether `text/plain` ranks above `application/json` in the request's `Accept` header.
+///
+/// Without an `Accept` header, or when it names neither, JSON is preferred.
+fn prefers_plain_text(req: &HttpRequest) -> bool {
+    let Some(accept) = req.get_header::<http::header::Accept>() else {
+        return false;
+    };
+    accept.ranked().iter()
+        .find_map(|mime| match (mime.type_().as_str(), mime.subtype().as_str()) {
+            ("application", "json") | ("*", "*") => Some(false),
+            ("text", "plain") | ("text", "*") => Some(true),
+            _ => None,
+        })
+        .unwrap_or(false)
 }
 
 /// Health check endpoint handler.
 ///
 /// Returns a simple JSON response indicating the server is running.
 /// Used by load balancers and monitoring systems to verify server availability.
-async fn health() -> Result<HttpResponse> {
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "status": "ok",
-        "service": "mcp-server"
-    })))
-}
-
-/// MCP JSON-RPC request handler with metrics tracking.
+/// Monitors that expect a plain `OK` or an empty 204 can ask for them with
+/// `?format=text|empty` or, for text, `Accept: text/plain`.
 ///
-/// This is the main handler for all MCP protocol requests in HTTP mode.
-/// It increments a request counter for monitoring, routes requests to the
-/// appropriate method handler, and returns JSON-RPC 2.0 compliant responses.
+/// With `?deep=true`, the canary tool is also invoked (see `run_canary`), and
+/// the endpoint returns 503 with the error if it fails. This catches a broken
+/// tool registry, configuration or tool dependency that a static check misses.
 ///
 /// # Arguments
-/// * `state` - Application state containing server metadata
-/// * `registry` - Tool registry for accessing available tools
-/// * `counter` - Atomic counter for tracking total requests
-/// * `req` - JSON-RPC request from the client
-async fn mcp_handler_optimized(
-    state: web::Data<AppState>,
-    registry: web::Data<Arc<ToolRegistry>>,
-    counter: web::Data<std::sync::atomic::AtomicU64>,
-    req: web::Json<MCPRequest>,
-) -> Result<HttpResponse> {
-    // Increment request counter using relaxed ordering for performance.
-    // Relaxed ordering is sufficient here since we only need atomicity,
-    // not synchronization with other operations.
-    counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+/// * `req` - The request, whose `Accept` header selects the response format
+/// * `query` - Query string, optionally requesting a deep check or a format
+/// * `ctx` - Shared method context used to invoke the canary tool
+async fn health(req: HttpRequest, query: web::Query<HealthQuery>, ctx: web::Data<MethodContext>) -> Result<HttpResponse> {
+    let format = HealthFormat::negotiate(&req, query.format);
+    if !query.deep {
+        return Ok(health_response(format, true, serde_json::json!({
+            "status": "ok",
+            "service": "mcp-server"
+        })));
+    }
     
-    // Route request to appropriate method handler based on method name
-    let response = match req.method.as_str() {
-        "initialize" => handle_initialize(state, req.id.clone()),
-        "tools/list" => handle_tools_list(registry, req.id.clone()),
-        "tools/call" => handle_tools_call(registry, req.id.clone(), req.params.clone()).await,
-        _ => {
-            // Method not found - return JSON-RPC error
-            MCPResponse {
-                jsonrpc: "2.0".to_string(),
-                id: req.id.clone(),
-                result: None,
-                error: Some(MCPError {
-                    code: -32601, // Method not found
-                    message: format!("Method not found: {}", req.method),
-                    data: None,
-                }),
-            }
+    let tool = utils::get_env_var("HEALTH_CANARY_TOOL", "echo");
+    Ok(match run_canary(&ctx, &tool).await {
+        Ok(()) => health_response(format, true, serde_json::json!({
+            "status": "ok",
+            "service": "mcp-server",
+            "canary": { "tool": tool, "status": "ok" }
+        })),
+        Err(error) => {
+            utils::log_stderr!("Deep health check failed: canary tool '{}': {}", tool, error);
+            health_response(format, false, serde_json::json!({
+                "status": "unhealthy",
+                "service": "mcp-server",
+                "canary": { "tool": tool, "status": "failed", "error": error }
+            }))
         }
+    })
+}
+
+/// Render a health check result in the negotiated format.
+///
+/// # Arguments
+/// * `format` - Response format
+/// * `healthy` - Whether the check passed; failures are 503 in every format
+/// * `body` - JSON status object, sent as is in the JSON format
+fn health_response(format: HealthFormat, healthy: bool, body: serde_json::Value) -> HttpResponse {
+    let mut response = if healthy {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::ServiceUnavailable()
+    };
+    match format {
+        HealthFormat::Json => response.json(body),
+        HealthFormat::Text => response
+            .content_type("text/plain; charset=utf-8")
+            .body(if healthy { "OK" } else { "UNHEALTHY" }),
+        HealthFormat::Empty if healthy => HttpResponse::NoContent().finish(),
+        HealthFormat::Empty => response.finish(),
+    }
+}
+
+/// Invoke the canary tool used by the deep health check.
+///
+/// The tool is called with the JSON object in `HEALTH_CANARY_ARGS`, which
+/// defaults to a fixed message for echo and to `{}` for other tools. The
+/// arguments are validated and the tool runs under its usual timeout, but not
+/// through the tool middleware.
+///
+/// # Arguments
+/// * `ctx` - Method context holding the tool registry
+/// * `tool` - Name of the canary tool (`HEALTH_CANARY_TOOL`, default `echo`)
+///
+/// # Returns
+/// `Err` with a description if the tool could not be called or reported an error.
+async fn run_canary(ctx: &MethodContext, tool: &str) -> std::result::Result<(), String> {
+    let arguments = match std::env::var("HEALTH_CANARY_ARGS") {
+        Ok(args) => serde_json::from_str::<serde_json::Value>(&args)
+            .ok()
+            .filter(|args| args.is_object())
+            .ok_or("HEALTH_CANARY_ARGS is not a JSON object")?,
+        Err(_) if tool == "echo" => serde_json::json!({ "message": "health check" }),
+        Err(_) => serde_json::json!({}),
     };
+    let handler = ctx.tools.handlers.get(tool)
+        .cloned()
+        .ok_or("tool is not registered")?;
+    validate_tool_arguments(&ctx.tools, tool, &arguments).map_err(|e| e.message)?;
     
-    Ok(HttpResponse::Ok().json(response))
+    match run_tool_handler(tool, handler, arguments, CallTimeout::for_call(tool, None), ctx, ProgressReporter::default()).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(e) => Err(e.message),
+    }
 }
 
-/// Metrics endpoint handler for monitoring.
+/// Version endpoint handler.
 ///
-/// Returns the total number of requests processed since server start.
-/// This endpoint can be used by monitoring systems to track server load.
+/// Returns the server name and version together with the build metadata
+/// embedded at compile time, so operators can confirm which build is deployed.
 ///
 /// # Arguments
-/// * `counter` - Atomic counter tracking total requests
-async fn metrics_handler(
-    counter: web::Data<std::sync::atomic::AtomicU64>,
-) -> Result<HttpResponse> {
-    let count = counter.load(std::sync::atomic::Ordering::Relaxed);
+/// * `state` - Application state containing server name and version
+async fn version_info(state: web::Data<AppState>) -> Result<HttpResponse> {
+    let build = build_info::build_info();
     Ok(HttpResponse::Ok().json(serde_json::json!({
-        "requests_total": count,
-        "status": "ok"
+        "name": state.server_name,
+        "version": state.server_version,
+        "git_sha": build.git_sha,
+        "build_timestamp": build.build_timestamp,
+        "rustc_version": build.rustc_version
     })))
 }
 
-/// MCP over Streamable HTTP endpoint.
+/// Readiness of the HTTP server to serve traffic, reported by `/readyz`.
 ///
-/// This endpoint supports MCP protocol over Streamable HTTP (SSE is deprecated).
-/// For GET requests, it establishes a streaming connection. For POST requests, it handles
-/// MCP JSON-RPC requests and streams responses back.
+/// The server starts out not ready and is marked ready at the end of startup,
+/// once the tools are registered and the configuration has loaded. Unlike
+/// `/health`, which only shows the process is alive, readiness tells a load
+/// balancer or orchestrator whether to route requests to this instance.
+pub struct Readiness {
+    /// Set once startup has completed successfully
+    ready: AtomicBool,
+    /// Why the server is not ready, reported while `ready` is false
+    reason: Mutex<String>,
+}
+
+impl Readiness {
+    /// Create a readiness flag in the not-ready state.
+    pub fn new() -> Self {
+        Self {
+            ready: AtomicBool::new(false),
+            reason: Mutex::new("starting up".to_string()),
+        }
+    }
+
+    /// Mark the server ready to serve traffic.
+    pub fn set_ready(&self) {
+        self.ready.store(true, Ordering::Release);
+    }
+
+    /// Mark the server not ready.
+    ///
+    /// # Arguments
+    /// * `reason` - Explanation reported by `/readyz`
+    pub fn set_not_ready(&self, reason: impl Into<String>) {
+        *self.reason.lock().unwrap() = reason.into();
+        self.ready.store(false, Ordering::Release);
+    }
+
+    /// Whether the server is ready, or the reason it is not.
+    pub fn status(&self) -> std::result::Result<(), String> {
+        if self.ready.load(Ordering::Acquire) {
+            Ok(())
+        } else {
+            Err(self.reason.lock().unwrap().clone())
+        }
+    }
+}
+
+impl Default for Readiness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Readiness check endpoint handler.
+///
+/// Returns 200 once startup has completed, and 503 with the reason otherwise.
 ///
 /// # Arguments
-/// * `req` - HTTP request (GET for SSE connection, POST for MCP requests)
-/// * `state` - Application state
-/// * `registry` - Tool registry
-/// * `counter` - Request counter
-async fn mcp_sse_handler(
-    req: HttpRequest,
-    state: web::Data<AppState>,
-    registry: web::Data<Arc<ToolRegistry>>,
-    counter: web::Data<std::sync::atomic::AtomicU64>,
-    body: web::Payload,
-) -> Result<HttpResponse> {
-    use actix_web::http::header;
-    
-    // Handle GET requests - establish streaming connection for StreamableHttp
-    // Note: SSE is deprecated, but StreamableHttp uses the same endpoint
-    if req.method() == "GET" {
-        // StreamableHttp expects a simple streaming connection
-        // Create a minimal streaming response that keeps connection open
-        let (tx, rx) = mpsc::unbounded_channel::<Bytes>();
-        
-        // Send initial connection acknowledgment
-        let init_message = format!("data: {}\n\n", serde_json::json!({
-            "type": "connection",
-            "status": "connected"
-        }));
-        let _ = tx.send(Bytes::from(init_message));
-        
-        // Send periodic keepalive to prevent connection timeout
-        let keepalive_tx = tx.clone();
-        tokio::spawn(async move {
-            tokio::time::sleep(Duration::from_secs(1)).await;
-            let mut interval = tokio::time::interval(Duration::from_secs(30));
-            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
-            loop {
-                interval.tick().await;
-                if keepalive_tx.send(Bytes::from(": keepalive\n\n")).is_err() {
-                    break;
-                }
-            }
-        });
-        
-        // Create streaming response
-        let stream = futures_util::stream::unfold(rx, |mut rx| async move {
-            rx.recv().await.map(|item| (Ok::<Bytes, actix_web::Error>(item), rx))
-        });
-        
-        return Ok(HttpResponse::Ok()
-            .content_type("text/event-stream")
-            .insert_header(header::CacheControl(vec![
-                header::CacheDirective::NoCache,
-                header::CacheDirective::NoStore,
-                header::CacheDirective::MustRevalidate,
-            ]))
-            .insert_header(("x-accel-buffering", "no"))
-            .insert_header(("Access-Control-Allow-Origin", "*"))
-            .insert_header(("Access-Control-Allow-Methods", "GET, POST, OPTIONS, DELETE"))
-            .insert_header(("Access-Control-Allow-Headers", "Content-Type"))
-            .insert_header(("Connection", "keep-alive"))
-            .streaming(stream));
-    }
-    
-    // Handle POST requests - process MCP JSON-RPC requests
-    if req.method() == "POST" {
-        // Parse the request body as JSON
-        let mut payload = BytesMut::new();
-        let mut stream = body.into_stream();
-        
-        while let Some(item) = stream.next().await {
-            let chunk = item.map_err(|e| actix_web::error::ErrorBadRequest(e.to_string()))?;
-            payload.extend_from_slice(&chunk);
+/// * `readiness` - Shared readiness flag
+async fn readyz(readiness: web::Data<Readiness>) -> Result<HttpResponse> {
+    Ok(match readiness.status() {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({ "status": "ready" })),
+        Err(reason) => HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "status": "not ready",
+            "reason": reason
+        })),
+    })
+}
+
+/// Admin endpoint configuration shared across worker threads.
+///
+/// Admin endpoints are disabled unless `MCP_ADMIN_TOKEN` is set, and every
+/// admin request must send that token as `Authorization: Bearer <token>`.
+pub struct AdminControl {
+    /// Token required by admin endpoints; `None` disables them
+    token: Option<String>,
+    /// Handle of the running server, set once it has started
+    server: OnceLock<ServerHandle>,
+}
+
+impl AdminControl {
+    /// Create the admin configuration. `None` disables the admin endpoints.
+    pub fn new(token: Option<String>) -> Self {
+        Self {
+            token,
+            server: OnceLock::new(),
         }
-        
-        // Parse JSON-RPC request
-        let mcp_request: MCPRequest = serde_json::from_slice(&payload)
-            .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid JSON: {}", e)))?;
-        
-        counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        
-        // Process the MCP request
-        let response = match mcp_request.method.as_str() {
-            "initialize" => handle_initialize(state, mcp_request.id.clone()),
-            "tools/list" => handle_tools_list(registry, mcp_request.id.clone()),
-            "tools/call" => handle_tools_call(registry, mcp_request.id.clone(), mcp_request.params.clone()).await,
-            _ => {
-                MCPResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: mcp_request.id.clone(),
-                    result: None,
-                    error: Some(MCPError {
-                        code: -32601,
-                        message: format!("Method not found: {}", mcp_request.method),
-                        data: None,
-                    }),
-                }
-            }
-        };
-        
-        // Format response as SSE event
-        let response_json = serde_json::to_string(&response)
-            .unwrap_or_else(|_| "{}".to_string());
-        let sse_data = format!("data: {}\n\n", response_json);
-        
-        return Ok(HttpResponse::Ok()
-            .content_type("text/event-stream")
-            .insert_header(header::CacheControl(vec![
-                header::CacheDirective::NoCache,
-                header::CacheDirective::NoStore,
-                header::CacheDirective::MustRevalidate,
-            ]))
-            .insert_header(("x-accel-buffering", "no"))
-            .insert_header(("Access-Control-Allow-Origin", "*"))
-            .body(sse_data));
-    }
-    
-    // Handle DELETE requests - StreamableHttp cleanup
-    if req.method() == "DELETE" {
-        // StreamableHttp sends DELETE to close the connection
-        return Ok(HttpResponse::Ok()
-            .insert_header(("Access-Control-Allow-Origin", "*"))
-            .finish());
     }
-    
-    // Handle OPTIONS for CORS
-    if req.method() == "OPTIONS" {
-        return Ok(HttpResponse::Ok()
-            .insert_header(("Access-Control-Allow-Origin", "*"))
-            .insert_header(("Access-Control-Allow-Methods", "GET, POST, OPTIONS, DELETE"))
-            .insert_header(("Access-Control-Allow-Headers", "Content-Type"))
-            .finish());
+
+    /// Record the handle of the running server, used to stop it.
+    pub fn set_server(&self, handle: ServerHandle) {
+        let _ = self.server.set(handle);
     }
-    
-    Err(actix_web::error::ErrorMethodNotAllowed("Method not allowed"))
 }
 
-/// Server-Sent Events endpoint for tools discovery (legacy).
+/// Graceful shutdown endpoint handler.
 ///
-/// Returns a stream of tool information in SSE format. This is kept for backward compatibility.
-/// The main MCP protocol endpoint is now at `/sse` via `mcp_sse_handler`.
+/// Stops accepting new connections and stops the server once in-flight
+/// requests have completed, waiting at most `SHUTDOWN_TIMEOUT_SECS`. Returns
+/// 202 with `{"status": "shutting_down"}`, 401 without the admin token, and
+/// 404 when admin endpoints are disabled.
 ///
 /// # Arguments
-/// * `registry` - Tool registry containing all registered tools
-async fn sse_tools_discovery(
-    registry: web::Data<Arc<ToolRegistry>>,
+/// * `req` - HTTP request carrying the admin token
+/// * `admin` - Admin token and server handle
+/// * `readiness` - Shared readiness flag, cleared so load balancers drain the server
+async fn admin_shutdown(
+    req: HttpRequest,
+    admin: web::Data<AdminControl>,
+    readiness: web::Data<Readiness>,
 ) -> Result<HttpResponse> {
-    use actix_web::http::header;
+    let Some(token) = &admin.token else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({ "error": "admin endpoints are disabled" })));
+    };
+    if !middleware::has_valid_token(req.headers(), token) {
+        return Ok(HttpResponse::Unauthorized()
+            .insert_header((http::header::WWW_AUTHENTICATE, "Bearer"))
+            .json(serde_json::json!({ "error": "missing or invalid admin token" })));
+    }
+    let Some(handle) = admin.server.get().cloned() else {
+        return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({ "error": "server is starting up" })));
+    };
     
-    // Serialize all tools to JSON format matching MCP tools/list response
-    let tools_json: Vec<serde_json::Value> = registry.tools.iter()
-        .map(|tool| serde_json::json!({
-            "name": tool.name,
-            "description": tool.description,
-            "inputSchema": tool.input_schema
-        }))
-        .collect();
+    utils::log_stderr!("Shutdown requested via /admin/shutdown");
+    readiness.set_not_ready("shutting down");
     
-    // Create SSE event data with tools list and count
-    let tools_data = serde_json::json!({
-        "tools": tools_json,
-        "count": tools_json.len()
+    // A graceful stop waits for in-flight requests, this one included,
+    // so it runs in the background rather than being awaited here
+    actix_web::rt::spawn(async move {
+        handle.stop(true).await;
     });
     
-    // Format as SSE event: "data: {json}\n\n"
-    let sse_data = format!(
-        "data: {}\n\n",
-        serde_json::to_string(&tools_data).unwrap_or_else(|_| "{}".to_string())
-    );
-    
-    // Return SSE response with appropriate headers
-    Ok(HttpResponse::Ok()
-        .content_type("text/event-stream")
-        // Disable caching to ensure clients always get fresh data
-        .insert_header(header::CacheControl(vec![
-            header::CacheDirective::NoCache,
-            header::CacheDirective::NoStore,
-            header::CacheDirective::MustRevalidate,
-        ]))
-        // Disable nginx buffering for real-time streaming
-        .insert_header(("x-accel-buffering", "no"))
-        .body(sse_data))
+    Ok(HttpResponse::Accepted().json(serde_json::json!({ "status": "shutting_down" })))
 }
 
-/// Handle MCP initialize method.
-///
-/// The initialize method is the first method called by MCP clients to establish
-/// a connection. It returns the protocol version, server capabilities, and
-/// server information.
+/// Default maximum request body size in HTTP mode (1 MiB).
+const DEFAULT_MAX_REQUEST_BYTES: usize = 1024 * 1024;
+
+/// Maximum accepted request body size in bytes, shared with HTTP handlers
+/// that read their payload manually.
+#[derive(Clone, Copy)]
+pub struct BodyLimit(pub usize);
+
+/// How long a `POST /mcp` or `POST /sse` request waits for an in-flight slot before it is rejected as busy.
+const INFLIGHT_WAIT: Duration = Duration::from_millis(100);
+
+/// Limit on concurrently handled `POST /mcp` and `POST /sse` requests, set by `MAX_INFLIGHT_REQUESTS`.
 ///
-/// # Arguments
-/// * `state` - Application state containing server name and version
-/// * `id` - Request ID from the client
-fn handle_initialize(state: web::Data<AppState>, id: Option<serde_json::Value>) -> MCPResponse {
-    MCPResponse {
-        jsonrpc: "2.0".to_string(),
-        id,
-        result: Some(serde_json::json!({
-            "protocolVersion": "2024-11-05",
-            "capabilities": {
-                "tools": {}
-            },
-            "serverInfo": {
-                "name": state.server_name,
-                "version": state.server_version
-            }
-        })),
-        error: None,
-    }
+/// Shared by every worker, so the limit applies to the whole process.
+pub struct InflightLimit {
+    /// Most requests handled at once, 0 for unlimited
+    max: usize,
+    /// Permits of requests being handled, or None when unlimited
+    permits: Option<tokio::sync::Semaphore>,
 }
 
-/// Handle MCP tools/list method.
-///
-/// Returns a list of all available tools with their names, descriptions,
-/// and input schemas. This allows clients to discover what tools are available
-/// before calling them.
-///
-/// # Arguments
-/// * `registry` - Tool registry containing all registered tools
-/// * `id` - Request ID from the client
-fn handle_tools_list(registry: web::Data<Arc<ToolRegistry>>, id: Option<serde_json::Value>) -> MCPResponse {
-    // Serialize tools with proper MCP protocol field names
-    // inputSchema must be in camelCase per MCP specification
-    let tools_json: Vec<serde_json::Value> = registry.tools.iter()
-        .map(|tool| serde_json::json!({
-            "name": tool.name,
-            "description": tool.description,
-            "inputSchema": tool.input_schema
-        }))
-        .collect();
-    
-    MCPResponse {
-        jsonrpc: "2.0".to_string(),
-        id,
-        result: Some(serde_json::json!({
-            "tools": tools_json
-        })),
-        error: None,
+impl InflightLimit {
+    /// Allow at most `max` requests in flight; 0 means unlimited.
+    pub fn new(max: usize) -> Self {
+        Self {
+            max,
+            permits: (max > 0).then(|| tokio::sync::Semaphore::new(max)),
+        }
+    }
+
+    /// Limit from `MAX_INFLIGHT_REQUESTS` (default 0, unlimited).
+    pub fn from_env() -> Self {
+        Self::new(utils::parse_env_var("MAX_INFLIGHT_REQUESTS", 0usize))
+    }
+
+    /// Most requests handled at once, 0 for unlimited.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Wait up to `INFLIGHT_WAIT` for a slot, held until the permit is dropped.
+    ///
+    /// # Returns
+    /// The permit (None when unlimited), or the -32000 "server busy" error.
+    async fn acquire(&self) -> std::result::Result<Option<tokio::sync::SemaphorePermit<'_>>, MCPError> {
+        let Some(permits) = &self.permits else {
+            return Ok(None);
+        };
+        match tokio::time::timeout(INFLIGHT_WAIT, permits.acquire()).await {
+            Ok(Ok(permit)) => Ok(Some(permit)),
+            _ => Err(MCPError::new(-32000, "server busy")),
+        }
     }
 }
 
-/// Handle MCP tools/call method.
-///
-/// Executes a tool with the provided arguments. The tool name and arguments
-/// are extracted from the params, the tool handler is looked up in the
-/// registry, and the handler is executed. Results or errors are formatted
-/// according to MCP protocol specifications.
-///
-/// # Arguments
-/// * `registry` - Tool registry for looking up tool handlers
-/// * `id` - Request ID from the client
-/// * `params` - Method parameters containing tool name and arguments
-async fn handle_tools_call(
-    registry: web::Data<Arc<ToolRegistry>>,
-    id: Option<serde_json::Value>,
-    params: Option<serde_json::Value>,
-) -> MCPResponse {
-    // Extract tool call parameters from the request
-    let tool_params: serde_json::Value = match params {
-        Some(p) => p,
-        None => {
-            // Missing params - return invalid params error
-            return MCPResponse {
-                jsonrpc: "2.0".to_string(),
-                id,
-                result: None,
-                error: Some(MCPError {
-                    code: -32602, // Invalid params
-                    message: "Invalid params".to_string(),
-                    data: None,
-                }),
-            };
+/// Media types accepted for JSON-RPC bodies unless `MCP_ACCEPTED_CONTENT_TYPES` is set.
+const DEFAULT_ACCEPTED_CONTENT_TYPES: &str = "application/json,application/json-rpc,text/json";
+
+/// Media types accepted in the `Content-Type` of JSON-RPC POST requests.
+#[derive(Clone)]
+pub struct AcceptedContentTypes(Vec<String>);
+
+impl AcceptedContentTypes {
+    /// Read the accepted media types from `MCP_ACCEPTED_CONTENT_TYPES`, a
+    /// comma-separated list defaulting to JSON's usual media types.
+    pub fn from_env() -> Self {
+        let list = utils::get_env_var("MCP_ACCEPTED_CONTENT_TYPES", DEFAULT_ACCEPTED_CONTENT_TYPES);
+        Self(list.split(',')
+            .map(|media_type| media_type.trim().to_ascii_lowercase())
+            .filter(|media_type| !media_type.is_empty())
+            .collect())
+    }
+
+    /// The accepted media types, in configuration order.
+    pub fn media_types(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Check the `Content-Type` of a request.
+    ///
+    /// Requests without one are accepted. Otherwise the media type must be
+    /// accepted, ignoring case, and a `charset` parameter must be UTF-8, the
+    /// only encoding of JSON.
+    ///
+    /// # Returns
+    /// Why the content type is rejected, if it is.
+    fn check(&self, req: &HttpRequest) -> std::result::Result<(), String> {
+        let Some(content_type) = req.headers().get(http::header::CONTENT_TYPE) else {
+            return Ok(());
+        };
+        let content_type = content_type.to_str().unwrap_or_default();
+        let mut parts = content_type.split(';');
+        let media_type = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+        let charset = parts
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+            .map(|(_, value)| value.trim().trim_matches('"').to_ascii_lowercase());
+        
+        if !self.0.contains(&media_type) {
+            return Err(format!("unsupported content type {}; expected one of {}", media_type, self.0.join(", ")));
         }
-    };
-    
-    // Extract tool name from parameters
-    let tool_name = tool_params.get("name")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    
-    // Extract tool arguments, defaulting to empty object if not provided
-    let arguments = tool_params.get("arguments")
-        .cloned()
-        .unwrap_or(serde_json::json!({}));
-    
-    // Look up tool handler in registry
-    if let Some(handler) = registry.handlers.get(tool_name) {
-        // Execute tool handler with provided arguments
-        match handler(arguments) {
-            Ok(result) => {
-                // Tool executed successfully - format as MCP content response
-                MCPResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id,
-                    result: Some(serde_json::json!({
-                        "content": [
-                            {
-                                "type": "text",
-                                "text": serde_json::to_string(&result).unwrap_or_default()
-                            }
-                        ],
-                        "isError": false
-                    })),
-                    error: None,
-                }
+        match charset {
+            Some(charset) if charset != "utf-8" && charset != "utf8" => {
+                Err(format!("unsupported charset {}; JSON-RPC bodies must be UTF-8", charset))
             }
-            Err(e) => {
-                // Tool execution failed - format as MCP error response
-                MCPResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id,
-                    result: Some(serde_json::json!({
-                        "content": [
-                            {
-                                "type": "text",
-                                "text": format!("Error: {}", e)
-                            }
-                        ],
-                        "isError": true
-                    })),
-                    error: None,
-                }
-            }
-        }
-    } else {
-        // Tool not found in registry
-        MCPResponse {
-            jsonrpc: "2.0".to_string(),
-            id,
-            result: None,
-            error: Some(MCPError {
-                code: -32601, // Method not found
-                message: format!("Unknown tool: {}", tool_name),
-                data: None,
-            }),
+            _ => Ok(()),
         }
     }
 }
 
-/// Initialize and register all tools.
+/// Build the JSON-RPC error returned when a request body exceeds the limit.
+///
+/// The id is unknown because the body was never parsed, so it is null.
+pub fn payload_too_large(limit: usize) -> HttpResponse {
+    HttpResponse::PayloadTooLarge().json(MCPResponse::error(
+        None,
+        MCPError::new(
+            -32600, // Invalid Request
+            format!("Request body exceeds maximum size of {} bytes", limit),
+        ),
+    ))
+}
+
+/// Read an HTTP request body, enforcing the configured size limit.
 ///
-/// This function is called during server startup to create the tool registry
-/// and register all available tools. Add new tool registrations here when
-/// implementing additional tools.
+/// Bodies are read manually rather than through `web::Json` so that oversized or
+/// unreadable bodies produce JSON-RPC errors instead of actix's plain-text ones.
+///
+/// # Arguments
+/// * `body` - Raw request payload stream
+/// * `limit` - Maximum accepted body size in bytes
 ///
 /// # Returns
-/// An Arc-wrapped ToolRegistry containing all registered tools and handlers
-pub fn initialize_tools() -> Arc<ToolRegistry> {
-    let mut registry = ToolRegistry::new();
+/// The complete body, or the error response to send back.
+pub async fn read_body(body: web::Payload, limit: usize) -> std::result::Result<BytesMut, HttpResponse> {
+    let mut payload = BytesMut::new();
+    let mut stream = body.into_stream();
     
-    // Register all available tools
-    // Add new tool registrations here following this pattern:
-    // tools::your_tool::register(&mut registry);
-    tools::echo::register(&mut registry);
+    while let Some(item) = stream.next().await {
+        let chunk = item.map_err(|e| {
+            HttpResponse::BadRequest().json(MCPResponse::error(
+                None,
+                MCPError::new(-32600, format!("Failed to read request body: {}", e)),
+            ))
+        })?;
+        if payload.len() + chunk.len() > limit {
+            return Err(payload_too_large(limit));
+        }
+        payload.extend_from_slice(&chunk);
+    }
     
-    Arc::new(registry)
+    Ok(payload)
 }
 
-/// Run the MCP server in HTTP mode.
+/// Parse a JSON-RPC request, producing the error response for invalid input.
 ///
-/// Configures and starts an Actix Web HTTP server with optimized settings
-/// for high-traffic production deployments. The server handles MCP protocol
-/// requests over HTTP/JSON-RPC 2.0.
+/// Shared by every transport so malformed input is reported identically:
+/// - Input that is not valid JSON yields a -32700 Parse error with a null id.
+/// - Valid JSON that is not a JSON-RPC request (e.g. missing `method`, a
+///   `jsonrpc` field other than "2.0", or an `id` that is not a string, number
+///   or null) yields a -32600 Invalid Request error, echoing the request's id
+///   when it is valid.
 ///
 /// # Arguments
-/// * `name` - Server name for MCP protocol responses
-/// * `version` - Server version string
-/// * `host` - Bind address (e.g., "0.0.0.0" for all interfaces)
-/// * `port` - Port number to listen on
-///
-/// # Configuration
-/// The server is configured with:
-/// - Worker threads: Auto-detected from CPU count (max 16)
-/// - Max connections: 10,000 concurrent connections
-/// - Connection rate limit: 1,000 connections per second
-/// - Keep-alive: 30 seconds
-/// - Request timeout: 30 seconds
-/// - Disconnect timeout: 2 seconds
-/// - Shutdown timeout: 10 seconds
-pub async fn run_server_http(name: String, version: String, host: String, port: u16) -> std::io::Result<()> {
-    use std::time::Duration;
-    use std::sync::atomic::AtomicU64;
-    
-    let bind_addr = format!("{}:{}", host, port);
-    
-    // Create application state shared across all worker threads
-    let app_state = web::Data::new(AppState {
-        server_name: name.clone(),
-        server_version: version.clone(),
-    });
+/// * `input` - Raw request bytes (an HTTP body or one STDIO line)
+pub fn parse_request(input: &[u8]) -> std::result::Result<MCPRequest, Box<MCPResponse>> {
+    request_from_value(parse_json(input)?)
+}
+
+/// Parse raw input as JSON, producing a -32700 Parse error response on failure.
+fn parse_json(input: &[u8]) -> std::result::Result<serde_json::Value, Box<MCPResponse>> {
+    serde_json::from_slice(input).map_err(|e| {
+        let error = MCPError::new(-32700, format!("Parse error: {}", e))
+            .with_data(parse_error_location(input, &e));
+        Box::new(MCPResponse::error(None, error))
+    })
+}
+
+/// Interpret a parsed JSON value as a JSON-RPC request (see `parse_request`).
+fn request_from_value(value: serde_json::Value) -> std::result::Result<MCPRequest, Box<MCPResponse>> {
+    // Only echo ids of a valid JSON-RPC type; anything else is reported as null
+    let id = value.get("id")
+        .filter(|id| id.is_string() || id.is_number())
+        .cloned();
     
-    // Initialize tool registry and wrap in Arc for sharing across threads
-    let tool_registry = web::Data::new(initialize_tools());
+    let invalid = |id: Option<serde_json::Value>, reason: String| {
+        Box::new(MCPResponse::error(id, MCPError::new(-32600, format!("Invalid Request: {}", reason))))
+    };
     
-    // Create atomic request counter for metrics endpoint
-    // Using AtomicU64 for lock-free counting across worker threads
-    let request_count = web::Data::new(AtomicU64::new(0));
-    let request_count_clone = request_count.clone();
+    // Objects and arrays are not valid ids
+    if value.get("id").is_some_and(|id| id.is_object() || id.is_array()) {
+        return Err(invalid(None, "id must be a string, number or null".to_string()));
+    }
     
-    // Determine optimal worker thread count
-    // Defaults to CPU count but capped at 16 to avoid excessive context switching
-    // Can be overridden via WORKER_THREADS environment variable
-    let workers = std::env::var("WORKER_THREADS")
-        .ok()
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or_else(|| {
-            (num_cpus::get()).min(16).max(1)
-        });
+    // Checked before deserializing so the reason names the problem rather
+    // than repeating the deserializer's message
+    if !value.is_object() {
+        return Err(invalid(None, "request must be a JSON object".to_string()));
+    }
+    match value.get("method") {
+        None => return Err(invalid(id, "missing method".to_string())),
+        Some(method) if !method.is_string() => return Err(invalid(id, "method must be a string".to_string())),
+        Some(_) => {}
+    }
     
-    // Log server startup information to stderr (not stdout to avoid interfering with JSON-RPC)
-    eprintln!("MCP Server Starting (HTTP mode)");
-    eprintln!("  Name: {}", name);
-    eprintln!("  Version: {}", version);
-    eprintln!("  Bind Address: {}", bind_addr);
-    eprintln!("  Worker Threads: {}", workers);
-    eprintln!("  Max Connections: 10000");
-    eprintln!("  MCP Protocol: JSON-RPC 2.0");
+    let request: MCPRequest = serde_json::from_value(value)
+        .map_err(|e| invalid(id.clone(), e.to_string()))?;
+    if request.jsonrpc != "2.0" {
+        return Err(invalid(id, "jsonrpc must be \"2.0\"".to_string()));
+    }
     
-    // Create and configure HTTP server
-    HttpServer::new(move || {
-        App::new()
-            // Share application state with all routes
-            .app_data(app_state.clone())
-            .app_data(tool_registry.clone())
-            .app_data(request_count_clone.clone())
-            // Enable compression for JSON responses (gzip/brotli)
-            .wrap(Compress::default())
-            // Add security headers to all responses
-            .wrap(
-                DefaultHeaders::new()
-                    .add(("X-Content-Type-Options", "nosniff"))
-                    .add(("X-Frame-Options", "DENY"))
-                    .add(("X-XSS-Protection", "1; mode=block"))
-            )
-            // Configure request logging
-            // Format: %r = request line, %s = status, %Dms = duration in milliseconds
-            .wrap(Logger::new("%r %s %Dms"))
-            // Register route handlers
-            .route("/health", web::get().to(health))
-            .route("/metrics", web::get().to(metrics_handler))
-            // MCP over Streamable HTTP - supports GET (connection), POST (requests), DELETE (cleanup)
-            // Note: SSE is deprecated but this endpoint works for both StreamableHttp and legacy SSE
-            .route("/sse", web::get().to(mcp_sse_handler))
-            .route("/sse", web::post().to(mcp_sse_handler))
-            .route("/sse", web::method(http::Method::OPTIONS).to(mcp_sse_handler))
-            .route("/sse", web::method(http::Method::DELETE).to(mcp_sse_handler))
-            // Legacy tools discovery endpoint
-            .route("/tools/sse", web::get().to(sse_tools_discovery))
-            // Standard MCP JSON-RPC endpoint
-            .route("/mcp", web::post().to(mcp_handler_optimized))
-            .route("/", web::post().to(mcp_handler_optimized))
-            .route("/", web::get().to(health))
-    })
-    .workers(workers)
-    // Connection limits for high-traffic scenarios
-    .max_connections(10000)
-    .max_connection_rate(1000)
-    // Timeout configurations to prevent resource exhaustion
-    .keep_alive(Duration::from_secs(30))
-    .client_request_timeout(Duration::from_secs(30))
-    .client_disconnect_timeout(Duration::from_secs(2))
-    // Graceful shutdown timeout
-    .shutdown_timeout(10)
-    .bind(&bind_addr)?
-    .run()
-    .await
+    Ok(request)
 }
 
-/// Run the MCP server in STDIO mode.
+/// Default maximum number of requests in a JSON-RPC batch.
+const DEFAULT_MAX_BATCH_SIZE: usize = 100;
+
+/// Maximum number of requests in a batch (`MAX_BATCH_SIZE`), read once.
+static MAX_BATCH_SIZE: OnceLock<usize> = OnceLock::new();
+
+/// Get the maximum number of requests accepted in one batch.
+fn max_batch_size() -> usize {
+    *MAX_BATCH_SIZE.get_or_init(|| utils::parse_env_var("MAX_BATCH_SIZE", DEFAULT_MAX_BATCH_SIZE))
+}
+
+/// A JSON-RPC message from the client: one request or a batch of them.
+pub enum IncomingMessage {
+    /// A single request or notification
+    Single(MCPRequest),
+    /// A batch, with each element parsed or the error response it produced
+    Batch(Vec<std::result::Result<MCPRequest, Box<MCPResponse>>>),
+}
+
+/// Parse a JSON-RPC message that may be a batch.
 ///
-/// Implements MCP protocol over standard input/output for compatibility with
-/// MCP Inspector and local development. The server reads JSON-RPC requests
-/// line-by-line from stdin and writes responses to stdout. All logging goes
-/// to stderr to avoid interfering with the JSON-RPC protocol stream.
+/// A JSON array is a batch. An empty batch, or one with more than
+/// `MAX_BATCH_SIZE` elements (default 100), is rejected as a whole with a
+/// single -32600 error before any element is looked at. Invalid elements of an
+/// accepted batch are answered individually. Anything else is parsed as a
+/// single request with `parse_request`.
 ///
 /// # Arguments
-/// * `name` - Server name for MCP protocol responses
-/// * `version` - Server version string
-///
-/// # Implementation Details
-/// - Uses buffered I/O with 8KB buffers for optimal throughput
-/// - Processes requests synchronously (one at a time)
-/// - Skips notifications (requests without IDs)
-/// - Flushes after each response for low latency
-pub async fn run_server_stdio(name: String, version: String) -> std::io::Result<()> {
-    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
-    
-    // Log startup information to stderr (not stdout to avoid interfering with JSON-RPC)
-    eprintln!("MCP Server Starting (STDIO mode)");
-    eprintln!("  Name: {}", name);
-    eprintln!("  Version: {}", version);
-    eprintln!("  MCP Protocol: JSON-RPC 2.0");
+/// * `input` - Raw message bytes (an HTTP body or one STDIO message)
+pub fn parse_message(input: &[u8]) -> std::result::Result<IncomingMessage, Box<MCPResponse>> {
+    let value = parse_json(input)?;
+    let serde_json::Value::Array(items) = value else {
+        return request_from_value(value).map(IncomingMessage::Single);
+    };
     
-    // Initialize tool registry and application state
-    let tool_registry = Arc::new(initialize_tools());
-    let app_state = AppState {
-        server_name: name,
-        server_version: version,
+    let invalid = |reason: String| {
+        Box::new(MCPResponse::error(None, MCPError::new(-32600, format!("Invalid Request: {}", reason))))
     };
+    let max = max_batch_size();
+    if items.is_empty() {
+        return Err(invalid("empty batch".to_string()));
+    }
+    if items.len() > max {
+        return Err(invalid(format!(
+            "batch of {} requests exceeds the maximum of {}",
+            items.len(),
+            max
+        )));
+    }
     
-    // Set up buffered I/O for optimal performance
-    // 8KB buffer size balances memory usage with I/O efficiency
-    let stdin = tokio::io::stdin();
-    let mut stdin = BufReader::with_capacity(8192, stdin).lines();
-    let stdout = tokio::io::stdout();
-    let mut stdout = BufWriter::with_capacity(8192, stdout);
+    Ok(IncomingMessage::Batch(items.into_iter().map(request_from_value).collect()))
+}
+
+/// Location of a JSON syntax error, as `{line, column, offset}`.
+///
+/// Line and column come from serde (both 1-based); `offset` is the 0-based
+/// byte offset into the input they point at.
+fn parse_error_location(input: &[u8], error: &serde_json::Error) -> serde_json::Value {
+    // Byte offset of the start of the error's line, then the column within it
+    let line_start = match error.line() {
+        0 | 1 => 0,
+        line => input.iter()
+            .enumerate()
+            .filter(|&(_, &byte)| byte == b'\n')
+            .nth(line - 2)
+            .map_or(input.len(), |(i, _)| i + 1),
+    };
+    let offset = (line_start + error.column().saturating_sub(1)).min(input.len());
     
-    // Main request processing loop
-    // Reads one line at a time from stdin, processes JSON-RPC requests
-    while let Some(line) = stdin.next_line().await? {
-        // Skip empty lines
-        if line.trim().is_empty() {
-            continue;
+    serde_json::json!({
+        "line": error.line(),
+        "column": error.column(),
+        "offset": offset
+    })
+}
+
+/// MCP JSON-RPC request handler with metrics tracking.
+///
+/// This is the main handler for all MCP protocol requests in HTTP mode.
+/// It increments a request counter for monitoring, routes requests to the
+/// appropriate method handler, and returns JSON-RPC 2.0 compliant responses.
+///
+/// # Arguments
+/// * `req` - HTTP request, whose content type is checked
+/// * `ctx` - Shared method context (server metadata, tools and methods)
+/// * `counter` - Atomic counter for tracking total requests
+/// * `limit` - Maximum accepted request body size
+/// * `content_types` - Accepted request content types
+/// * `inflight` - Limit on concurrently handled requests
+/// * `sessions` - Sessions of clients that initialized over HTTP
+/// * `body` - Raw request body containing the JSON-RPC request
+#[allow(clippy::too_many_arguments)]
+async fn mcp_handler_optimized(
+    req: HttpRequest,
+    ctx: web::Data<MethodContext>,
+    counter: web::Data<std::sync::atomic::AtomicU64>,
+    limit: web::Data<BodyLimit>,
+    content_types: web::Data<AcceptedContentTypes>,
+    inflight: web::Data<InflightLimit>,
+    sessions: web::Data<HttpSessions>,
+    body: web::Payload,
+) -> Result<HttpResponse> {
+    let post = PostedMessage { req, ctx, counter, limit, content_types, inflight, sessions, body };
+    Ok(post.serve(ResponseFormat::Json).await)
+}
+
+/// How the response to a POSTed JSON-RPC message is sent.
+#[derive(Clone, Copy)]
+enum ResponseFormat {
+    /// A JSON body, with streaming tool results sent as a chunked body (`/mcp`)
+    Json,
+    /// A single SSE event holding the JSON-RPC response (`POST /sse`)
+    Event,
+}
+
+impl ResponseFormat {
+    /// Build the 200 response carrying `body`.
+    fn respond<T: Serialize>(self, body: &T) -> HttpResponse {
+        use actix_web::http::header;
+        
+        match self {
+            ResponseFormat::Json => HttpResponse::Ok().json(body),
+            ResponseFormat::Event => {
+                let data = serde_json::to_string(body).unwrap_or_else(|_| "{}".to_string());
+                HttpResponse::Ok()
+                    .content_type("text/event-stream")
+                    .insert_header(header::CacheControl(vec![
+                        header::CacheDirective::NoCache,
+                        header::CacheDirective::NoStore,
+                        header::CacheDirective::MustRevalidate,
+                    ]))
+                    .insert_header(("x-accel-buffering", "no"))
+                    .body(format!("data: {}\n\n", data))
+            }
         }
+    }
+}
+
+/// A JSON-RPC message POSTed to `/mcp` or `/sse`, with what is needed to serve it.
+///
+/// Both endpoints handle a POST the same way, so content type checks, the
+/// in-flight limit, deadline headers and batches apply alike; only the shape
+/// of a successful response differs (see `ResponseFormat`).
+struct PostedMessage {
+    req: HttpRequest,
+    ctx: web::Data<MethodContext>,
+    counter: web::Data<std::sync::atomic::AtomicU64>,
+    limit: web::Data<BodyLimit>,
+    content_types: web::Data<AcceptedContentTypes>,
+    inflight: web::Data<InflightLimit>,
+    sessions: web::Data<HttpSessions>,
+    body: web::Payload,
+}
+
+impl PostedMessage {
+    /// Read, parse and dispatch the message, answering in `format`.
+    async fn serve(self, format: ResponseFormat) -> HttpResponse {
+        let PostedMessage { req, ctx, counter, limit, content_types, inflight, sessions, body } = self;
         
-        // Parse JSON-RPC request from input line
-        let request: Result<MCPRequest, _> = serde_json::from_str(&line);
-        match request {
-            Ok(req) => {
-                // Skip notifications (requests without ID)
-                // Notifications are one-way messages that don't require responses
-                if req.id.is_none() {
-                    // Handle specific notifications if needed
-                    if req.method == "notifications/initialized" {
-                        // Client has finished initialization - acknowledge silently
-                        continue;
-                    }
-                    continue;
-                }
-                
-                // Process request and generate response based on method
-                let response = match req.method.as_str() {
-                    "initialize" => handle_initialize_stdio(&app_state, req.id.clone()),
-                    "tools/list" => handle_tools_list_stdio(&tool_registry, req.id.clone()),
-                    "tools/call" => {
-                        handle_tools_call_stdio(&tool_registry, req.id.clone(), req.params.clone())
-                    }
-                    _ => {
-                        // Unknown method - return method not found error
-                        MCPResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: req.id.clone(),
-                            result: None,
-                            error: Some(MCPError {
-                                code: -32601, // Method not found
-                                message: format!("Method not found: {}", req.method),
-                                data: None,
-                            }),
-                        }
-                    }
-                };
-                
-                // Serialize response to JSON string
-                let response_json = match serde_json::to_string(&response) {
-                    Ok(json) => json,
-                    Err(e) => {
-                        // Serialization error - log and skip this response
-                        eprintln!("Error serializing response: {}", e);
-                        continue;
-                    }
-                };
-                
-                // Write response to stdout (buffered)
-                // Each response must be on a single line followed by newline
-                if let Err(e) = stdout.write_all(response_json.as_bytes()).await {
-                    eprintln!("Error writing to stdout: {}", e);
-                    break;
-                }
-                if let Err(e) = stdout.write_all(b"\n").await {
-                    eprintln!("Error writing newline: {}", e);
-                    break;
-                }
-                // Flush after each response for low latency
-                // This ensures responses are sent immediately rather than waiting for buffer fill
-                if let Err(e) = stdout.flush().await {
-                    eprintln!("Error flushing stdout: {}", e);
-                    break;
-                }
+        // Increment request counter using relaxed ordering for performance.
+        // Relaxed ordering is sufficient here since we only need atomicity,
+        // not synchronization with other operations.
+        counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        
+        // Held until the response is built; a streamed tool result keeps running after
+        let _permit = match inflight.acquire().await {
+            Ok(permit) => permit,
+            Err(error) => {
+                return HttpResponse::ServiceUnavailable()
+                    .insert_header((http::header::RETRY_AFTER, "1"))
+                    .json(MCPResponse::error(None, error));
             }
-            Err(e) => {
-                // Invalid JSON-RPC request - attempt to extract ID for error response
-                eprintln!("Parse error: {}", e);
-                // Try to parse as generic JSON to extract ID if present
-                if let Ok(partial) = serde_json::from_str::<serde_json::Value>(&line) {
-                    if let Some(id) = partial.get("id") {
-                        // Send parse error response if we can extract the ID
-                        let error_response = MCPResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: Some(id.clone()),
-                            result: None,
-                            error: Some(MCPError {
-                                code: -32700, // Parse error
-                                message: format!("Parse error: {}", e),
-                                data: None,
-                            }),
-                        };
-                        if let Ok(response_json) = serde_json::to_string(&error_response) {
-                            let _ = stdout.write_all(response_json.as_bytes()).await;
-                            let _ = stdout.write_all(b"\n").await;
-                            let _ = stdout.flush().await;
-                        }
-                    }
+        };
+        
+        // Reject bodies that are not declared as UTF-8 JSON
+        if let Err(reason) = content_types.check(&req) {
+            return HttpResponse::UnsupportedMediaType().json(MCPResponse::error(
+                None,
+                MCPError::new(-32600, format!("Invalid Request: {}", reason)),
+            ));
+        }
+        
+        // Read and parse the body, answering malformed input with a JSON-RPC error
+        let payload = match read_body(body, limit.0).await {
+            Ok(payload) => payload,
+            Err(response) => return response,
+        };
+        let mut message = match parse_message(&payload) {
+            Ok(message) => message,
+            Err(response) => return HttpResponse::BadRequest().json(response),
+        };
+        
+        // A deadline header applies to every request of the message
+        if let Some(header) = req.headers().get(DEADLINE_HEADER) {
+            let Some(deadline_ms) = header.to_str().ok().and_then(|v| v.trim().parse::<u64>().ok()) else {
+                return HttpResponse::BadRequest().json(MCPResponse::error(
+                    None,
+                    MCPError::new(-32600, format!("Invalid Request: {} must be a Unix time in milliseconds", DEADLINE_HEADER)),
+                ));
+            };
+            match &mut message {
+                IncomingMessage::Single(req) => apply_deadline_header(req, deadline_ms),
+                IncomingMessage::Batch(items) => items.iter_mut()
+                    .filter_map(|item| item.as_mut().ok())
+                    .for_each(|req| apply_deadline_header(req, deadline_ms)),
+            }
+        }
+        
+        // Requests naming a session with the session header run in it; others are
+        // stateless, so each one (or batch) runs in a fresh session. Only calls
+        // running concurrently in one session can be cancelled, so HTTP clients
+        // usually cancel over an SSE session instead
+        let resumed = match req.headers().get(SESSION_HEADER) {
+            Some(header) => match header.to_str().ok().and_then(|id| sessions.get(id)) {
+                Some(session) => Some(session),
+                None => return unknown_http_session(),
+            },
+            None => None,
+        };
+        let is_new = resumed.is_none();
+        let session = resumed.unwrap_or_else(|| Arc::new(Session::new()));
+        let ctx = ctx.with_session(session.clone());
+        let response = match message {
+            IncomingMessage::Single(req) => {
+                // An SSE event holds a whole message, so only JSON responses stream
+                let streamed = match format {
+                    ResponseFormat::Json => stream_tool_call(&ctx, &req),
+                    ResponseFormat::Event => None,
+                };
+                match streamed {
+                    Some(response) => Some(response),
+                    None => dispatch_request(ctx, req).await
+                        .map(|response| format.respond(&response)),
                 }
             }
+            IncomingMessage::Batch(items) => dispatch_batch(ctx, items).await
+                .map(|responses| format.respond(&responses)),
+        };
+        // Notifications (and batches of only notifications) are accepted without a body
+        let mut response = response.unwrap_or_else(|| HttpResponse::Accepted().finish());
+        
+        // A fresh session that initialized is kept for the requests that name it
+        if is_new && session.is_initialized()
+            && let Ok(id) = http::header::HeaderValue::from_str(&session.id)
+        {
+            sessions.insert(session);
+            response.headers_mut().insert(http::header::HeaderName::from_static("mcp-session-id"), id);
         }
+        response
     }
-    
-    Ok(())
 }
 
-/// Handle MCP initialize method in STDIO mode.
+/// Methods callable over `GET /mcp` by default; none of them changes state.
+const GET_SAFE_METHODS: [&str; 5] = ["ping", "tools/list", "tools/describe", "capabilities/probe", "completion/complete"];
+
+/// Whether `GET /mcp` may also call tools, set by `MCP_HTTP_GET_TOOL_CALLS`.
+#[derive(Clone, Copy)]
+pub struct GetToolCalls(pub bool);
+
+/// JSON-RPC over HTTP GET, for clients that cannot send POST requests.
+///
+/// The request is built from query parameters and dispatched like a POSTed
+/// one, returning the normal JSON-RPC response:
+/// - `method` - Method name (required)
+/// - `id` - Request id, a number if it parses as one (default 1)
+/// - `params` - URL-encoded JSON object of params (default `{}`)
+/// - `name` - Sets `params.name`, the tool of `tools/call` and `tools/describe`
+/// - `args` - URL-encoded JSON object setting `params.arguments`
 ///
-/// Same functionality as HTTP mode but takes a reference to AppState instead
-/// of web::Data wrapper.
+/// Only the read-only `GET_SAFE_METHODS` are accepted, plus `tools/call` when
+/// `MCP_HTTP_GET_TOOL_CALLS` is set; other methods get a 405.
 ///
 /// # Arguments
-/// * `state` - Application state containing server name and version
-/// * `id` - Request ID from the client
-fn handle_initialize_stdio(state: &AppState, id: Option<serde_json::Value>) -> MCPResponse {
-    MCPResponse {
-        jsonrpc: "2.0".to_string(),
-        id,
-        result: Some(serde_json::json!({
-            "protocolVersion": "2024-11-05",
-            "capabilities": {
-                "tools": {}
-            },
-            "serverInfo": {
-                "name": state.server_name,
-                "version": state.server_version
-            }
-        })),
-        error: None,
+/// * `ctx` - Shared method context
+/// * `counter` - Request counter
+/// * `tool_calls` - Whether tools/call is allowed
+/// * `query` - Query parameters of the request
+async fn mcp_get_handler(
+    ctx: web::Data<MethodContext>,
+    counter: web::Data<std::sync::atomic::AtomicU64>,
+    tool_calls: web::Data<GetToolCalls>,
+    query: web::Query<HashMap<String, String>>,
+) -> Result<HttpResponse> {
+    counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    
+    let id = match query.get("id") {
+        Some(id) => id.parse::<i64>().map_or_else(|_| serde_json::json!(id), |id| serde_json::json!(id)),
+        None => serde_json::json!(1),
+    };
+    let invalid = |status: http::StatusCode, code: i32, message: String| {
+        HttpResponse::build(status).json(MCPResponse::error(Some(id.clone()), MCPError::new(code, message)))
+    };
+    
+    let Some(method) = query.get("method") else {
+        return Ok(invalid(http::StatusCode::BAD_REQUEST, -32600, "Invalid Request: missing method".to_string()));
+    };
+    let allowed = GET_SAFE_METHODS.contains(&method.as_str()) || (method == "tools/call" && tool_calls.0);
+    if !allowed {
+        return Ok(invalid(
+            http::StatusCode::METHOD_NOT_ALLOWED,
+            -32600,
+            format!("Invalid Request: method {} is not allowed over GET; use POST", method),
+        ));
+    }
+    
+    // Params from `params`, with `name` and `args` as shorthands for tool methods
+    let parse_object = |key: &str| match query.get(key) {
+        Some(raw) => serde_json::from_str::<serde_json::Value>(raw).ok()
+            .filter(|value| value.is_object())
+            .map(Some)
+            .ok_or_else(|| format!("Parse error: {} is not a JSON object", key)),
+        None => Ok(None),
+    };
+    let (params, args) = match (parse_object("params"), parse_object("args")) {
+        (Ok(params), Ok(args)) => (params, args),
+        (Err(message), _) | (_, Err(message)) => return Ok(invalid(http::StatusCode::BAD_REQUEST, -32700, message)),
+    };
+    let mut params = params.unwrap_or_else(|| serde_json::json!({}));
+    if let Some(name) = query.get("name") {
+        params["name"] = serde_json::json!(name);
     }
+    if let Some(args) = args {
+        params["arguments"] = args;
+    }
+    
+    let req = MCPRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(id),
+        method: method.clone(),
+        params: Some(params),
+    };
+    let ctx = ctx.with_session(Arc::new(Session::new()));
+    Ok(match dispatch_request(ctx, req).await {
+        Some(response) => HttpResponse::Ok().json(response),
+        None => HttpResponse::Accepted().finish(),
+    })
 }
 
-/// Handle MCP tools/list method in STDIO mode.
+/// Serve a `tools/call` of a streaming tool with a chunked response body.
 ///
-/// Serializes tools with proper field names (inputSchema in camelCase) to match
-/// MCP protocol specification. Same functionality as HTTP mode but takes a
-/// reference to Arc<ToolRegistry> instead of web::Data wrapper.
+/// Only plain HTTP requests stream. Calls that validate their arguments only,
+/// ask for a raw result or fail argument validation are left to
+/// `handle_tools_call`, as are other methods and tools, in which case this
+/// returns None.
 ///
 /// # Arguments
-/// * `registry` - Tool registry containing all registered tools
-/// * `id` - Request ID from the client
-fn handle_tools_list_stdio(registry: &Arc<ToolRegistry>, id: Option<serde_json::Value>) -> MCPResponse {
-    // Serialize tools with proper MCP protocol field names
-    // inputSchema must be in camelCase per MCP specification
-    let tools_json: Vec<serde_json::Value> = registry.tools.iter()
-        .map(|tool| serde_json::json!({
-            "name": tool.name,
-            "description": tool.description,
-            "inputSchema": tool.input_schema
-        }))
-        .collect();
+/// * `ctx` - Method context of the request
+/// * `req` - JSON-RPC request from the client
+fn stream_tool_call(ctx: &MethodContext, req: &MCPRequest) -> Option<HttpResponse> {
+    // Disabled tools/call (read-only mode) and expired deadlines are rejected
+    // by the usual dispatch
+    if req.method != "tools/call" || ctx.methods.get("tools/call").is_none() || deadline_passed(req.params.as_ref()) {
+        return None;
+    }
+    let id = req.id.clone()?;
+    let params = req.params.as_ref()?;
+    let tool_name = ctx.tools.resolve_alias(params.get("name")?.as_str()?).to_string();
+    let Some(RegisteredHandler::Stream(handler)) = ctx.tools.handlers.get(&tool_name) else {
+        return None;
+    };
+    let flag = |value: Option<&serde_json::Value>| value.and_then(|v| v.as_bool()).unwrap_or(false);
+    if flag(params.get("validateOnly")) || flag(params.get("_meta").and_then(|meta| meta.get("rawResult"))) {
+        return None;
+    }
+    // Non-object arguments are rejected by the usual dispatch
+    let mut arguments = tool_arguments(params).ok()?;
+    coerce_tool_arguments(&ctx.tools, &tool_name, &mut arguments);
+    let warnings = validate_tool_arguments(&ctx.tools, &tool_name, &arguments).ok()?;
     
-    MCPResponse {
-        jsonrpc: "2.0".to_string(),
-        id,
-        result: Some(serde_json::json!({
-            "tools": tools_json
-        })),
-        error: None,
+    // A failing `before` hook rejects the call with an ordinary response
+    let started = Instant::now();
+    if let Some(e) = rejected_by_middleware(&ctx.tools, &tool_name, &arguments) {
+        let elapsed = started.elapsed();
+        finish_tool_call(ctx, &tool_name, Some(&arguments), &Ok(Err(e.clone())), elapsed);
+        ctx.metrics.record_method(&req.method, elapsed);
+        let response = match tool_failure(ctx, &tool_name, e) {
+            Ok(result) => MCPResponse { jsonrpc: "2.0".to_string(), id: Some(id), result: Some(result), error: None },
+            Err(error) => MCPResponse::error(Some(id), error),
+        };
+        return Some(HttpResponse::Ok().json(response));
     }
+    
+    // The handler is called from the body, so a panic creating the stream is
+    // reported like one producing a chunk.
+    // Plain HTTP cannot carry notifications, so progress reports are dropped.
+    let audit_arguments = ctx.tools.audit.as_ref().map(|_| arguments.clone());
+    let cancellation = CancellationToken::new();
+    let timeout = CallTimeout::for_call(&tool_name, request_deadline(Some(params)));
+    let call = CallContext {
+        arguments,
+        session_id: Some(ctx.session.id.clone()),
+        cancellation: cancellation.clone(),
+        progress: ProgressReporter::default(),
+        deadline: timeout.deadline,
+    };
+    let handler = handler.clone();
+    let stream: ToolStream = Box::pin(futures_util::stream::once(async move { handler(call) }).flatten());
+    
+    // The `after` hooks run once the stream ends; the output they see has no content
+    let body_tool_name = tool_name.clone();
+    let ctx = ctx.clone();
+    let on_finish: streaming::FinishHook = Box::new(move |outcome, elapsed| {
+        let outcome = Ok(outcome.map(|()| ToolOutput::default()));
+        finish_tool_call(&ctx, &tool_name, audit_arguments.as_ref(), &outcome, elapsed);
+        // The call bypassed `dispatch_request`, so its method is timed here too
+        ctx.metrics.record_method("tools/call", elapsed);
+    });
+    let body = streaming::response_body(&body_tool_name, &id, stream, warnings, cancellation, timeout, on_finish);
+    Some(HttpResponse::Ok().content_type("application/json").streaming(body))
 }
 
-/// Handle MCP tools/call method in STDIO mode.
+/// Route an MCP request to its registered method handler.
+///
+/// Shared by every transport (`/mcp`, `/sse`, SSE sessions and STDIO) so that
+/// method routing stays identical regardless of how a request arrived.
 ///
-/// Executes a tool synchronously (STDIO mode processes one request at a time).
-/// Same functionality as HTTP mode but takes a reference to Arc<ToolRegistry>
-/// instead of web::Data wrapper and is synchronous.
+/// While a request is being handled it is tracked in the session so that a
+/// `notifications/cancelled` from the client can abort it. Notifications are
+/// handled here too, and never produce a response.
 ///
 /// # Arguments
-/// * `registry` - Tool registry for looking up tool handlers
-/// * `id` - Request ID from the client
-/// * `params` - Method parameters containing tool name and arguments
-fn handle_tools_call_stdio(
-    registry: &Arc<ToolRegistry>,
-    id: Option<serde_json::Value>,
-    params: Option<serde_json::Value>,
-) -> MCPResponse {
-    // Extract tool call parameters from the request
-    let tool_params: serde_json::Value = match params {
-        Some(p) => p,
+/// * `ctx` - Shared method context (server metadata, tools and methods)
+/// * `req` - JSON-RPC request from the client
+///
+/// # Returns
+/// The response to send, or None for notifications and cancelled requests.
+pub async fn dispatch_request(ctx: MethodContext, req: MCPRequest) -> Option<MCPResponse> {
+    let id = match req.id {
+        Some(id) => id,
         None => {
-            // Missing params - return invalid params error
-            return MCPResponse {
-                jsonrpc: "2.0".to_string(),
-                id,
-                result: None,
-                error: Some(MCPError {
-                    code: -32602, // Invalid params
-                    message: "Invalid params".to_string(),
-                    data: None,
-                }),
-            };
+            dispatch_notification(&ctx, &req.method, req.params);
+            return None;
         }
     };
     
-    // Extract tool name from parameters
-    let tool_name = tool_params.get("name")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
+    // A request whose deadline passed before it arrived is not worth starting
+    if deadline_passed(req.params.as_ref()) {
+        return Some(MCPResponse::error(Some(id), MCPError::new(-32000, "deadline exceeded")));
+    }
     
-    // Extract tool arguments, defaulting to empty object if not provided
-    let arguments = tool_params.get("arguments")
-        .cloned()
-        .unwrap_or(serde_json::json!({}));
+    // Look up the handler registered for this method
+    let handler = match ctx.methods.get(&req.method) {
+        Some(handler) => handler,
+        // Method not found, or disabled - return JSON-RPC error
+        None => {
+            let message = match ctx.methods.disabled_reason(&req.method) {
+                Some(reason) => reason.to_string(),
+                None => format!("Method not found: {}", req.method),
+            };
+            let error = MCPError::new(-32601, message);
+            return Some(MCPResponse::error(Some(id), error));
+        }
+    };
     
-    // Look up tool handler in registry
-    if let Some(handler) = registry.handlers.get(tool_name) {
-        // Execute tool handler with provided arguments
-        match handler(arguments) {
-            Ok(result) => {
-                // Tool executed successfully - format as MCP content response
-                MCPResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id,
-                    result: Some(serde_json::json!({
-                        "content": [
-                            {
-                                "type": "text",
-                                "text": serde_json::to_string(&result).unwrap_or_default()
-                            }
-                        ],
-                        "isError": false
-                    })),
-                    error: None,
-                }
-            }
-            Err(e) => {
-                // Tool execution failed - format as MCP error response
-                MCPResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id,
-                    result: Some(serde_json::json!({
-                        "content": [
-                            {
-                                "type": "text",
-                                "text": format!("Error: {}", e)
-                            }
-                        ],
-                        "isError": true
-                    })),
-                    error: None,
-                }
+    // Reject a request whose id is already in flight on this session; the
+    // original request keeps running and still gets its own response
+    let Some(cancellation) = ctx.session.begin_request(&id) else {
+        let error = MCPError::new(-32600, "Invalid Request: duplicate request id");
+        return Some(MCPResponse::error(Some(id), error));
+    };
+    let started = Instant::now();
+    let outcome = tokio::select! {
+        // Checked first so a handler that returns because it saw the
+        // cancellation still gets no response, per the MCP specification
+        biased;
+        _ = cancellation.cancelled() => None,
+        outcome = handler(ctx.with_cancellation(cancellation.clone()), req.params) => Some(outcome),
+    };
+    ctx.session.finish_request(&id);
+    ctx.metrics.record_method(&req.method, started.elapsed());
+    
+    match outcome? {
+        Ok(result) => Some(MCPResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Some(id),
+            result: Some(result),
+            error: None,
+        }),
+        Err(error) => Some(MCPResponse::error(Some(id), error)),
+    }
+}
+
+/// Dispatch every request of a batch concurrently.
+///
+/// # Arguments
+/// * `ctx` - Shared method context; every request runs in its session
+/// * `items` - Parsed batch elements, or the error responses of invalid ones
+///
+/// # Returns
+/// The responses in the order of the batch, or None if every element was a
+/// notification (or cancelled), in which case nothing is sent back.
+pub async fn dispatch_batch(
+    ctx: MethodContext,
+    items: Vec<std::result::Result<MCPRequest, Box<MCPResponse>>>,
+) -> Option<Vec<MCPResponse>> {
+    let responses = futures_util::future::join_all(items.into_iter().map(|item| {
+        let ctx = ctx.clone();
+        async move {
+            match item {
+                Ok(req) => dispatch_request(ctx, req).await,
+                Err(response) => Some(*response),
             }
         }
+    })).await;
+    
+    let responses: Vec<MCPResponse> = responses.into_iter().flatten().collect();
+    (!responses.is_empty()).then_some(responses)
+}
+
+/// Handler of a client notification.
+type NotificationHandler = fn(&MethodContext, Option<serde_json::Value>);
+
+/// Notifications the server acts on, by method name.
+const NOTIFICATION_HANDLERS: &[(&str, NotificationHandler)] = &[
+    ("notifications/initialized", handle_initialized_notification),
+    ("notifications/cancelled", handle_cancelled_notification),
+    // Clients may report progress of server-initiated requests, which this server never sends
+    ("notifications/progress", |_, _| {}),
+];
+
+/// Handle a JSON-RPC notification from the client.
+///
+/// Notifications never get a response. Known notifications are routed
+/// through `NOTIFICATION_HANDLERS`; unknown ones are ignored, and logged to
+/// the client at debug level.
+///
+/// # Arguments
+/// * `ctx` - Method context carrying the client's session
+/// * `method` - Notification method name
+/// * `params` - Notification params
+fn dispatch_notification(ctx: &MethodContext, method: &str, params: Option<serde_json::Value>) {
+    match NOTIFICATION_HANDLERS.iter().find(|(name, _)| *name == method) {
+        Some((_, handler)) => handler(ctx, params),
+        None => {
+            logging::send_log(
+                &ctx.session,
+                LogLevel::Debug,
+                Some(&ctx.state.server_name),
+                serde_json::json!(format!("Ignoring unknown notification '{}'", method)),
+            );
+        }
+    }
+}
+
+/// `notifications/initialized`: the client has finished the handshake.
+fn handle_initialized_notification(ctx: &MethodContext, _params: Option<serde_json::Value>) {
+    ctx.session.mark_ready();
+}
+
+/// `notifications/cancelled`: the client asks to abandon an in-flight request.
+fn handle_cancelled_notification(ctx: &MethodContext, params: Option<serde_json::Value>) {
+    let params = params.unwrap_or_default();
+    if let Some(request_id) = params.get("requestId") {
+        let reason = params.get("reason").and_then(|v| v.as_str()).unwrap_or("no reason given");
+        if ctx.session.cancel_request(request_id) {
+            utils::log_stderr!("Request {} cancelled by client: {}", request_id, reason);
+        }
+    }
+}
+
+/// Query parameters accepted by the metrics endpoint.
+#[derive(Deserialize)]
+struct MetricsQuery {
+    /// Response format (`?format=json|prometheus`), overriding the `Accept` header
+    format: Option<MetricsFormat>,
+}
+
+/// Format of the metrics endpoint's response.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum MetricsFormat {
+    /// JSON object (the default)
+    Json,
+    /// Prometheus text exposition format
+    Prometheus,
+}
+
+/// Metrics endpoint handler for monitoring.
+///
+/// Returns the total number of requests processed since server start and the
+/// sessions initialized by each client application. This endpoint can be used
+/// by monitoring systems to track server load. Prometheus scrapers get the
+/// text exposition format with `?format=prometheus` or by preferring
+/// `text/plain` in their `Accept` header. JSON responses may be enveloped, as
+/// for `/tools` (see `rest_response`).
+///
+/// # Arguments
+/// * `req` - The request, whose `Accept` header selects the response format
+/// * `query` - Query string, optionally selecting the format
+/// * `counter` - Atomic counter tracking total requests
+/// * `ctx` - Shared method context holding the metrics
+/// * `envelope` - Whether JSON responses are enveloped by default
+/// * `envelope_query` - Query string, optionally selecting the envelope
+async fn metrics_handler(
+    req: HttpRequest,
+    query: web::Query<MetricsQuery>,
+    counter: web::Data<std::sync::atomic::AtomicU64>,
+    ctx: web::Data<MethodContext>,
+    envelope: web::Data<RestEnvelope>,
+    envelope_query: web::Query<EnvelopeQuery>,
+) -> Result<HttpResponse> {
+    let count = counter.load(std::sync::atomic::Ordering::Relaxed);
+    let prometheus = match query.format {
+        Some(format) => format == MetricsFormat::Prometheus,
+        None => prefers_plain_text(&req),
+    };
+    if prometheus {
+        return Ok(HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4; charset=utf-8")
+            .body(ctx.metrics.prometheus(count)));
+    }
+    
+    let clients: Vec<serde_json::Value> = ctx.metrics.clients().into_iter()
+        .map(|(name, version, sessions)| serde_json::json!({
+            "name": name,
+            "version": version,
+            "sessions": sessions
+        }))
+        .collect();
+    Ok(rest_response(&ctx.state, envelope.applies(&envelope_query), Ok(serde_json::json!({
+        "requests_total": count,
+        "clients": clients,
+        "status": "ok"
+    }))))
+}
+
+/// Whether the development-only `/debug` endpoints are enabled, set by
+/// `MCP_DEBUG_ENDPOINTS`.
+#[derive(Clone, Copy)]
+pub struct DebugEndpoints(pub bool);
+
+/// Debug snapshot endpoint handler.
+///
+/// Returns the server's live state for inspection during development: the
+/// registered tool names, the request counter, the loaded configuration with
+/// secret values redacted (see `utils::redact_secrets`) and the open SSE
+/// sessions with the client info they negotiated. Returns 404 unless
+/// `MCP_DEBUG_ENDPOINTS` is set.
+///
+/// # Arguments
+/// * `debug` - Whether debug endpoints are enabled
+/// * `ctx` - Shared method context (server metadata and tools)
+/// * `counter` - Request counter
+/// * `sessions` - Open SSE sessions
+async fn debug_state(
+    debug: web::Data<DebugEndpoints>,
+    ctx: web::Data<MethodContext>,
+    counter: web::Data<std::sync::atomic::AtomicU64>,
+    sessions: web::Data<SseSessions>,
+) -> Result<HttpResponse> {
+    if !debug.0 {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({ "error": "debug endpoints are disabled" })));
+    }
+    
+    let tools: Vec<&str> = ctx.tools.tools.iter().map(|tool| tool.name.as_str()).collect();
+    let config: serde_json::Map<String, serde_json::Value> = utils::load_config().into_iter().collect();
+    let sessions: Vec<serde_json::Value> = sessions.sessions().iter()
+        .map(|session| serde_json::json!({
+            "id": session.id,
+            "initialized": session.is_initialized(),
+            "ready": session.is_ready(),
+            "protocolVersion": session.protocol_version(),
+            "clientInfo": session.client_info()
+        }))
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "server": {
+            "name": ctx.state.server_name,
+            "version": ctx.state.server_version
+        },
+        "tools": tools,
+        "requests_total": counter.load(std::sync::atomic::Ordering::Relaxed),
+        "config": utils::redact_secrets(&serde_json::Value::Object(config)),
+        "sessions": sessions
+    })))
+}
+
+/// MCP over Streamable HTTP endpoint.
+///
+/// This endpoint supports MCP protocol over Streamable HTTP. POST requests carry
+/// MCP JSON-RPC requests, or batches of them, and are handled as on `/mcp`;
+/// the response is sent as a single SSE event. GET requests on `/sse` are
+/// served by the session-based SSE transport in `sse.rs`.
+///
+/// CORS headers, including answers to preflight requests, come from the CORS
+/// middleware as for every other endpoint.
+///
+/// # Arguments
+/// * `req` - HTTP request (POST for MCP requests, DELETE for housekeeping)
+/// * `ctx` - Shared method context
+/// * `counter` - Request counter
+/// * `limit` - Maximum accepted request body size
+/// * `content_types` - Accepted request content types
+/// * `inflight` - Limit on concurrently handled requests
+/// * `sessions` - Sessions of clients that initialized over HTTP
+/// * `body` - Raw request body
+#[allow(clippy::too_many_arguments)]
+async fn mcp_sse_handler(
+    req: HttpRequest,
+    ctx: web::Data<MethodContext>,
+    counter: web::Data<std::sync::atomic::AtomicU64>,
+    limit: web::Data<BodyLimit>,
+    content_types: web::Data<AcceptedContentTypes>,
+    inflight: web::Data<InflightLimit>,
+    sessions: web::Data<HttpSessions>,
+    body: web::Payload,
+) -> Result<HttpResponse> {
+    // Handle POST requests - process MCP JSON-RPC requests
+    if req.method() == "POST" {
+        let post = PostedMessage { req, ctx, counter, limit, content_types, inflight, sessions, body };
+        return Ok(post.serve(ResponseFormat::Event).await);
+    }
+    
+    // Handle DELETE requests - StreamableHttp cleanup
+    if req.method() == "DELETE" {
+        return end_http_session(req, sessions).await;
+    }
+    
+    Err(actix_web::error::ErrorMethodNotAllowed("Method not allowed"))
+}
+
+/// End the HTTP session named by the request's `Mcp-Session-Id` header.
+///
+/// Streamable HTTP clients send `DELETE` to `/mcp` or `/sse` when they are
+/// done. A request without the header has no session to end and gets a 200
+/// as well; an unknown or expired session gets a 404.
+///
+/// # Arguments
+/// * `req` - HTTP request, whose session header is read
+/// * `sessions` - Sessions of clients that initialized over HTTP
+async fn end_http_session(req: HttpRequest, sessions: web::Data<HttpSessions>) -> Result<HttpResponse> {
+    let Some(header) = req.headers().get(SESSION_HEADER) else {
+        return Ok(HttpResponse::Ok().finish());
+    };
+    if header.to_str().is_ok_and(|id| sessions.remove(id)) {
+        Ok(HttpResponse::Ok().finish())
     } else {
-        // Tool not found in registry
-        MCPResponse {
-            jsonrpc: "2.0".to_string(),
-            id,
-            result: None,
-            error: Some(MCPError {
-                code: -32601, // Method not found
-                message: format!("Unknown tool: {}", tool_name),
-                data: None,
-            }),
+        Ok(unknown_http_session())
+    }
+}
+
+/// The 404 answering a request for an unknown or expired HTTP session.
+///
+/// Clients receiving it start a new session with `initialize`.
+fn unknown_http_session() -> HttpResponse {
+    HttpResponse::NotFound().json(MCPResponse::error(
+        None,
+        MCPError::new(-32600, format!("Invalid Request: unknown or expired session in {}", SESSION_HEADER)),
+    ))
+}
+
+/// Server-Sent Events endpoint for tools discovery (legacy).
+///
+/// Returns a single SSE event with the tool list. This is kept for backward compatibility
+/// under `/sse/tools` and `/tools/sse`. The MCP SSE transport itself lives at `/sse`.
+///
+/// # Arguments
+/// * `registry` - Tool registry containing all registered tools
+async fn sse_tools_discovery(
+    registry: web::Data<Arc<ToolRegistry>>,
+) -> Result<HttpResponse> {
+    use actix_web::http::header;
+    
+    // Serialize all tools to JSON format matching MCP tools/list response
+    let tools_json = tools_list_json(&registry);
+    
+    // Create SSE event data with tools list and count
+    let tools_data = serde_json::json!({
+        "tools": tools_json,
+        "count": tools_json.len()
+    });
+    
+    // Format as SSE event: "data: {json}\n\n"
+    let sse_data = format!(
+        "data: {}\n\n",
+        serde_json::to_string(&tools_data).unwrap_or_else(|_| "{}".to_string())
+    );
+    
+    // Return SSE response with appropriate headers
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        // Disable caching to ensure clients always get fresh data
+        .insert_header(header::CacheControl(vec![
+            header::CacheDirective::NoCache,
+            header::CacheDirective::NoStore,
+            header::CacheDirective::MustRevalidate,
+        ]))
+        // Disable nginx buffering for real-time streaming
+        .insert_header(("x-accel-buffering", "no"))
+        .body(sse_data))
+}
+
+/// Params of the `initialize` request.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializeParams {
+    /// Protocol version the client wants to speak
+    pub protocol_version: Option<String>,
+    /// Capabilities the client supports
+    #[serde(default)]
+    pub capabilities: serde_json::Map<String, serde_json::Value>,
+    /// Client application name and version
+    pub client_info: Option<ClientInfo>,
+}
+
+/// Handle MCP initialize method.
+///
+/// The initialize method is the first method called by MCP clients to establish
+/// a connection. It returns the protocol version, server capabilities, and
+/// server information.
+///
+/// Clients may call initialize again on an existing session, for example to
+/// downgrade to an older protocol version. The session's negotiated version and
+/// capabilities are reset to the new values. A re-initialize requesting an
+/// unsupported version is rejected with -32602 and leaves the session unchanged.
+///
+/// # Arguments
+/// * `ctx` - Method context containing server metadata and the client session
+/// * `params` - Initialize params (see `InitializeParams`)
+async fn handle_initialize(ctx: MethodContext, params: Option<serde_json::Value>) -> MethodResult {
+    let params: InitializeParams = match params {
+        Some(params) => serde_json::from_value(params)
+            .map_err(|e| MCPError::new(-32602, format!("Invalid params: {}", e)))?,
+        None => InitializeParams::default(),
+    };
+    
+    // A re-initialize is the same client, so only a session's first initialize is counted
+    let first_initialize = !ctx.session.is_initialized();
+    let client_info = params.client_info.clone();
+    let protocol_version = ctx.session
+        .negotiate(
+            params.protocol_version.as_deref(),
+            serde_json::Value::Object(params.capabilities),
+            params.client_info,
+        )
+        .map_err(|msg| MCPError::new(-32602, msg))?;
+    
+    if let Some(client) = client_info.filter(|_| first_initialize) {
+        match utils::log_format() {
+            LogFormat::Text => utils::log_stderr!(
+                "Client connected: {} {} (session {})", client.name, client.version, ctx.session.id
+            ),
+            LogFormat::Json => utils::log_record(serde_json::json!({
+                "event": "client_connected",
+                "client_name": client.name,
+                "client_version": client.version,
+                "session_id": ctx.session.id,
+                "protocol_version": protocol_version
+            })),
+        }
+        ctx.metrics.record_client(&client);
+    }
+    
+    let mut result = serde_json::json!({
+        "protocolVersion": protocol_version,
+        "capabilities": *ctx.capabilities,
+        "serverInfo": {
+            "name": ctx.state.server_name,
+            "version": ctx.state.server_version,
+            "buildInfo": build_info::build_info()
+        }
+    });
+    
+    // Instructions are optional and omitted entirely when not configured
+    if let Some(instructions) = &ctx.state.instructions {
+        result["instructions"] = serde_json::json!(instructions);
+    }
+    
+    // Tools see the same id as the `session_id` of their `CallContext`
+    result["_meta"] = serde_json::json!({ "sessionId": ctx.session.id });
+    Ok(result)
+}
+
+/// Default number of tools returned per tools/list page.
+const DEFAULT_TOOLS_PAGE_SIZE: usize = 100;
+
+/// Handle MCP tools/list method.
+///
+/// Returns a list of the available tools, sorted by name, with their names,
+/// descriptions and input schemas. This allows clients to discover what tools
+/// are available before calling them.
+///
+/// Results are paginated: each page holds up to `TOOLS_PAGE_SIZE` tools
+/// (default 100), and `nextCursor` is included when more tools remain. The
+/// cursor is opaque to clients (base64 of the next offset) and is passed back
+/// as the `cursor` param to fetch the following page.
+///
+/// `outputSchema` is left out for sessions that negotiated a protocol version
+/// older than 2025-06-18, which predate it.
+///
+/// # Arguments
+/// * `ctx` - Method context containing the tool registry
+/// * `params` - Optional params with a `cursor` from a previous page
+async fn handle_tools_list(ctx: MethodContext, params: Option<serde_json::Value>) -> MethodResult {
+    // Resolve the starting offset from the cursor, if any
+    let offset = match params.as_ref().and_then(|p| p.get("cursor")) {
+        Some(cursor) => cursor
+            .as_str()
+            .and_then(decode_tools_cursor)
+            .filter(|&offset| offset <= ctx.tools.tools.len())
+            .ok_or_else(|| MCPError::new(-32602, "Invalid params: invalid cursor"))?,
+        None => 0,
+    };
+    
+    // A zero page size would never make progress, so treat it as one
+    let page_size = utils::parse_env_var("TOOLS_PAGE_SIZE", DEFAULT_TOOLS_PAGE_SIZE).max(1);
+    let mut tools = tools_list_json(&ctx.tools);
+    if !ctx.session.supports_structured_content() {
+        for tool in &mut tools {
+            if let Some(tool) = tool.as_object_mut() {
+                tool.remove("outputSchema");
+            }
+        }
+    }
+    let end = (offset + page_size).min(tools.len());
+    
+    let mut result = serde_json::json!({
+        "tools": &tools[offset..end]
+    });
+    if end < tools.len() {
+        result["nextCursor"] = serde_json::json!(BASE64.encode(end.to_string()));
+    }
+    Ok(result)
+}
+
+/// Handle the tools/describe method.
+///
+/// Returns a single tool's definition in the same shape as its tools/list
+/// entry, for clients that need one schema without listing every tool. This
+/// is not part of MCP; it mirrors the REST `/tools/{name}/schema` endpoint.
+///
+/// # Arguments
+/// * `ctx` - Method context containing the tool registry
+/// * `params` - `{"name": ...}` naming the tool
+async fn handle_tools_describe(ctx: MethodContext, params: Option<serde_json::Value>) -> MethodResult {
+    let name = params.as_ref()
+        .and_then(|p| p.get("name"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| MCPError::new(-32602, "Invalid params: missing name"))?;
+    
+    ctx.tools.get_tool(name)
+        .map(tool_json)
+        .ok_or_else(|| {
+            MCPError::new(-32602, format!("Invalid params: unknown tool: {}", name))
+                .with_data(serde_json::json!({ "tool": name }))
+        })
+}
+
+/// Decode a tools/list cursor back into a tool offset.
+///
+/// Returns `None` if the cursor is not one this server issued.
+fn decode_tools_cursor(cursor: &str) -> Option<usize> {
+    let bytes = BASE64.decode(cursor).ok()?;
+    std::str::from_utf8(&bytes).ok()?.parse().ok()
+}
+
+/// Serialize a tool definition as it appears in tools/list.
+///
+/// Field names follow the MCP specification (camelCase `inputSchema`), and
+/// `outputSchema` is only included for tools that return structured content.
+/// Every endpoint that lists tools goes through this function so they agree.
+///
+/// # Arguments
+/// * `tool` - Tool definition to serialize
+fn tool_json(tool: &MCPTool) -> serde_json::Value {
+    let mut json = serde_json::json!({
+        "name": tool.name,
+        "description": tool.description,
+        "inputSchema": tool.input_schema
+    });
+    if let Some(output_schema) = &tool.output_schema {
+        json["outputSchema"] = output_schema.clone();
+    }
+    json
+}
+
+/// Serialize every registered tool as it appears in tools/list.
+///
+/// Tools are sorted by name so listings are stable regardless of registration
+/// order, which also keeps tools/list pagination offsets consistent. The
+/// registry itself keeps registration order.
+fn tools_list_json(registry: &ToolRegistry) -> Vec<serde_json::Value> {
+    let mut tools: Vec<&MCPTool> = registry.tools.iter().collect();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    tools.into_iter().map(tool_json).collect()
+}
+
+/// Whether REST endpoint responses are wrapped in an envelope by default,
+/// set by `MCP_REST_ENVELOPE`.
+#[derive(Clone, Copy)]
+pub struct RestEnvelope(pub bool);
+
+impl RestEnvelope {
+    /// Whether to wrap this response: `?envelope=` if given, else the default.
+    fn applies(self, query: &EnvelopeQuery) -> bool {
+        match &query.envelope {
+            Some(envelope) => utils::is_truthy(envelope),
+            None => self.0,
+        }
+    }
+}
+
+/// Query parameters selecting the REST response envelope.
+#[derive(Deserialize)]
+struct EnvelopeQuery {
+    /// Wrap (`?envelope=1`) or do not wrap (`?envelope=0`) the response,
+    /// overriding `MCP_REST_ENVELOPE`
+    envelope: Option<String>,
+}
+
+/// Build a REST endpoint's JSON response.
+///
+/// Enveloped responses are `{"data": ..., "error": null, "meta": {...}}` on
+/// success and `{"data": null, "error": {"message": ...}, "meta": {...}}` on
+/// failure, with the same status code as the plain response. `meta` holds the
+/// server name and version and the time of the response. Plain failures are
+/// `{"error": message}`.
+///
+/// # Arguments
+/// * `state` - Server metadata, for `meta`
+/// * `enveloped` - Whether to wrap the response
+/// * `result` - Response body, or the status and message of the failure
+fn rest_response(
+    state: &AppState,
+    enveloped: bool,
+    result: std::result::Result<serde_json::Value, (http::StatusCode, &str)>,
+) -> HttpResponse {
+    let (status, body) = match (result, enveloped) {
+        (Ok(data), false) => (http::StatusCode::OK, data),
+        (Err((status, message)), false) => (status, serde_json::json!({ "error": message })),
+        (result, true) => {
+            let meta = serde_json::json!({
+                "server": state.server_name,
+                "version": state.server_version,
+                "timestamp": utils::rfc3339(SystemTime::now())
+            });
+            match result {
+                Ok(data) => (http::StatusCode::OK, serde_json::json!({ "data": data, "error": null, "meta": meta })),
+                Err((status, message)) => (status, serde_json::json!({
+                    "data": null,
+                    "error": { "message": message },
+                    "meta": meta
+                })),
+            }
+        }
+    };
+    HttpResponse::build(status).json(body)
+}
+
+/// REST endpoint listing all tools.
+///
+/// Returns `{"tools": [...]}` in the same shape as the tools/list result, for
+/// integrations that do not speak JSON-RPC. The response is wrapped in an
+/// envelope with `?envelope=1` or `MCP_REST_ENVELOPE` (see `rest_response`).
+///
+/// # Arguments
+/// * `registry` - Tool registry containing all registered tools
+/// * `state` - Server metadata
+/// * `envelope` - Whether responses are enveloped by default
+/// * `query` - Query string, optionally selecting the envelope
+async fn rest_tools_list(
+    registry: web::Data<Arc<ToolRegistry>>,
+    state: web::Data<AppState>,
+    envelope: web::Data<RestEnvelope>,
+    query: web::Query<EnvelopeQuery>,
+) -> Result<HttpResponse> {
+    Ok(rest_response(&state, envelope.applies(&query), Ok(serde_json::json!({
+        "tools": tools_list_json(&registry)
+    }))))
+}
+
+/// REST endpoint returning a single tool's schema.
+///
+/// Returns the tool's tools/list entry, or 404 `{"error": "unknown tool"}` if no
+/// tool with that name is registered. Either may be enveloped, as for `/tools`.
+///
+/// # Arguments
+/// * `registry` - Tool registry containing all registered tools
+/// * `name` - Tool name from the request path
+/// * `state` - Server metadata
+/// * `envelope` - Whether responses are enveloped by default
+/// * `query` - Query string, optionally selecting the envelope
+async fn rest_tool_schema(
+    registry: web::Data<Arc<ToolRegistry>>,
+    name: web::Path<String>,
+    state: web::Data<AppState>,
+    envelope: web::Data<RestEnvelope>,
+    query: web::Query<EnvelopeQuery>,
+) -> Result<HttpResponse> {
+    let result = registry.get_tool(&name)
+        .map(tool_json)
+        .ok_or((http::StatusCode::NOT_FOUND, "unknown tool"));
+    Ok(rest_response(&state, envelope.applies(&query), result))
+}
+
+/// Capability manifest endpoint (`GET /.well-known/mcp.json`).
+///
+/// Describes the server for discovery before a client connects: its name and
+/// version, the preferred protocol version, the capabilities `initialize`
+/// advertises and every tool as listed by tools/list. `instructions` is
+/// included when configured.
+///
+/// # Arguments
+/// * `ctx` - Shared method context (server metadata, capabilities and tools)
+async fn well_known_manifest(ctx: web::Data<MethodContext>) -> Result<HttpResponse> {
+    let mut manifest = serde_json::json!({
+        "name": ctx.state.server_name,
+        "version": ctx.state.server_version,
+        "protocolVersion": SUPPORTED_PROTOCOL_VERSIONS[0],
+        "capabilities": *ctx.capabilities,
+        "tools": tools_list_json(&ctx.tools)
+    });
+    if let Some(instructions) = &ctx.state.instructions {
+        manifest["instructions"] = serde_json::json!(instructions);
+    }
+    Ok(HttpResponse::Ok().json(manifest))
+}
+
+/// OpenAPI document endpoint.
+///
+/// Returns the OpenAPI 3.1 description of the HTTP API, including the input
+/// schema of every registered tool.
+///
+/// # Arguments
+/// * `state` - Server metadata
+/// * `registry` - Tool registry containing all registered tools
+async fn openapi_json(
+    state: web::Data<AppState>,
+    registry: web::Data<Arc<ToolRegistry>>,
+) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(openapi::openapi_document(&state, &registry)))
+}
+
+/// Handle MCP tools/call method.
+///
+/// Executes a tool with the provided arguments. The tool name and arguments
+/// are extracted from the params, the tool handler is looked up in the
+/// registry, and the handler is executed. Results or errors are formatted
+/// according to MCP protocol specifications.
+///
+/// With `validateOnly: true` in the params, the arguments are validated
+/// against the tool's input schema and `{"valid": true}` is returned without
+/// running the tool or its middleware.
+///
+/// With `_meta.rawResult: true`, a successful call returns the handler's JSON
+/// result itself as the JSON-RPC result (see `raw_tool_result`). This is not
+/// part of MCP; failures are still reported in the standard shape.
+///
+/// # Arguments
+/// * `ctx` - Method context containing the tool registry
+/// * `params` - Method parameters containing tool name and arguments
+async fn handle_tools_call(ctx: MethodContext, params: Option<serde_json::Value>) -> MethodResult {
+    // Sessions that enforce the MCP lifecycle only accept tool calls after initialize
+    if ctx.session.requires_initialize() && !ctx.session.is_initialized() {
+        return Err(MCPError::new(-32002, "server not initialized"));
+    }
+    
+    // Extract tool call parameters from the request
+    // Missing params - return invalid params error
+    let tool_params = params.ok_or_else(|| MCPError::new(-32602, "Invalid params"))?;
+    
+    // Extract tool name from parameters
+    // Aliases are resolved first, so the call is handled exactly as one to
+    // the canonical name
+    let tool_name = tool_params.get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let tool_name = ctx.tools.resolve_alias(tool_name);
+    
+    // Extract tool arguments, defaulting to empty object if not provided
+    let mut arguments = tool_arguments(&tool_params)?;
+    
+    // Look up tool handler in registry
+    let handler = ctx.tools.handlers.get(tool_name)
+        .cloned()
+        // Tool not found in registry - list the available tools to help the client
+        .ok_or_else(|| unknown_tool_error(&ctx.tools, tool_name))?;
+    
+    coerce_tool_arguments(&ctx.tools, tool_name, &mut arguments);
+    
+    // Validate arguments against the tool's input schema before executing.
+    // Hard errors reject the call; warnings are returned alongside the result.
+    let mut warnings = validate_tool_arguments(&ctx.tools, tool_name, &arguments)?;
+    
+    // With validateOnly, the arguments are checked but the tool is not run
+    if tool_params.get("validateOnly").and_then(|v| v.as_bool()).unwrap_or(false) {
+        let mut result = serde_json::json!({ "valid": true });
+        attach_meta(&mut result, warnings);
+        return Ok(result);
+    }
+    
+    logging::send_log(
+        &ctx.session,
+        LogLevel::Debug,
+        Some(&ctx.state.server_name),
+        serde_json::json!(format!("Calling tool '{}'", tool_name)),
+    );
+    
+    // Progress notifications are only sent if the client supplied a token
+    let progress_token = tool_params.get("_meta")
+        .and_then(|meta| meta.get("progressToken"))
+        .filter(|token| token.is_string() || token.is_number())
+        .cloned();
+    let progress = ProgressReporter::new(ctx.session.clone(), progress_token);
+    
+    // Non-standard: programmatic clients may ask for the result without the MCP wrapper
+    let raw_result = tool_params.get("_meta")
+        .and_then(|meta| meta.get("rawResult"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    
+    // Execute tool handler with provided arguments, bounded by the tool's timeout
+    // and wrapped in the middleware chain. A failing `before` hook short-circuits
+    // the call; every `after` hook sees the outcome, including timeouts.
+    // Cacheable tools are served from the result cache when the same call
+    // succeeded before; the middleware still runs around a cached result.
+    let started = Instant::now();
+    let cacheable = ctx.tools.cache.is_enabled()
+        && ctx.tools.get_tool(tool_name).is_some_and(|tool| tool.cacheable);
+    let rejected = rejected_by_middleware(&ctx.tools, tool_name, &arguments);
+    let cached = match rejected {
+        None if cacheable => ctx.tools.cache.get(tool_name, &arguments),
+        _ => None,
+    };
+    let cache_arguments = (cacheable && cached.is_none()).then(|| arguments.clone());
+    let audit_arguments = ctx.tools.audit.as_ref().map(|_| arguments.clone());
+    let outcome = match (rejected, cached) {
+        (Some(e), _) => Ok(Err(e)),
+        (None, Some(output)) => {
+            logging::send_log(
+                &ctx.session,
+                LogLevel::Debug,
+                Some(&ctx.state.server_name),
+                serde_json::json!(format!("Serving tool '{}' from cache", tool_name)),
+            );
+            Ok(Ok(output))
+        }
+        (None, None) => {
+            let timeout = CallTimeout::for_call(tool_name, request_deadline(Some(&tool_params)));
+            run_tool_handler(tool_name, handler, arguments, timeout, &ctx, progress).await
+        }
+    };
+    finish_tool_call(&ctx, tool_name, audit_arguments.as_ref(), &outcome, started.elapsed());
+    
+    match outcome? {
+        Ok(output) => {
+            // Only successful results are cached
+            if let Some(arguments) = cache_arguments {
+                ctx.tools.cache.put(tool_name, &arguments, &output);
+            }
+            
+            // With _meta.rawResult the handler's result is returned unwrapped
+            let mut result = if raw_result {
+                raw_tool_result(output)
+            } else {
+                // Tool executed successfully - format as MCP content response,
+                // with the tool's own warnings after any validation warnings
+                warnings.extend(output.warnings.iter().cloned());
+                let tool = ctx.tools.get_tool(tool_name);
+                let mut result = render_tool_output(tool_name, tool, output)?;
+                // Sessions on an older protocol version only read the content blocks
+                if !ctx.session.supports_structured_content()
+                    && let Some(result) = result.as_object_mut()
+                {
+                    result.remove("structuredContent");
+                }
+                attach_meta(&mut result, warnings);
+                result
+            };
+            
+            // Keep large integer ids exact for JavaScript clients
+            if ctx.tools.big_int_as_string {
+                utils::stringify_unsafe_integers(&mut result);
+            }
+            Ok(result)
+        }
+        Err(e) => tool_failure(&ctx, tool_name, e),
+    }
+}
+
+/// The error of the first `before` middleware hook to reject a tool call, if any.
+fn rejected_by_middleware(registry: &ToolRegistry, tool_name: &str, arguments: &serde_json::Value) -> Option<ToolError> {
+    registry.middleware.iter().find_map(|middleware| middleware.before(tool_name, arguments).err())
+}
+
+/// Account for a finished tool call.
+///
+/// Runs every `after` middleware hook with the outcome, writes the audit
+/// record and times the tool. Shared by `handle_tools_call` and streamed
+/// calls, so a call is accounted for the same way however its result is sent.
+///
+/// # Arguments
+/// * `ctx` - Method context of the call
+/// * `tool_name` - Name of the tool that was called
+/// * `audit_arguments` - The call's arguments, when an audit log is configured
+/// * `outcome` - The handler's result, or the error that kept it from finishing
+/// * `elapsed` - Time the call took
+fn finish_tool_call(
+    ctx: &MethodContext,
+    tool_name: &str,
+    audit_arguments: Option<&serde_json::Value>,
+    outcome: &std::result::Result<std::result::Result<ToolOutput, ToolError>, MCPError>,
+    elapsed: Duration,
+) {
+    for middleware in &ctx.tools.middleware {
+        match outcome {
+            Ok(result) => middleware.after(tool_name, result, elapsed),
+            Err(e) => middleware.after(tool_name, &Err(ToolError::ExecutionFailed(e.message.clone())), elapsed),
+        }
+    }
+    if let (Some(audit), Some(arguments)) = (&ctx.tools.audit, audit_arguments) {
+        let error = match outcome {
+            Ok(Ok(_)) => None,
+            Ok(Err(e)) => Some(e.to_string()),
+            Err(e) => Some(e.message.clone()),
+        };
+        audit.record(tool_name, arguments, error.as_deref(), elapsed);
+    }
+    ctx.metrics.record_tool(tool_name, elapsed);
+}
+
+/// Report a failed tool call.
+///
+/// Arguments rejected by the tool become a -32602 error; other failures are
+/// returned as a tool result with `isError: true`.
+///
+/// # Arguments
+/// * `ctx` - Method context of the call
+/// * `tool_name` - Name of the tool that failed
+/// * `error` - The tool's error
+fn tool_failure(ctx: &MethodContext, tool_name: &str, error: ToolError) -> MethodResult {
+    match error {
+        // Arguments rejected by the tool - return invalid params error
+        ToolError::InvalidArguments(msg) => {
+            Err(MCPError::new(-32602, format!("Invalid arguments: {}", msg)))
+        }
+        // Tool execution failed - format as MCP error response
+        e => {
+            // A cancelled call's outcome is discarded, so its failure is not reported
+            if ctx.cancellation.is_cancelled() {
+                return Err(MCPError::new(-32800, "Request cancelled"));
+            }
+            logging::send_log(
+                &ctx.session,
+                LogLevel::Error,
+                Some(&ctx.state.server_name),
+                serde_json::json!(format!("Tool '{}' failed: {}", tool_name, e)),
+            );
+            Ok(serde_json::json!({
+                "content": [
+                    {
+                        "type": "text",
+                        "text": format!("Error: {}", e)
+                    }
+                ],
+                "isError": true,
+                "_meta": tool_result_meta(Vec::new())
+            }))
+        }
+    }
+}
+
+/// Most tool names listed in the `data` of an unknown tool error.
+const MAX_LISTED_TOOLS: usize = 50;
+
+/// The `arguments` of tools/call params, which default to an empty object.
+///
+/// Arguments of any other type are rejected with -32602 before the tool is
+/// looked at, so every tool sees an object whatever its input schema says.
+fn tool_arguments(params: &serde_json::Value) -> std::result::Result<serde_json::Value, MCPError> {
+    match params.get("arguments") {
+        None | Some(serde_json::Value::Null) => Ok(serde_json::json!({})),
+        Some(arguments) if arguments.is_object() => Ok(arguments.clone()),
+        Some(_) => Err(MCPError::new(-32602, "Invalid params: arguments must be an object")),
+    }
+}
+
+/// Build the -32601 error for a call to an unregistered tool.
+///
+/// The error's `data` holds the requested name and the available tool names,
+/// sorted and capped at `MAX_LISTED_TOOLS`, with `truncated` set when the
+/// list was cut short.
+fn unknown_tool_error(registry: &ToolRegistry, tool_name: &str) -> MCPError {
+    let mut available: Vec<&str> = registry.tools.iter().map(|tool| tool.name.as_str()).collect();
+    available.sort_unstable();
+    let truncated = available.len() > MAX_LISTED_TOOLS;
+    available.truncate(MAX_LISTED_TOOLS);
+    
+    MCPError::new(-32601, format!("Unknown tool: {}", tool_name)).with_data(serde_json::json!({
+        "tool": tool_name,
+        "available": available,
+        "truncated": truncated
+    }))
+}
+
+/// Handle the logging/setLevel method.
+///
+/// Sets the minimum severity of `notifications/message` log messages sent to
+/// the requesting session's client.
+///
+/// # Arguments
+/// * `ctx` - Method context carrying the client's session
+/// * `params` - `{"level": ...}` with one of the RFC 5424 level names
+async fn handle_logging_set_level(ctx: MethodContext, params: Option<serde_json::Value>) -> MethodResult {
+    let level = params.as_ref()
+        .and_then(|p| p.get("level"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| MCPError::new(-32602, "Invalid params: missing level"))?;
+    let level: LogLevel = level.parse()
+        .map_err(|e| MCPError::new(-32602, format!("Invalid params: {}", e)))?;
+    
+    ctx.session.set_log_level(level);
+    Ok(serde_json::json!({}))
+}
+
+/// Read the `uri` param of a resources/subscribe or resources/unsubscribe request.
+fn resource_uri(params: Option<&serde_json::Value>) -> Result<&str, MCPError> {
+    params
+        .and_then(|p| p.get("uri"))
+        .and_then(|v| v.as_str())
+        .filter(|uri| !uri.is_empty())
+        .ok_or_else(|| MCPError::new(-32602, "Invalid params: missing uri"))
+}
+
+/// Handle the resources/subscribe method.
+///
+/// Subscribes the requesting session to `notifications/resources/updated`
+/// for a URI. Only sessions that can receive notifications may subscribe;
+/// plain HTTP requests are rejected.
+///
+/// # Arguments
+/// * `ctx` - Method context carrying the client's session
+/// * `params` - `{"uri": ...}` naming the resource
+async fn handle_resources_subscribe(ctx: MethodContext, params: Option<serde_json::Value>) -> MethodResult {
+    let uri = resource_uri(params.as_ref())?;
+    if !ctx.session.can_notify() {
+        return Err(MCPError::new(
+            -32600,
+            "resources/subscribe requires a transport that delivers notifications (SSE, STDIO or Unix socket)",
+        ));
+    }
+    
+    ctx.subscriptions.subscribe(uri, &ctx.session);
+    Ok(serde_json::json!({}))
+}
+
+/// Handle the resources/unsubscribe method.
+///
+/// Unsubscribing from a URI the session is not subscribed to is not an error.
+///
+/// # Arguments
+/// * `ctx` - Method context carrying the client's session
+/// * `params` - `{"uri": ...}` naming the resource
+async fn handle_resources_unsubscribe(ctx: MethodContext, params: Option<serde_json::Value>) -> MethodResult {
+    let uri = resource_uri(params.as_ref())?;
+    ctx.subscriptions.unsubscribe(uri, &ctx.session);
+    Ok(serde_json::json!({}))
+}
+
+/// Handle the completion/complete method.
+///
+/// Suggests values for an argument of a prompt or resource using the
+/// registered completion providers. Arguments without a provider complete to
+/// an empty list rather than an error.
+///
+/// # Arguments
+/// * `ctx` - Method context containing the completion registry
+/// * `params` - `{"ref": {...}, "argument": {"name": ..., "value": ...}}`
+async fn handle_completion_complete(ctx: MethodContext, params: Option<serde_json::Value>) -> MethodResult {
+    let params = params.unwrap_or_default();
+    let reference = params.get("ref")
+        .ok_or_else(|| MCPError::new(-32602, "Invalid params: missing ref"))?;
+    let reference = CompletionRef::from_json(reference)
+        .map_err(|e| MCPError::new(-32602, format!("Invalid params: {}", e)))?;
+    let argument = params.get("argument")
+        .ok_or_else(|| MCPError::new(-32602, "Invalid params: missing argument"))?;
+    let name = argument.get("name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| MCPError::new(-32602, "Invalid params: missing argument name"))?;
+    let value = argument.get("value")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    
+    Ok(serde_json::json!({
+        "completion": ctx.completions.complete(&reference, name, value)
+    }))
+}
+
+/// Handle the ping method.
+///
+/// Lets a client check that the server is alive over a long-lived connection.
+/// The response is an empty result; ping is answered before initialize too.
+///
+/// # Arguments
+/// * `_ctx` - Method context (unused)
+/// * `_params` - Ping params (unused)
+async fn handle_ping(_ctx: MethodContext, _params: Option<serde_json::Value>) -> MethodResult {
+    Ok(serde_json::json!({}))
+}
+
+/// Handle the capabilities/probe method.
+///
+/// Returns a machine-readable description of the server's API: every method
+/// currently registered with its params schema, the enabled capabilities and
+/// the names of the available tools. The method list is read from the method
+/// registry, so methods registered at any point are reflected automatically.
+///
+/// # Arguments
+/// * `ctx` - Method context containing the method and tool registries
+/// * `_params` - Probe params (currently unused)
+async fn handle_capabilities_probe(ctx: MethodContext, _params: Option<serde_json::Value>) -> MethodResult {
+    let tool_names: Vec<&str> = ctx.tools.tools.iter()
+        .map(|tool| tool.name.as_str())
+        .collect();
+    
+    Ok(serde_json::json!({
+        "protocolVersion": ctx.session.protocol_version(),
+        "supportedProtocolVersions": SUPPORTED_PROTOCOL_VERSIONS,
+        "serverInfo": {
+            "name": ctx.state.server_name,
+            "version": ctx.state.server_version
+        },
+        "methods": ctx.methods.methods,
+        "capabilities": *ctx.capabilities,
+        "tools": tool_names
+    }))
+}
+
+/// Handle the server/stats method.
+///
+/// Reports the counters behind `GET /metrics` on every transport, so STDIO
+/// clients can read them too: the number of requests and their total duration
+/// by method, the same for calls by tool, and the server's uptime.
+/// `requests_total` counts requests to registered methods on every transport,
+/// unlike the HTTP-only `requests_total` of `/metrics`; the stats request
+/// itself is counted once it completes.
+///
+/// # Arguments
+/// * `ctx` - Method context holding the metrics
+/// * `_params` - Stats params (unused)
+async fn handle_server_stats(ctx: MethodContext, _params: Option<serde_json::Value>) -> MethodResult {
+    let stats = |durations: Vec<(String, Histogram)>| -> serde_json::Map<String, serde_json::Value> {
+        durations.into_iter()
+            .map(|(name, histogram)| (name, serde_json::json!({
+                "count": histogram.count(),
+                "total_secs": histogram.sum()
+            })))
+            .collect()
+    };
+    let per_method = ctx.metrics.method_durations();
+    let requests_total: u64 = per_method.iter().map(|(_, histogram)| histogram.count()).sum();
+    
+    Ok(serde_json::json!({
+        "requests_total": requests_total,
+        "per_method": stats(per_method),
+        "per_tool": stats(ctx.metrics.tool_durations()),
+        "uptime_secs": ctx.metrics.uptime().as_secs()
+    }))
+}
+
+/// Default tool execution timeout in seconds.
+const DEFAULT_TOOL_TIMEOUT_SECS: u64 = 30;
+
+/// Resolve the execution timeout for a tool.
+///
+/// A `timeout_secs` value in the tool's configuration (or in `tools._default`)
+/// takes precedence over the global `TOOL_TIMEOUT_SECS` environment variable,
+/// which defaults to 30 seconds.
+///
+/// # Arguments
+/// * `tool_name` - Name of the tool being called
+fn tool_timeout(tool_name: &str) -> Duration {
+    let secs = utils::get_tool_config_value::<Option<u64>>(tool_name, "timeout_secs", None)
+        .or_else(|| {
+            std::env::var("TOOL_TIMEOUT_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+        })
+        .filter(|&secs| secs > 0)
+        .unwrap_or(DEFAULT_TOOL_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Time limit of one tool call.
+///
+/// This is the tool's timeout (see `tool_timeout`), shortened to the time left
+/// before the client's deadline when the request carries one that is sooner.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct CallTimeout {
+    /// Maximum time the call may run
+    pub duration: Duration,
+    /// Whether the limit comes from the client's deadline
+    from_deadline: bool,
+    /// The client's deadline for the call, if it set one
+    pub deadline: Option<SystemTime>,
+}
+
+impl CallTimeout {
+    /// Time limit for a call to `tool_name` that must finish by `deadline`, if set.
+    ///
+    /// # Arguments
+    /// * `tool_name` - Name of the tool being called
+    /// * `deadline` - The request's deadline (see `request_deadline`)
+    fn for_call(tool_name: &str, deadline: Option<SystemTime>) -> Self {
+        let duration = tool_timeout(tool_name);
+        let remaining = deadline.map(|deadline| {
+            deadline.duration_since(SystemTime::now()).unwrap_or_default()
+        });
+        match remaining {
+            Some(remaining) if remaining < duration => Self { duration: remaining, from_deadline: true, deadline },
+            _ => Self { duration, from_deadline: false, deadline },
+        }
+    }
+    
+    /// Message of the -32000 error for a call that ran out of time.
+    pub fn message(&self) -> &'static str {
+        if self.from_deadline {
+            "deadline exceeded"
+        } else {
+            "tool execution timed out"
+        }
+    }
+}
+
+/// Read a request's deadline from `_meta.deadlineMs` in its params.
+///
+/// The deadline is a Unix timestamp in milliseconds. Over HTTP it can also be
+/// sent in the `X-Request-Deadline-Ms` header (see `apply_deadline_header`).
+fn request_deadline(params: Option<&serde_json::Value>) -> Option<SystemTime> {
+    let ms = params?.get("_meta")?.get("deadlineMs")?.as_u64()?;
+    UNIX_EPOCH.checked_add(Duration::from_millis(ms))
+}
+
+/// Whether a request's deadline has already passed.
+fn deadline_passed(params: Option<&serde_json::Value>) -> bool {
+    request_deadline(params).is_some_and(|deadline| deadline <= SystemTime::now())
+}
+
+/// Header carrying the deadline of a `POST /mcp` request, in Unix milliseconds.
+const DEADLINE_HEADER: &str = "X-Request-Deadline-Ms";
+
+/// Copy the `X-Request-Deadline-Ms` header into a request's `_meta.deadlineMs`.
+///
+/// A deadline already present in the params takes precedence over the header.
+///
+/// # Arguments
+/// * `req` - Request to update
+/// * `deadline_ms` - Deadline from the header, in Unix milliseconds
+fn apply_deadline_header(req: &mut MCPRequest, deadline_ms: u64) {
+    let params = req.params.get_or_insert_with(|| serde_json::json!({}));
+    let Some(params) = params.as_object_mut() else {
+        return;
+    };
+    let meta = params.entry("_meta").or_insert_with(|| serde_json::json!({}));
+    if let Some(meta) = meta.as_object_mut() {
+        meta.entry("deadlineMs").or_insert(serde_json::json!(deadline_ms));
+    }
+}
+
+/// Run a tool handler, failing with a -32000 error if it exceeds `timeout`.
+///
+/// A handler that panics fails the call with a -32603 internal error instead of
+/// taking down the worker or transport loop serving it; the panic message is
+/// logged but not sent to the client.
+///
+/// Sync handlers run on tokio's blocking thread pool so they never stall the
+/// worker serving the request. A timed-out blocking handler cannot be stopped and
+/// keeps its blocking thread until it returns, but its result is discarded.
+/// Async handlers are dropped, and so cancelled, when they time out, and
+/// so are streaming handlers, whose chunks are collected into one text block.
+///
+/// # Arguments
+/// * `tool_name` - Name of the tool being called
+/// * `handler` - The tool's registered handler
+/// * `arguments` - Validated arguments for the call
+/// * `timeout` - Maximum time the handler may run, and the error reported when it is exceeded
+/// * `ctx` - Context of the call, providing the client's session (for
+///   `logging::log_to_client`) and the call's cancellation token
+/// * `progress` - Progress reporter for the call, passed in its `CallContext`
+async fn run_tool_handler(
+    tool_name: &str,
+    handler: RegisteredHandler,
+    arguments: serde_json::Value,
+    timeout: CallTimeout,
+    ctx: &MethodContext,
+    progress: ProgressReporter,
+) -> std::result::Result<std::result::Result<ToolOutput, ToolError>, MCPError> {
+    let timed_out = || MCPError::new(-32000, timeout.message());
+    let deadline = timeout.deadline;
+    let timeout = timeout.duration;
+    let session = ctx.session.clone();
+    let call = CallContext {
+        arguments,
+        session_id: Some(session.id.clone()),
+        cancellation: ctx.cancellation.clone(),
+        progress,
+        deadline,
+    };
+    
+    match handler {
+        RegisteredHandler::Sync(handler) => {
+            let task = tokio::task::spawn_blocking(move || {
+                logging::scope_call_blocking(session, || handler(call))
+            });
+            match tokio::time::timeout(timeout, task).await {
+                Ok(Ok(result)) => Ok(result),
+                Ok(Err(e)) if e.is_panic() => Err(handler_panicked(tool_name, e.into_panic())),
+                // The blocking task was cancelled
+                Ok(Err(e)) => Ok(Err(ToolError::ExecutionFailed(e.to_string()))),
+                Err(_) => Err(timed_out()),
+            }
+        }
+        RegisteredHandler::Async(handler) => {
+            // The handler is called inside the future so a panic creating it is caught too
+            let call = logging::scope_call(session, async move { handler(call).await });
+            match tokio::time::timeout(timeout, AssertUnwindSafe(call).catch_unwind()).await {
+                Ok(Ok(result)) => Ok(result),
+                Ok(Err(panic)) => Err(handler_panicked(tool_name, panic)),
+                Err(_) => Err(timed_out()),
+            }
+        }
+        // Transports that frame whole messages receive the collected output
+        RegisteredHandler::Stream(handler) => {
+            let call = logging::scope_call(session, async move { streaming::collect(handler(call)).await });
+            match tokio::time::timeout(timeout, AssertUnwindSafe(call).catch_unwind()).await {
+                Ok(Ok(result)) => Ok(result),
+                Ok(Err(panic)) => Err(handler_panicked(tool_name, panic)),
+                Err(_) => Err(timed_out()),
+            }
+        }
+    }
+}
+
+/// Log a tool handler's panic and build the -32603 error reporting it.
+///
+/// The panic message may reveal internals, so the client only gets a generic message.
+///
+/// # Arguments
+/// * `tool_name` - Name of the tool whose handler panicked
+/// * `panic` - Payload of the panic
+fn handler_panicked(tool_name: &str, panic: Box<dyn Any + Send>) -> MCPError {
+    utils::log_stderr!("Tool '{}' panicked: {}", tool_name, panic_message(panic.as_ref()));
+    MCPError::new(-32603, "Internal error: tool handler panicked")
+}
+
+/// The message of a panic, if its payload is a string.
+pub fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic.downcast_ref::<&str>().copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+/// Convert string arguments to the types the tool's input schema expects,
+/// when `MCP_COERCE_ARGS` is enabled.
+///
+/// Runs before validation, so values that cannot be converted are still
+/// reported as invalid.
+fn coerce_tool_arguments(registry: &ToolRegistry, tool_name: &str, arguments: &mut serde_json::Value) {
+    if !registry.coerce_arguments {
+        return;
+    }
+    if let Some(tool) = registry.get_tool(tool_name) {
+        validation::coerce_arguments(&tool.input_schema, arguments);
+    }
+}
+
+/// Validate tool call arguments against the tool's input schema.
+///
+/// Returns the warning messages to surface in `_meta.warnings` when the
+/// arguments are acceptable, or a -32602 error listing every hard error.
+///
+/// # Arguments
+/// * `registry` - Tool registry holding the tool definitions
+/// * `tool_name` - Name of the tool being called
+/// * `arguments` - Arguments supplied by the client
+fn validate_tool_arguments(
+    registry: &ToolRegistry,
+    tool_name: &str,
+    arguments: &serde_json::Value,
+) -> std::result::Result<Vec<String>, MCPError> {
+    let tool = match registry.get_tool(tool_name) {
+        Some(tool) => tool,
+        None => return Ok(Vec::new()),
+    };
+    
+    let report = validation::validate_arguments(&tool.input_schema, arguments);
+    if report.has_errors() {
+        let errors = report.errors();
+        let summary: Vec<String> = errors.iter()
+            .map(|issue| format!("{}: {}", issue.path, issue.message))
+            .collect();
+        return Err(MCPError {
+            code: -32602, // Invalid params
+            message: format!("Invalid params: {}", summary.join("; ")),
+            data: Some(serde_json::json!({
+                "tool": tool_name,
+                "missing": report.missing_fields(),
+                "errors": errors
+            })),
+        });
+    }
+    
+    Ok(report.warnings())
+}
+
+/// Validate a tool's structured result against its output schema.
+///
+/// Non-conforming output is a server-side bug, so it is reported as a -32603
+/// internal error listing every problem rather than returned to the client.
+///
+/// # Arguments
+/// * `schema` - The tool's output schema
+/// * `output` - Structured result returned by the tool handler
+fn validate_tool_output(
+    schema: &serde_json::Value,
+    output: &serde_json::Value,
+) -> std::result::Result<(), MCPError> {
+    let report = validation::validate_output(schema, output);
+    if report.has_errors() {
+        let errors = report.errors();
+        let summary: Vec<String> = errors.iter()
+            .map(|issue| format!("{}: {}", issue.path, issue.message))
+            .collect();
+        return Err(MCPError {
+            code: -32603, // Internal error
+            message: format!("Tool output does not match outputSchema: {}", summary.join("; ")),
+            data: Some(serde_json::json!({ "errors": errors })),
+        });
+    }
+    
+    Ok(())
+}
+
+/// Attach the `_meta` of a tool call result: the server's `instanceId`, and
+/// any validation or tool warnings under `warnings`.
+fn attach_meta(result: &mut serde_json::Value, warnings: Vec<String>) {
+    result["_meta"] = tool_result_meta(warnings);
+}
+
+/// Build the `_meta` member of a tool call result.
+///
+/// # Arguments
+/// * `warnings` - Warnings to report, omitted when empty
+pub(crate) fn tool_result_meta(warnings: Vec<String>) -> serde_json::Value {
+    let mut meta = serde_json::json!({ "instanceId": utils::instance_id() });
+    if !warnings.is_empty() {
+        meta["warnings"] = serde_json::json!(warnings);
+    }
+    meta
+}
+
+/// Format a tool's output as a successful tools/call result.
+///
+/// A plain JSON value is serialized into a single text block after applying
+/// the tool's output template, and doubles as the structured content of tools
+/// that declare an output schema. An explicit `ToolOutput` keeps its own
+/// content blocks (text, image or embedded resource), with the template applied
+/// to each text block and every block checked for required fields; if it has
+/// structured content but no blocks, the serialized structured content is
+/// added as a text block for clients that do not read `structuredContent`.
+///
+/// Structured content is validated against the tool's output schema, and a
+/// tool that declares a schema must return structured content.
+///
+/// # Arguments
+/// * `tool_name` - Name of the tool that produced the output
+/// * `tool` - The tool's definition, if registered
+/// * `output` - Output returned by the tool handler
+fn render_tool_output(
+    tool_name: &str,
+    tool: Option<&MCPTool>,
+    output: ToolOutput,
+) -> std::result::Result<serde_json::Value, MCPError> {
+    let output_schema = tool.and_then(|tool| tool.output_schema.as_ref());
+    
+    let (content, structured) = match output.value {
+        Some(value) => {
+            // Tools with an output schema return their value as structured content
+            let structured = output_schema.map(|_| value.clone());
+            let value = apply_output_template(tool_name, value);
+            let text = serde_json::to_string(&value).unwrap_or_default();
+            (vec![ContentBlock::Text { text }], structured)
+        }
+        None => {
+            let mut content: Vec<ContentBlock> = output.content.into_iter()
+                .map(|block| match block {
+                    ContentBlock::Text { text } => {
+                        let text = apply_output_template(tool_name, serde_json::Value::String(text));
+                        ContentBlock::Text { text: text.as_str().unwrap_or_default().to_string() }
+                    }
+                    other => other,
+                })
+                .collect();
+            // Malformed blocks are a server-side bug, not a tool failure
+            for block in &content {
+                block.validate().map_err(|e| {
+                    MCPError::new(-32603, format!("Invalid tool output: {}", e))
+                })?;
+            }
+            if content.is_empty()
+                && let Some(structured) = &output.structured_content
+            {
+                let text = serde_json::to_string(structured).unwrap_or_default();
+                content.push(ContentBlock::Text { text });
+            }
+            (content, output.structured_content)
+        }
+    };
+    
+    if let Some(schema) = output_schema {
+        match &structured {
+            Some(structured) => validate_tool_output(schema, structured)?,
+            None => {
+                return Err(MCPError::new(
+                    -32603,
+                    "Tool declares an outputSchema but returned no structured content",
+                ));
+            }
+        }
+    }
+    
+    let mut result = serde_json::json!({
+        "content": content,
+        "isError": false
+    });
+    if let Some(structured) = structured {
+        result["structuredContent"] = structured;
+    }
+    Ok(result)
+}
+
+/// The unwrapped result of a tool call made with `_meta.rawResult: true`.
+///
+/// A plain JSON value returned by the handler is passed through as is. For an
+/// explicit `ToolOutput`, the structured content is returned if there is any,
+/// otherwise the array of content blocks. Output templates and output schema
+/// validation do not apply, and validation warnings are dropped.
+fn raw_tool_result(output: ToolOutput) -> serde_json::Value {
+    match (output.value, output.structured_content) {
+        (Some(value), _) | (None, Some(value)) => value,
+        (None, None) => serde_json::json!(output.content),
+    }
+}
+
+/// Apply a tool's configured output template to its result.
+///
+/// Tools may declare an `output_template` in their configuration section, for
+/// example `output_template: "Result:\n```\n{result}\n```"`. Every `{result}`
+/// placeholder in the template is replaced with the tool's string output.
+///
+/// Only string outputs are templated: either a bare string result or the
+/// `{"result": "..."}` object returned by tools such as echo. Any other result
+/// is returned unchanged, as is every result when no template is configured.
+///
+/// # Arguments
+/// * `tool_name` - Name of the tool whose configuration holds the template
+/// * `result` - Result value returned by the tool handler
+fn apply_output_template(tool_name: &str, mut result: serde_json::Value) -> serde_json::Value {
+    let template: String = match utils::get_tool_config_value(tool_name, "output_template", None) {
+        Some(template) => template,
+        None => return result,
+    };
+
+    // Locate the string output, either the result itself or its "result" field
+    let output = match &mut result {
+        serde_json::Value::String(_) => Some(&mut result),
+        serde_json::Value::Object(map) => map.get_mut("result").filter(|v| v.is_string()),
+        _ => None,
+    };
+
+    if let Some(output) = output {
+        let rendered = template.replace("{result}", output.as_str().unwrap_or_default());
+        *output = serde_json::Value::String(rendered);
+    }
+
+    result
+}
+
+/// Initialize and register all tools.
+///
+/// This function is called by `ServerBuilder` to create the tool registry and
+/// register all built-in tools. Tools submitted as plugins are registered
+/// automatically; other tools are registered here explicitly. New tools can
+/// also be registered on the builder instead.
+///
+/// Tools named in the comma-separated `DISABLED_TOOLS` environment variable
+/// are removed again, so they are neither listed nor callable.
+///
+/// Every tool's input schema is then compiled as JSON Schema. A tool with an
+/// invalid schema is skipped, or the process exits when
+/// `STRICT_SCHEMA_VALIDATION` is `true`.
+///
+/// # Returns
+/// A ToolRegistry containing all built-in tools and handlers
+pub fn initialize_tools() -> ToolRegistry {
+    let mut registry = ToolRegistry::new();
+    
+    // Register every tool submitted as a plugin with `submit_tool_plugin!`
+    for plugin in plugin::plugins() {
+        plugin.register(&mut registry);
+    }
+    
+    // Register the remaining tools
+    // Add new tool registrations here following this pattern:
+    // tools::your_tool::register(&mut registry);
+    tools::calc::register(&mut registry);
+    tools::fetch::register(&mut registry);
+    tools::kv::register(&mut registry);
+    tools::read_file::register(&mut registry);
+    tools::list_dir::register(&mut registry);
+    tools::render_template::register(&mut registry);
+    tools::stats::register(&mut registry);
+    
+    // Tools that only exist to exercise the server, such as timeouts with a
+    // slow call, are registered when MCP_EXAMPLE_TOOLS is enabled
+    if utils::env_flag("MCP_EXAMPLE_TOOLS") {
+        tools::lines::register(&mut registry);
+        tools::long_task::register(&mut registry);
+        tools::pixel::register(&mut registry);
+        tools::read_files::register(&mut registry);
+        tools::session_counter::register(&mut registry);
+        tools::sleep::register(&mut registry);
+    }
+    
+    // Log the duration of every tool call when LOG_TOOL_TIMINGS is set
+    if utils::env_flag("LOG_TOOL_TIMINGS") {
+        registry.add_middleware(TimingMiddleware);
+    }
+    
+    // Append every executed call to the audit log when MCP_AUDIT_LOG_PATH is set
+    match AuditLog::from_env() {
+        Ok(audit) => registry.audit = audit,
+        Err(e) => {
+            utils::log_stderr!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+    
+    // Drop the tools disabled for this deployment (comma-separated names)
+    let disabled = utils::get_env_var("DISABLED_TOOLS", "");
+    for name in disabled.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        if registry.unregister(name) {
+            utils::log_stderr!("Tool '{}' disabled by DISABLED_TOOLS", name);
+        } else {
+            utils::log_stderr!("Warning: DISABLED_TOOLS names unknown tool '{}'", name);
+        }
+    }
+    
+    // A tool with a broken input schema is skipped, or aborts startup when strict
+    let strict = utils::env_flag("STRICT_SCHEMA_VALIDATION");
+    if let Err(e) = check_tool_schemas(&mut registry, strict) {
+        utils::log_stderr!("Error: {}", e);
+        std::process::exit(1);
+    }
+    
+    // Unknown or mistyped tool config keys are warnings, or abort startup when strict
+    let config = serde_json::Value::Object(utils::load_config().into_iter().collect());
+    let strict = utils::env_flag("STRICT_CONFIG_VALIDATION");
+    if let Err(e) = check_tool_configs(&registry, &config, strict) {
+        utils::log_stderr!("Error: {}", e);
+        std::process::exit(1);
+    }
+    
+    registry
+}
+
+/// Config keys the server reads for every tool, as schema `properties`.
+fn shared_tool_config_properties() -> serde_json::Value {
+    serde_json::json!({
+        "timeout_secs": { "type": "integer" },
+        "output_template": { "type": "string" }
+    })
+}
+
+/// Check the config section of every tool that declared a config schema.
+///
+/// Sections are checked with `validation::validate_config`, so keys the
+/// schema does not describe are reported as well as mistyped values. Tools
+/// without a config schema, and the shared `tools._default` section, are not
+/// checked. In lenient mode every problem is logged as a warning; a mistyped
+/// value is then ignored when the tool reads it. In strict mode the first
+/// problem is an error, and the caller is expected to abort startup.
+///
+/// # Arguments
+/// * `registry` - Registry whose tools' config schemas are used
+/// * `config` - Loaded configuration
+/// * `strict` - Whether a problem is fatal (`STRICT_CONFIG_VALIDATION`)
+fn check_tool_configs(
+    registry: &ToolRegistry,
+    config: &serde_json::Value,
+    strict: bool,
+) -> std::result::Result<(), String> {
+    let mut tools: Vec<(&String, &serde_json::Value)> = registry.config_schemas.iter().collect();
+    tools.sort_by(|a, b| a.0.cmp(b.0));
+    
+    for (tool, schema) in tools {
+        let Some(section) = config.get("tools").and_then(|tools| tools.get(tool.as_str())) else {
+            continue;
+        };
+        let mut schema = schema.clone();
+        if let Some(properties) = schema.get_mut("properties").and_then(|v| v.as_object_mut())
+            && let serde_json::Value::Object(shared) = shared_tool_config_properties() {
+            for (key, property) in shared {
+                properties.entry(key).or_insert(property);
+            }
+        }
+        
+        let report = validation::validate_config(&schema, section, &format!("tools.{}", tool));
+        for issue in &report.issues {
+            let message = format!("invalid config of tool '{}': {}: {}", tool, issue.path, issue.message);
+            if strict {
+                return Err(message);
+            }
+            utils::log_stderr!("Warning: {}", message);
+        }
+    }
+    Ok(())
+}
+
+/// Check every registered tool's input schema, logging each invalid one.
+///
+/// In lenient mode, tools with an invalid schema are unregistered so they are
+/// neither listed nor callable. In strict mode the first invalid schema is an
+/// error, and the caller is expected to abort startup.
+///
+/// # Arguments
+/// * `registry` - Registry whose tools are checked
+/// * `strict` - Whether an invalid schema is fatal (`STRICT_SCHEMA_VALIDATION`)
+fn check_tool_schemas(registry: &mut ToolRegistry, strict: bool) -> std::result::Result<(), String> {
+    let invalid: Vec<(String, String)> = registry.tools.iter()
+        .filter_map(|tool| {
+            validation::check_schema(&tool.input_schema)
+                .err()
+                .map(|e| (tool.name.clone(), e))
+        })
+        .collect();
+    
+    for (name, error) in invalid {
+        let message = format!("tool '{}' has an invalid input schema: {}", name, error);
+        if strict {
+            return Err(message);
+        }
+        utils::log_stderr!("Error: {}; skipping the tool", message);
+        registry.unregister(&name);
+    }
+    Ok(())
+}
+
+/// Initialize and register all JSON-RPC methods.
+///
+/// This function is called during server startup to create the method registry
+/// shared by every transport. Add new MCP methods here when implementing
+/// additional protocol features.
+///
+/// # Returns
+/// An Arc-wrapped MethodRegistry containing all registered methods and handlers
+pub fn initialize_methods() -> Arc<MethodRegistry> {
+    let mut methods = MethodRegistry::new();
+    
+    methods.register(MethodInfo {
+        name: "initialize".to_string(),
+        description: "Establish a session and negotiate protocol version and capabilities.".to_string(),
+        params_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "protocolVersion": { "type": "string" },
+                "capabilities": { "type": "object" },
+                "clientInfo": { "type": "object" }
+            }
+        }),
+    }, handle_initialize);
+    
+    methods.register(MethodInfo {
+        name: "tools/list".to_string(),
+        description: "List the available tools and their input schemas.".to_string(),
+        params_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "cursor": { "type": "string" }
+            }
+        }),
+    }, handle_tools_list);
+    
+    methods.register(MethodInfo {
+        name: "tools/describe".to_string(),
+        description: "Describe a single tool and its input and output schemas (non-standard).".to_string(),
+        params_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" }
+            },
+            "required": ["name"]
+        }),
+    }, handle_tools_describe);
+    
+    methods.register(MethodInfo {
+        name: "tools/call".to_string(),
+        description: "Call a tool with the given arguments.".to_string(),
+        params_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "arguments": { "type": "object" },
+                "validateOnly": { "type": "boolean" },
+                "_meta": {
+                    "type": "object",
+                    "properties": {
+                        "progressToken": { "type": ["string", "integer"] },
+                        "rawResult": { "type": "boolean" },
+                        "deadlineMs": { "type": "integer" }
+                    }
+                }
+            },
+            "required": ["name"]
+        }),
+    }, handle_tools_call);
+    
+    methods.register(MethodInfo {
+        name: "logging/setLevel".to_string(),
+        description: "Set the minimum level of log messages sent to the client.".to_string(),
+        params_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "level": {
+                    "type": "string",
+                    "enum": LogLevel::ALL.iter().map(|level| level.as_str()).collect::<Vec<_>>()
+                }
+            },
+            "required": ["level"]
+        }),
+    }, handle_logging_set_level);
+    
+    let uri_params = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "uri": { "type": "string" }
+        },
+        "required": ["uri"]
+    });
+    methods.register(MethodInfo {
+        name: "resources/subscribe".to_string(),
+        description: "Receive notifications/resources/updated when a resource changes.".to_string(),
+        params_schema: uri_params.clone(),
+    }, handle_resources_subscribe);
+    
+    methods.register(MethodInfo {
+        name: "resources/unsubscribe".to_string(),
+        description: "Stop receiving update notifications for a resource.".to_string(),
+        params_schema: uri_params,
+    }, handle_resources_unsubscribe);
+    
+    methods.register(MethodInfo {
+        name: "completion/complete".to_string(),
+        description: "Suggest values for an argument of a prompt or resource.".to_string(),
+        params_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "ref": {
+                    "type": "object",
+                    "properties": {
+                        "type": { "type": "string", "enum": ["ref/prompt", "ref/resource"] },
+                        "name": { "type": "string" },
+                        "uri": { "type": "string" }
+                    },
+                    "required": ["type"]
+                },
+                "argument": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "value": { "type": "string" }
+                    },
+                    "required": ["name", "value"]
+                }
+            },
+            "required": ["ref", "argument"]
+        }),
+    }, handle_completion_complete);
+    
+    methods.register(MethodInfo {
+        name: "ping".to_string(),
+        description: "Check that the server is alive.".to_string(),
+        params_schema: serde_json::json!({ "type": "object" }),
+    }, handle_ping);
+    
+    methods.register(MethodInfo {
+        name: "capabilities/probe".to_string(),
+        description: "Describe the methods, capabilities and tools this server supports.".to_string(),
+        params_schema: serde_json::json!({ "type": "object" }),
+    }, handle_capabilities_probe);
+    
+    methods.register(MethodInfo {
+        name: "server/stats".to_string(),
+        description: "Report request and tool call counts by method and tool, and the server's uptime (non-standard).".to_string(),
+        params_schema: serde_json::json!({ "type": "object" }),
+    }, handle_server_stats);
+    
+    // Read-only deployments expose the tool list but never run a tool
+    if utils::env_flag("MCP_READONLY") {
+        methods.disable("tools/call", "tools/call disabled in read-only mode");
+    }
+    
+    Arc::new(methods)
+}
+
+/// Transport the server communicates over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// JSON-RPC over standard input/output
+    Stdio,
+    /// JSON-RPC over HTTP
+    Http,
+    /// STDIO and HTTP concurrently
+    Both,
+    /// Line-delimited JSON-RPC over a Unix domain socket
+    Unix,
+}
+
+impl std::str::FromStr for Transport {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "stdio" => Ok(Transport::Stdio),
+            "http" => Ok(Transport::Http),
+            "both" => Ok(Transport::Both),
+            "unix" => Ok(Transport::Unix),
+            other => Err(format!(
+                "Invalid transport mode '{}'. Must be 'stdio', 'http', 'both', or 'unix'",
+                other
+            )),
+        }
+    }
+}
+
+/// Default path of the Unix domain socket used by the `unix` transport.
+pub const DEFAULT_SOCKET_PATH: &str = "/tmp/mcp-server.sock";
+
+/// Builder for configuring and running the MCP server from code.
+///
+/// The builder starts with the default name, version, transport and address,
+/// and with every built-in tool from `initialize_tools` registered. Additional
+/// tools can be registered on the builder, which lets another binary embed the
+/// server without editing `initialize_tools`.
+///
+/// # Example
+/// ```rust,ignore
+/// ServerBuilder::new()
+///     .name("my-server")
+///     .transport(Transport::Http)
+///     .port(8080)
+///     .register_tool(tool, handler)
+///     .run()
+///     .await
+/// ```
+pub struct ServerBuilder {
+    name: String,
+    version: String,
+    transport: Transport,
+    host: String,
+    port: u16,
+    socket_path: String,
+    tools: ToolRegistry,
+    completions: CompletionRegistry,
+}
+
+impl ServerBuilder {
+    /// Create a builder with default settings and the built-in tools.
+    ///
+    /// Defaults: name `mcp-server`, version `0.1.0`, transport `Both`,
+    /// host `0.0.0.0`, port 3000 and socket path `/tmp/mcp-server.sock`.
+    pub fn new() -> Self {
+        Self {
+            name: "mcp-server".to_string(),
+            version: "0.1.0".to_string(),
+            transport: Transport::Both,
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            socket_path: DEFAULT_SOCKET_PATH.to_string(),
+            tools: initialize_tools(),
+            completions: CompletionRegistry::new(),
+        }
+    }
+
+    /// Set the server name reported to clients.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set the server version reported to clients.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Set the transport to serve on.
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Set the address the HTTP server binds to.
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Set the port the HTTP server listens on.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Set the path of the Unix domain socket used by the `unix` transport.
+    pub fn socket_path(mut self, socket_path: impl Into<String>) -> Self {
+        self.socket_path = socket_path.into();
+        self
+    }
+
+    /// Register an additional tool alongside the built-in tools.
+    ///
+    /// # Arguments
+    /// * `tool` - Tool definition with name, description, and input schema
+    /// * `handler` - Function that executes the tool when called
+    #[allow(dead_code)] // Available for binaries that embed the server
+    pub fn register_tool(mut self, tool: MCPTool, handler: ToolHandler) -> Self {
+        self.tools.register(tool, handler);
+        self
+    }
+
+    /// Add middleware to run around every tool call.
+    ///
+    /// # Arguments
+    /// * `middleware` - Hooks to run before and after each call
+    #[allow(dead_code)] // Available for binaries that embed the server
+    pub fn tool_middleware(mut self, middleware: impl ToolMiddleware + 'static) -> Self {
+        self.tools.add_middleware(middleware);
+        self
+    }
+
+    /// Register a completion provider for an argument of a prompt or resource.
+    ///
+    /// # Arguments
+    /// * `reference` - Prompt or resource the argument belongs to
+    /// * `argument` - Name of the argument to complete
+    /// * `provider` - Source of suggestions, e.g. a `Fn(&str) -> Vec<String>` closure
+    #[allow(dead_code)] // Available for binaries that embed the server
+    pub fn register_completion(
+        mut self,
+        reference: CompletionRef,
+        argument: impl Into<String>,
+        provider: impl CompletionProvider + 'static,
+    ) -> Self {
+        self.completions.register(reference, argument, provider);
+        self
+    }
+
+    /// Run the server on the configured transport until it shuts down.
+    ///
+    /// With `Transport::Both`, STDIO runs in a background task and the HTTP
+    /// server in the foreground. The two fail independently: if STDIO ends or
+    /// fails, HTTP keeps running, and if HTTP fails to start or errors, STDIO
+    /// keeps running until its input ends. A normal HTTP shutdown stops STDIO.
+    pub async fn run(self) -> std::io::Result<()> {
+        let tools = Arc::new(self.tools);
+        let completions = Arc::new(self.completions);
+        
+        // Optional smoke test of every method's responses (MCP_SELF_TEST)
+        if self_test::enabled() {
+            let ctx = MethodContext::new(
+                AppState {
+                    server_name: self.name.clone(),
+                    server_version: self.version.clone(),
+                    instructions: server_instructions(),
+                },
+                tools.clone(),
+                initialize_methods(),
+                completions.clone(),
+            );
+            if !self_test::run_and_log(&ctx).await && self_test::abort_on_failure() {
+                return Err(std::io::Error::other("startup self-test failed"));
+            }
+        }
+        
+        match self.transport {
+            Transport::Stdio => run_server_stdio(self.name, self.version, tools, completions).await,
+            Transport::Http => {
+                run_server_http(self.name, self.version, self.host, self.port, tools, completions).await
+            }
+            #[cfg(unix)]
+            Transport::Unix => run_server_unix(self.name, self.version, self.socket_path, tools, completions).await,
+            #[cfg(not(unix))]
+            Transport::Unix => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "the unix transport is only available on Unix platforms",
+            )),
+            Transport::Both => {
+                let name = self.name.clone();
+                let version = self.version.clone();
+                let stdio_tools = tools.clone();
+                let stdio_completions = completions.clone();
+                
+                // Spawn STDIO server in a background task
+                // A STDIO failure is logged here and leaves the HTTP server running
+                let stdio_handle = tokio::spawn(async move {
+                    let result = run_server_stdio(name, version, stdio_tools, stdio_completions).await;
+                    if let Err(e) = &result {
+                        utils::log_stderr!("STDIO server error: {}", e);
+                    }
+                    result
+                });
+                
+                // Run HTTP server in the foreground
+                match run_server_http(self.name, self.version, self.host, self.port, tools, completions).await {
+                    // HTTP shut down normally (e.g. on a signal) - stop STDIO as well
+                    Ok(()) => {
+                        stdio_handle.abort();
+                        Ok(())
+                    }
+                    // HTTP failed (e.g. the port is in use) - keep serving STDIO until it ends
+                    Err(e) => {
+                        utils::log_stderr!("HTTP server error: {}; continuing with STDIO only", e);
+                        match stdio_handle.await {
+                            Ok(result) => result,
+                            Err(e) => Err(std::io::Error::other(e)),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for ServerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Run the MCP server in HTTP mode.
+///
+/// Configures and starts an Actix Web HTTP server with optimized settings
+/// for high-traffic production deployments. The server handles MCP protocol
+/// requests over HTTP/JSON-RPC 2.0.
+///
+/// # Arguments
+/// * `name` - Server name for MCP protocol responses
+/// * `version` - Server version string
+/// * `host` - Bind address (e.g., "0.0.0.0" for all interfaces)
+/// * `port` - Port number to listen on
+/// * `tools` - Registry of the tools to serve
+/// * `completions` - Registry of argument completion providers
+///
+/// # Configuration
+/// The server is configured with:
+/// - Worker threads: Auto-detected from CPU count (max 16)
+/// - Max connections: 10,000 concurrent connections
+/// - Connection rate limit: 1,000 connections per second
+/// - Keep-alive: 30 seconds
+/// - Request timeout: 30 seconds
+/// - Disconnect timeout: 2 seconds
+/// - Shutdown timeout: 10 seconds
+pub async fn run_server_http(
+    name: String,
+    version: String,
+    host: String,
+    port: u16,
+    tools: Arc<ToolRegistry>,
+    completions: Arc<CompletionRegistry>,
+) -> std::io::Result<()> {
+    use std::time::Duration;
+    use std::sync::atomic::AtomicU64;
+    
+    let bind_addr = format!("{}:{}", host, port);
+    
+    // Create application state shared across all worker threads
+    let app_state = web::Data::new(AppState {
+        server_name: name.clone(),
+        server_version: version.clone(),
+        instructions: server_instructions(),
+    });
+    
+    // Share the tool registry across worker threads
+    let tool_registry = web::Data::new(tools.clone());
+    
+    // Method context shared by every JSON-RPC endpoint
+    let method_context = web::Data::new(MethodContext::new(
+        app_state.get_ref().clone(),
+        tools,
+        initialize_methods(),
+        completions,
+    ));
+    
+    // Create atomic request counter for metrics endpoint
+    // Using AtomicU64 for lock-free counting across worker threads
+    let request_count = web::Data::new(AtomicU64::new(0));
+    let request_count_clone = request_count.clone();
+    
+    // Readiness reported by /readyz, set once startup completes
+    let readiness = web::Data::new(Readiness::new());
+    let readiness_flag = readiness.clone();
+    
+    // Registry of open SSE transport sessions, shared across worker threads
+    let sse_sessions = web::Data::new(SseSessions::new());
+    
+    // Optional bearer token protecting the MCP endpoints
+    let auth_token = std::env::var("MCP_AUTH_TOKEN").ok().filter(|t| !t.is_empty());
+    
+    // Optional token enabling the admin endpoints
+    let admin = web::Data::new(AdminControl::new(
+        std::env::var("MCP_ADMIN_TOKEN").ok().filter(|t| !t.is_empty()),
+    ));
+    let admin_server = admin.clone();
+    
+    // Tool calls over GET /mcp, for clients limited to GET requests
+    let get_tool_calls = GetToolCalls(utils::env_flag("MCP_HTTP_GET_TOOL_CALLS"));
+    
+    // Envelope around REST endpoint responses, for dashboards
+    let rest_envelope = RestEnvelope(utils::env_flag("MCP_REST_ENVELOPE"));
+    
+    // Development-only endpoints exposing runtime state
+    let debug_endpoints = DebugEndpoints(utils::env_flag("MCP_DEBUG_ENDPOINTS"));
+    
+    // Optional per-client rate limit, shared by every worker
+    let rate_limit_per_min = std::env::var("RATE_LIMIT_PER_MIN")
+        .ok()
+        .and_then(|s| s.parse::<u32>().ok())
+        .filter(|&n| n > 0);
+    let rate_limiter = rate_limit_per_min.map(|n| Arc::new(RateLimiter::new(n)));
+    
+    // Optional CORS for browser clients; an invalid origin list is a fatal error
+    let cors_origins = match middleware::cors_origins_from_env() {
+        Ok(origins) => origins,
+        Err(e) => {
+            utils::log_stderr!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    // Response compression; an unknown algorithm is a fatal error
+    let compression = match CompressionConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            utils::log_stderr!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    // Request logging, sampled by LOG_SAMPLE_RATE and shared by every worker
+    let request_log = RequestLog::from_env();
+    
+    // Content types accepted on POST /mcp, configurable via MCP_ACCEPTED_CONTENT_TYPES
+    let content_types = AcceptedContentTypes::from_env();
+    
+    // Maximum request body size, configurable via MAX_REQUEST_BYTES
+    let max_request_bytes = utils::parse_env_var("MAX_REQUEST_BYTES", DEFAULT_MAX_REQUEST_BYTES);
+    
+    // Request body decompression, capped at MAX_REQUEST_BYTES once decoded;
+    // an unknown algorithm is a fatal error
+    let decompression = match DecompressionConfig::from_env(max_request_bytes) {
+        Ok(config) => config,
+        Err(e) => {
+            utils::log_stderr!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    // Concurrent POST /mcp requests, shared by every worker (MAX_INFLIGHT_REQUESTS)
+    let inflight = web::Data::new(InflightLimit::from_env());
+    
+    // Sessions of clients that initialized over POST, shared by every worker
+    let http_sessions = web::Data::new(HttpSessions::from_env());
+    
+    // Determine optimal worker thread count
+    // Defaults to the CPUs available to the process (honouring cgroup quotas),
+    // capped at 16 to avoid excessive context switching
+    // Can be overridden via WORKER_THREADS environment variable
+    let (workers, workers_source) = utils::workers_from_env();
+    
+    // Connection limits and timeouts, each overridable via the environment
+    let max_connections = utils::parse_env_var("MAX_CONNECTIONS", 10000usize);
+    let max_connection_rate = utils::parse_env_var("MAX_CONNECTION_RATE", 1000usize);
+    let keep_alive_secs = utils::parse_env_var("KEEP_ALIVE_SECS", 30u64);
+    let client_timeout_secs = utils::parse_env_var("CLIENT_TIMEOUT_SECS", 30u64);
+    let client_disconnect_secs = utils::parse_env_var("CLIENT_DISCONNECT_SECS", 2u64);
+    let shutdown_timeout_secs = utils::parse_env_var("SHUTDOWN_TIMEOUT_SECS", 10u64);
+    
+    // HTTP/2 over cleartext (h2c, prior knowledge only) when MCP_HTTP2 is set.
+    // Over TLS, HTTP/2 is always offered through ALPN.
+    let http2 = utils::env_flag("MCP_HTTP2");
+    
+    // Optional TLS; a half-configured certificate/key pair is a fatal error
+    let tls_config = match tls::load_tls_config_from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            utils::log_stderr!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    // Log server startup information to stderr (not stdout to avoid interfering with JSON-RPC)
+    match utils::log_format() {
+        LogFormat::Text => {
+            eprintln!("MCP Server Starting (HTTP mode)");
+            eprintln!("  Name: {}", name);
+            eprintln!("  Version: {}", version);
+            eprintln!("  Bind Address: {}", bind_addr);
+            eprintln!("  Instance Id: {}", utils::instance_id());
+            eprintln!("  TLS: {}", if tls_config.is_some() { "enabled (HTTPS)" } else { "disabled" });
+            eprintln!(
+                "  HTTP/2: {}",
+                match (tls_config.is_some(), http2) {
+                    (true, _) => "negotiated via ALPN",
+                    (false, true) => "h2c (prior knowledge)",
+                    (false, false) => "disabled",
+                }
+            );
+            eprintln!("  Worker Threads: {} (from {})", workers, workers_source.as_str());
+            eprintln!("  Max Connections: {}", max_connections);
+            eprintln!("  Max Connection Rate: {}", max_connection_rate);
+            eprintln!(
+                "  Timeouts: keep-alive {}s, client request {}s, client disconnect {}s, shutdown {}s",
+                keep_alive_secs, client_timeout_secs, client_disconnect_secs, shutdown_timeout_secs
+            );
+            eprintln!("  Max Request Bytes: {}", max_request_bytes);
+            match inflight.max() {
+                0 => eprintln!("  Max In-Flight Requests: unlimited"),
+                max => eprintln!("  Max In-Flight Requests: {}", max),
+            }
+            eprintln!("  Accepted Content Types: {}", content_types.media_types().join(", "));
+            if compression.encodings.is_empty() {
+                eprintln!("  Compression: disabled");
+            } else {
+                eprintln!(
+                    "  Compression: {} (bodies of {} bytes or more)",
+                    compression.encodings.iter().map(|e| e.as_str()).collect::<Vec<_>>().join(", "),
+                    compression.min_bytes
+                );
+            }
+            if decompression.encodings.is_empty() {
+                eprintln!("  Request Decompression: disabled");
+            } else {
+                eprintln!(
+                    "  Request Decompression: {}",
+                    decompression.encodings.iter().map(|e| e.as_str()).collect::<Vec<_>>().join(", ")
+                );
+            }
+            eprintln!("  Request Log Sample Rate: {}", request_log.sample_rate());
+            eprintln!("  Auth: {}", if auth_token.is_some() { "bearer token required" } else { "disabled" });
+            eprintln!("  Admin Endpoints: {}", if admin.token.is_some() { "enabled" } else { "disabled" });
+            eprintln!("  Debug Endpoints: {}", if debug_endpoints.0 { "enabled" } else { "disabled" });
+            eprintln!("  Tool Calls over GET: {}", if get_tool_calls.0 { "enabled" } else { "disabled" });
+            eprintln!("  REST Envelope: {}", if rest_envelope.0 { "enabled" } else { "disabled" });
+            match rate_limit_per_min {
+                Some(limit) => eprintln!("  Rate Limit: {} requests/min per client", limit),
+                None => eprintln!("  Rate Limit: disabled"),
+            }
+            match &cors_origins {
+                Some(CorsOrigins::Any) => eprintln!("  CORS: any origin"),
+                Some(CorsOrigins::List(list)) => eprintln!("  CORS: {}", list.join(", ")),
+                None => eprintln!("  CORS: disabled"),
+            }
+            eprintln!("  MCP Protocol: JSON-RPC 2.0");
+        }
+        LogFormat::Json => utils::log_record(serde_json::json!({
+            "event": "startup",
+            "transport": "http",
+            "name": name,
+            "version": version,
+            "host": host,
+            "port": port,
+            "instance_id": utils::instance_id(),
+            "tls": tls_config.is_some(),
+            "http2": tls_config.is_some() || http2,
+            "workers": workers,
+            "workers_source": workers_source.as_str(),
+            "max_connections": max_connections,
+            "max_connection_rate": max_connection_rate,
+            "keep_alive_secs": keep_alive_secs,
+            "client_timeout_secs": client_timeout_secs,
+            "client_disconnect_secs": client_disconnect_secs,
+            "shutdown_timeout_secs": shutdown_timeout_secs,
+            "max_request_bytes": max_request_bytes,
+            "max_inflight_requests": inflight.max(),
+            "accepted_content_types": content_types.media_types(),
+            "compression_algorithms": compression.encodings.iter().map(|e| e.as_str()).collect::<Vec<_>>(),
+            "compression_min_bytes": compression.min_bytes,
+            "request_decompression_algorithms": decompression.encodings.iter().map(|e| e.as_str()).collect::<Vec<_>>(),
+            "log_sample_rate": request_log.sample_rate(),
+            "auth": auth_token.is_some(),
+            "admin": admin.token.is_some(),
+            "debug_endpoints": debug_endpoints.0,
+            "get_tool_calls": get_tool_calls.0,
+            "rest_envelope": rest_envelope.0,
+            "rate_limit_per_min": rate_limit_per_min,
+            "cors_allowed_origins": match &cors_origins {
+                Some(CorsOrigins::Any) => serde_json::json!("*"),
+                Some(CorsOrigins::List(list)) => serde_json::json!(list),
+                None => serde_json::Value::Null,
+            },
+            "protocol": "JSON-RPC 2.0"
+        })),
+    }
+    
+    // Create and configure HTTP server
+    let server = HttpServer::new(move || {
+        App::new()
+            // Share application state with all routes
+            .app_data(app_state.clone())
+            .app_data(tool_registry.clone())
+            .app_data(method_context.clone())
+            .app_data(request_count_clone.clone())
+            .app_data(sse_sessions.clone())
+            .app_data(readiness.clone())
+            .app_data(admin.clone())
+            .app_data(web::Data::new(debug_endpoints))
+            .app_data(web::Data::new(get_tool_calls))
+            .app_data(web::Data::new(rest_envelope))
+            // Limit request bodies; oversized ones are reported as JSON-RPC errors
+            .app_data(web::Data::new(BodyLimit(max_request_bytes)))
+            .app_data(web::Data::new(content_types.clone()))
+            .app_data(inflight.clone())
+            .app_data(http_sessions.clone())
+            // Compress responses above COMPRESSION_MIN_BYTES with the algorithms
+            // enabled by COMPRESSION_ALGORITHMS (brotli/gzip/deflate by default)
+            .wrap(Compression::new(compression.clone()))
+            // Decode gzip/zstd request bodies (REQUEST_DECOMPRESSION_ALGORITHMS),
+            // rejecting those that expand beyond MAX_REQUEST_BYTES
+            .wrap(RequestDecompression::new(decompression.clone()))
+            // Add security headers to all responses
+            .wrap(
+                DefaultHeaders::new()
+                    .add(("X-Content-Type-Options", "nosniff"))
+                    .add(("X-Frame-Options", "DENY"))
+                    .add(("X-XSS-Protection", "1; mode=block"))
+                    // Identify the replica that served the request (INSTANCE_ID)
+                    .add(("X-Instance-Id", utils::instance_id()))
+            )
+            // Require a bearer token on MCP endpoints when MCP_AUTH_TOKEN is set
+            .wrap(BearerAuth::new(auth_token.clone()))
+            // Rate limit MCP endpoints per client when RATE_LIMIT_PER_MIN is set.
+            // Wrapped after auth so it runs first and also throttles bad tokens.
+            .wrap(RateLimit::new(rate_limiter.clone()))
+            // Answer CORS preflights and add CORS headers when MCP_CORS_ALLOWED_ORIGINS
+            // is set. Wrapped last before logging so preflights skip auth and rate limits.
+            .wrap(middleware::cors(cors_origins.as_ref()))
+            // Log the request line, status and duration; successful requests are
+            // sampled at LOG_SAMPLE_RATE while errors are always logged
+            .wrap(request_log.clone())
+            // Register route handlers
+            .route("/health", web::get().to(health))
+            .route("/readyz", web::get().to(readyz))
+            .route("/version", web::get().to(version_info))
+            .route("/metrics", web::get().to(metrics_handler))
+            // MCP SSE transport - GET opens a session stream, requests are POSTed to /messages
+            .route("/sse", web::get().to(sse::sse_connect))
+            .route("/messages", web::post().to(sse::sse_message))
+            // MCP over Streamable HTTP - supports POST (requests), DELETE (cleanup)
+            .route("/sse", web::post().to(mcp_sse_handler))
+            .route("/sse", web::method(http::Method::DELETE).to(mcp_sse_handler))
+            // Legacy tools discovery endpoints
+            .route("/sse/tools", web::get().to(sse_tools_discovery))
+            .route("/tools/sse", web::get().to(sse_tools_discovery))
+            // REST tool schema introspection
+            .route("/tools", web::get().to(rest_tools_list))
+            .route("/tools/{name}/schema", web::get().to(rest_tool_schema))
+            // Static manifest for discovery before connecting
+            .route("/.well-known/mcp.json", web::get().to(well_known_manifest))
+            // OpenAPI description of the HTTP API
+            .route("/openapi.json", web::get().to(openapi_json))
+            // Admin endpoints, enabled by MCP_ADMIN_TOKEN
+            .route("/admin/shutdown", web::post().to(admin_shutdown))
+            // Runtime state snapshot, enabled by MCP_DEBUG_ENDPOINTS
+            .route("/debug/state", web::get().to(debug_state))
+            // Standard MCP JSON-RPC endpoint
+            .route("/mcp", web::post().to(mcp_handler_optimized))
+            // Read-only JSON-RPC over GET; tools/call only with MCP_HTTP_GET_TOOL_CALLS
+            .route("/mcp", web::get().to(mcp_get_handler))
+            // Ends a session started by a POSTed initialize (Mcp-Session-Id)
+            .route("/mcp", web::delete().to(end_http_session))
+            .route("/", web::post().to(mcp_handler_optimized))
+            .route("/", web::get().to(health))
+    })
+    .workers(workers)
+    // Connection limits for high-traffic scenarios
+    .max_connections(max_connections)
+    .max_connection_rate(max_connection_rate)
+    // Timeout configurations to prevent resource exhaustion
+    .keep_alive(Duration::from_secs(keep_alive_secs))
+    .client_request_timeout(Duration::from_secs(client_timeout_secs))
+    .client_disconnect_timeout(Duration::from_secs(client_disconnect_secs))
+    // Graceful shutdown timeout
+    .shutdown_timeout(shutdown_timeout_secs);
+    
+    // Serve HTTPS when TLS is configured, plain HTTP otherwise. With MCP_HTTP2,
+    // plain HTTP also accepts HTTP/2 connections that start with the h2 preface.
+    let server = match tls_config {
+        Some(config) => server.bind_rustls_0_23(&bind_addr, config)?,
+        None if http2 => server.bind_auto_h2c(&bind_addr)?,
+        None => server.bind(&bind_addr)?,
+    };
+    
+    // Operators can change kmcp.yaml and send SIGHUP instead of restarting
+    #[cfg(unix)]
+    spawn_config_reload_on_sighup();
+    
+    // Tools are registered by now; the server is ready unless the config is broken
+    match utils::check_config() {
+        Ok(()) => readiness_flag.set_ready(),
+        Err(e) => {
+            utils::log_stderr!("Not ready: invalid config file {}", e);
+            readiness_flag.set_not_ready(format!("invalid config file {}", e));
+        }
+    }
+    
+    // The admin shutdown endpoint stops the server through its handle
+    let server = server.run();
+    admin_server.set_server(server.handle());
+    server.await
+}
+
+/// Run the MCP server in STDIO mode.
+///
+/// Implements MCP protocol over standard input/output for compatibility with
+/// MCP Inspector and local development. The server reads JSON-RPC requests
+/// from stdin and writes responses to stdout, one message per line or, with
+/// `MCP_STDIO_FRAMING=content-length`, in LSP-style `Content-Length` frames.
+/// All logging goes to stderr to avoid interfering with the JSON-RPC protocol
+/// stream.
+///
+/// # Arguments
+/// * `name` - Server name for MCP protocol responses
+/// * `version` - Server version string
+/// * `tools` - Registry of the tools to serve
+/// * `completions` - Registry of argument completion providers
+///
+/// # Implementation Details
+/// - Uses buffered I/O with 8KB buffers for optimal throughput
+/// - Processes requests concurrently, except `initialize`
+/// - Sends no response to notifications (requests without IDs)
+/// - Flushes after each response for low latency
+/// - Writes an optional `STDIO_BANNER` notification before the first request
+pub async fn run_server_stdio(
+    name: String,
+    version: String,
+    tools: Arc<ToolRegistry>,
+    completions: Arc<CompletionRegistry>,
+) -> std::io::Result<()> {
+    use tokio::io::{BufReader, BufWriter};
+    
+    // Message framing on stdin/stdout, line-delimited unless configured otherwise
+    let framing = utils::parse_env_var("MCP_STDIO_FRAMING", Framing::Line);
+    let max_message_bytes = utils::parse_env_var("MCP_MAX_LINE_BYTES", DEFAULT_MAX_MESSAGE_BYTES);
+    
+    // Log startup information to stderr (not stdout to avoid interfering with JSON-RPC)
+    match utils::log_format() {
+        LogFormat::Text => {
+            eprintln!("MCP Server Starting (STDIO mode)");
+            eprintln!("  Name: {}", name);
+            eprintln!("  Version: {}", version);
+            eprintln!("  MCP Protocol: JSON-RPC 2.0");
+            eprintln!("  Framing: {}", framing);
+            eprintln!("  Max Message Bytes: {}", max_message_bytes);
+        }
+        LogFormat::Json => utils::log_record(serde_json::json!({
+            "event": "startup",
+            "transport": "stdio",
+            "name": name,
+            "version": version,
+            "framing": framing.to_string(),
+            "max_message_bytes": max_message_bytes,
+            "protocol": "JSON-RPC 2.0"
+        })),
+    }
+    
+    // Initialize tool and method registries and application state
+    let ctx = MethodContext::new(
+        AppState {
+            server_name: name,
+            server_version: version,
+            instructions: server_instructions(),
+        },
+        tools,
+        initialize_methods(),
+        completions,
+    );
+    
+    // STDIO is one long-lived connection, so it holds the client to the
+    // initialize-first handshake
+    let ctx = ctx.with_session(Arc::new(Session::with_lifecycle()));
+    
+    // Operators can change kmcp.yaml and send SIGHUP instead of restarting
+    #[cfg(unix)]
+    spawn_config_reload_on_sighup();
+    
+    // Optional banner announced to the client as a notification (off by default)
+    let banner = std::env::var("STDIO_BANNER").ok().filter(|b| !b.is_empty());
+    
+    // Set up buffered I/O for optimal performance
+    // 8KB buffer size balances memory usage with I/O efficiency
+    let stdin = BufReader::with_capacity(8192, tokio::io::stdin());
+    let stdout = BufWriter::with_capacity(8192, tokio::io::stdout());
+    
+    serve_stdio(stdin, stdout, &ctx, framing, max_message_bytes, banner.as_deref()).await
+}
+
+/// Reload the configuration file whenever the process receives SIGHUP.
+///
+/// Tools read the configuration per call, so new values (such as the echo
+/// prefix) apply from the next call. A file that fails to load is logged and
+/// the previous configuration kept. The handler is installed once per
+/// process, even when several transports run.
+#[cfg(unix)]
+fn spawn_config_reload_on_sighup() {
+    use tokio::signal::unix::{signal, SignalKind};
+    
+    static INSTALLED: AtomicBool = AtomicBool::new(false);
+    if INSTALLED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            utils::log_stderr!("Error installing SIGHUP handler, config reload is unavailable: {}", e);
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            match utils::reload_config() {
+                Ok(()) => match utils::config_path() {
+                    Some(path) => utils::log_stderr!("Reloaded config file {}", path),
+                    None => utils::log_stderr!("Reloaded config: MCP_CONFIG_PATH is not set, using tool defaults"),
+                },
+                Err(e) => utils::log_stderr!("Error reloading config file {}; keeping the previous config", e),
+            }
+        }
+    });
+}
+
+/// Run the MCP server on a Unix domain socket.
+///
+/// Each accepted connection runs the same line-delimited JSON-RPC loop as
+/// STDIO mode, with its own session, and all connections share one tool
+/// registry. A stale socket file left behind by a previous run is replaced,
+/// but a socket another server is still listening on is not. The socket file
+/// is removed when the server shuts down on SIGINT or SIGTERM.
+///
+/// # Arguments
+/// * `name` - Server name for MCP protocol responses
+/// * `version` - Server version string
+/// * `socket_path` - Filesystem path of the socket to listen on
+/// * `tools` - Registry of the tools to serve
+/// * `completions` - Registry of argument completion providers
+#[cfg(unix)]
+pub async fn run_server_unix(
+    name: String,
+    version: String,
+    socket_path: String,
+    tools: Arc<ToolRegistry>,
+    completions: Arc<CompletionRegistry>,
+) -> std::io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+    use tokio::io::BufReader;
+    use tokio::net::{UnixListener, UnixStream};
+    use tokio::signal::unix::{signal, SignalKind};
+    
+    // An existing socket file is stale unless something still accepts on it;
+    // anything other than a socket at the path is never removed
+    if let Ok(metadata) = std::fs::symlink_metadata(&socket_path) {
+        if !metadata.file_type().is_socket() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", socket_path),
+            ));
+        }
+        if UnixStream::connect(&socket_path).await.is_ok() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!("socket {} is already in use", socket_path),
+            ));
+        }
+        utils::log_stderr!("Removing stale socket file {}", socket_path);
+        std::fs::remove_file(&socket_path)?;
+    }
+    let listener = UnixListener::bind(&socket_path)?;
+    
+    let max_message_bytes = utils::parse_env_var("MCP_MAX_LINE_BYTES", DEFAULT_MAX_MESSAGE_BYTES);
+    
+    match utils::log_format() {
+        LogFormat::Text => {
+            eprintln!("MCP Server Starting (Unix socket mode)");
+            eprintln!("  Name: {}", name);
+            eprintln!("  Version: {}", version);
+            eprintln!("  Socket: {}", socket_path);
+            eprintln!("  MCP Protocol: JSON-RPC 2.0");
+        }
+        LogFormat::Json => utils::log_record(serde_json::json!({
+            "event": "startup",
+            "transport": "unix",
+            "name": name,
+            "version": version,
+            "socket": socket_path,
+            "max_message_bytes": max_message_bytes,
+            "protocol": "JSON-RPC 2.0"
+        })),
+    }
+    
+    let ctx = MethodContext::new(
+        AppState {
+            server_name: name,
+            server_version: version,
+            instructions: server_instructions(),
+        },
+        tools,
+        initialize_methods(),
+        completions,
+    );
+    
+    // Operators can change kmcp.yaml and send SIGHUP instead of restarting
+    spawn_config_reload_on_sighup();
+    
+    let mut terminate = signal(SignalKind::terminate())?;
+    let result = loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let stream = match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => break Err(e),
+                };
+                // Every connection is its own client, held to the initialize-first handshake
+                let ctx = ctx.with_session(Arc::new(Session::with_lifecycle()));
+                tokio::spawn(async move {
+                    let (reader, writer) = stream.into_split();
+                    let reader = BufReader::with_capacity(8192, reader);
+                    if let Err(e) = serve_stdio(reader, writer, &ctx, Framing::Line, max_message_bytes, None).await {
+                        utils::log_stderr!("Unix socket connection error: {}", e);
+                    }
+                });
+            }
+            _ = tokio::signal::ctrl_c() => break Ok(()),
+            _ = terminate.recv() => break Ok(()),
+        }
+    };
+    
+    // Remove the socket file so the next run can bind without cleanup
+    utils::log_stderr!("Shutting down, removing socket {}", socket_path);
+    if let Err(e) = std::fs::remove_file(&socket_path) {
+        utils::log_stderr!("Error removing socket file {}: {}", socket_path, e);
+    }
+    result
+}
+
+/// Serialize a response (or batch of responses) onto the STDIO output queue.
+fn queue_response(tx: &tokio::sync::mpsc::UnboundedSender<String>, response: &impl Serialize) {
+    match serde_json::to_string(response) {
+        Ok(json) => {
+            // A send only fails once the writer has stopped
+            let _ = tx.send(json);
+        }
+        // Serialization error - log and skip this response
+        Err(e) => utils::log_stderr!("Error serializing response: {}", e),
+    }
+}
+
+/// Serve JSON-RPC over an arbitrary reader/writer pair.
+///
+/// This is the protocol loop behind STDIO mode. It is generic over its I/O so
+/// the same loop can be driven by stdin/stdout, a socket or in-memory buffers.
+///
+/// Every outgoing message, whether a response or a server notification, is
+/// queued on one channel drained by a single writer. Notifications sent while a
+/// request is being handled (e.g. log messages from a tool) are therefore
+/// written as they happen, and messages are never interleaved.
+///
+/// # Arguments
+/// * `reader` - Buffered source of framed JSON-RPC requests
+/// * `stdout` - Destination for framed JSON-RPC responses
+/// * `ctx` - Method context used to dispatch requests
+/// * `framing` - How messages are delimited in both directions
+/// * `max_message_bytes` - Largest request accepted; larger ones get a -32700 error
+/// * `banner` - Optional banner sent as a `notifications/message` before any response
+pub async fn serve_stdio<R, W>(
+    mut reader: R,
+    mut stdout: W,
+    ctx: &MethodContext,
+    framing: Framing,
+    max_message_bytes: usize,
+    banner: Option<&str>,
+) -> std::io::Result<()>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    // Queue of serialized messages waiting to be written to stdout
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    
+    // The whole process is one session; its notifications share the queue
+    let notifications = tx.clone();
+    ctx.session.set_notifier(Arc::new(move |json: String| notifications.send(json).is_ok()));
+    
+    // Announce the banner first so it precedes every response on the stream.
+    // It is sent as a JSON-RPC notification to keep the protocol stream valid.
+    if let Some(banner) = banner {
+        ctx.session.notify(&MCPNotification::new("notifications/message", serde_json::json!({
+            "level": "info",
+            "logger": ctx.state.server_name,
+            "data": banner
+        })));
+    }
+    
+    // Main request processing loop
+    // Reads one message at a time from stdin. Each request is handled on its
+    // own task so the loop keeps reading while tools run, which lets a
+    // `notifications/cancelled` reach a call that is still in progress.
+    let read_loop = async move {
+        let mut in_flight = tokio::task::JoinSet::new();
+        
+        while let Some(message) = framing::read_message(&mut reader, framing, max_message_bytes).await? {
+            let message = match message {
+                Message::Complete(message) => message,
+                // Oversized input was skipped - report it and carry on with the next message
+                Message::TooLarge => {
+                    utils::log_stderr!("Parse error: message exceeds {} bytes", max_message_bytes);
+                    let error = MCPError::new(
+                        -32700,
+                        format!("Parse error: message exceeds {} bytes", max_message_bytes),
+                    );
+                    queue_response(&tx, &MCPResponse::error(None, error));
+                    continue;
+                }
+            };
+            
+            // Skip empty lines
+            if message.trim_ascii().is_empty() {
+                continue;
+            }
+            
+            // Show the request in readable form when MCP_PRETTY_LOG is set
+            utils::log_pretty("request", &message);
+            
+            // Reap finished request tasks
+            while in_flight.try_join_next().is_some() {}
+            
+            // Parse JSON-RPC request (or batch) from input line
+            match parse_message(&message) {
+                // initialize is handled before reading further, so requests that
+                // follow it on the stream always see the initialized session
+                Ok(IncomingMessage::Single(req)) if req.method == "initialize" => {
+                    if let Some(response) = dispatch_request(ctx.clone(), req).await {
+                        queue_response(&tx, &response);
+                    }
+                }
+                Ok(IncomingMessage::Batch(items)) => {
+                    // A batch is answered with one array once all its requests are done
+                    let ctx = ctx.clone();
+                    let tx = tx.clone();
+                    in_flight.spawn(async move {
+                        if let Some(responses) = dispatch_batch(ctx, items).await {
+                            queue_response(&tx, &responses);
+                        }
+                    });
+                }
+                Ok(IncomingMessage::Single(req)) => {
+                    // Process request through the shared method router
+                    let ctx = ctx.clone();
+                    let tx = tx.clone();
+                    in_flight.spawn(async move {
+                        // Notifications and cancelled requests produce no response
+                        if let Some(response) = dispatch_request(ctx, req).await {
+                            queue_response(&tx, &response);
+                        }
+                    });
+                }
+                Err(error_response) => {
+                    // Invalid input - report it with the shared parse error response
+                    utils::log_stderr!("Parse error: {}", String::from_utf8_lossy(&message));
+                    queue_response(&tx, &error_response);
+                }
+            }
+            
+            // A closed queue means the writer has failed
+            if tx.is_closed() {
+                break;
+            }
+        }
+        
+        // Input is exhausted - let in-flight requests finish, then release the
+        // queue so the writer can finish
+        while in_flight.join_next().await.is_some() {}
+        ctx.session.clear_notifier();
+        Ok::<(), std::io::Error>(())
+    };
+    
+    // Writer loop
+    // Each message is framed and flushed as soon as it is written
+    let write_loop = async move {
+        while let Some(message) = rx.recv().await {
+            utils::log_pretty("response", message.as_bytes());
+            if let Err(e) = framing::write_message(&mut stdout, framing, &message).await {
+                utils::log_stderr!("Error writing to stdout: {}", e);
+                break;
+            }
+        }
+        Ok::<(), std::io::Error>(())
+    };
+    
+    tokio::try_join!(read_loop, write_loop)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    
+    /// Method context of a server named "test" with the given tools.
+    fn test_context(registry: ToolRegistry) -> MethodContext {
+        let state = AppState {
+            server_name: "test".to_string(),
+            server_version: "0.0.0".to_string(),
+            instructions: None,
+        };
+        MethodContext::new(state, Arc::new(registry), initialize_methods(), Arc::new(CompletionRegistry::new()))
+    }
+    
+    /// Serve newline-delimited `input` as STDIO would and parse every message written back.
+    async fn serve_lines(ctx: &MethodContext, input: &str, banner: Option<&str>) -> Vec<serde_json::Value> {
+        let mut output = Vec::new();
+        serve_stdio(input.as_bytes(), &mut output, ctx, Framing::Line, DEFAULT_MAX_MESSAGE_BYTES, banner).await.unwrap();
+        String::from_utf8(output).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn slow_stdio_call_does_not_hold_up_later_requests() {
+        let mut registry = ToolRegistry::new();
+        tools::echo::register(&mut registry);
+        tools::sleep::register(&mut registry);
+        let ctx = test_context(registry);
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "sleep", "arguments": {"seconds": 0.5}}}"#, "\n",
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "echo", "arguments": {"message": "fast"}}}"#, "\n",
+        );
+        let mut output = Vec::new();
+
+        serve_stdio(input.as_bytes(), &mut output, &ctx, Framing::Line, DEFAULT_MAX_MESSAGE_BYTES, None).await.unwrap();
+
+        let ids: Vec<serde_json::Value> = String::from_utf8(output).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["id"].clone())
+            .collect();
+        assert_eq!(ids, [serde_json::json!(2), serde_json::json!(1)]);
+    }
+
+    #[tokio::test]
+    async fn panicking_handlers_fail_the_call_without_stopping_the_server() {
+        let mut registry = ToolRegistry::new();
+        tools::echo::register(&mut registry);
+        let panicking_tool = |name: &str| MCPTool {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema: serde_json::json!({ "type": "object" }),
+            output_schema: None,
+            cacheable: false,
+        };
+        registry.register(panicking_tool("panic_sync"), Box::new(|_| panic!("sync handler failed")));
+        registry.register_async(panicking_tool("panic_async"), Box::new(|_| Box::pin(async { panic!("async handler failed") })));
+        let ctx = test_context(registry);
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "panic_sync"}}"#, "\n",
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "panic_async"}}"#, "\n",
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {"name": "echo", "arguments": {"message": "still here"}}}"#, "\n",
+        );
+        let mut output = Vec::new();
+
+        serve_stdio(input.as_bytes(), &mut output, &ctx, Framing::Line, DEFAULT_MAX_MESSAGE_BYTES, None).await.unwrap();
+
+        let responses: HashMap<u64, serde_json::Value> = String::from_utf8(output).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .map(|response| (response["id"].as_u64().unwrap(), response))
+            .collect();
+        for id in [1, 2] {
+            assert_eq!(responses[&id]["error"]["code"], -32603);
+            assert_eq!(responses[&id]["error"]["message"], "Internal error: tool handler panicked");
+        }
+        let echoed = responses[&3]["result"]["content"][0]["text"].as_str().unwrap();
+        assert_eq!(echoed, "still here");
+    }
+
+    #[tokio::test]
+    async fn notifications_are_handled_without_a_response() {
+        let ctx = test_context(ToolRegistry::new());
+        let inbox = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let received = inbox.clone();
+        ctx.session.set_notifier(Arc::new(move |json: String| {
+            received.lock().unwrap().push(serde_json::from_str::<serde_json::Value>(&json).unwrap());
+            true
+        }));
+        ctx.session.set_log_level(LogLevel::Debug);
+        let notification = |method: &str| MCPRequest {
+            jsonrpc: "2.0".to_string(),
+            id: None,
+            method: method.to_string(),
+            params: None,
+        };
+
+        assert!(!ctx.session.is_ready());
+        assert!(dispatch_request(ctx.clone(), notification("notifications/initialized")).await.is_none());
+        assert!(ctx.session.is_ready());
+        assert!(dispatch_request(ctx.clone(), notification("notifications/progress")).await.is_none());
+        assert!(inbox.lock().unwrap().is_empty());
+
+        // Unknown notifications are logged at debug level
+        assert!(dispatch_request(ctx.clone(), notification("notifications/unheard_of")).await.is_none());
+        let logged = inbox.lock().unwrap().clone();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(logged[0]["method"], "notifications/message");
+        assert_eq!(logged[0]["params"]["level"], "debug");
+        assert!(logged[0]["params"]["data"].as_str().unwrap().contains("notifications/unheard_of"));
+    }
+
+    #[tokio::test]
+    async fn self_test_passes_for_every_method() {
+        let mut registry = ToolRegistry::new();
+        tools::echo::register(&mut registry);
+        let ctx = test_context(registry);
+
+        let report = self_test::run(&ctx).await;
+
+        assert!(report.is_success(), "self-test failed: {:?}", report.failed);
+        assert!(report.skipped.is_empty(), "methods skipped: {:?}", report.skipped);
+        let mut passed = report.passed.clone();
+        passed.sort();
+        let mut methods: Vec<String> = ctx.methods.methods.iter().map(|method| method.name.clone()).collect();
+        methods.sort();
+        assert_eq!(passed, methods);
+    }
+
+    #[tokio::test]
+    async fn resource_updates_reach_only_subscribed_sessions() {
+        let ctx = test_context(ToolRegistry::new());
+        let session_with_inbox = || {
+            let session = Arc::new(Session::new());
+            let inbox = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let received = inbox.clone();
+            session.set_notifier(Arc::new(move |json: String| {
+                received.lock().unwrap().push(serde_json::from_str::<serde_json::Value>(&json).unwrap());
+                true
+            }));
+            (session, inbox)
+        };
+        let (subscriber, subscriber_inbox) = session_with_inbox();
+        let (bystander, bystander_inbox) = session_with_inbox();
+        let uri = serde_json::json!({ "uri": "file:///notes.txt" });
+
+        handle_resources_subscribe(ctx.with_session(subscriber.clone()), Some(uri.clone())).await.unwrap();
+        let other = serde_json::json!({ "uri": "file:///other.txt" });
+        handle_resources_subscribe(ctx.with_session(bystander), Some(other)).await.unwrap();
+        let delivered = ctx.subscriptions.notify_resource_updated("file:///notes.txt");
+
+        assert_eq!(delivered, 1);
+        assert_eq!(*subscriber_inbox.lock().unwrap(), [serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/resources/updated",
+            "params": { "uri": "file:///notes.txt" }
+        })]);
+        assert!(bystander_inbox.lock().unwrap().is_empty());
+
+        // After unsubscribing, further updates are not delivered
+        handle_resources_unsubscribe(ctx.with_session(subscriber), Some(uri.clone())).await.unwrap();
+        assert_eq!(ctx.subscriptions.notify_resource_updated("file:///notes.txt"), 0);
+
+        // Sessions that cannot receive notifications may not subscribe
+        let error = handle_resources_subscribe(ctx.clone(), Some(uri)).await.unwrap_err();
+        assert_eq!(error.code, -32600);
+        assert!(ctx.capabilities.resources.as_ref().is_some_and(|resources| resources.subscribe));
+    }
+
+    #[test]
+    fn requests_without_a_method_are_invalid_not_unparseable() {
+        let error = |input: &str| {
+            let response = match parse_message(input.as_bytes()) {
+                Err(response) => serde_json::to_value(response).unwrap(),
+                Ok(_) => panic!("{} was accepted", input),
+            };
+            (response["id"].clone(), response["error"]["code"].clone(), response["error"]["message"].clone())
+        };
+
+        assert_eq!(
+            error(r#"{"jsonrpc": "2.0", "id": 7, "params": {}}"#),
+            (serde_json::json!(7), serde_json::json!(-32600), serde_json::json!("Invalid Request: missing method"))
+        );
+        assert_eq!(
+            error(r#"{"jsonrpc": "2.0", "id": "a", "method": 42}"#),
+            (serde_json::json!("a"), serde_json::json!(-32600), serde_json::json!("Invalid Request: method must be a string"))
+        );
+        assert_eq!(
+            error("42"),
+            (serde_json::Value::Null, serde_json::json!(-32600), serde_json::json!("Invalid Request: request must be a JSON object"))
+        );
+        let (id, code, _) = error(r#"{"jsonrpc": "2.0", "id": 7, "method""#);
+        assert_eq!((id, code), (serde_json::Value::Null, serde_json::json!(-32700)));
+    }
+
+    #[tokio::test]
+    async fn big_integers_are_returned_as_strings_when_enabled() {
+        let mut registry = ToolRegistry::new();
+        registry.register(MCPTool {
+            name: "snowflake".to_string(),
+            description: String::new(),
+            input_schema: serde_json::json!({ "type": "object" }),
+            output_schema: Some(serde_json::json!({ "type": "object" })),
+            cacheable: false,
+        }, Box::new(|_| Ok(serde_json::json!({ "id": 1u64 << 60, "negative": -(1i64 << 60), "small": 42 }).into())));
+        let params = serde_json::json!({ "name": "snowflake" });
+
+        let ctx = test_context(registry);
+        let result = handle_tools_call(ctx.clone(), Some(params.clone())).await.unwrap();
+        assert_eq!(result["structuredContent"]["id"], serde_json::json!(1152921504606846976u64));
+
+        let mut registry = Arc::into_inner(ctx.tools).unwrap();
+        registry.big_int_as_string = true;
+        let ctx = test_context(registry);
+        let result = handle_tools_call(ctx, Some(params)).await.unwrap();
+        assert_eq!(result["structuredContent"], serde_json::json!({
+            "id": "1152921504606846976",
+            "negative": "-1152921504606846976",
+            "small": 42
+        }));
+    }
+
+    #[tokio::test]
+    async fn sessions_have_independent_tool_state() {
+        let mut registry = ToolRegistry::new();
+        tools::session_counter::register(&mut registry);
+        let ctx = test_context(registry);
+        let first = ctx.with_session(Arc::new(Session::new()));
+        let second = ctx.with_session(Arc::new(Session::new()));
+        let count = |ctx: MethodContext| async move {
+            let params = serde_json::json!({ "name": "session_counter" });
+            let result = handle_tools_call(ctx, Some(params)).await.unwrap();
+            serde_json::from_str::<serde_json::Value>(result["content"][0]["text"].as_str().unwrap()).unwrap()
+        };
+
+        assert_eq!(count(first.clone()).await["count"], 1);
+        assert_eq!(count(first.clone()).await["count"], 2);
+        let counted = count(second.clone()).await;
+        assert_eq!(counted["count"], 1);
+        assert_eq!(counted["session_id"], second.session.id.as_str());
+        assert_eq!(count(first).await["count"], 3);
+
+        // initialize reports the same id the tools see
+        let initialized = handle_initialize(second.clone(), None).await.unwrap();
+        assert_eq!(initialized["_meta"]["sessionId"], second.session.id.as_str());
+    }
+
+    #[tokio::test]
+    async fn kv_store_is_scoped_to_the_session_and_capped() {
+        let mut registry = ToolRegistry::new();
+        tools::kv::register_with_limits(&mut registry, tools::kv::KvLimits { max_entries: 2, ttl: None });
+        let ctx = test_context(registry);
+        let first = ctx.with_session(Arc::new(Session::new()));
+        let second = ctx.with_session(Arc::new(Session::new()));
+        let call = |ctx: MethodContext, name: &str, arguments: serde_json::Value| {
+            let params = serde_json::json!({ "name": name, "arguments": arguments });
+            async move {
+                let result = handle_tools_call(ctx, Some(params)).await.unwrap();
+                let text = result["content"][0]["text"].as_str().unwrap().to_string();
+                (result["isError"].as_bool().unwrap(), serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text)))
+            }
+        };
+
+        let value = serde_json::json!({ "theme": "dark", "size": [1, 2] });
+        let (_, set) = call(first.clone(), "kv_set", serde_json::json!({ "key": "prefs", "value": value })).await;
+        assert_eq!(set["created"], true);
+        let (_, got) = call(first.clone(), "kv_get", serde_json::json!({ "key": "prefs" })).await;
+        assert_eq!((got["found"].clone(), got["value"].clone()), (serde_json::json!(true), value));
+
+        // Another session does not see the entry
+        let (_, got) = call(second.clone(), "kv_get", serde_json::json!({ "key": "prefs" })).await;
+        assert_eq!(got["found"], false);
+
+        // The cap counts new keys only; replacing a value always succeeds
+        call(first.clone(), "kv_set", serde_json::json!({ "key": "other", "value": 1 })).await;
+        let (is_error, error) = call(first.clone(), "kv_set", serde_json::json!({ "key": "third", "value": 1 })).await;
+        assert!(is_error);
+        assert_eq!(error, "Error: Session store is full: at most 2 entries");
+        let (is_error, _) = call(first.clone(), "kv_set", serde_json::json!({ "key": "other", "value": 2 })).await;
+        assert!(!is_error);
+
+        let (_, deleted) = call(first.clone(), "kv_delete", serde_json::json!({ "key": "prefs" })).await;
+        assert_eq!(deleted["deleted"], true);
+        let (is_error, _) = call(first, "kv_set", serde_json::json!({ "key": "third", "value": 1 })).await;
+        assert!(!is_error);
+    }
+
+    #[tokio::test]
+    async fn handlers_receive_the_call_context() {
+        let mut registry = ToolRegistry::new();
+        tools::echo::register(&mut registry);
+        let tool = MCPTool {
+            name: "whoami".to_string(),
+            description: "Report the call context".to_string(),
+            input_schema: serde_json::json!({ "type": "object" }),
+            output_schema: None,
+            cacheable: false,
+        };
+        registry.register(tool, Box::new(|call: CallContext| {
+            let deadline_ms = call.deadline
+                .and_then(|deadline| deadline.duration_since(UNIX_EPOCH).ok())
+                .map(|since| since.as_millis() as u64);
+            Ok(serde_json::json!({ "session_id": call.session_id, "deadline_ms": deadline_ms }).into())
+        }));
+
+        // Simple handlers can still be called with bare arguments
+        let Some(RegisteredHandler::Sync(echo)) = registry.handlers.get("echo").cloned() else {
+            panic!("echo is a sync tool");
+        };
+        let output = echo(serde_json::json!({ "message": "hi" }).into()).unwrap();
+        assert_eq!(output.structured_content, Some(serde_json::json!({ "result": "hi" })));
+
+        let ctx = test_context(registry);
+        let deadline_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64 + 60_000;
+        let params = serde_json::json!({ "name": "whoami", "_meta": { "deadlineMs": deadline_ms } });
+        let result = handle_tools_call(ctx.clone(), Some(params)).await.unwrap();
+        let reported: serde_json::Value = serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(reported["session_id"], ctx.session.id.as_str());
+        assert_eq!(reported["deadline_ms"], deadline_ms);
+    }
+
+    #[tokio::test]
+    async fn aliases_call_the_canonical_tool_without_being_listed() {
+        let mut registry = ToolRegistry::new();
+        tools::echo::register(&mut registry);
+        assert!(registry.add_alias("say", "echo"));
+        // Aliases must not shadow a tool or point at a missing one
+        assert!(!registry.add_alias("echo", "echo"));
+        assert!(!registry.add_alias("shout", "missing"));
+
+        let ctx = test_context(registry);
+        let call = |name: &str| serde_json::json!({ "name": name, "arguments": { "message": "hi" } });
+        let by_alias = handle_tools_call(ctx.clone(), Some(call("say"))).await.unwrap();
+        let by_name = handle_tools_call(ctx.clone(), Some(call("echo"))).await.unwrap();
+        assert_eq!(by_alias, by_name);
+        assert_eq!(by_alias["structuredContent"], serde_json::json!({ "result": "hi" }));
+
+        let listed = handle_tools_list(ctx.clone(), None).await.unwrap();
+        let names: Vec<&str> = listed["tools"].as_array().unwrap().iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["echo"]);
+    }
+
+    #[tokio::test]
+    async fn reloaded_config_applies_to_the_next_call() {
+        // A tool of its own keeps these settings away from tests calling echo
+        let mut registry = ToolRegistry::new();
+        let tool = MCPTool {
+            name: "greeting".to_string(),
+            description: "Report the configured greeting".to_string(),
+            input_schema: serde_json::json!({ "type": "object" }),
+            output_schema: None,
+            cacheable: false,
+        };
+        registry.register(tool, Box::new(|_| {
+            Ok(ToolOutput::text(utils::get_tool_config_value("greeting", "text", String::new())))
+        }));
+        let ctx = test_context(registry);
+        let config = utils::TestConfigFile::new("mcp-reload.yaml");
+        let greeting = || async {
+            let params = serde_json::json!({ "name": "greeting" });
+            let result = handle_tools_call(ctx.clone(), Some(params)).await.unwrap();
+            result["content"][0]["text"].as_str().unwrap().to_string()
+        };
+
+        config.load("tools:\n  greeting:\n    text: First\n").unwrap();
+        assert_eq!(greeting().await, "First");
+
+        config.load("tools:\n  greeting:\n    text: Second\n").unwrap();
+        assert_eq!(greeting().await, "Second");
+
+        // A broken file leaves the previous configuration in effect
+        assert!(config.load("tools: [unclosed\n").is_err());
+        assert_eq!(greeting().await, "Second");
+    }
+
+    /// Tool whose structured result must be an object with an integer `count`.
+    fn tool_with_output_schema() -> MCPTool {
+        MCPTool {
+            name: "counter".to_string(),
+            description: String::new(),
+            input_schema: serde_json::json!({ "type": "object" }),
+            output_schema: Some(serde_json::json!({
+                "type": "object",
+                "properties": { "count": { "type": "integer" } },
+                "required": ["count"]
+            })),
+            cacheable: false,
+        }
+    }
+
+    /// Registry holding one tool with a valid and one with an invalid input schema.
+    fn registry_with_schemas() -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        for (name, input_schema) in [
+            ("valid", serde_json::json!({ "type": "object", "properties": { "x": { "type": "string" } } })),
+            ("invalid", serde_json::json!({ "type": "object", "properties": { "x": { "type": "strng" } } })),
+        ] {
+            let tool = MCPTool {
+                name: name.to_string(),
+                description: String::new(),
+                input_schema,
+                output_schema: None,
+                cacheable: false,
+            };
+            registry.register(tool, Box::new(|_| Ok(ToolOutput::text("ok"))));
+        }
+        registry
+    }
+
+    #[test]
+    fn lenient_schema_check_skips_invalid_tools() {
+        let mut registry = registry_with_schemas();
+
+        check_tool_schemas(&mut registry, false).unwrap();
+
+        assert!(registry.get_tool("valid").is_some());
+        assert!(registry.get_tool("invalid").is_none());
+        assert!(!registry.handlers.contains_key("invalid"));
+    }
+
+    #[test]
+    fn strict_schema_check_rejects_invalid_tools() {
+        let mut registry = registry_with_schemas();
+
+        let error = check_tool_schemas(&mut registry, true).unwrap_err();
+
+        assert!(error.contains("'invalid'"), "unexpected error: {}", error);
+        assert!(registry.get_tool("valid").is_some());
+
+        registry.unregister("invalid");
+        check_tool_schemas(&mut registry, true).unwrap();
+    }
+
+    #[test]
+    fn tool_configs_are_checked_against_declared_schemas() {
+        let mut registry = ToolRegistry::new();
+        tools::echo::register(&mut registry);
+        let config = |echo: serde_json::Value| serde_json::json!({ "tools": { "echo": echo } });
+
+        // Declared keys and the keys every tool shares are accepted
+        let valid = config(serde_json::json!({ "prefix": "Echo: ", "timeout_secs": 5 }));
+        check_tool_configs(&registry, &valid, true).unwrap();
+
+        let typo = config(serde_json::json!({ "prefx": "Echo: " }));
+        let error = check_tool_configs(&registry, &typo, true).unwrap_err();
+        assert_eq!(error, "invalid config of tool 'echo': tools.echo.prefx: unknown config key");
+
+        let mistyped = config(serde_json::json!({ "prefix": 3 }));
+        let error = check_tool_configs(&registry, &mistyped, true).unwrap_err();
+        assert_eq!(error, "invalid config of tool 'echo': tools.echo.prefix: expected string, got number");
+
+        // Lenient mode only warns
+        check_tool_configs(&registry, &mistyped, false).unwrap();
+    }
+
+    #[test]
+    fn conforming_output_is_returned_as_structured_content() {
+        let tool = tool_with_output_schema();
+        let output = ToolOutput::from(serde_json::json!({ "count": 3 }));
+
+        let result = render_tool_output("counter", Some(&tool), output).unwrap();
+
+        assert_eq!(result["structuredContent"], serde_json::json!({ "count": 3 }));
+        assert_eq!(result["content"][0]["text"], r#"{"count":3}"#);
+        validate_tool_output(tool.output_schema.as_ref().unwrap(), &result["structuredContent"]).unwrap();
+    }
+
+    #[test]
+    fn non_conforming_output_is_an_internal_error() {
+        let tool = tool_with_output_schema();
+
+        for output in [
+            ToolOutput::from(serde_json::json!({ "count": "three" })),
+            ToolOutput::text("no structured content"),
+        ] {
+            let error = render_tool_output("counter", Some(&tool), output).unwrap_err();
+            assert_eq!(error.code, -32603, "{}", error.message);
+        }
+        let error = validate_tool_output(tool.output_schema.as_ref().unwrap(), &serde_json::json!({})).unwrap_err();
+        assert_eq!(error.code, -32603);
+        assert!(error.message.contains("count"), "unexpected message: {}", error.message);
+    }
+    
+    #[tokio::test]
+    async fn banner_is_the_first_message_written() {
+        let ping = concat!(r#"{"jsonrpc": "2.0", "id": 1, "method": "ping"}"#, "\n");
+        
+        let messages = serve_lines(&test_context(ToolRegistry::new()), ping, Some("Welcome to test")).await;
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert_eq!(messages[0], serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/message",
+            "params": { "level": "info", "logger": "test", "data": "Welcome to test" }
+        }));
+        assert_eq!(messages[1]["id"], 1);
+        
+        // Without a banner the response is the only message
+        let messages = serve_lines(&test_context(ToolRegistry::new()), ping, None).await;
+        assert_eq!(messages.len(), 1, "{:?}", messages);
+        assert_eq!(messages[0]["id"], 1);
+    }
+    
+    #[tokio::test]
+    async fn deprecated_fields_warn_while_missing_fields_reject() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counted = calls.clone();
+        let mut registry = ToolRegistry::new();
+        registry.register(MCPTool {
+            name: "greet".to_string(),
+            description: "Greet someone".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "nickname": { "type": "string", "deprecated": true }
+                },
+                "required": ["name"]
+            }),
+            output_schema: None,
+            cacheable: false,
+        }, Box::new(move |call: CallContext| {
+            counted.fetch_add(1, Ordering::SeqCst);
+            Ok(ToolOutput::text(format!("Hello, {}", call.arguments["name"].as_str().unwrap_or_default())))
+        }));
+        let ctx = test_context(registry);
+        
+        // A missing required field is an error: the call is rejected before the handler runs
+        let params = serde_json::json!({ "name": "greet", "arguments": { "nickname": "Al" } });
+        let error = handle_tools_call(ctx.clone(), Some(params)).await.unwrap_err();
+        assert_eq!(error.code, -32602);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        
+        // A deprecated field is a warning: the handler runs and the warning is passed back
+        let params = serde_json::json!({ "name": "greet", "arguments": { "name": "Alice", "nickname": "Al" } });
+        let result = handle_tools_call(ctx, Some(params)).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(result["content"][0]["text"], "Hello, Alice");
+        assert_eq!(result["_meta"]["warnings"], serde_json::json!(["arguments.nickname: field is deprecated"]));
+    }
+    
+    /// Names of the methods listed by a capabilities/probe of `ctx`.
+    async fn probed_methods(ctx: MethodContext) -> Vec<String> {
+        let probe = handle_capabilities_probe(ctx, None).await.unwrap();
+        probe["methods"].as_array().unwrap().iter()
+            .map(|method| method["name"].as_str().unwrap().to_string())
+            .collect()
+    }
+    
+    #[tokio::test]
+    async fn probe_lists_the_registered_methods() {
+        let ctx = test_context(ToolRegistry::new());
+        let probed = probed_methods(ctx.clone()).await;
+        
+        // Every probed method is dispatched, and every registered method is probed
+        for name in &probed {
+            assert!(ctx.methods.get(name).is_some(), "{} is probed but not handled", name);
+        }
+        for name in ["initialize", "ping", "tools/list", "tools/call", "capabilities/probe"] {
+            assert!(probed.iter().any(|probed| probed == name), "{} is not probed", name);
+        }
+        assert_eq!(probed.len(), ctx.methods.methods.len());
+        
+        // A method registered later is reflected without any change to the probe
+        let mut methods = Arc::try_unwrap(initialize_methods()).ok().unwrap();
+        methods.register(MethodInfo {
+            name: "custom/hello".to_string(),
+            description: "Say hello.".to_string(),
+            params_schema: serde_json::json!({ "type": "object" }),
+        }, |_ctx, _params| async { Ok(serde_json::json!({ "hello": "world" })) });
+        let state = AppState {
+            server_name: "test".to_string(),
+            server_version: "0.0.0".to_string(),
+            instructions: None,
+        };
+        let ctx = MethodContext::new(state, Arc::new(ToolRegistry::new()), Arc::new(methods), Arc::new(CompletionRegistry::new()));
+        let extended = probed_methods(ctx).await;
+        assert_eq!(extended.len(), probed.len() + 1);
+        assert!(extended.iter().any(|name| name == "custom/hello"), "{:?}", extended);
+    }
+    
+    #[tokio::test]
+    async fn reinitializing_at_an_older_version_drops_structured_content() {
+        let mut registry = ToolRegistry::new();
+        registry.register(tool_with_output_schema(), Box::new(|_| {
+            Ok(ToolOutput::text("3 items").with_structured(serde_json::json!({ "count": 3 })))
+        }));
+        let ctx = test_context(registry);
+        let initialize = |version: &str| serde_json::json!({ "protocolVersion": version, "capabilities": {} });
+        let call = || serde_json::json!({ "name": "counter", "arguments": {} });
+        
+        let result = handle_initialize(ctx.clone(), Some(initialize("2025-06-18"))).await.unwrap();
+        assert_eq!(result["protocolVersion"], "2025-06-18");
+        let tools = handle_tools_list(ctx.clone(), None).await.unwrap();
+        assert!(tools["tools"][0]["outputSchema"].is_object(), "{}", tools);
+        let result = handle_tools_call(ctx.clone(), Some(call())).await.unwrap();
+        assert_eq!(result["structuredContent"], serde_json::json!({ "count": 3 }));
+        
+        // The downgraded session gets only what its version defines
+        let result = handle_initialize(ctx.clone(), Some(initialize("2025-03-26"))).await.unwrap();
+        assert_eq!(result["protocolVersion"], "2025-03-26");
+        let tools = handle_tools_list(ctx.clone(), None).await.unwrap();
+        assert!(tools["tools"][0].get("outputSchema").is_none(), "{}", tools);
+        let result = handle_tools_call(ctx.clone(), Some(call())).await.unwrap();
+        assert!(result.get("structuredContent").is_none(), "{}", result);
+        assert_eq!(result["content"][0]["text"], "3 items");
+        
+        // An unsupported downgrade is rejected and the session keeps its version
+        let error = handle_initialize(ctx.clone(), Some(initialize("2023-01-01"))).await.unwrap_err();
+        assert_eq!(error.code, -32602);
+        assert_eq!(ctx.session.protocol_version(), "2025-03-26");
+        let result = handle_tools_call(ctx, Some(call())).await.unwrap();
+        assert!(result.get("structuredContent").is_none(), "{}", result);
+    }
+    
+    #[tokio::test]
+    async fn registering_a_name_twice_replaces_the_tool() {
+        let tool = |description: &str| MCPTool {
+            name: "twice".to_string(),
+            description: description.to_string(),
+            input_schema: serde_json::json!({ "type": "object" }),
+            output_schema: None,
+            cacheable: false,
+        };
+        let mut registry = ToolRegistry::new();
+        registry.register(tool("first"), Box::new(|_| Ok(ToolOutput::text("first"))));
+        tools::sleep::register(&mut registry);
+        registry.register_async(tool("second"), Box::new(|_| {
+            Box::pin(async { Ok(ToolOutput::text("second")) })
+        }));
+        
+        // The tool is listed once, in its original place, with the later definition
+        let names: Vec<&str> = registry.tools.iter().map(|tool| tool.name.as_str()).collect();
+        assert_eq!(names, ["twice", "sleep"]);
+        assert_eq!(registry.get_tool("twice").unwrap().description, "second");
+        
+        let ctx = test_context(registry);
+        let result = handle_tools_call(ctx, Some(serde_json::json!({ "name": "twice", "arguments": {} }))).await.unwrap();
+        assert_eq!(result["content"][0]["text"], "second");
+    }
+    
+    #[tokio::test]
+    async fn cancelled_stdio_calls_stop_without_a_response() {
+        let token = Arc::new(Mutex::new(None::<CancellationToken>));
+        let handed_out = token.clone();
+        let mut registry = ToolRegistry::new();
+        registry.register_async(MCPTool {
+            name: "wait".to_string(),
+            description: String::new(),
+            input_schema: serde_json::json!({ "type": "object" }),
+            output_schema: None,
+            cacheable: false,
+        }, Box::new(move |call: CallContext| {
+            *handed_out.lock().unwrap() = Some(call.cancellation);
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(ToolOutput::text("finished"))
+            })
+        }));
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "wait"}}"#, "\n",
+            r#"{"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": 1, "reason": "test"}}"#, "\n",
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "ping"}"#, "\n",
+        );
+        let started = Instant::now();
+        
+        let messages = serve_lines(&test_context(registry), input, None).await;
+        
+        // The handler's token fired and only the ping was answered
+        assert!(token.lock().unwrap().as_ref().expect("handler ran").is_cancelled());
+        assert!(started.elapsed() < Duration::from_secs(3), "took {:?}", started.elapsed());
+        assert_eq!(messages.len(), 1, "{:?}", messages);
+        assert_eq!(messages[0]["id"], 2);
+    }
+    
+    #[tokio::test]
+    async fn stdio_requires_initialize_before_tool_calls() {
+        let mut registry = ToolRegistry::new();
+        registry.register(MCPTool {
+            name: "hello".to_string(),
+            description: String::new(),
+            input_schema: serde_json::json!({ "type": "object" }),
+            output_schema: None,
+            cacheable: false,
+        }, Box::new(|_| Ok(ToolOutput::text("hi"))));
+        let ctx = test_context(registry).with_session(Arc::new(Session::with_lifecycle()));
+        let call = concat!(r#"{"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "hello"}}"#, "\n");
+        
+        let messages = serve_lines(&ctx, call, None).await;
+        assert_eq!(messages[0]["error"]["code"], -32002);
+        assert_eq!(messages[0]["error"]["message"], "server not initialized");
+        
+        let initialize = concat!(
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "initialize", "params": {"protocolVersion": "2025-06-18", "capabilities": {}, "clientInfo": {"name": "inspector", "version": "1.2.3"}}}"#,
+            "\n",
+        );
+        let messages = serve_lines(&ctx, initialize, None).await;
+        assert_eq!(messages[0]["result"]["protocolVersion"], "2025-06-18");
+        let client = ctx.session.client_info().expect("client info is stored");
+        assert_eq!((client.name.as_str(), client.version.as_str()), ("inspector", "1.2.3"));
+        
+        let messages = serve_lines(&ctx, call, None).await;
+        assert_eq!(messages[0]["result"]["content"][0]["text"], "hi");
+    }
+    
+    #[test]
+    fn tool_outputs_render_text_and_structured_content() {
+        let tool = MCPTool {
+            name: "plain".to_string(),
+            description: String::new(),
+            input_schema: serde_json::json!({ "type": "object" }),
+            output_schema: None,
+            cacheable: false,
+        };
+        
+        // An explicit output keeps its text block and its structured content
+        let output = ToolOutput::text("hello").with_structured(serde_json::json!({ "result": "hello" }));
+        let result = render_tool_output("plain", Some(&tool), output).unwrap();
+        assert_eq!(result, serde_json::json!({
+            "content": [{ "type": "text", "text": "hello" }],
+            "structuredContent": { "result": "hello" },
+            "isError": false
+        }));
+        
+        // A plain value is serialized into a text block only
+        let result = render_tool_output("plain", Some(&tool), serde_json::json!({ "n": 1 }).into()).unwrap();
+        assert_eq!(result, serde_json::json!({
+            "content": [{ "type": "text", "text": r#"{"n":1}"# }],
+            "isError": false
+        }));
+    }
+    
+    #[test]
+    fn image_blocks_need_a_mime_type() {
+        let output = ToolOutput::text("an image").with_content(ContentBlock::image(b"not really a png", ""));
+        
+        let error = render_tool_output("pixel", None, output).unwrap_err();
+        assert_eq!(error.code, -32603);
+        assert_eq!(error.message, "Invalid tool output: image content block is missing a mimeType");
+    }
+    
+    #[tokio::test]
+    async fn ping_is_answered_before_initialize() {
+        let ctx = test_context(ToolRegistry::new()).with_session(Arc::new(Session::with_lifecycle()));
+        let ping = concat!(r#"{"jsonrpc": "2.0", "id": "alive?", "method": "ping"}"#, "\n");
+        
+        let messages = serve_lines(&ctx, ping, None).await;
+        assert_eq!(messages, [serde_json::json!({ "jsonrpc": "2.0", "id": "alive?", "result": {} })]);
+        assert!(!ctx.session.is_initialized());
+    }
+    
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn builder_serves_its_custom_tools() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+        
+        let socket_path = std::env::temp_dir().join(format!("mcp-builder-test-{}.sock", std::process::id()));
+        let socket_path = socket_path.to_str().unwrap().to_string();
+        let server = ServerBuilder::new()
+            .name("embedded")
+            .transport(Transport::Unix)
+            .socket_path(socket_path.clone())
+            .register_tool(MCPTool {
+                name: "shout".to_string(),
+                description: "Upper-case a message".to_string(),
+                input_schema: serde_json::json!({ "type": "object" }),
+                output_schema: None,
+                cacheable: false,
+            }, Box::new(|call: CallContext| {
+                Ok(ToolOutput::text(call.arguments["message"].as_str().unwrap_or_default().to_uppercase()))
+            }));
+        let running = tokio::spawn(server.run());
+        
+        // Wait for the listener, then talk to it as a client would
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let stream = loop {
+            match tokio::net::UnixStream::connect(&socket_path).await {
+                Ok(stream) => break stream,
+                Err(_) if Instant::now() < deadline => tokio::time::sleep(Duration::from_millis(20)).await,
+                Err(e) => panic!("server did not start: {}", e),
+            }
+        };
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = tokio::io::BufReader::new(reader).lines();
+        let mut request = async |line: &str| -> serde_json::Value {
+            writer.write_all(format!("{}\n", line).as_bytes()).await.unwrap();
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap()
+        };
+        
+        let initialized = request(r#"{"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2025-06-18", "capabilities": {}}}"#).await;
+        assert_eq!(initialized["result"]["serverInfo"]["name"], "embedded");
+        let listed = request(r#"{"jsonrpc": "2.0", "id": 2, "method": "tools/list"}"#).await;
+        let shout = listed["result"]["tools"].as_array().unwrap().iter()
+            .find(|tool| tool["name"] == "shout")
+            .expect("the custom tool is listed");
+        assert_eq!(shout["description"], "Upper-case a message");
+        let called = request(r#"{"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {"name": "shout", "arguments": {"message": "hi"}}}"#).await;
+        assert_eq!(called["result"]["content"][0]["text"], "HI");
+        
+        running.abort();
+        let _ = std::fs::remove_file(&socket_path);
+    }
+    
+    #[tokio::test]
+    async fn ids_and_jsonrpc_versions_are_validated_over_stdio() {
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "id": {"nested": 1}, "method": "ping"}"#, "\n",
+            r#"{"jsonrpc": "1.0", "id": 2, "method": "ping"}"#, "\n",
+            r#"{"id": 3, "method": "ping"}"#, "\n",
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "ping"}"#, "\n",
+        );
+        
+        let mut messages = serve_lines(&test_context(ToolRegistry::new()), input, None).await;
+        messages.sort_by_key(|message| message["id"].as_u64());
+        let summary: Vec<(serde_json::Value, serde_json::Value)> = messages.iter()
+            .map(|message| (message["id"].clone(), message["error"]["code"].clone()))
+            .collect();
+        assert_eq!(summary, [
+            // An object id is never echoed back
+            (serde_json::Value::Null, serde_json::json!(-32600)),
+            (serde_json::json!(2), serde_json::json!(-32600)),
+            (serde_json::json!(3), serde_json::json!(-32600)),
+            (serde_json::json!(4), serde_json::Value::Null),
+        ]);
+        assert_eq!(messages[3]["result"], serde_json::json!({}));
+    }
+    
+    #[actix_rt::test]
+    async fn readyz_reports_the_readiness_flag() {
+        use actix_web::{test, App};
+        
+        let readiness = web::Data::new(Readiness::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(readiness.clone())
+                .route("/readyz", web::get().to(readyz)),
+        ).await;
+        let check = async || {
+            let response = test::call_service(&app, test::TestRequest::get().uri("/readyz").to_request()).await;
+            let status = response.status().as_u16();
+            (status, test::read_body_json::<serde_json::Value, _>(response).await)
+        };
+        
+        assert_eq!(check().await, (503, serde_json::json!({ "status": "not ready", "reason": "starting up" })));
+        
+        readiness.set_ready();
+        assert_eq!(check().await, (200, serde_json::json!({ "status": "ready" })));
+        
+        readiness.set_not_ready("configuration failed to load");
+        assert_eq!(check().await.1["reason"], "configuration failed to load");
+    }
+    
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_answers_initialize_despite_a_stale_socket_file() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+        
+        // A leftover socket nothing listens on, as after a crash
+        let socket_path = std::env::temp_dir().join(format!("mcp-unix-test-{}.sock", std::process::id()));
+        drop(std::os::unix::net::UnixListener::bind(&socket_path).unwrap());
+        let socket_path = socket_path.to_str().unwrap().to_string();
+        let running = tokio::spawn(run_server_unix(
+            "unix-test".to_string(),
+            "0.0.0".to_string(),
+            socket_path.clone(),
+            Arc::new(ToolRegistry::new()),
+            Arc::new(CompletionRegistry::new()),
+        ));
+        
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut stream = loop {
+            match tokio::net::UnixStream::connect(&socket_path).await {
+                Ok(stream) => break stream,
+                Err(_) if Instant::now() < deadline => tokio::time::sleep(Duration::from_millis(20)).await,
+                Err(e) => panic!("server did not start: {}", e),
+            }
+        };
+        stream.write_all(concat!(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2025-06-18", "capabilities": {}}}"#,
+            "\n",
+        ).as_bytes()).await.unwrap();
+        let line = tokio::io::BufReader::new(stream).lines().next_line().await.unwrap().unwrap();
+        let response: serde_json::Value = serde_json::from_str(&line).unwrap();
+        
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["protocolVersion"], "2025-06-18");
+        assert_eq!(response["result"]["serverInfo"]["name"], "unix-test");
+        
+        running.abort();
+        let _ = std::fs::remove_file(&socket_path);
+    }
+    
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_path_holding_a_regular_file_is_left_alone() {
+        let path = std::env::temp_dir().join(format!("mcp-unix-file-{}.sock", std::process::id()));
+        std::fs::write(&path, b"keep me").unwrap();
+        
+        let err = run_server_unix(
+            "unix-test".to_string(),
+            "0.0.0".to_string(),
+            path.to_str().unwrap().to_string(),
+            Arc::new(ToolRegistry::new()),
+            Arc::new(CompletionRegistry::new()),
+        ).await.unwrap_err();
+        
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read(&path).unwrap(), b"keep me");
+        std::fs::remove_file(&path).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn content_length_framed_initialize_gets_a_framed_response() {
+        let body = r#"{"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2025-06-18", "capabilities": {}, "clientInfo": {"name": "clïent", "version": "1"}}}"#;
+        let input = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+        let mut output = Vec::new();
+        
+        let ctx = test_context(ToolRegistry::new());
+        serve_stdio(input.as_bytes(), &mut output, &ctx, Framing::ContentLength, DEFAULT_MAX_MESSAGE_BYTES, None).await.unwrap();
+        
+        let output = String::from_utf8(output).unwrap();
+        let (header, response) = output.split_once("\r\n\r\n").expect("framed response");
+        assert_eq!(header, format!("Content-Length: {}", response.len()));
+        let response: serde_json::Value = serde_json::from_str(response).unwrap();
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["protocolVersion"], "2025-06-18");
+    }
+    
+    #[tokio::test]
+    async fn middleware_can_reject_calls_to_a_tool() {
+        struct Block(&'static str);
+        impl ToolMiddleware for Block {
+            fn before(&self, name: &str, _args: &serde_json::Value) -> std::result::Result<(), ToolError> {
+                if name == self.0 {
+                    return Err(ToolError::ExecutionFailed(format!("{} is blocked", name)));
+                }
+                Ok(())
+            }
+        }
+        
+        let runs = Arc::new(AtomicUsize::new(0));
+        let mut registry = ToolRegistry::new();
+        for name in ["hello", "forbidden"] {
+            let runs = runs.clone();
+            registry.register(MCPTool {
+                name: name.to_string(),
+                description: String::new(),
+                input_schema: serde_json::json!({ "type": "object" }),
+                output_schema: None,
+                cacheable: false,
+            }, Box::new(move |_| {
+                runs.fetch_add(1, Ordering::SeqCst);
+                Ok(ToolOutput::text("hi"))
+            }));
+        }
+        registry.add_middleware(Block("forbidden"));
+        let ctx = test_context(registry);
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "forbidden"}}"#, "\n",
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "hello"}}"#, "\n",
+        );
+        
+        // Responses only; the failure is also logged as a notification
+        let mut messages: Vec<_> = serve_lines(&ctx, input, None).await
+            .into_iter()
+            .filter(|message| message.get("id").is_some())
+            .collect();
+        messages.sort_by_key(|message| message["id"].as_i64());
+        assert_eq!(messages[0]["result"]["isError"], true, "{}", messages[0]);
+        assert_eq!(messages[0]["result"]["content"][0]["text"], "Error: forbidden is blocked");
+        assert_eq!(messages[1]["result"]["content"][0]["text"], "hi");
+        // The rejected call never reached its handler
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+    
+    #[tokio::test]
+    async fn oversized_stdio_lines_fail_without_losing_the_next_request() {
+        let ctx = test_context(ToolRegistry::new());
+        let long_line = format!(r#"{{"jsonrpc": "2.0", "id": 1, "method": "ping", "params": {{"pad": "{}"}}}}"#, "x".repeat(4096));
+        let input = format!("{}\n{}\n", long_line, r#"{"jsonrpc": "2.0", "id": 2, "method": "ping"}"#);
+        
+        let mut output = Vec::new();
+        serve_stdio(input.as_bytes(), &mut output, &ctx, Framing::Line, 1024, None).await.unwrap();
+        let messages: Vec<serde_json::Value> = String::from_utf8(output).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert_eq!(messages[0]["id"], serde_json::Value::Null);
+        assert_eq!(messages[0]["error"]["code"], -32700);
+        assert_eq!(messages[0]["error"]["message"], "Parse error: message exceeds 1024 bytes");
+        assert_eq!(messages[1]["id"], 2);
+        assert_eq!(messages[1]["result"], serde_json::json!({}));
+    }
+    
+    #[tokio::test]
+    async fn validate_only_over_stdio_does_not_run_the_tool() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let mut registry = ToolRegistry::new();
+        let counter = runs.clone();
+        registry.register(MCPTool {
+            name: "greet".to_string(),
+            description: String::new(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": { "name": { "type": "string" } },
+                "required": ["name"]
+            }),
+            output_schema: None,
+            cacheable: false,
+        }, Box::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(ToolOutput::text("hi"))
+        }));
+        let ctx = test_context(registry);
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "greet", "arguments": {"name": "Ada"}, "validateOnly": true}}"#, "\n",
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "greet", "arguments": {}, "validateOnly": true}}"#, "\n",
+        );
+        
+        let mut messages = serve_lines(&ctx, input, None).await;
+        messages.sort_by_key(|message| message["id"].as_i64());
+        assert_eq!(messages[0]["result"]["valid"], true, "{}", messages[0]);
+        assert_eq!(messages[1]["error"]["code"], -32602, "{}", messages[1]);
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+    }
+    
+    #[tokio::test]
+    async fn tools_are_listed_in_name_order() {
+        let mut registry = ToolRegistry::new();
+        for name in ["zeta", "alpha", "mu"] {
+            registry.register(MCPTool {
+                name: name.to_string(),
+                description: String::new(),
+                input_schema: serde_json::json!({ "type": "object" }),
+                output_schema: None,
+                cacheable: false,
+            }, Box::new(|_| Ok(ToolOutput::text("hi"))));
+        }
+        
+        let result = handle_tools_list(test_context(registry), None).await.unwrap();
+        
+        let names: Vec<&str> = result["tools"].as_array().unwrap().iter()
+            .filter_map(|tool| tool["name"].as_str())
+            .collect();
+        assert_eq!(names, ["alpha", "mu", "zeta"]);
+    }
+    
+    #[tokio::test]
+    async fn duplicate_ids_in_flight_are_rejected() {
+        let mut registry = ToolRegistry::new();
+        tools::sleep::register(&mut registry);
+        let ctx = test_context(registry);
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "id": 7, "method": "tools/call", "params": {"name": "sleep", "arguments": {"seconds": 0.3}}}"#, "\n",
+            r#"{"jsonrpc": "2.0", "id": 7, "method": "tools/call", "params": {"name": "sleep", "arguments": {"seconds": 0}}}"#, "\n",
+        );
+        
+        let messages = serve_lines(&ctx, input, None).await;
+        
+        // The duplicate is answered at once; the original still completes
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert_eq!(messages[0]["id"], 7);
+        assert_eq!(messages[0]["error"]["code"], -32600);
+        assert_eq!(messages[0]["error"]["message"], "Invalid Request: duplicate request id");
+        assert_eq!(messages[1]["id"], 7);
+        assert_eq!(messages[1]["result"]["isError"], false, "{}", messages[1]);
+        
+        // Once answered, the id can be used again
+        let input = concat!(r#"{"jsonrpc": "2.0", "id": 7, "method": "ping"}"#, "\n");
+        let messages = serve_lines(&ctx, input, None).await;
+        assert_eq!(messages[0]["result"], serde_json::json!({}));
+    }
+    
+    #[tokio::test]
+    async fn long_task_reports_progress_before_its_result() {
+        let mut registry = ToolRegistry::new();
+        tools::long_task::register(&mut registry);
+        let ctx = test_context(registry);
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "id": 6, "method": "tools/call", "params": {"name": "long_task", "arguments": {"steps": 3, "step_ms": 10}, "_meta": {"progressToken": "job-1"}}}"#,
+            "\n",
+        );
+        
+        let messages: Vec<_> = serve_lines(&ctx, input, None).await
+            .into_iter()
+            .filter(|message| message["method"] != "notifications/message")
+            .collect();
+        
+        let (response, notifications) = messages.split_last().expect("a response");
+        assert_eq!(response["id"], 6);
+        assert_eq!(response["result"]["isError"], false, "{}", response);
+        let progress: Vec<f64> = notifications.iter()
+            .map(|notification| {
+                assert_eq!(notification["method"], "notifications/progress");
+                assert_eq!(notification["params"]["progressToken"], "job-1");
+                assert_eq!(notification["params"]["total"], 3.0);
+                notification["params"]["progress"].as_f64().unwrap()
+            })
+            .collect();
+        assert_eq!(progress, [0.0, 1.0, 2.0, 3.0]);
+    }
+    
+    #[tokio::test]
+    async fn cache_hits_skip_the_handler() {
+        let mut registry = ToolRegistry::new();
+        registry.cache = ToolCache::new(8);
+        let calls: Arc<Mutex<HashMap<String, usize>>> = Arc::default();
+        for (name, cacheable) in [("square", true), ("tick", false)] {
+            let calls = calls.clone();
+            registry.register(MCPTool {
+                name: name.to_string(),
+                description: String::new(),
+                input_schema: serde_json::json!({ "type": "object" }),
+                output_schema: None,
+                cacheable,
+            }, Box::new(move |call: CallContext| {
+                *calls.lock().unwrap().entry(name.to_string()).or_default() += 1;
+                let n = call.arguments.get("n").and_then(|v| v.as_i64()).unwrap_or(0);
+                Ok(ToolOutput::text((n * n).to_string()))
+            }));
+        }
+        let ctx = test_context(registry);
+        let call = |name: &str, arguments: serde_json::Value| {
+            handle_tools_call(ctx.clone(), Some(serde_json::json!({ "name": name, "arguments": arguments })))
+        };
+        
+        // The same arguments in any key order hit the cache
+        let first = call("square", serde_json::json!({ "n": 3, "label": "x" })).await.unwrap();
+        let second = call("square", serde_json::json!({ "label": "x", "n": 3 })).await.unwrap();
+        assert_eq!(first["content"], second["content"]);
+        assert_eq!(calls.lock().unwrap()["square"], 1);
+        
+        // Other arguments miss it
+        let other = call("square", serde_json::json!({ "n": 4 })).await.unwrap();
+        assert_eq!(other["content"][0]["text"], "16");
+        assert_eq!(calls.lock().unwrap()["square"], 2);
+        
+        // Tools that opt out always run
+        call("tick", serde_json::json!({ "n": 3 })).await.unwrap();
+        call("tick", serde_json::json!({ "n": 3 })).await.unwrap();
+        assert_eq!(calls.lock().unwrap()["tick"], 2);
+    }
+    
+    #[tokio::test]
+    async fn a_panicking_call_is_followed_by_a_working_one() {
+        let mut registry = ToolRegistry::new();
+        let tool = |name: &str| MCPTool {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema: serde_json::json!({ "type": "object" }),
+            output_schema: None,
+            cacheable: false,
+        };
+        registry.register(tool("flaky"), Box::new(|call: CallContext| {
+            if call.arguments.get("fail").is_some() {
+                panic!("flaky handler failed");
+            }
+            Ok(ToolOutput::text("fine"))
+        }));
+        let ctx = test_context(registry);
+        
+        let error = handle_tools_call(ctx.clone(), Some(serde_json::json!({ "name": "flaky", "arguments": { "fail": true } })))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, -32603);
+        assert_eq!(error.message, "Internal error: tool handler panicked");
+        
+        let result = handle_tools_call(ctx, Some(serde_json::json!({ "name": "flaky", "arguments": {} }))).await.unwrap();
+        assert_eq!(result["content"][0]["text"], "fine");
+    }
+    
+    #[tokio::test]
+    async fn async_and_streaming_handlers_receive_the_call_context() {
+        let tool = |name: &str| MCPTool {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema: serde_json::json!({ "type": "object" }),
+            output_schema: None,
+            cacheable: false,
+        };
+        let mut registry = ToolRegistry::new();
+        registry.register_async(tool("async_session"), Box::new(|call: CallContext| {
+            Box::pin(async move {
+                call.progress.report(1.0, None, None);
+                Ok(ToolOutput::text(call.session_id.unwrap_or_default()))
+            })
+        }));
+        registry.register_stream(tool("stream_session"), Box::new(|call: CallContext| {
+            call.progress.report(1.0, None, None);
+            let session_id = call.session_id.unwrap_or_default();
+            Box::pin(futures_util::stream::once(async move { Ok(Bytes::from(session_id)) }))
+        }));
+        let ctx = test_context(registry);
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "async_session", "_meta": {"progressToken": "a"}}}"#, "\n",
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "stream_session", "_meta": {"progressToken": "s"}}}"#, "\n",
+        );
+        
+        let messages = serve_lines(&ctx, input, None).await;
+        
+        for (id, token) in [(1, "a"), (2, "s")] {
+            let response = messages.iter().find(|message| message["id"] == id).expect("a response");
+            assert_eq!(response["result"]["content"][0]["text"], ctx.session.id.as_str());
+            assert!(
+                messages.iter().any(|message| message["method"] == "notifications/progress"
+                    && message["params"]["progressToken"] == token),
+                "no progress for {}: {:?}", token, messages
+            );
         }
     }
 }
@@ -0,0 +1,370 @@
+//! Client Session State
+//!
+//! A session holds the state negotiated with one client connection: the
+//! protocol version agreed during `initialize` and the capabilities the client
+//! declared. STDIO mode serves a single session for the lifetime of the process,
+//! each SSE stream is its own session, and plain HTTP requests are stateless
+//! (every request gets a fresh session) unless they send back the
+//! `Mcp-Session-Id` of an earlier `initialize` (see `HttpSessions`).
+//!
+//! Sessions on streaming transports also carry a notifier, through which the
+//! server sends notifications (such as log messages) to the client.
+
+use dashmap::{mapref::entry::Entry, DashMap};
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::core::logging::LogLevel;
+use crate::core::server::MCPNotification;
+use crate::core::utils;
+
+/// Protocol versions this server can speak, newest first.
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Protocol version used before a client has negotiated one.
+pub const DEFAULT_PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// First protocol version with `structuredContent` and tool `outputSchema`.
+pub const STRUCTURED_CONTENT_VERSION: &str = "2025-06-18";
+
+/// Check whether a protocol version is supported by this server.
+pub fn is_supported_version(version: &str) -> bool {
+    SUPPORTED_PROTOCOL_VERSIONS.contains(&version)
+}
+
+/// Name and version of the client application, from `initialize`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientInfo {
+    /// Client application name
+    pub name: String,
+    /// Client application version
+    #[serde(default)]
+    pub version: String,
+}
+
+/// Values agreed with the client during `initialize`.
+#[derive(Debug, Clone)]
+struct Negotiated {
+    /// Protocol version in effect for the session
+    protocol_version: String,
+    /// Capabilities the client declared
+    client_capabilities: serde_json::Value,
+    /// Client application the client reported, if any
+    client_info: Option<ClientInfo>,
+}
+
+/// Delivers a serialized JSON-RPC message to a session's client.
+///
+/// Returns false if the client can no longer be reached.
+pub type Notifier = Arc<dyn Fn(String) -> bool + Send + Sync>;
+
+/// Minimum log level sent to clients that have not called `logging/setLevel`.
+pub const DEFAULT_LOG_LEVEL: LogLevel = LogLevel::Info;
+
+/// State for a single client connection.
+pub struct Session {
+    /// Unique session identifier
+    pub id: String,
+    /// Negotiated state, None until the client has initialized
+    negotiated: Mutex<Option<Negotiated>>,
+    /// Whether the client has sent `notifications/initialized`
+    ready: AtomicBool,
+    /// Minimum severity of log messages sent to the client
+    log_level: Mutex<LogLevel>,
+    /// Channel to the client for server-initiated messages, if the transport has one
+    notifier: Mutex<Option<Notifier>>,
+    /// Requests being handled, keyed by serialized request id, with their cancellation tokens
+    in_flight: DashMap<String, CancellationToken>,
+    /// Whether tool calls are rejected until the client has initialized
+    requires_initialize: bool,
+}
+
+impl Session {
+    /// Create a new, uninitialized session with a random id.
+    pub fn new() -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            negotiated: Mutex::new(None),
+            ready: AtomicBool::new(false),
+            log_level: Mutex::new(DEFAULT_LOG_LEVEL),
+            notifier: Mutex::new(None),
+            in_flight: DashMap::new(),
+            requires_initialize: false,
+        }
+    }
+
+    /// Create a session that enforces the MCP lifecycle.
+    ///
+    /// Used for long-lived connections (STDIO), where a client calling tools
+    /// before `initialize` is violating the handshake. Plain HTTP requests are
+    /// stateless and so cannot be held to it.
+    pub fn with_lifecycle() -> Self {
+        Self {
+            requires_initialize: true,
+            ..Self::new()
+        }
+    }
+
+    /// Whether the client has completed `initialize`.
+    pub fn is_initialized(&self) -> bool {
+        self.negotiated.lock().unwrap().is_some()
+    }
+
+    /// Record that the client sent `notifications/initialized`, completing the handshake.
+    pub fn mark_ready(&self) {
+        self.ready.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the client has completed the handshake with `notifications/initialized`.
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Relaxed)
+    }
+
+    /// Whether requests other than `initialize` must wait for initialization.
+    pub fn requires_initialize(&self) -> bool {
+        self.requires_initialize
+    }
+
+    /// Client application reported during `initialize`.
+    #[allow(dead_code)] // Available for features that depend on the client application
+    pub fn client_info(&self) -> Option<ClientInfo> {
+        self.negotiated.lock().unwrap()
+            .as_ref()
+            .and_then(|n| n.client_info.clone())
+    }
+
+    /// Minimum severity of log messages sent to the client.
+    pub fn log_level(&self) -> LogLevel {
+        *self.log_level.lock().unwrap()
+    }
+
+    /// Set the minimum severity of log messages sent to the client.
+    pub fn set_log_level(&self, level: LogLevel) {
+        *self.log_level.lock().unwrap() = level;
+    }
+
+    /// Attach the channel used to send notifications to the client.
+    pub fn set_notifier(&self, notifier: Notifier) {
+        *self.notifier.lock().unwrap() = Some(notifier);
+    }
+
+    /// Detach the notification channel, e.g. once the transport has closed.
+    pub fn clear_notifier(&self) {
+        *self.notifier.lock().unwrap() = None;
+    }
+
+    /// Whether the session has a channel for notifications to the client.
+    pub fn can_notify(&self) -> bool {
+        self.notifier.lock().unwrap().is_some()
+    }
+
+    /// Send a notification to the client.
+    ///
+    /// # Returns
+    /// Whether the notification was handed to the transport. Sessions without a
+    /// notifier (plain HTTP requests) cannot receive notifications.
+    pub fn notify(&self, notification: &MCPNotification) -> bool {
+        // Clone the notifier so the lock is not held while sending
+        let notifier = self.notifier.lock().unwrap().clone();
+        match (notifier, serde_json::to_string(notification)) {
+            (Some(notifier), Ok(json)) => notifier(json),
+            _ => false,
+        }
+    }
+
+    /// Protocol version in effect for this session.
+    ///
+    /// Features whose wire format depends on the protocol version should
+    /// consult this rather than assuming the newest version.
+    pub fn protocol_version(&self) -> String {
+        self.negotiated.lock().unwrap()
+            .as_ref()
+            .map(|n| n.protocol_version.clone())
+            .unwrap_or_else(|| DEFAULT_PROTOCOL_VERSION.to_string())
+    }
+
+    /// Whether responses may include `structuredContent` and tool `outputSchema`.
+    ///
+    /// Both were added in protocol version 2025-06-18 and are left out for
+    /// sessions that negotiated an older version. Sessions that never
+    /// negotiated one, such as plain HTTP requests, get the newest format.
+    pub fn supports_structured_content(&self) -> bool {
+        self.negotiated.lock().unwrap()
+            .as_ref()
+            // Protocol versions are dates, so they order as strings
+            .is_none_or(|n| n.protocol_version.as_str() >= STRUCTURED_CONTENT_VERSION)
+    }
+
+    /// Capabilities the client declared during `initialize`.
+    #[allow(dead_code)] // Available for features that depend on client capabilities
+    pub fn client_capabilities(&self) -> serde_json::Value {
+        self.negotiated.lock().unwrap()
+            .as_ref()
+            .map(|n| n.client_capabilities.clone())
+            .unwrap_or(serde_json::Value::Null)
+    }
+
+    /// Track a request as in flight and return the token that cancels it.
+    ///
+    /// Request ids must be unique among the session's in-flight requests, or
+    /// responses (and cancellations) could not be matched to their requests.
+    ///
+    /// # Arguments
+    /// * `id` - JSON-RPC id of the request
+    ///
+    /// # Returns
+    /// The cancellation token, or `None` if a request with the same id is
+    /// already in flight. The existing request is left untouched.
+    pub fn begin_request(&self, id: &serde_json::Value) -> Option<CancellationToken> {
+        match self.in_flight.entry(id.to_string()) {
+            Entry::Occupied(_) => None,
+            Entry::Vacant(entry) => {
+                let token = CancellationToken::new();
+                entry.insert(token.clone());
+                Some(token)
+            }
+        }
+    }
+
+    /// Stop tracking a request once its response has been produced.
+    pub fn finish_request(&self, id: &serde_json::Value) {
+        self.in_flight.remove(&id.to_string());
+    }
+
+    /// Cancel an in-flight request.
+    ///
+    /// # Returns
+    /// Whether a request with that id was in flight.
+    pub fn cancel_request(&self, id: &serde_json::Value) -> bool {
+        match self.in_flight.get(&id.to_string()) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Negotiate the protocol version for an `initialize` request.
+    ///
+    /// A supported requested version is accepted as-is, which lets a client
+    /// re-initialize mid-session to downgrade (or upgrade) the version; the
+    /// negotiated version and capabilities are reset to the new values.
+    ///
+    /// An unsupported version is handled according to the session state. On the
+    /// first `initialize` the server answers with its newest version, as the MCP
+    /// specification requires. On a re-initialize the request is rejected and the
+    /// existing negotiated state is left untouched.
+    ///
+    /// # Arguments
+    /// * `requested` - Protocol version requested by the client, if any
+    /// * `client_capabilities` - Capabilities declared by the client
+    /// * `client_info` - Client application name and version, if reported
+    ///
+    /// # Returns
+    /// The negotiated protocol version, or an error message if rejected.
+    pub fn negotiate(
+        &self,
+        requested: Option<&str>,
+        client_capabilities: serde_json::Value,
+        client_info: Option<ClientInfo>,
+    ) -> Result<String, String> {
+        let mut negotiated = self.negotiated.lock().unwrap();
+        
+        let version = match requested {
+            Some(version) if is_supported_version(version) => version.to_string(),
+            Some(version) if negotiated.is_some() => {
+                return Err(format!(
+                    "Unsupported protocol version: {} (supported: {})",
+                    version,
+                    SUPPORTED_PROTOCOL_VERSIONS.join(", ")
+                ));
+            }
+            _ => SUPPORTED_PROTOCOL_VERSIONS[0].to_string(),
+        };
+        
+        if let Some(previous) = negotiated.as_ref() {
+            utils::log_stderr!(
+                "Session {} re-initialized: protocol version {} -> {}",
+                self.id, previous.protocol_version, version
+            );
+        }
+        
+        *negotiated = Some(Negotiated {
+            protocol_version: version.clone(),
+            client_capabilities,
+            client_info,
+        });
+        
+        Ok(version)
+    }
+}
+
+/// Header carrying the session id of an HTTP client.
+pub const SESSION_HEADER: &str = "Mcp-Session-Id";
+
+/// Most HTTP sessions kept at once; beyond it the least recently used is forgotten.
+const MAX_HTTP_SESSIONS: usize = 10_000;
+
+/// Default time an HTTP session may go unused before it is forgotten.
+pub const DEFAULT_HTTP_SESSION_IDLE: Duration = Duration::from_secs(30 * 60);
+
+/// Sessions of HTTP clients, keyed by the `Mcp-Session-Id` header.
+///
+/// An `initialize` POSTed without the header starts a session, whose id the
+/// response returns in the header. Requests sending the id back are handled
+/// in that session until the client ends it with `DELETE`. Sessions unused
+/// for the idle time are forgotten, as are the least recently used ones once
+/// `MAX_HTTP_SESSIONS` are kept.
+pub struct HttpSessions {
+    /// Sessions by id, with the time each was last used
+    sessions: Mutex<LruCache<String, (Arc<Session>, Instant)>>,
+    /// How long a session may go unused
+    idle: Duration,
+}
+
+impl HttpSessions {
+    /// Keep sessions for `idle` after their last use.
+    pub fn new(idle: Duration) -> Self {
+        Self {
+            sessions: Mutex::new(LruCache::new(
+                NonZeroUsize::new(MAX_HTTP_SESSIONS).expect("MAX_HTTP_SESSIONS is not zero"),
+            )),
+            idle,
+        }
+    }
+
+    /// Idle time from `MCP_HTTP_SESSION_IDLE_SECS` (default 30 minutes).
+    pub fn from_env() -> Self {
+        let secs = utils::parse_env_var("MCP_HTTP_SESSION_IDLE_SECS", DEFAULT_HTTP_SESSION_IDLE.as_secs());
+        Self::new(Duration::from_secs(secs))
+    }
+
+    /// Keep `session` for later requests naming its id.
+    pub fn insert(&self, session: Arc<Session>) {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.put(session.id.clone(), (session, Instant::now()));
+    }
+
+    /// The session with `id`, unless it is unknown or has expired.
+    pub fn get(&self, id: &str) -> Option<Arc<Session>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let (session, last_used) = sessions.get_mut(id)?;
+        if last_used.elapsed() > self.idle {
+            sessions.pop(id);
+            return None;
+        }
+        *last_used = Instant::now();
+        Some(session.clone())
+    }
+
+    /// End the session with `id`, returning whether there was one.
+    pub fn remove(&self, id: &str) -> bool {
+        self.sessions.lock().unwrap().pop(id).is_some()
+    }
+}
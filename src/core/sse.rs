@@ -0,0 +1,199 @@
+//! MCP Server-Sent Events Transport
+//!
+//! This module implements the MCP SSE transport. A client opens a long-lived
+//! event stream with `GET /sse` and receives an initial `endpoint` event naming
+//! the URL it must POST its JSON-RPC requests to. Each POST is dispatched through
+//! the shared method router and the response is delivered on the session's
+//! event stream as a `message` event.
+
+use actix_web::{web, HttpResponse, Result, http::header};
+use actix_web::web::Bytes;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::core::router::MethodContext;
+use crate::core::server::{dispatch_request, parse_request, read_body, BodyLimit};
+use crate::core::session::Session;
+
+/// Path clients POST session messages to, announced in the `endpoint` event.
+const MESSAGES_PATH: &str = "/messages";
+
+/// An open SSE session: its protocol state and the sender feeding its event stream.
+#[derive(Clone)]
+struct SseSession {
+    session: Arc<Session>,
+    sender: mpsc::UnboundedSender<Bytes>,
+}
+
+/// Registry of open SSE sessions.
+///
+/// Maps each session id to the session state and the sending half of the
+/// channel feeding that session's event stream. Entries are removed once the
+/// client disconnects.
+pub struct SseSessions {
+    sessions: Mutex<HashMap<String, SseSession>>,
+}
+
+impl SseSessions {
+    /// Create an empty session registry.
+    pub fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Register a new session and return its id.
+    ///
+    /// Server-initiated notifications for the session are sent on its event
+    /// stream as `message` events.
+    fn open(&self, sender: mpsc::UnboundedSender<Bytes>) -> String {
+        let session = Arc::new(Session::new());
+        let session_id = session.id.clone();
+        let notifications = sender.clone();
+        session.set_notifier(Arc::new(move |json: String| {
+            notifications.send(sse_event("message", &json)).is_ok()
+        }));
+        self.sessions.lock().unwrap().insert(session_id.clone(), SseSession { session, sender });
+        session_id
+    }
+
+    /// Look up an open session.
+    fn get(&self, session_id: &str) -> Option<SseSession> {
+        self.sessions.lock().unwrap().get(session_id).cloned()
+    }
+
+    /// Every open session, in no particular order.
+    pub fn sessions(&self) -> Vec<Arc<Session>> {
+        self.sessions.lock().unwrap().values().map(|entry| entry.session.clone()).collect()
+    }
+
+    /// Remove a session once its client has disconnected.
+    fn close(&self, session_id: &str) {
+        self.sessions.lock().unwrap().remove(session_id);
+    }
+}
+
+/// Query parameters identifying the session a message belongs to.
+#[derive(Deserialize)]
+pub struct SessionQuery {
+    #[serde(rename = "sessionId")]
+    session_id: String,
+}
+
+/// Format a named SSE event.
+fn sse_event(event: &str, data: &str) -> Bytes {
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+}
+
+/// Open an MCP SSE session.
+///
+/// Creates a session, sends the initial `endpoint` event pointing at the
+/// session-scoped message URL, and keeps the stream open. Server-originated
+/// JSON-RPC messages for the session are forwarded as `message` events.
+/// A periodic keepalive comment detects disconnected clients so their session
+/// can be removed.
+///
+/// # Arguments
+/// * `sessions` - Registry of open SSE sessions
+pub async fn sse_connect(sessions: web::Data<SseSessions>) -> Result<HttpResponse> {
+    let (tx, rx) = mpsc::unbounded_channel::<Bytes>();
+    let session_id = sessions.open(tx.clone());
+    
+    // Tell the client where to POST its requests for this session
+    let endpoint = format!("{}?sessionId={}", MESSAGES_PATH, session_id);
+    let _ = tx.send(sse_event("endpoint", &endpoint));
+    
+    // Send periodic keepalive to prevent connection timeout. A failed send means
+    // the client has gone away, so the session is cleaned up.
+    let keepalive_sessions = sessions.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(30));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if tx.send(Bytes::from(": keepalive\n\n")).is_err() {
+                keepalive_sessions.close(&session_id);
+                break;
+            }
+        }
+    });
+    
+    // Create streaming response fed by the session channel
+    let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|item| (Ok::<Bytes, actix_web::Error>(item), rx))
+    });
+    
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(header::CacheControl(vec![
+            header::CacheDirective::NoCache,
+            header::CacheDirective::NoStore,
+            header::CacheDirective::MustRevalidate,
+        ]))
+        // Disable nginx buffering for real-time streaming
+        .insert_header(("x-accel-buffering", "no"))
+        .insert_header(("Connection", "keep-alive"))
+        .streaming(stream))
+}
+
+/// Accept a JSON-RPC message for an SSE session.
+///
+/// The request is dispatched through the shared method router and its response
+/// is written to the session's event stream. The POST itself is acknowledged
+/// with 202 Accepted. Notifications are accepted without producing a response.
+///
+/// # Arguments
+/// * `query` - Query string carrying the `sessionId`
+/// * `sessions` - Registry of open SSE sessions
+/// * `ctx` - Shared method context
+/// * `limit` - Maximum accepted request body size
+/// * `body` - Raw request body containing the JSON-RPC request
+pub async fn sse_message(
+    query: web::Query<SessionQuery>,
+    sessions: web::Data<SseSessions>,
+    ctx: web::Data<MethodContext>,
+    limit: web::Data<BodyLimit>,
+    body: web::Payload,
+) -> Result<HttpResponse> {
+    let SseSession { session, sender } = match sessions.get(&query.session_id) {
+        Some(open) => open,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "unknown session"
+            })));
+        }
+    };
+    
+    // Malformed messages are rejected on the POST itself, since they cannot be
+    // correlated with a request on the event stream
+    let payload = match read_body(body, limit.0).await {
+        Ok(payload) => payload,
+        Err(response) => return Ok(response),
+    };
+    let req = match parse_request(&payload) {
+        Ok(req) => req,
+        Err(response) => return Ok(HttpResponse::BadRequest().json(response)),
+    };
+    
+    // Notifications and cancelled requests produce no response event
+    let response = match dispatch_request(ctx.with_session(session), req).await {
+        Some(response) => response,
+        None => return Ok(HttpResponse::Accepted().finish()),
+    };
+    let response_json = serde_json::to_string(&response)
+        .unwrap_or_else(|_| "{}".to_string());
+    
+    if sender.send(sse_event("message", &response_json)).is_err() {
+        // The event stream is gone - drop the session
+        sessions.close(&query.session_id);
+        return Ok(HttpResponse::Gone().json(serde_json::json!({
+            "error": "session closed"
+        })));
+    }
+    
+    Ok(HttpResponse::Accepted().finish())
+}
@@ -0,0 +1,185 @@
+//! Streamed Tool Results
+//!
+//! Tools registered with `ToolRegistry::register_stream` produce their output
+//! as a stream of byte chunks instead of a single result. Over the plain HTTP
+//! transport (`POST /mcp`) the chunks are forwarded as they arrive in a
+//! chunked response body, so a large result is never held in memory in full.
+//! The body is still a single, ordinary JSON-RPC response: the chunks form the
+//! text of one text content block.
+//!
+//! Other transports frame whole messages, so they collect the chunks into a
+//! single text block before responding (see `collect`).
+//!
+//! A failure after the response has started cannot change its status, so it is
+//! reported in the result instead: the text streamed so far is kept, followed
+//! by a text block holding the error, with `isError: true`. A panic while
+//! producing a chunk is reported the same way, with a generic message.
+
+use bytes::Bytes;
+use futures_util::stream::{self, Stream, StreamExt};
+use futures_util::FutureExt;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::core::server::{self, CallTimeout, ToolError, ToolOutput, ToolStream};
+use crate::core::utils;
+
+/// Called once when a streamed call finishes, with its outcome and duration.
+pub type FinishHook = Box<dyn FnOnce(Result<(), ToolError>, Duration) + Send>;
+
+/// Collect a tool's stream into a single text output.
+///
+/// Invalid UTF-8 is replaced rather than rejected.
+///
+/// # Arguments
+/// * `stream` - Output stream of the tool call
+///
+/// # Returns
+/// The concatenated output as one text block, or the first error the stream produced.
+pub async fn collect(mut stream: ToolStream) -> Result<ToolOutput, ToolError> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        bytes.extend_from_slice(&chunk?);
+    }
+    Ok(ToolOutput::text(String::from_utf8_lossy(&bytes)))
+}
+
+/// State of a response body while its tool stream is forwarded.
+struct BodyState {
+    /// Name of the tool being called
+    tool_name: String,
+    /// Opening of the JSON-RPC response, sent before the first chunk
+    prefix: Option<Bytes>,
+    /// Output stream of the tool call
+    stream: ToolStream,
+    /// Trailing bytes of an incomplete UTF-8 sequence, held for the next chunk
+    pending: Vec<u8>,
+    /// Time after which the call fails as timed out
+    deadline: Instant,
+    /// Error message of a call that runs out of time
+    timed_out: &'static str,
+    /// When the call started
+    started: Instant,
+    /// `_meta` member added to the result, with the instance id and any warnings
+    meta: String,
+    /// Run when the call finishes; taken once the closing bytes are sent
+    on_finish: Option<FinishHook>,
+    /// Cancels the call's token if the body is dropped before the stream ends,
+    /// for instance because the client disconnected
+    _cancel_on_drop: DropGuard,
+}
+
+/// Build the body of a streamed `tools/call` response.
+///
+/// # Arguments
+/// * `tool_name` - Name of the tool being called
+/// * `id` - Id of the JSON-RPC request
+/// * `stream` - Output stream of the tool call
+/// * `warnings` - Argument validation warnings, returned in `_meta.warnings`
+/// * `cancellation` - The call's cancellation token, cancelled if the body is dropped early
+/// * `timeout` - Maximum time the whole stream may take, and the error reported when it is exceeded
+/// * `on_finish` - Hook run with the call's outcome once the stream ends
+pub fn response_body(
+    tool_name: &str,
+    id: &serde_json::Value,
+    stream: ToolStream,
+    warnings: Vec<String>,
+    cancellation: CancellationToken,
+    timeout: CallTimeout,
+    on_finish: FinishHook,
+) -> impl Stream<Item = Result<Bytes, actix_web::Error>> + 'static {
+    let prefix = format!(
+        r#"{{"jsonrpc":"2.0","id":{},"result":{{"content":[{{"type":"text","text":""#,
+        id
+    );
+    let meta = format!(r#","_meta":{}"#, server::tool_result_meta(warnings));
+    let started = Instant::now();
+    let state = BodyState {
+        tool_name: tool_name.to_string(),
+        prefix: Some(Bytes::from(prefix)),
+        stream,
+        pending: Vec::new(),
+        deadline: started + timeout.duration,
+        timed_out: timeout.message(),
+        started,
+        meta,
+        on_finish: Some(on_finish),
+        _cancel_on_drop: cancellation.drop_guard(),
+    };
+
+    stream::unfold(state, |mut state| async move {
+        if let Some(prefix) = state.prefix.take() {
+            return Some((Ok(prefix), state));
+        }
+        let on_finish = state.on_finish.take()?;
+
+        loop {
+            let next = AssertUnwindSafe(state.stream.next()).catch_unwind();
+            let outcome = match tokio::time::timeout_at(state.deadline, next).await {
+                Ok(Ok(Some(Ok(chunk)))) => {
+                    state.pending.extend_from_slice(&chunk);
+                    let text = take_utf8(&mut state.pending);
+                    // A chunk ending inside a character produces no text yet
+                    if text.is_empty() {
+                        continue;
+                    }
+                    state.on_finish = Some(on_finish);
+                    return Some((Ok(Bytes::from(escape(&text))), state));
+                }
+                Ok(Ok(Some(Err(e)))) => Err(e),
+                Ok(Ok(None)) => Ok(()),
+                Ok(Err(panic)) => {
+                    utils::log_stderr!("Tool '{}' panicked: {}", state.tool_name, server::panic_message(panic.as_ref()));
+                    Err(ToolError::ExecutionFailed("internal error: tool handler panicked".to_string()))
+                }
+                Err(_) => Err(ToolError::ExecutionFailed(state.timed_out.to_string())),
+            };
+
+            let closing = closing(&state.pending, outcome.as_ref().err(), &state.meta);
+            on_finish(outcome, state.started.elapsed());
+            return Some((Ok(Bytes::from(closing)), state));
+        }
+    })
+}
+
+/// Take the longest valid UTF-8 prefix of `buffer` as text.
+///
+/// An incomplete character at the end is left in the buffer to be completed by
+/// the next chunk; invalid bytes elsewhere are replaced.
+fn take_utf8(buffer: &mut Vec<u8>) -> String {
+    let complete = match std::str::from_utf8(buffer) {
+        Ok(_) => buffer.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => buffer.len(),
+    };
+    let text = String::from_utf8_lossy(&buffer[..complete]).into_owned();
+    buffer.drain(..complete);
+    text
+}
+
+/// Escape text for use inside a JSON string literal, without the quotes.
+fn escape(text: &str) -> String {
+    let quoted = serde_json::Value::from(text).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Build the end of the response body.
+///
+/// # Arguments
+/// * `pending` - Bytes left over from an incomplete final character
+/// * `error` - The error that ended the stream, if any
+/// * `meta` - `_meta` member to add to the result, with its leading comma
+fn closing(pending: &[u8], error: Option<&ToolError>, meta: &str) -> String {
+    let rest = escape(&String::from_utf8_lossy(pending));
+    match error {
+        None => format!(r#"{}"}}],"isError":false{}}}}}"#, rest, meta),
+        Some(e) => format!(
+            r#"{}"}},{{"type":"text","text":"{}"}}],"isError":true{}}}}}"#,
+            rest,
+            escape(&format!("Error: {}", e)),
+            meta
+        ),
+    }
+}
@@ -0,0 +1,53 @@
+//! TLS Configuration
+//!
+//! Optional HTTPS support for HTTP mode. When both `TLS_CERT_PATH` and
+//! `TLS_KEY_PATH` are set, the server loads a PEM certificate chain and private
+//! key and serves HTTPS with rustls instead of plain HTTP.
+
+use std::fs::File;
+use std::io::BufReader;
+
+/// Build the rustls server configuration from the environment.
+///
+/// # Returns
+/// - `Ok(None)` when neither `TLS_CERT_PATH` nor `TLS_KEY_PATH` is set (plain HTTP)
+/// - `Ok(Some(config))` when both are set and the files load successfully
+/// - `Err(message)` when only one is set or the files cannot be loaded
+pub fn load_tls_config_from_env() -> Result<Option<rustls::ServerConfig>, String> {
+    let cert_path = std::env::var("TLS_CERT_PATH").ok().filter(|p| !p.is_empty());
+    let key_path = std::env::var("TLS_KEY_PATH").ok().filter(|p| !p.is_empty());
+    
+    match (cert_path, key_path) {
+        (None, None) => Ok(None),
+        (Some(cert_path), Some(key_path)) => load_tls_config(&cert_path, &key_path).map(Some),
+        (Some(_), None) => Err("TLS_CERT_PATH is set but TLS_KEY_PATH is not; set both to enable TLS".to_string()),
+        (None, Some(_)) => Err("TLS_KEY_PATH is set but TLS_CERT_PATH is not; set both to enable TLS".to_string()),
+    }
+}
+
+/// Load a PEM certificate chain and private key into a rustls server configuration.
+///
+/// # Arguments
+/// * `cert_path` - Path to the PEM certificate chain (leaf certificate first)
+/// * `key_path` - Path to the PEM private key (PKCS#8, PKCS#1 or SEC1)
+pub fn load_tls_config(cert_path: &str, key_path: &str) -> Result<rustls::ServerConfig, String> {
+    let cert_file = File::open(cert_path)
+        .map_err(|e| format!("Failed to open TLS certificate {}: {}", cert_path, e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(cert_file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to parse TLS certificate {}: {}", cert_path, e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificates found in {}", cert_path));
+    }
+    
+    let key_file = File::open(key_path)
+        .map_err(|e| format!("Failed to open TLS private key {}: {}", key_path, e))?;
+    let key = rustls_pemfile::private_key(&mut BufReader::new(key_file))
+        .map_err(|e| format!("Failed to parse TLS private key {}: {}", key_path, e))?
+        .ok_or_else(|| format!("No private key found in {}", key_path))?;
+    
+    rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("Invalid TLS certificate/key pair: {}", e))
+}
@@ -0,0 +1,60 @@
+//! Tool Call Middleware
+//!
+//! Tool middleware runs around every `tools/call`, on every transport, which
+//! makes it the place for cross-cutting concerns such as logging, timing,
+//! access control or argument redaction that would otherwise have to be
+//! repeated in each tool handler.
+//!
+//! Middleware is registered on the `ToolRegistry` and runs in registration
+//! order: every `before` hook runs before the handler, and every `after` hook
+//! runs once the call has finished.
+
+use std::time::Duration;
+
+use crate::core::server::{ToolError, ToolOutput};
+use crate::core::utils;
+
+/// Hooks run around every tool call.
+pub trait ToolMiddleware: Send + Sync {
+    /// Called before the tool runs, after its arguments passed schema validation.
+    ///
+    /// Returning an error short-circuits the call: the handler and the
+    /// remaining `before` hooks are skipped, and the error is reported as if
+    /// the tool had returned it.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the tool being called
+    /// * `args` - Arguments of the call
+    fn before(&self, _name: &str, _args: &serde_json::Value) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    /// Called after the call finished, including calls rejected by a `before`
+    /// hook and calls that failed or timed out.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the tool that was called
+    /// * `result` - Outcome of the call
+    /// * `elapsed` - Time from the first `before` hook until the call finished
+    fn after(&self, _name: &str, _result: &Result<ToolOutput, ToolError>, _elapsed: Duration) {}
+}
+
+/// Middleware that logs the duration and outcome of every tool call to stderr.
+///
+/// Enabled by setting `LOG_TOOL_TIMINGS=true`.
+pub struct TimingMiddleware;
+
+impl ToolMiddleware for TimingMiddleware {
+    fn after(&self, name: &str, result: &Result<ToolOutput, ToolError>, elapsed: Duration) {
+        let outcome = match result {
+            Ok(_) => "ok",
+            Err(_) => "error",
+        };
+        utils::log_stderr!(
+            "Tool '{}' finished in {:.3}ms ({})",
+            name,
+            elapsed.as_secs_f64() * 1000.0,
+            outcome
+        );
+    }
+}
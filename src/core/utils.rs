@@ -1,24 +1,33 @@
-/// Utility Functions for Configuration and Environment Management
-///
-/// This module provides functions for loading configuration from YAML files
-/// and accessing environment variables. Configuration is organized hierarchically
-/// with tool-specific sections.
+//! Utility Functions for Configuration and Environment Management
+//!
+//! This module provides functions for loading configuration from YAML, JSON or
+//! TOML files and accessing environment variables. Configuration is organized hierarchically
+//! with tool-specific sections. The file is read once and cached; `reload_config`
+//! replaces the cached configuration, for instance on SIGHUP. It also provides the `log_stderr!` macro for the
+//! server's own diagnostic logs, which honours `MCP_LOG_FORMAT`.
 
 use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::de::DeserializeOwned;
 use serde_json::Value;
 
-/// Load configuration from YAML file.
+/// Configuration cached by `cached_config`, replaced whole by `reload_config`.
+static CONFIG: RwLock<Option<Arc<HashMap<String, Value>>>> = RwLock::new(None);
+
+/// Load configuration from a YAML, JSON or TOML file.
 ///
-/// Currently returns an empty configuration. This function can be extended
-/// to load configuration from kmcp.yaml or other YAML files. The configuration
-/// structure should match the kmcp.yaml format with a "tools" section containing
-/// tool-specific settings.
+/// Reads the file named by the `MCP_CONFIG_PATH` environment variable; without
+/// it no file is read and every tool uses its defaults. The format is chosen
+/// by the file extension (see `ConfigFormat`). The configuration structure should match the kmcp.yaml
+/// format with a "tools" section containing tool-specific settings.
+///
+/// The file is read on first use and cached until `reload_config`.
 ///
 /// # Returns
 /// A HashMap containing the loaded configuration, or an empty HashMap if no
 /// configuration file is found or if loading fails.
 pub fn load_config() -> HashMap<String, Value> {
-    // TODO: Implement YAML file loading
     // Example structure:
     // {
     //   "tools": {
@@ -26,7 +35,251 @@ pub fn load_config() -> HashMap<String, Value> {
     //     "weather": { "api_key_env": "WEATHER_API_KEY" }
     //   }
     // }
-    HashMap::new()
+    cached_config().as_ref().clone()
+}
+
+/// Get the cached configuration, reading the file on first use.
+///
+/// A configuration that fails to load is logged and cached as empty, so
+/// tools fall back to their defaults until a successful reload.
+fn cached_config() -> Arc<HashMap<String, Value>> {
+    if let Some(config) = CONFIG.read().unwrap().as_ref() {
+        return config.clone();
+    }
+    let mut cached = CONFIG.write().unwrap();
+    cached.get_or_insert_with(|| {
+        Arc::new(read_configured().unwrap_or_else(|e| {
+            log_stderr!("Error parsing config file {}", e);
+            HashMap::new()
+        }))
+    }).clone()
+}
+
+/// Re-read the configuration file and replace the cached configuration.
+///
+/// The new configuration replaces the old one in a single step, so concurrent
+/// readers see either the old or the new configuration, never a mix. If the
+/// file cannot be loaded the previous configuration stays in effect.
+///
+/// # Returns
+/// `Ok(())` if the configuration was replaced, or an error naming the file and problem
+pub fn reload_config() -> Result<(), String> {
+    match config_path() {
+        Some(path) => reload_config_from(&path),
+        None => {
+            *CONFIG.write().unwrap() = Some(Arc::new(HashMap::new()));
+            Ok(())
+        }
+    }
+}
+
+/// Replace the cached configuration with the one in the file at `path`.
+///
+/// # Arguments
+/// * `path` - Configuration file to read
+pub fn reload_config_from(path: &str) -> Result<(), String> {
+    let config = read_config(path)?;
+    *CONFIG.write().unwrap() = Some(Arc::new(config));
+    Ok(())
+}
+
+/// Configuration file written by a test, loaded in place of the cached one.
+///
+/// Dropping it deletes the file and restores the configuration that was
+/// cached before, so other tests never see its settings linger.
+#[cfg(test)]
+pub(crate) struct TestConfigFile {
+    path: std::path::PathBuf,
+    previous: Option<Arc<HashMap<String, Value>>>,
+}
+
+#[cfg(test)]
+impl TestConfigFile {
+    /// Claim a file named `name` in the temporary directory.
+    pub(crate) fn new(name: &str) -> Self {
+        TestConfigFile {
+            path: std::env::temp_dir().join(format!("{}-{}", std::process::id(), name)),
+            previous: CONFIG.read().unwrap().clone(),
+        }
+    }
+
+    /// Write `contents` to the file and reload the configuration from it.
+    pub(crate) fn load(&self, contents: &str) -> Result<(), String> {
+        std::fs::write(&self.path, contents).map_err(|e| e.to_string())?;
+        reload_config_from(self.path.to_str().unwrap())
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestConfigFile {
+    fn drop(&mut self) {
+        *CONFIG.write().unwrap() = self.previous.take();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Check that the configuration file, if present, can be loaded.
+///
+/// A missing configuration file is valid, since tools fall back to their
+/// defaults; a file that cannot be parsed is not.
+///
+/// # Returns
+/// `Ok(())` if the configuration loads, or an error naming the file and problem
+pub fn check_config() -> Result<(), String> {
+    read_configured().map(|_| ())
+}
+
+/// Format of a configuration file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// YAML (`.yaml`, `.yml` and any unrecognized extension)
+    Yaml,
+    /// JSON (`.json`)
+    Json,
+    /// TOML (`.toml`)
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Detect the format from a file's extension.
+    ///
+    /// `.json` and `.toml` files are parsed as such; every other file,
+    /// including `.yaml` and `.yml`, is parsed as YAML.
+    pub fn from_path(path: &str) -> Self {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("json") => ConfigFormat::Json,
+            Some("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Yaml,
+        }
+    }
+
+    /// Parse configuration file contents in this format.
+    pub fn parse(self, contents: &str) -> Result<HashMap<String, Value>, String> {
+        match self {
+            ConfigFormat::Yaml => serde_yaml::from_str(contents).map_err(|e| e.to_string()),
+            ConfigFormat::Json => serde_json::from_str(contents).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => toml::from_str(contents).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Path of the configuration file set by `MCP_CONFIG_PATH`, if any.
+///
+/// There is no default path, so the sample `kmcp.yaml` in the working
+/// directory is only used when named explicitly.
+pub fn config_path() -> Option<String> {
+    std::env::var("MCP_CONFIG_PATH").ok().filter(|path| !path.trim().is_empty())
+}
+
+/// Read the configuration file set by `MCP_CONFIG_PATH`, or an empty
+/// configuration when it is unset.
+fn read_configured() -> Result<HashMap<String, Value>, String> {
+    match config_path() {
+        Some(path) => read_config(&path),
+        None => Ok(HashMap::new()),
+    }
+}
+
+/// Read and parse a configuration file (see `load_config`).
+///
+/// A missing file yields an empty configuration; a parse failure yields an
+/// error of the form "<path>: <problem>".
+///
+/// # Arguments
+/// * `path` - Configuration file to read
+fn read_config(path: &str) -> Result<HashMap<String, Value>, String> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        // A missing config file is not an error - tools fall back to defaults
+        Err(_) => return Ok(HashMap::new()),
+    };
+
+    let mut config = ConfigFormat::from_path(path)
+        .parse(&contents)
+        .map_err(|e| format!("{}: {}", path, e))?;
+
+    // Substitute ${VAR} references in string values from the environment
+    let strict = env_flag("MCP_CONFIG_STRICT_ENV");
+    let lookup = |name: &str| std::env::var(name).ok();
+    for value in config.values_mut() {
+        interpolate_env(value, &lookup, strict).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    Ok(config)
+}
+
+/// Substitute environment variable references in every string of a config tree.
+///
+/// Strings may reference variables as `${VAR}`, and `$$` stands for a literal
+/// `$`. Keys are left unchanged.
+///
+/// # Arguments
+/// * `value` - Config value, interpolated in place
+/// * `lookup` - Returns the value of a variable, or `None` if it is unset
+/// * `strict` - Whether an unset variable is an error rather than an empty string
+fn interpolate_env(
+    value: &mut Value,
+    lookup: &impl Fn(&str) -> Option<String>,
+    strict: bool,
+) -> Result<(), String> {
+    match value {
+        Value::String(text) => *text = interpolate_str(text, lookup, strict)?,
+        Value::Array(items) => {
+            for item in items {
+                interpolate_env(item, lookup, strict)?;
+            }
+        }
+        Value::Object(fields) => {
+            for field in fields.values_mut() {
+                interpolate_env(field, lookup, strict)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Substitute `${VAR}` references and `$$` escapes in one string.
+///
+/// A `$` followed by anything else is kept as is.
+fn interpolate_str(
+    text: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+    strict: bool,
+) -> Result<String, String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('$') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index..];
+        if let Some(after) = rest.strip_prefix("$$") {
+            result.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after.find('}')
+                .ok_or_else(|| format!("unterminated ${{ in {:?}", text))?;
+            let name = &after[..end];
+            let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(format!("invalid variable name {:?} in {:?}", name, text));
+            }
+            match lookup(name) {
+                Some(value) => result.push_str(&value),
+                None if strict => return Err(format!("environment variable {} is not set", name)),
+                None => {}
+            }
+            rest = &after[end + 1..];
+        } else {
+            result.push('$');
+            rest = &rest[1..];
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
 }
 
 /// Get tool-specific configuration from the loaded configuration.
@@ -50,21 +303,160 @@ pub fn load_config() -> HashMap<String, Value> {
 ///     prefix: "Echo: "
 /// ```
 /// Then `get_tool_config("echo")` returns `{"prefix": "Echo: "}`
+///
+/// The `tools._default` section is not merged in; use `get_tool_config_value`
+/// for settings that fall back to it.
+#[allow(dead_code)] // Available for tools that read their whole configuration section
 pub fn get_tool_config(tool_name: &str) -> HashMap<String, Value> {
-    let config = load_config();
+    let config = cached_config();
     // Navigate the configuration hierarchy: config -> tools -> tool_name
-    if let Some(tools) = config.get("tools").and_then(|v| v.as_object()) {
-        if let Some(tool_config) = tools.get(tool_name).and_then(|v| v.as_object()) {
-            // Convert the tool's configuration object to a HashMap
-            return tool_config.iter()
-                .map(|(k, v)| (k.clone(), v.clone()))
-                .collect();
-        }
+    if let Some(tools) = config.get("tools").and_then(|v| v.as_object())
+        && let Some(tool_config) = tools.get(tool_name).and_then(|v| v.as_object()) {
+        // Convert the tool's configuration object to a HashMap
+        return tool_config.iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
     }
     // Return empty HashMap if tool configuration not found
     HashMap::new()
 }
 
+/// Entry of the `tools` configuration section holding defaults for every tool.
+pub const DEFAULT_TOOL_CONFIG: &str = "_default";
+
+/// Get one typed setting of a tool from the loaded configuration.
+///
+/// The value is looked up in the tool's own section first, then in the
+/// `tools._default` section shared by all tools, and `default` is returned
+/// when neither has it. A value that does not deserialize into `T` is skipped
+/// as if it were missing.
+///
+/// # Arguments
+/// * `tool_name` - Name of the tool the setting belongs to
+/// * `key` - Setting name within the tool's section (e.g. "prefix")
+/// * `default` - Value used when no section sets the key
+///
+/// # Example
+/// With this configuration, `get_tool_config_value("echo", "prefix", String::new())`
+/// returns `"Echo: "`, and `"> "` for any other tool:
+/// ```yaml
+/// tools:
+///   _default:
+///     prefix: "> "
+///   echo:
+///     prefix: "Echo: "
+/// ```
+pub fn get_tool_config_value<T: DeserializeOwned>(tool_name: &str, key: &str, default: T) -> T {
+    let config = cached_config();
+    let Some(tools) = config.get("tools").and_then(|v| v.as_object()) else {
+        return default;
+    };
+    [tool_name, DEFAULT_TOOL_CONFIG].iter()
+        .filter_map(|section| tools.get(*section)?.get(key))
+        .find_map(|value| T::deserialize(value).ok())
+        .unwrap_or(default)
+}
+
+/// Suffixes of configuration keys whose values are treated as secrets.
+const SECRET_KEY_SUFFIXES: [&str; 3] = ["_key", "_token", "_secret"];
+
+/// Copy a configuration value with its secrets replaced by `"[REDACTED]"`.
+///
+/// A value is secret when its key ends in `_key`, `_token` or `_secret`,
+/// ignoring case, at any depth of the configuration.
+///
+/// # Arguments
+/// * `value` - Configuration value to copy
+pub fn redact_secrets(value: &Value) -> Value {
+    redact_keys(value, &SECRET_KEY_SUFFIXES)
+}
+
+/// Copy a value with the values of matching keys replaced by `"[REDACTED]"`.
+///
+/// A key matches when it ends in one of `suffixes`, ignoring case, at any
+/// depth of the value. Suffixes are expected in lowercase.
+///
+/// # Arguments
+/// * `value` - Value to copy
+/// * `suffixes` - Lowercase key suffixes whose values are redacted
+pub fn redact_keys<S: AsRef<str>>(value: &Value, suffixes: &[S]) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.iter()
+            .map(|(key, value)| {
+                let key_lower = key.to_ascii_lowercase();
+                let value = if suffixes.iter().any(|suffix| key_lower.ends_with(suffix.as_ref())) {
+                    Value::from("[REDACTED]")
+                } else {
+                    redact_keys(value, suffixes)
+                };
+                (key.clone(), value)
+            })
+            .collect()),
+        Value::Array(items) => Value::Array(items.iter().map(|item| redact_keys(item, suffixes)).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Largest integer a JavaScript number holds exactly (2^53 - 1).
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Replace integers outside JavaScript's safe range with their decimal strings.
+///
+/// JSON parsers in JavaScript read every number as a double, which silently
+/// rounds integers beyond ±(2^53 - 1), such as snowflake ids. Converting them to
+/// strings keeps them exact. Floating-point numbers are left unchanged.
+///
+/// # Arguments
+/// * `value` - Value to convert in place, at any depth
+pub fn stringify_unsafe_integers(value: &mut Value) {
+    match value {
+        Value::Number(number) => {
+            let unsafe_integer = number.as_u64().map(|n| n > MAX_SAFE_INTEGER)
+                .or_else(|| number.as_i64().map(|n| n.unsigned_abs() > MAX_SAFE_INTEGER))
+                .unwrap_or(false);
+            if unsafe_integer {
+                *value = Value::String(number.to_string());
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(stringify_unsafe_integers),
+        Value::Object(map) => map.values_mut().for_each(stringify_unsafe_integers),
+        _ => {}
+    }
+}
+
+/// Format a point in time as an RFC 3339 UTC timestamp with milliseconds.
+///
+/// # Arguments
+/// * `time` - Time to format; times before the Unix epoch format as the epoch
+pub fn rfc3339(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let days = secs / 86_400;
+    let rem = secs % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
 /// Get environment variable value with a default fallback.
 ///
 /// Retrieves an environment variable by key, returning the default value if
@@ -83,8 +475,281 @@ pub fn get_tool_config(tool_name: &str) -> HashMap<String, Value> {
 /// let api_key = get_env_var("WEATHER_API_KEY", "");
 /// let port = get_env_var("PORT", "3000");
 /// ```
-#[allow(dead_code)] // Utility function for tools to use
 pub fn get_env_var(key: &str, default: &str) -> String {
     std::env::var(key).unwrap_or_else(|_| default.to_string())
 }
 
+/// Parse an environment variable, falling back to a default.
+///
+/// Unset variables silently use the default. Variables that are set but cannot
+/// be parsed also use the default, with a warning on stderr so misconfiguration
+/// does not go unnoticed.
+///
+/// # Arguments
+/// * `key` - Environment variable name to look up
+/// * `default` - Value to use when the variable is unset or invalid
+///
+/// # Returns
+/// The parsed environment variable value, or the default
+///
+/// # Example
+/// ```rust
+/// let max_connections: usize = parse_env_var("MAX_CONNECTIONS", 10000);
+/// ```
+pub fn parse_env_var<T>(key: &str, default: T) -> T
+where
+    T: std::str::FromStr + std::fmt::Display,
+{
+    match std::env::var(key) {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            eprintln!("Warning: invalid value {:?} for {}, using default {}", value, key, default);
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+/// Whether `value` switches a flag on: `1`, `true`, `yes` or `on`, in any case.
+pub fn is_truthy(value: &str) -> bool {
+    matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on")
+}
+
+/// Read a boolean flag from an environment variable.
+///
+/// The flag is on when the variable is `1`, `true`, `yes` or `on` (in any
+/// case), and off when it is unset or holds anything else. Every on/off
+/// setting of the server is read this way, so they all accept the same values.
+///
+/// # Example
+/// ```rust
+/// let pretty = env_flag("MCP_PRETTY_LOG");
+/// ```
+pub fn env_flag(key: &str) -> bool {
+    std::env::var(key).is_ok_and(|value| is_truthy(&value))
+}
+
+/// Most HTTP worker threads chosen automatically; `WORKER_THREADS` may exceed it.
+const MAX_AUTO_WORKERS: usize = 16;
+
+/// Where the HTTP worker thread count came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerSource {
+    /// Set explicitly with `WORKER_THREADS`
+    Explicit,
+    /// Detected with `std::thread::available_parallelism`, which honours cgroup CPU quotas
+    Detected,
+    /// Detection failed, so a single worker is used
+    Fallback,
+}
+
+impl WorkerSource {
+    /// Name of the source, as logged at startup.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WorkerSource::Explicit => "WORKER_THREADS",
+            WorkerSource::Detected => "available_parallelism",
+            WorkerSource::Fallback => "fallback",
+        }
+    }
+}
+
+/// Compute the number of HTTP worker threads.
+///
+/// An explicit count is used as is. Otherwise the detected parallelism is
+/// used, capped at 16 to avoid excessive context switching, and a single
+/// worker is used if detection failed.
+///
+/// # Arguments
+/// * `available` - Detected parallelism, or `None` if it could not be determined
+/// * `explicit` - Count set by the operator, or `None` (a zero count is ignored)
+///
+/// # Returns
+/// The worker count and where it came from.
+pub fn worker_count(available: Option<usize>, explicit: Option<usize>) -> (usize, WorkerSource) {
+    match (explicit.filter(|&n| n > 0), available.filter(|&n| n > 0)) {
+        (Some(n), _) => (n, WorkerSource::Explicit),
+        (None, Some(n)) => (n.min(MAX_AUTO_WORKERS), WorkerSource::Detected),
+        (None, None) => (1, WorkerSource::Fallback),
+    }
+}
+
+/// Determine the HTTP worker thread count from `WORKER_THREADS` and the
+/// parallelism available to the process.
+///
+/// # Returns
+/// The worker count and where it came from.
+pub fn workers_from_env() -> (usize, WorkerSource) {
+    let available = std::thread::available_parallelism().ok().map(|n| n.get());
+    let explicit = std::env::var("WORKER_THREADS").ok().and_then(|value| match value.trim().parse() {
+        Ok(n) => Some(n),
+        Err(_) => {
+            eprintln!("Warning: invalid value {:?} for WORKER_THREADS, detecting the worker count", value);
+            None
+        }
+    });
+    worker_count(available, explicit)
+}
+
+/// Format of the server's own diagnostic logs on stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per line, for log aggregation
+    Json,
+}
+
+impl std::fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFormat::Text => write!(f, "text"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("Unknown log format: {}", other)),
+        }
+    }
+}
+
+/// Log format selected by `MCP_LOG_FORMAT`, read once.
+static LOG_FORMAT: OnceLock<LogFormat> = OnceLock::new();
+
+/// Get the configured log format (`MCP_LOG_FORMAT`, default `text`).
+pub fn log_format() -> LogFormat {
+    *LOG_FORMAT.get_or_init(|| parse_env_var("MCP_LOG_FORMAT", LogFormat::Text))
+}
+
+/// Identifier of this server instance, read or generated once.
+static INSTANCE_ID: OnceLock<String> = OnceLock::new();
+
+/// Get the identifier of this server instance.
+///
+/// Taken from `INSTANCE_ID`, or a random UUID generated at first use when the
+/// variable is unset or blank, so replicas behind a load balancer can be told
+/// apart.
+pub fn instance_id() -> &'static str {
+    INSTANCE_ID.get_or_init(|| {
+        let configured = get_env_var("INSTANCE_ID", "");
+        match configured.trim() {
+            "" => uuid::Uuid::new_v4().to_string(),
+            id => id.to_string(),
+        }
+    })
+}
+
+/// Write a structured log record to stderr as a single JSON line.
+///
+/// # Arguments
+/// * `record` - JSON object describing the event, with an `event` field
+pub fn log_record(record: Value) {
+    eprintln!("{}", record);
+}
+
+/// Write a diagnostic message to stderr in the configured log format.
+///
+/// Text mode writes the message as is; JSON mode wraps it in a
+/// `{"event": "log", "message": ...}` record. Use the `log_stderr!` macro
+/// rather than calling this directly.
+pub fn log_message(message: std::fmt::Arguments) {
+    match log_format() {
+        LogFormat::Text => eprintln!("{}", message),
+        LogFormat::Json => log_record(serde_json::json!({
+            "event": "log",
+            "message": message.to_string()
+        })),
+    }
+}
+
+/// Log a formatted diagnostic message to stderr, like `eprintln!`, in the
+/// format selected by `MCP_LOG_FORMAT`.
+macro_rules! log_stderr {
+    ($($arg:tt)*) => {
+        $crate::core::utils::log_message(format_args!($($arg)*))
+    };
+}
+pub(crate) use log_stderr;
+
+/// Whether `MCP_PRETTY_LOG` is enabled, read once.
+static PRETTY_LOG: OnceLock<bool> = OnceLock::new();
+
+/// Log a pretty-printed copy of a protocol message to stderr when
+/// `MCP_PRETTY_LOG` is `true`.
+///
+/// This is a debugging aid only: the message on the wire stays compact.
+/// Input that is not valid JSON is not logged here, since it is already
+/// reported as a parse error.
+///
+/// # Arguments
+/// * `direction` - Label for the message, e.g. `"request"` or `"response"`
+/// * `message` - Raw JSON message as read or written
+pub fn log_pretty(direction: &str, message: &[u8]) {
+    if !*PRETTY_LOG.get_or_init(|| env_flag("MCP_PRETTY_LOG")) {
+        return;
+    }
+
+    if let Ok(value) = serde_json::from_slice::<Value>(message)
+        && let Ok(pretty) = serde_json::to_string_pretty(&value)
+    {
+        eprintln!("--- {} ---\n{}", direction, pretty);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_vars_fall_back_to_their_default() {
+        // Each case uses its own variable, as tests run concurrently
+        // SAFETY: no other test reads or writes these variables
+        unsafe {
+            std::env::set_var("TEST_PARSE_VALID", " 250 ");
+            std::env::set_var("TEST_PARSE_GARBAGE", "lots");
+            std::env::set_var("TEST_PARSE_NEGATIVE", "-5");
+            std::env::set_var("TEST_PARSE_EMPTY", "");
+        }
+
+        assert_eq!(parse_env_var("TEST_PARSE_UNSET", 10000usize), 10000);
+        assert_eq!(parse_env_var("TEST_PARSE_VALID", 10000usize), 250);
+        assert_eq!(parse_env_var("TEST_PARSE_GARBAGE", 10000usize), 10000);
+        assert_eq!(parse_env_var("TEST_PARSE_NEGATIVE", 30u64), 30);
+        assert_eq!(parse_env_var("TEST_PARSE_EMPTY", 5u64), 5);
+    }
+
+    #[test]
+    fn flags_accept_the_usual_spellings_of_on() {
+        for value in ["1", "true", "TRUE", "yes", "On", " on "] {
+            assert!(is_truthy(value), "{:?}", value);
+        }
+        for value in ["", "0", "false", "no", "off", "enabled"] {
+            assert!(!is_truthy(value), "{:?}", value);
+        }
+        assert!(!env_flag("MCP_TEST_FLAG_THAT_IS_NEVER_SET"));
+    }
+
+    #[test]
+    fn worker_count_prefers_an_explicit_count() {
+        assert_eq!(worker_count(Some(4), Some(32)), (32, WorkerSource::Explicit));
+        assert_eq!(worker_count(None, Some(3)), (3, WorkerSource::Explicit));
+
+        // A zero override is ignored in favour of detection
+        assert_eq!(worker_count(Some(4), Some(0)), (4, WorkerSource::Detected));
+    }
+
+    #[test]
+    fn detected_worker_count_is_capped_with_a_single_worker_fallback() {
+        assert_eq!(worker_count(Some(1), None), (1, WorkerSource::Detected));
+        assert_eq!(worker_count(Some(64), None), (MAX_AUTO_WORKERS, WorkerSource::Detected));
+        assert_eq!(worker_count(Some(0), None), (1, WorkerSource::Fallback));
+        assert_eq!(worker_count(None, None), (1, WorkerSource::Fallback));
+    }
+}
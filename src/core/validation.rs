@@ -0,0 +1,311 @@
+//! Tool Argument Validation
+//!
+//! This module validates tool call arguments against a tool's JSON input schema,
+//! and structured tool results against a tool's JSON output schema.
+//! Validation collects every issue it finds rather than stopping at the first,
+//! and classifies each one by severity:
+//! - Errors (missing required fields, wrong types, values outside an enum) cause
+//!   the call to be rejected with a JSON-RPC -32602 error.
+//! - Warnings (fields marked `deprecated: true` in the schema) are passed back to
+//!   the client in the result's `_meta.warnings` while the tool still runs.
+//!
+//! Only the subset of JSON Schema used by tool input schemas is understood:
+//! `type`, `properties`, `required`, `enum`, `items` and the `deprecated` extension.
+//!
+//! Tool schemas themselves are checked once at startup with `check_schema`,
+//! which compiles them as full JSON Schema documents.
+//!
+//! When `MCP_COERCE_ARGS` is enabled, `coerce_arguments` first converts string
+//! values to the number or boolean their schema asks for (`"42"` to `42`,
+//! `"true"` to `true`). Strings that do not convert are left as they are and
+//! reported by validation as usual.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Severity of a validation issue.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The arguments are unusable and the call must be rejected
+    Error,
+    /// The arguments are usable but the client should change them
+    Warning,
+}
+
+/// Message of the issue reported for a missing required field.
+const MISSING_FIELD: &str = "missing required field";
+
+/// A single problem found while validating arguments.
+#[derive(Serialize, Debug, Clone)]
+pub struct ValidationIssue {
+    /// How serious the issue is
+    pub severity: Severity,
+    /// Location of the offending value (e.g., "arguments.message")
+    pub path: String,
+    /// Human-readable description of the problem
+    pub message: String,
+}
+
+/// All issues found while validating a set of arguments.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    /// Issues in the order they were found
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Whether any issue is severe enough to reject the call.
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|issue| issue.severity == Severity::Error)
+    }
+
+    /// Issues with error severity.
+    pub fn errors(&self) -> Vec<&ValidationIssue> {
+        self.issues.iter().filter(|issue| issue.severity == Severity::Error).collect()
+    }
+
+    /// Messages of all issues with warning severity.
+    pub fn warnings(&self) -> Vec<String> {
+        self.issues.iter()
+            .filter(|issue| issue.severity == Severity::Warning)
+            .map(|issue| format!("{}: {}", issue.path, issue.message))
+            .collect()
+    }
+
+    /// Required fields that were missing, as paths below the root (e.g.,
+    /// "message" for "arguments.message").
+    pub fn missing_fields(&self) -> Vec<String> {
+        self.issues.iter()
+            .filter(|issue| issue.severity == Severity::Error && issue.message == MISSING_FIELD)
+            .map(|issue| {
+                issue.path.split_once('.')
+                    .map_or(issue.path.as_str(), |(_, field)| field)
+                    .to_string()
+            })
+            .collect()
+    }
+
+    fn push(&mut self, severity: Severity, path: &str, message: String) {
+        self.issues.push(ValidationIssue {
+            severity,
+            path: path.to_string(),
+            message,
+        });
+    }
+}
+
+/// Validate tool arguments against a JSON input schema.
+///
+/// # Arguments
+/// * `schema` - The tool's input schema
+/// * `arguments` - Arguments supplied by the client
+///
+/// # Returns
+/// A report containing every error and warning found.
+pub fn validate_arguments(schema: &Value, arguments: &Value) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    validate_value(schema, arguments, "arguments", &mut report);
+    report
+}
+
+/// Validate a tool's structured output against its JSON output schema.
+///
+/// # Arguments
+/// * `schema` - The tool's output schema
+/// * `output` - Structured content produced by the tool handler
+///
+/// # Returns
+/// A report containing every error and warning found, with paths rooted at
+/// "structuredContent".
+pub fn validate_output(schema: &Value, output: &Value) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    validate_value(schema, output, "structuredContent", &mut report);
+    report
+}
+
+/// Validate a tool's configuration section against its config schema.
+///
+/// Besides the checks made on arguments, keys of the section that the
+/// schema's `properties` do not describe are reported as errors, so a typo
+/// such as `prefx` is caught instead of being silently ignored.
+///
+/// # Arguments
+/// * `schema` - The tool's config schema
+/// * `config` - The tool's configuration section
+/// * `path` - Location of the section (e.g., "tools.echo")
+///
+/// # Returns
+/// A report containing every error and warning found, with paths rooted at `path`.
+pub fn validate_config(schema: &Value, config: &Value, path: &str) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    validate_value(schema, config, path, &mut report);
+    if let (Some(properties), Some(object)) = (schema.get("properties").and_then(|v| v.as_object()), config.as_object()) {
+        for key in object.keys().filter(|key| !properties.contains_key(*key)) {
+            report.push(Severity::Error, &format!("{}.{}", path, key), "unknown config key".to_string());
+        }
+    }
+    report
+}
+
+/// Convert string arguments to the scalar types their schema expects.
+///
+/// Strings holding a number are converted for `integer` and `number` fields,
+/// and `"true"`/`"false"` for `boolean` fields, at any depth of `properties`
+/// and `items`. Other values, and strings that do not convert, are unchanged.
+///
+/// # Arguments
+/// * `schema` - The tool's input schema
+/// * `arguments` - Arguments supplied by the client, converted in place
+pub fn coerce_arguments(schema: &Value, arguments: &mut Value) {
+    match arguments {
+        Value::String(text) => {
+            let expected = schema.get("type").and_then(|v| v.as_str()).unwrap_or_default();
+            if let Some(coerced) = coerce_string(expected, text) {
+                *arguments = coerced;
+            }
+        }
+        Value::Object(object) => {
+            if let Some(properties) = schema.get("properties").and_then(|v| v.as_object()) {
+                for (field, field_value) in object.iter_mut() {
+                    if let Some(field_schema) = properties.get(field) {
+                        coerce_arguments(field_schema, field_value);
+                    }
+                }
+            }
+        }
+        Value::Array(array) => {
+            if let Some(items) = schema.get("items") {
+                for item in array {
+                    coerce_arguments(items, item);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Convert a string to a value of the expected schema type, if it represents one.
+fn coerce_string(expected: &str, text: &str) -> Option<Value> {
+    let text = text.trim();
+    match expected {
+        "integer" => text.parse::<i64>().map(Value::from)
+            .or_else(|_| text.parse::<u64>().map(Value::from))
+            .ok(),
+        "number" => text.parse::<i64>().map(Value::from)
+            .ok()
+            .or_else(|| text.parse::<f64>().ok().and_then(serde_json::Number::from_f64).map(Value::Number)),
+        "boolean" => match text {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Check that a tool schema is a valid JSON Schema document.
+///
+/// The schema is compiled with the `jsonschema` crate, which also validates it
+/// against its meta-schema, so mistakes such as an unknown `type` name are
+/// caught. Remote `$ref`s are not resolved.
+///
+/// # Returns
+/// `Ok(())` if the schema compiles, or a description of the first problem.
+pub fn check_schema(schema: &Value) -> Result<(), String> {
+    jsonschema::validator_for(schema)
+        .map(|_| ())
+        .map_err(|e| match e.instance_path().as_str() {
+            "" => e.to_string(),
+            path => format!("{} (at {})", e, path),
+        })
+}
+
+/// Recursively validate a value against a (sub)schema.
+fn validate_value(schema: &Value, value: &Value, path: &str, report: &mut ValidationReport) {
+    if let Some(expected) = schema.get("type").and_then(|v| v.as_str())
+        && !matches_type(expected, value) {
+        report.push(
+            Severity::Error,
+            path,
+            format!("expected {}, got {}", expected, type_name(value)),
+        );
+        // Nested checks are meaningless once the type is wrong
+        return;
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|v| v.as_array())
+        && !allowed.contains(value) {
+        report.push(
+            Severity::Error,
+            path,
+            format!("value must be one of {}", Value::Array(allowed.clone())),
+        );
+    }
+
+    if let Some(object) = value.as_object() {
+        // Required fields must be present
+        if let Some(required) = schema.get("required").and_then(|v| v.as_array()) {
+            for field in required.iter().filter_map(|v| v.as_str()) {
+                if !object.contains_key(field) {
+                    report.push(
+                        Severity::Error,
+                        &format!("{}.{}", path, field),
+                        MISSING_FIELD.to_string(),
+                    );
+                }
+            }
+        }
+
+        // Validate each supplied field that the schema describes
+        if let Some(properties) = schema.get("properties").and_then(|v| v.as_object()) {
+            for (field, field_value) in object {
+                if let Some(field_schema) = properties.get(field) {
+                    let field_path = format!("{}.{}", path, field);
+                    if field_schema.get("deprecated").and_then(|v| v.as_bool()).unwrap_or(false) {
+                        report.push(
+                            Severity::Warning,
+                            &field_path,
+                            "field is deprecated".to_string(),
+                        );
+                    }
+                    validate_value(field_schema, field_value, &field_path, report);
+                }
+            }
+        }
+    }
+
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (index, item) in array.iter().enumerate() {
+            validate_value(items, item, &format!("{}[{}]", path, index), report);
+        }
+    }
+}
+
+/// Check a value against a JSON Schema primitive type name.
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64()
+            || value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        // Unknown type keywords are not enforced
+        _ => true,
+    }
+}
+
+/// JSON type name of a value, for error messages.
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
@@ -1,21 +1,23 @@
-/// MCP Server Entry Point
-///
-/// This is the main entry point for the MCP server. It parses environment variables
-/// to determine the transport mode (STDIO or HTTP) and server configuration, then
-/// starts the appropriate server implementation.
-///
-/// Environment Variables:
-/// - SERVER_NAME: Name of the server (default: "mcp-server")
-/// - SERVER_VERSION: Version string (default: "0.1.0")
-/// - MCP_TRANSPORT_MODE: "stdio", "http", or "both" (default: "both")
-/// - HOST: Bind address for HTTP mode (default: "0.0.0.0")
-/// - PORT: Port number for HTTP mode (default: 3000)
+//! MCP Server Entry Point
+//!
+//! This is the main entry point for the MCP server. It parses environment variables
+//! to determine the transport mode (STDIO or HTTP) and server configuration, then
+//! builds and runs the server with `ServerBuilder`.
+//!
+//! Environment Variables:
+//! - SERVER_NAME: Name of the server (default: "mcp-server")
+//! - SERVER_VERSION: Version string (default: "0.1.0")
+//! - MCP_TRANSPORT_MODE: "stdio", "http", "both", or "unix" (default: "both")
+//! - HOST: Bind address for HTTP mode (default: "0.0.0.0")
+//! - PORT: Port number for HTTP mode (default: 3000)
+//! - MCP_SOCKET_PATH: Socket path for Unix socket mode (default: "/tmp/mcp-server.sock")
+//! - STDIO_BANNER: Banner notification written on STDIO startup (default: unset)
 
 mod core;
 mod tools;
 
 use std::env;
-use crate::core::server;
+use crate::core::server::{ServerBuilder, Transport, DEFAULT_SOCKET_PATH};
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
@@ -27,53 +29,31 @@ async fn main() -> std::io::Result<()> {
     // Default to "both" to support both STDIO (MCP Inspector) and HTTP simultaneously
     let transport = env::var("MCP_TRANSPORT_MODE")
         .unwrap_or_else(|_| "both".to_string());
-    
-    match transport.as_str() {
-        "stdio" => {
-            // STDIO mode only: Read from stdin, write to stdout
-            // Used for MCP Inspector and local development
-            server::run_server_stdio(name, version).await
-        }
-        "http" => {
-            // HTTP mode only: Run as HTTP server with Actix Web
-            // Used for production deployments and web integrations
-            let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-            let port = env::var("PORT")
-                .unwrap_or_else(|_| "3000".to_string())
-                .parse::<u16>()
-                .unwrap_or(3000);
-            server::run_server_http(name, version, host, port).await
-        }
-        "both" => {
-            // Both modes: Run STDIO and HTTP concurrently
-            // This allows MCP Inspector to use STDIO while HTTP endpoints are available
-            let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-            let port = env::var("PORT")
-                .unwrap_or_else(|_| "3000".to_string())
-                .parse::<u16>()
-                .unwrap_or(3000);
-            
-            let name_clone = name.clone();
-            let version_clone = version.clone();
-            
-            // Spawn STDIO server in a background task
-            let stdio_handle = tokio::spawn(async move {
-                if let Err(e) = server::run_server_stdio(name_clone, version_clone).await {
-                    eprintln!("STDIO server error: {}", e);
-                }
-            });
-            
-            // Run HTTP server in the foreground
-            let http_result = server::run_server_http(name, version, host, port).await;
-            
-            // If HTTP server exits, abort STDIO task
-            stdio_handle.abort();
-            
-            http_result
-        }
-        _ => {
-            eprintln!("Error: Invalid transport mode '{}'. Must be 'stdio', 'http', or 'both'", transport);
+    let transport: Transport = match transport.parse() {
+        Ok(transport) => transport,
+        Err(e) => {
+            eprintln!("Error: {}", e);
             std::process::exit(1);
         }
-    }
+    };
+    
+    // HTTP bind address, used by the "http" and "both" modes
+    let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
+    let port = env::var("PORT")
+        .unwrap_or_else(|_| "3000".to_string())
+        .parse::<u16>()
+        .unwrap_or(3000);
+    
+    // Unix domain socket path, used by the "unix" mode
+    let socket_path = env::var("MCP_SOCKET_PATH").unwrap_or_else(|_| DEFAULT_SOCKET_PATH.to_string());
+    
+    ServerBuilder::new()
+        .name(name)
+        .version(version)
+        .transport(transport)
+        .host(host)
+        .port(port)
+        .socket_path(socket_path)
+        .run()
+        .await
 }
@@ -0,0 +1,76 @@
+//! Calc Tool Implementation
+//!
+//! A small arithmetic tool that applies a binary operation (add, sub, mul, div)
+//! to two numbers. It demonstrates argument validation with `ToolError`:
+//! unknown operations, non-numeric operands and division by zero are all
+//! reported as invalid arguments.
+
+use crate::core::server::{CallContext, MCPTool, ToolError, ToolHandler, ToolOutput, ToolRegistry};
+use serde_json::Value;
+
+/// Register the calc tool with the tool registry.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tool will be registered
+pub fn register(registry: &mut ToolRegistry) {
+    let tool = MCPTool {
+        name: "calc".to_string(),
+        description: "Perform basic arithmetic on two numbers.".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["add", "sub", "mul", "div"],
+                    "description": "The arithmetic operation to perform"
+                },
+                "a": {
+                    "type": "number",
+                    "description": "The left-hand operand"
+                },
+                "b": {
+                    "type": "number",
+                    "description": "The right-hand operand"
+                }
+            },
+            "required": ["operation", "a", "b"]
+        }),
+        output_schema: None,
+        cacheable: true,
+    };
+    
+    let handler: ToolHandler = Box::new(|call: CallContext| -> Result<ToolOutput, ToolError> {
+        let args = &call.arguments;
+        let operation = args.get("operation")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidArguments("Missing required parameter: operation".to_string()))?;
+        let a = number_arg(args, "a")?;
+        let b = number_arg(args, "b")?;
+        
+        let result = match operation {
+            "add" => a + b,
+            "sub" => a - b,
+            "mul" => a * b,
+            "div" => {
+                if b == 0.0 {
+                    return Err(ToolError::InvalidArguments("Division by zero".to_string()));
+                }
+                a / b
+            }
+            other => {
+                return Err(ToolError::InvalidArguments(format!("Unknown operation: {}", other)));
+            }
+        };
+        
+        Ok(serde_json::json!({ "result": result }).into())
+    });
+    
+    registry.register(tool, handler);
+}
+
+/// Extract a required numeric argument.
+fn number_arg(args: &Value, name: &str) -> Result<f64, ToolError> {
+    args.get(name)
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| ToolError::InvalidArguments(format!("Missing or non-numeric parameter: {}", name)))
+}
@@ -1,71 +1,108 @@
-/// Echo Tool Implementation
-///
-/// This is an example tool that demonstrates the basic structure for implementing
-/// MCP tools. The echo tool takes a message parameter and returns it, optionally
-/// with a configurable prefix from the tool configuration.
+//! Echo Tool Implementation
+//!
+//! This is an example tool that demonstrates the basic structure for implementing
+//! MCP tools. The echo tool takes a message parameter and returns it, optionally
+//! with a prefix. The echoed text is returned both as a text block and as
+//! structured content.
+//!
+//! The prefix is resolved in this order:
+//! 1. The `prefix` argument of the call, when present
+//! 2. The `prefix` value in the tool configuration (`kmcp.yaml`)
+//! 3. The `prefix` value in the `tools._default` configuration section
+//! 4. No prefix
+//!
+//! The tool is submitted as a `ToolPlugin`, so it is registered automatically
+//! without an entry in `initialize_tools`.
 
-use crate::core::server::{MCPTool, ToolRegistry, ToolHandler};
+use crate::core::plugin::ToolPlugin;
+use crate::core::server::{CallContext, ToolError, ToolOutput, ToolRegistry};
 use crate::core::utils;
 use serde_json::Value;
 
+/// Plugin that registers the echo tool.
+struct EchoPlugin;
+
+impl ToolPlugin for EchoPlugin {
+    fn register(&self, registry: &mut ToolRegistry) {
+        register(registry);
+    }
+}
+
+submit_tool_plugin!(EchoPlugin);
+
 /// Register the echo tool with the tool registry.
 ///
 /// This function is called during server initialization to add the echo tool
 /// to the available tools list. It defines the tool's metadata (name, description,
-/// input schema) and implements the tool's handler function.
+/// input schema) and implements the tool's handler function via `register_tool!`.
 ///
 /// # Arguments
 /// * `registry` - Mutable reference to the tool registry where the tool will be registered
 pub fn register(registry: &mut ToolRegistry) {
-    let tool = MCPTool {
-        name: "echo".to_string(),
-        description: "Echo a message back to the client.".to_string(),
-        input_schema: serde_json::json!({
+    register_tool! {
+        registry,
+        name: "echo",
+        description: "Echo a message back to the client.",
+        properties: {
+            "message": {
+                "type": "string",
+                "description": "The message to echo"
+            } required,
+            "prefix": {
+                "type": "string",
+                "description": "Text prepended to the message; overrides the configured prefix"
+            },
+        },
+        // Settings read from the tool's section of kmcp.yaml
+        config_schema: {
             "type": "object",
             "properties": {
-                "message": {
+                "prefix": {
                     "type": "string",
-                    "description": "The message to echo"
+                    "description": "Text prepended to every message without a prefix argument"
                 }
-            },
-            "required": ["message"]
-        }),
-    };
-    
-    // Define the tool handler function
-    // The handler receives JSON arguments and returns either a JSON result or an error string
-    let handler: ToolHandler = Box::new(|args: Value| -> Result<Value, String> {
-        // Extract and validate the required "message" parameter
-        // Returns an error if the parameter is missing or not a string
-        let message = args.get("message")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| "Missing required parameter: message".to_string())?;
-        
-        // Load tool-specific configuration from kmcp.yaml
-        // The echo tool supports an optional "prefix" configuration value
-        let config = utils::get_tool_config("echo");
-        let prefix = config.get("prefix")
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
-        
-        // Build the result string with optional prefix
-        // Pre-allocate string capacity when prefix is present to avoid reallocations
-        let result = if prefix.is_empty() {
-            // No prefix configured - return message as-is
-            message.to_string()
-        } else {
-            // Prefix configured - concatenate prefix and message
-            // Pre-allocate with known capacity for efficiency
-            let mut result = String::with_capacity(prefix.len() + message.len());
-            result.push_str(prefix);
-            result.push_str(message);
-            result
-        };
-        
-        // Return result as JSON object
-        Ok(serde_json::json!({ "result": result }))
-    });
-    
-    registry.register(tool, handler);
+            }
+        },
+        // Define the tool handler function
+        // The handler receives the call's context and returns either a ToolOutput or a ToolError
+        // Handlers that only need the arguments can take `|args: Value|` instead
+        handler: |call: CallContext| -> Result<ToolOutput, ToolError> {
+            let args = &call.arguments;
+            
+            // Extract and validate the required "message" parameter
+            // Returns an error if the parameter is missing or not a string
+            let message = args.get("message")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| "Missing required parameter: message".to_string())?;
+            
+            // A "prefix" argument takes precedence over the configured prefix
+            // Reject non-string values rather than silently falling back
+            let prefix = match args.get("prefix") {
+                Some(Value::String(prefix)) => prefix.clone(),
+                Some(_) => {
+                    return Err(ToolError::InvalidArguments("prefix must be a string".to_string()));
+                }
+                // Configured prefix from kmcp.yaml, for echo or for every tool
+                None => utils::get_tool_config_value("echo", "prefix", String::new()),
+            };
+            
+            // Build the result string with optional prefix
+            // Pre-allocate string capacity when prefix is present to avoid reallocations
+            let result = if prefix.is_empty() {
+                // No prefix configured - return message as-is
+                message.to_string()
+            } else {
+                // Prefix configured - concatenate prefix and message
+                // Pre-allocate with known capacity for efficiency
+                let mut result = String::with_capacity(prefix.len() + message.len());
+                result.push_str(&prefix);
+                result.push_str(message);
+                result
+            };
+            
+            // Return the message as text for the model and as structured content
+            let structured = serde_json::json!({ "result": result });
+            Ok(ToolOutput::text(result).with_structured(structured))
+        },
+    }
 }
-
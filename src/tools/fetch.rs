@@ -0,0 +1,175 @@
+//! Fetch Tool Implementation
+//!
+//! Performs an HTTP GET and returns the response body as text. It demonstrates
+//! outbound network access from an async tool handler, guarded by an allowlist:
+//! only hosts matching a pattern in `MCP_FETCH_ALLOWED_HOSTS` may be fetched,
+//! including on every redirect, so the tool cannot be turned against internal
+//! services. Without `MCP_FETCH_ALLOWED_HOSTS` every call fails.
+//!
+//! Host patterns are comma-separated and matched case-insensitively:
+//! - `example.com` matches exactly that host
+//! - `*.example.com` matches any subdomain of example.com, but not example.com itself
+//! - `*` matches any host
+//!
+//! Environment Variables:
+//! - MCP_FETCH_ALLOWED_HOSTS: Allowed host patterns (default: unset, tool disabled)
+//! - MCP_FETCH_TIMEOUT_SECS: Timeout of the whole request (default: 10)
+//! - MCP_FETCH_MAX_BYTES: Largest response body returned (default: 1048576)
+
+use crate::core::server::{AsyncToolHandler, CallContext, MCPTool, ToolError, ToolOutput, ToolRegistry};
+use crate::core::utils;
+use reqwest::Url;
+use std::time::Duration;
+
+/// Default request timeout, in seconds.
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+/// Default largest response body, in bytes (1 MiB).
+const DEFAULT_MAX_BYTES: usize = 1024 * 1024;
+
+/// Most redirects followed for one call.
+const MAX_REDIRECTS: usize = 5;
+
+/// Register the fetch tool with the tool registry.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tool will be registered
+pub fn register(registry: &mut ToolRegistry) {
+    let tool = MCPTool {
+        name: "fetch".to_string(),
+        description: "Fetch a URL with HTTP GET and return the response body as text.".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "http or https URL whose host is allowed by the server"
+                }
+            },
+            "required": ["url"]
+        }),
+        output_schema: None,
+        cacheable: false,
+    };
+    
+    let handler: AsyncToolHandler = Box::new(|call: CallContext| {
+        Box::pin(async move {
+            let url = call.arguments.get("url")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ToolError::InvalidArguments("Missing required parameter: url".to_string()))?;
+            
+            // Configuration is read per call so it can be changed without re-registering
+            let allowed = allowed_hosts();
+            if allowed.is_empty() {
+                return Err(ToolError::ExecutionFailed("fetch is disabled: MCP_FETCH_ALLOWED_HOSTS is not set".to_string()));
+            }
+            let timeout = utils::parse_env_var("MCP_FETCH_TIMEOUT_SECS", DEFAULT_TIMEOUT_SECS);
+            let max_bytes = utils::parse_env_var("MCP_FETCH_MAX_BYTES", DEFAULT_MAX_BYTES);
+            
+            let url = Url::parse(url)
+                .map_err(|e| ToolError::InvalidArguments(format!("Invalid URL {}: {}", url, e)))?;
+            check_url(&url, &allowed).map_err(ToolError::InvalidArguments)?;
+            
+            tokio::select! {
+                result = fetch(url, allowed, Duration::from_secs(timeout), max_bytes) => result,
+                // Stop early if the client cancels the call
+                _ = call.cancellation.cancelled() => Err(ToolError::ExecutionFailed("Cancelled".to_string())),
+            }
+        })
+    });
+    
+    registry.register_async(tool, handler);
+}
+
+/// GET a URL and read its body, refusing bodies larger than `max_bytes`.
+///
+/// # Arguments
+/// * `url` - URL to fetch, already checked against the allowlist
+/// * `allowed` - Allowed host patterns, applied to every redirect
+/// * `timeout` - Timeout of the whole request, including reading the body
+/// * `max_bytes` - Largest body accepted
+async fn fetch(url: Url, allowed: Vec<String>, timeout: Duration, max_bytes: usize) -> Result<ToolOutput, ToolError> {
+    // Redirects are followed only to allowed hosts
+    let redirects = reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else if let Err(e) = check_url(attempt.url(), &allowed) {
+            attempt.error(e)
+        } else {
+            attempt.follow()
+        }
+    });
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .redirect(redirects)
+        .build()
+        .map_err(|e| ToolError::ExecutionFailed(format!("Cannot create HTTP client: {}", e)))?;
+    
+    let mut response = client.get(url.clone()).send().await
+        .map_err(|e| ToolError::ExecutionFailed(format!("Request to {} failed: {}", url, e)))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(ToolError::ExecutionFailed(format!("{} returned HTTP {}", url, status)));
+    }
+    
+    // Refuse oversized bodies up front when the length is declared, and stop
+    // reading as soon as the cap is passed when it is not
+    let too_large = || ToolError::ExecutionFailed(format!("Response is larger than {} bytes", max_bytes));
+    if response.content_length().is_some_and(|length| length > max_bytes as u64) {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await
+        .map_err(|e| ToolError::ExecutionFailed(format!("Reading {} failed: {}", url, e)))?
+    {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    
+    Ok(ToolOutput::text(String::from_utf8_lossy(&body)))
+}
+
+/// Read the allowed host patterns from `MCP_FETCH_ALLOWED_HOSTS`.
+///
+/// # Returns
+/// The lower-cased patterns; empty when the variable is unset or blank.
+fn allowed_hosts() -> Vec<String> {
+    utils::get_env_var("MCP_FETCH_ALLOWED_HOSTS", "")
+        .split(',')
+        .map(|pattern| pattern.trim().to_ascii_lowercase())
+        .filter(|pattern| !pattern.is_empty())
+        .collect()
+}
+
+/// Check that a URL uses http(s) and that its host is allowed.
+///
+/// # Arguments
+/// * `url` - URL of the request or redirect
+/// * `allowed` - Lower-cased host patterns
+///
+/// # Returns
+/// A message explaining why the URL is refused, if it is.
+fn check_url(url: &Url, allowed: &[String]) -> Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Unsupported URL scheme: {}", url.scheme()));
+    }
+    let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+    if allowed.iter().any(|pattern| host_matches(pattern, &host)) {
+        Ok(())
+    } else {
+        Err(format!("Host is not allowed: {}", host))
+    }
+}
+
+/// Whether a host matches an allowlist pattern (see the module documentation).
+fn host_matches(pattern: &str, host: &str) -> bool {
+    if pattern == "*" {
+        return !host.is_empty();
+    }
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host.strip_suffix(domain).is_some_and(|sub| sub.ends_with('.') && sub.len() > 1),
+        None => pattern == host,
+    }
+}
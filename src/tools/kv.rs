@@ -0,0 +1,225 @@
+//! Key/Value Store Tool Implementation
+//!
+//! The `kv_set`, `kv_get` and `kv_delete` tools keep JSON values under string
+//! keys in memory, separately for each client session. They demonstrate a
+//! family of stateful tools sharing one store keyed by the `session_id` of the
+//! call. Values persist for the life of a STDIO or Unix socket connection, an
+//! SSE stream or an HTTP session (`Mcp-Session-Id`); other plain HTTP requests
+//! are stateless, so a value set by one request is not seen by the next.
+//!
+//! Each session holds at most `KV_MAX_ENTRIES` entries (default 1000), and
+//! setting a new key beyond that fails. Entries expire `KV_TTL_SECS` after
+//! they were last set (default 3600, `0` keeps them until the server stops).
+//! Expired entries, and sessions left without entries, are swept as the store
+//! is used, so closed sessions do not accumulate.
+
+use crate::core::server::{CallContext, ToolError, ToolRegistry};
+use crate::core::utils;
+use dashmap::DashMap;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Default most entries a session may hold.
+const DEFAULT_MAX_ENTRIES: usize = 1000;
+
+/// Default lifetime of an entry, in seconds.
+const DEFAULT_TTL_SECS: u64 = 3600;
+
+/// Least time between sweeps of every session for expired entries.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Limits of the store.
+#[derive(Debug, Clone, Copy)]
+pub struct KvLimits {
+    /// Most entries a session may hold
+    pub max_entries: usize,
+    /// Time after which an entry expires, or `None` to keep entries
+    pub ttl: Option<Duration>,
+}
+
+impl KvLimits {
+    /// Limits set by `KV_MAX_ENTRIES` and `KV_TTL_SECS`.
+    pub fn from_env() -> Self {
+        let ttl_secs = utils::parse_env_var("KV_TTL_SECS", DEFAULT_TTL_SECS);
+        Self {
+            max_entries: utils::parse_env_var("KV_MAX_ENTRIES", DEFAULT_MAX_ENTRIES),
+            ttl: (ttl_secs > 0).then(|| Duration::from_secs(ttl_secs)),
+        }
+    }
+}
+
+/// A stored value.
+struct Entry {
+    /// The value set
+    value: Value,
+    /// When the entry expires, if it does
+    expires: Option<Instant>,
+}
+
+impl Entry {
+    /// Whether the entry has expired at `now`.
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+/// Entries of every session, by session id and key.
+struct KvStore {
+    /// Entries by session id, then key
+    sessions: DashMap<String, HashMap<String, Entry>>,
+    /// Entry cap and lifetime
+    limits: KvLimits,
+    /// When every session was last swept for expired entries
+    last_sweep: Mutex<Instant>,
+}
+
+impl KvStore {
+    fn new(limits: KvLimits) -> Self {
+        Self {
+            sessions: DashMap::new(),
+            limits,
+            last_sweep: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Store `value` under `key`, replacing any previous value.
+    ///
+    /// # Returns
+    /// Whether the key is new to the session.
+    fn set(&self, session_id: &str, key: &str, value: Value) -> Result<bool, ToolError> {
+        self.sweep();
+        let now = Instant::now();
+        let mut entries = self.sessions.entry(session_id.to_string()).or_default();
+        entries.retain(|_, entry| !entry.is_expired(now));
+
+        let created = !entries.contains_key(key);
+        if created && entries.len() >= self.limits.max_entries {
+            return Err(ToolError::ExecutionFailed(format!(
+                "Session store is full: at most {} entries",
+                self.limits.max_entries
+            )));
+        }
+        let expires = self.limits.ttl.map(|ttl| now + ttl);
+        entries.insert(key.to_string(), Entry { value, expires });
+        Ok(created)
+    }
+
+    /// The unexpired value under `key`.
+    fn get(&self, session_id: &str, key: &str) -> Option<Value> {
+        let entries = self.sessions.get(session_id)?;
+        entries.get(key)
+            .filter(|entry| !entry.is_expired(Instant::now()))
+            .map(|entry| entry.value.clone())
+    }
+
+    /// Remove `key`, and the session once it has no entries left.
+    ///
+    /// # Returns
+    /// Whether an unexpired value was removed.
+    fn delete(&self, session_id: &str, key: &str) -> bool {
+        let removed = self.sessions.get_mut(session_id)
+            .and_then(|mut entries| entries.remove(key))
+            .is_some_and(|entry| !entry.is_expired(Instant::now()));
+        self.sessions.remove_if(session_id, |_, entries| entries.is_empty());
+        removed
+    }
+
+    /// Drop expired entries and empty sessions, at most once per `SWEEP_INTERVAL`.
+    fn sweep(&self) {
+        if self.limits.ttl.is_none() {
+            return;
+        }
+        {
+            let mut last_sweep = self.last_sweep.lock().unwrap_or_else(|e| e.into_inner());
+            if last_sweep.elapsed() < SWEEP_INTERVAL {
+                return;
+            }
+            *last_sweep = Instant::now();
+        }
+        let now = Instant::now();
+        self.sessions.retain(|_, entries| {
+            entries.retain(|_, entry| !entry.is_expired(now));
+            !entries.is_empty()
+        });
+    }
+}
+
+/// Id of the calling session, which every kv tool requires.
+fn session_id(call: &CallContext) -> Result<&str, ToolError> {
+    call.session_id.as_deref()
+        .ok_or_else(|| ToolError::ExecutionFailed("no client session".to_string()))
+}
+
+/// The required string `key` argument.
+fn key(call: &CallContext) -> Result<&str, ToolError> {
+    call.arguments.get("key")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArguments("Missing required parameter: key".to_string()))
+}
+
+/// Register the kv tools with limits from the environment.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tools will be registered
+pub fn register(registry: &mut ToolRegistry) {
+    register_with_limits(registry, KvLimits::from_env());
+}
+
+/// Register the kv tools, sharing one store with the given limits.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tools will be registered
+/// * `limits` - Entry cap and lifetime of the store
+pub fn register_with_limits(registry: &mut ToolRegistry, limits: KvLimits) {
+    let store = Arc::new(KvStore::new(limits));
+
+    let set_store = store.clone();
+    register_tool! {
+        registry,
+        name: "kv_set",
+        description: "Store a JSON value under a key for the current session.",
+        properties: {
+            "key": { "type": "string", "description": "Key to store the value under" } required,
+            "value": { "description": "Any JSON value" } required,
+        },
+        handler: move |call: CallContext| -> Result<Value, ToolError> {
+            let value = call.arguments.get("value")
+                .cloned()
+                .ok_or_else(|| ToolError::InvalidArguments("Missing required parameter: value".to_string()))?;
+            let key = key(&call)?;
+            let created = set_store.set(session_id(&call)?, key, value)?;
+            Ok(serde_json::json!({ "key": key, "created": created }))
+        },
+    }
+
+    let get_store = store.clone();
+    register_tool! {
+        registry,
+        name: "kv_get",
+        description: "Get the JSON value stored under a key for the current session.",
+        properties: {
+            "key": { "type": "string", "description": "Key to look up" } required,
+        },
+        handler: move |call: CallContext| -> Result<Value, ToolError> {
+            let key = key(&call)?;
+            let value = get_store.get(session_id(&call)?, key);
+            Ok(serde_json::json!({ "key": key, "found": value.is_some(), "value": value }))
+        },
+    }
+
+    register_tool! {
+        registry,
+        name: "kv_delete",
+        description: "Delete the value stored under a key for the current session.",
+        properties: {
+            "key": { "type": "string", "description": "Key to delete" } required,
+        },
+        handler: move |call: CallContext| -> Result<Value, ToolError> {
+            let key = key(&call)?;
+            let deleted = store.delete(session_id(&call)?, key);
+            Ok(serde_json::json!({ "key": key, "deleted": deleted }))
+        },
+    }
+}
@@ -0,0 +1,81 @@
+//! Lines Tool Implementation
+//!
+//! Produces a number of numbered lines of text, one chunk per line. It
+//! demonstrates streaming tool handlers: over HTTP each line is sent to the
+//! client as soon as it is produced, while other transports receive all lines
+//! in a single text block. An optional delay between lines makes the streaming
+//! visible, and the tool stops early if the call is cancelled.
+
+use crate::core::server::{CallContext, MCPTool, StreamToolHandler, ToolError, ToolRegistry, ToolStream};
+use bytes::Bytes;
+use futures_util::stream;
+use std::time::Duration;
+
+/// Most lines a single call may request.
+const MAX_LINES: u64 = 100_000;
+
+/// Longest delay between two lines, in milliseconds.
+const MAX_DELAY_MS: u64 = 1_000;
+
+/// Register the lines tool with the tool registry.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tool will be registered
+pub fn register(registry: &mut ToolRegistry) {
+    let tool = MCPTool {
+        name: "lines".to_string(),
+        description: "Stream a number of numbered lines of text.".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "count": {
+                    "type": "integer",
+                    "description": "Number of lines to produce (1 to 100000, default 10)"
+                },
+                "delay_ms": {
+                    "type": "integer",
+                    "description": "Delay before each line in milliseconds (at most 1000, default 0)"
+                }
+            }
+        }),
+        output_schema: None,
+        cacheable: false,
+    };
+
+    let handler: StreamToolHandler = Box::new(|call: CallContext| {
+        let args = &call.arguments;
+        let count = args.get("count").and_then(|v| v.as_u64()).unwrap_or(10);
+        if !(1..=MAX_LINES).contains(&count) {
+            return invalid(format!("count must be between 1 and {}", MAX_LINES));
+        }
+        let delay_ms = args.get("delay_ms").and_then(|v| v.as_u64()).unwrap_or(0);
+        if delay_ms > MAX_DELAY_MS {
+            return invalid(format!("delay_ms must be at most {}", MAX_DELAY_MS));
+        }
+
+        let cancellation = call.cancellation;
+        Box::pin(stream::unfold(1, move |line| {
+            let cancellation = cancellation.clone();
+            async move {
+                if line > count {
+                    return None;
+                }
+                if delay_ms > 0 {
+                    tokio::select! {
+                        _ = tokio::time::sleep(Duration::from_millis(delay_ms)) => {}
+                        // Stop early if the client cancels the call
+                        _ = cancellation.cancelled() => return None,
+                    }
+                }
+                Some((Ok(Bytes::from(format!("line {}\n", line))), line + 1))
+            }
+        }))
+    });
+
+    registry.register_stream(tool, handler);
+}
+
+/// Build a stream that fails at once with an invalid arguments error.
+fn invalid(message: String) -> ToolStream {
+    Box::pin(stream::once(async move { Err(ToolError::InvalidArguments(message)) }))
+}
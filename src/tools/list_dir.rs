@@ -0,0 +1,168 @@
+//! List Directory Tool Implementation
+//!
+//! Lists the entries of a directory below the sandboxed file root set by
+//! `MCP_FILE_ROOT`, with the same path rules as `read_file`: any path that
+//! escapes the root is rejected as invalid arguments. Entries are returned as
+//! structured content, sorted by name. With `recursive`, subdirectories are
+//! listed too, down to the depth set by the tool's `max_depth` configuration;
+//! symlinks are listed but never followed, so recursion cannot leave the root.
+
+use crate::core::server::{CallContext, MCPTool, ToolError, ToolHandler, ToolOutput, ToolRegistry};
+use crate::core::utils;
+use crate::tools::read_file;
+use serde_json::Value;
+use std::path::Path;
+
+/// Levels of subdirectories listed by a recursive call, unless configured.
+const DEFAULT_MAX_DEPTH: usize = 8;
+
+/// Most entries a single call returns; further entries are left out with a warning.
+const MAX_ENTRIES: usize = 10_000;
+
+/// Register the list_dir tool with the tool registry.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tool will be registered
+pub fn register(registry: &mut ToolRegistry) {
+    let tool = MCPTool {
+        name: "list_dir".to_string(),
+        description: "List the entries of a directory in the server's file root directory.".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the directory, relative to the file root (default: the root itself)"
+                },
+                "recursive": {
+                    "type": "boolean",
+                    "description": "Also list the contents of subdirectories (default false)"
+                }
+            }
+        }),
+        output_schema: Some(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "entries": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string" },
+                            "is_dir": { "type": "boolean" },
+                            "size": { "type": "integer" }
+                        },
+                        "required": ["name", "is_dir", "size"]
+                    }
+                }
+            },
+            "required": ["entries"]
+        })),
+        cacheable: false,
+    };
+
+    let handler: ToolHandler = Box::new(|call: CallContext| -> Result<ToolOutput, ToolError> {
+        let args = call.arguments;
+        let path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+        let recursive = args.get("recursive").and_then(|v| v.as_bool()).unwrap_or(false);
+        let max_depth = if recursive {
+            utils::get_tool_config_value("list_dir", "max_depth", DEFAULT_MAX_DEPTH)
+        } else {
+            0
+        };
+
+        let root = read_file::file_root("list_dir")?;
+        let dir = read_file::resolve_in_root(&root, path)?;
+        if !dir.is_dir() {
+            return Err(ToolError::InvalidArguments(format!("Not a directory: {}", path)));
+        }
+
+        let mut listing = Listing::default();
+        listing.walk(&dir, "", max_depth)?;
+
+        let mut output = ToolOutput::default()
+            .with_structured(serde_json::json!({ "entries": listing.entries }));
+        for warning in listing.warnings {
+            output = output.with_warning(warning);
+        }
+        Ok(output)
+    });
+
+    registry.register(tool, handler);
+    registry.set_config_schema("list_dir", serde_json::json!({
+        "type": "object",
+        "properties": {
+            "max_depth": {
+                "type": "integer",
+                "description": "Levels of subdirectories listed by a recursive call (default 8)"
+            }
+        }
+    }));
+}
+
+/// Entries collected while walking a directory tree.
+#[derive(Default)]
+struct Listing {
+    /// `{name, is_dir, size}` objects, names relative to the listed directory
+    entries: Vec<Value>,
+    /// Subdirectories that could not be read, and the truncation notice
+    warnings: Vec<String>,
+    /// Whether `MAX_ENTRIES` was reached and the walk stopped
+    truncated: bool,
+}
+
+impl Listing {
+    /// List `dir`, then its subdirectories while `depth` allows.
+    ///
+    /// Only a failure to read the listed directory itself is an error; an
+    /// unreadable subdirectory becomes a warning.
+    ///
+    /// # Arguments
+    /// * `dir` - Directory to list
+    /// * `prefix` - Path of `dir` relative to the listed directory, empty at the top
+    /// * `depth` - Levels of subdirectories still to descend into
+    fn walk(&mut self, dir: &Path, prefix: &str, depth: usize) -> Result<(), ToolError> {
+        let mut children: Vec<_> = std::fs::read_dir(dir)
+            .and_then(|entries| entries.collect::<Result<_, _>>())
+            .map_err(|e| ToolError::ExecutionFailed(format!("Cannot list {}: {}", display(prefix), e)))?;
+        children.sort_by_key(|entry| entry.file_name());
+
+        for child in children {
+            if self.truncated {
+                return Ok(());
+            }
+            if self.entries.len() >= MAX_ENTRIES {
+                self.warnings.push(format!("Listing truncated at {} entries", MAX_ENTRIES));
+                self.truncated = true;
+                return Ok(());
+            }
+
+            let name = format!("{}{}", prefix, child.file_name().to_string_lossy());
+            // DirEntry metadata does not follow symlinks
+            let metadata = child.metadata()
+                .map_err(|e| ToolError::ExecutionFailed(format!("Cannot read {}: {}", name, e)))?;
+            let is_dir = metadata.is_dir();
+            self.entries.push(serde_json::json!({
+                "name": name,
+                "is_dir": is_dir,
+                "size": if is_dir { 0 } else { metadata.len() }
+            }));
+
+            if is_dir && depth > 0 {
+                let nested = format!("{}/", name);
+                if let Err(e) = self.walk(&child.path(), &nested, depth - 1) {
+                    self.warnings.push(e.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Name of a listed directory for messages, `.` for the top level.
+fn display(prefix: &str) -> &str {
+    match prefix.trim_end_matches('/') {
+        "" => ".",
+        name => name,
+    }
+}
@@ -0,0 +1,74 @@
+//! Long Task Tool Implementation
+//!
+//! Simulates a long-running job made of a number of equal steps and reports
+//! its progress after each one. It demonstrates progress notifications: when
+//! the client sends a `progressToken` in the call's `_meta`, it receives
+//! `notifications/progress` from 0 up to the total number of steps before the
+//! result arrives. Like the sleep tool, it stops early if the call is cancelled.
+
+use crate::core::server::{AsyncToolHandler, CallContext, MCPTool, ToolError, ToolRegistry};
+use std::time::Duration;
+
+/// Most steps a single call may request.
+const MAX_STEPS: u64 = 100;
+
+/// Longest duration of one step, in milliseconds.
+const MAX_STEP_MS: u64 = 10_000;
+
+/// Register the long_task tool with the tool registry.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tool will be registered
+pub fn register(registry: &mut ToolRegistry) {
+    let tool = MCPTool {
+        name: "long_task".to_string(),
+        description: "Run a simulated long task, reporting progress after each step.".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "steps": {
+                    "type": "integer",
+                    "description": "Number of steps to run (1 to 100, default 5)"
+                },
+                "step_ms": {
+                    "type": "integer",
+                    "description": "Duration of each step in milliseconds (at most 10000, default 200)"
+                }
+            }
+        }),
+        output_schema: None,
+        cacheable: false,
+    };
+    
+    let handler: AsyncToolHandler = Box::new(|call: CallContext| {
+        Box::pin(async move {
+            let args = &call.arguments;
+            let steps = args.get("steps").and_then(|v| v.as_u64()).unwrap_or(5);
+            if !(1..=MAX_STEPS).contains(&steps) {
+                return Err(ToolError::InvalidArguments(format!("steps must be between 1 and {}", MAX_STEPS)));
+            }
+            let step_ms = args.get("step_ms").and_then(|v| v.as_u64()).unwrap_or(200);
+            if step_ms > MAX_STEP_MS {
+                return Err(ToolError::InvalidArguments(format!("step_ms must be at most {}", MAX_STEP_MS)));
+            }
+            
+            let total = steps as f64;
+            call.progress.report(0.0, Some(total), Some("Starting"));
+            for step in 1..=steps {
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_millis(step_ms)) => {}
+                    // Stop early if the client cancels the call
+                    _ = call.cancellation.cancelled() => {
+                        return Err(ToolError::ExecutionFailed("Cancelled".to_string()));
+                    }
+                }
+                let message = format!("Completed step {} of {}", step, steps);
+                call.progress.report(step as f64, Some(total), Some(&message));
+            }
+            
+            Ok(serde_json::json!({ "result": format!("Completed {} steps", steps) }).into())
+        })
+    });
+    
+    registry.register_async(tool, handler);
+}
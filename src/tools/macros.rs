@@ -0,0 +1,221 @@
+//! Tool Registration Macros
+//!
+//! This module provides the `register_tool!` macro, which removes the boilerplate
+//! shared by every tool's `register` function: building the `MCPTool`, assembling
+//! the JSON input schema, boxing the handler closure and adding both to the registry.
+//! It also provides `submit_tool_plugin!`, which submits a `ToolPlugin` for
+//! automatic registration at startup.
+//!
+//! # Example
+//! ```rust,ignore
+//! register_tool! {
+//!     registry,
+//!     name: "greet",
+//!     description: "Greet someone by name.",
+//!     properties: {
+//!         "name": { "type": "string", "description": "Who to greet" } required,
+//!         "shout": { "type": "boolean", "description": "Use upper case" },
+//!     },
+//!     handler: |args: Value| -> Result<Value, ToolError> {
+//!         Ok(serde_json::json!({ "result": format!("Hello, {}", args["name"]) }))
+//!     },
+//! }
+//! ```
+//!
+//! The handler takes either the call's JSON arguments (`|args: Value|`) or its
+//! whole `CallContext` (`|call: CallContext|`), and may return either a plain
+//! JSON value or a `ToolOutput`.
+//!
+//! Properties followed by the `required` keyword are collected, in declaration
+//! order, into the schema's `required` array. An optional `output_schema: { ... }`
+//! entry before the handler declares the schema of the tool's structured result,
+//! and an optional `cacheable: true` entry lets the tool's results be cached.
+//! An optional `aliases: ["old_name"]` entry lets the tool also be called by
+//! other names, e.g. a former name kept for existing clients, and an optional
+//! `config_schema: { ... }` entry declares the schema of the tool's section of
+//! the configuration file, which is checked at startup.
+
+/// Register a tool from a name, description, property schemas and handler.
+///
+/// Expands into the full registration: an object input schema whose `properties`
+/// are the given property schemas and whose `required` array lists every property
+/// marked `required`, an `MCPTool` definition, and a boxed `ToolHandler` that
+/// passes the handler its arguments or context and converts the handler's
+/// result into a `ToolOutput`.
+macro_rules! register_tool {
+    (
+        $registry:expr,
+        name: $name:expr,
+        description: $description:expr,
+        properties: { $( $field:literal : $schema:tt $( $marker:ident )? ),* $(,)? },
+        $( output_schema: $output_schema:tt, )?
+        $( cacheable: $cacheable:expr, )?
+        $( aliases: [ $( $alias:expr ),* $(,)? ], )?
+        $( config_schema: $config_schema:tt, )?
+        handler: $handler:expr $(,)?
+    ) => {{
+        #[allow(unused_mut)]
+        let mut properties = serde_json::Map::new();
+        #[allow(unused_mut)]
+        let mut required: Vec<&str> = Vec::new();
+        $(
+            properties.insert($field.to_string(), serde_json::json!($schema));
+            $( register_tool!(@mark $marker, required, $field); )?
+        )*
+        
+        let tool = $crate::core::server::MCPTool {
+            name: $name.to_string(),
+            description: $description.to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": properties,
+                "required": required
+            }),
+            output_schema: register_tool!(@output $( $output_schema )?),
+            cacheable: register_tool!(@cacheable $( $cacheable )?),
+        };
+        
+        // Handlers may take the arguments or the full `CallContext`, and may
+        // return a plain JSON value or a full `ToolOutput`
+        let call = $handler;
+        let handler: $crate::core::server::ToolHandler = Box::new(
+            move |context: $crate::core::server::CallContext| call(context.into()).map(Into::into)
+        );
+        $registry.register(tool, handler);
+        $( $(
+            if !$registry.add_alias($alias, $name) {
+                $crate::core::utils::log_stderr!("Ignoring alias '{}' of tool '{}': the name is taken", $alias, $name);
+            }
+        )* )?
+        $( $registry.set_config_schema($name, serde_json::json!($config_schema)); )?
+    }};
+    
+    // The output schema is optional
+    (@output) => { None };
+    (@output $output_schema:tt) => { Some(serde_json::json!($output_schema)) };
+    
+    // Tools are not cached unless they opt in
+    (@cacheable) => { false };
+    (@cacheable $cacheable:expr) => { $cacheable };
+    
+    // Only the `required` marker is accepted after a property schema
+    (@mark required, $required:ident, $field:literal) => {
+        $required.push($field);
+    };
+}
+
+/// Submit a `ToolPlugin` for automatic registration at startup.
+///
+/// The argument must be a constant expression, typically a unit struct. Every
+/// submitted plugin is registered by `initialize_tools`.
+macro_rules! submit_tool_plugin {
+    ($plugin:expr) => {
+        inventory::submit! {
+            $crate::core::plugin::PluginRegistration(&$plugin)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::server::{CallContext, ContentBlock, RegisteredHandler, ToolError, ToolOutput, ToolRegistry};
+    use serde_json::{json, Value};
+
+    /// Call a sync tool's handler directly with `arguments`.
+    fn call(registry: &ToolRegistry, name: &str, arguments: Value) -> Result<ToolOutput, ToolError> {
+        let Some(RegisteredHandler::Sync(handler)) = registry.handlers.get(name).cloned() else {
+            panic!("{} is not a sync tool", name);
+        };
+        handler(CallContext::from(arguments))
+    }
+
+    #[test]
+    fn macro_expands_into_a_full_registration() {
+        let mut registry = ToolRegistry::new();
+        register_tool! {
+            registry,
+            name: "greet",
+            description: "Greet someone by name.",
+            properties: {
+                "name": { "type": "string" } required,
+                "shout": { "type": "boolean" },
+                "greeting": { "type": "string" } required,
+            },
+            output_schema: { "type": "object" },
+            cacheable: true,
+            aliases: ["hello"],
+            handler: |args: Value| -> Result<ToolOutput, ToolError> {
+                let greeting = args["greeting"].as_str().unwrap_or_default();
+                let name = args["name"].as_str().unwrap_or_default();
+                Ok(ToolOutput::text(format!("{}, {}", greeting, name)))
+            },
+        }
+
+        let tool = registry.get_tool("greet").expect("greet is registered");
+        assert_eq!(tool.description, "Greet someone by name.");
+        assert_eq!(tool.input_schema, json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "shout": { "type": "boolean" },
+                "greeting": { "type": "string" }
+            },
+            "required": ["name", "greeting"]
+        }));
+        assert_eq!(tool.output_schema, Some(json!({ "type": "object" })));
+        assert!(tool.cacheable);
+        assert_eq!(registry.resolve_alias("hello"), "greet");
+
+        let output = call(&registry, "greet", json!({ "name": "Ada", "greeting": "Hi" })).unwrap();
+        assert_eq!(output.content, [ContentBlock::Text { text: "Hi, Ada".to_string() }]);
+    }
+
+    #[test]
+    fn macro_registered_echo_behaves_as_before() {
+        let mut registry = ToolRegistry::new();
+        crate::tools::echo::register(&mut registry);
+
+        let echo = registry.get_tool("echo").expect("echo is registered");
+        assert_eq!(echo.input_schema["required"], json!(["message"]));
+        assert!(!echo.cacheable);
+
+        let output = call(&registry, "echo", json!({ "message": "hi", "prefix": "> " })).unwrap();
+        assert_eq!(output.content, [ContentBlock::Text { text: "> hi".to_string() }]);
+        assert_eq!(output.structured_content, Some(json!({ "result": "> hi" })));
+
+        assert!(matches!(
+            call(&registry, "echo", json!({ "prefix": "> " })),
+            Err(ToolError::ExecutionFailed(message)) if message == "Missing required parameter: message"
+        ));
+        assert!(matches!(
+            call(&registry, "echo", json!({ "message": "hi", "prefix": 1 })),
+            Err(ToolError::InvalidArguments(message)) if message == "prefix must be a string"
+        ));
+    }
+
+    /// Plugin registering one tool, submitted only in test builds.
+    struct TestPlugin;
+
+    impl crate::core::plugin::ToolPlugin for TestPlugin {
+        fn register(&self, registry: &mut ToolRegistry) {
+            register_tool! {
+                registry,
+                name: "plugin_probe",
+                description: "Registered by a test plugin.",
+                properties: {},
+                handler: |_args: Value| -> Result<Value, ToolError> { Ok(json!({})) },
+            }
+        }
+    }
+
+    submit_tool_plugin!(TestPlugin);
+
+    #[test]
+    fn submitted_plugins_are_registered_at_startup() {
+        let registry = crate::core::server::initialize_tools();
+
+        assert!(registry.get_tool("plugin_probe").is_some());
+        // echo is registered through its own plugin
+        assert!(registry.get_tool("echo").is_some());
+    }
+}
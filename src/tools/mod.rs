@@ -1,8 +1,26 @@
-/// Tools Module
-///
-/// This module contains all MCP tool implementations. Each tool is implemented
-/// as a separate module that exports a `register` function to add the tool to
-/// the registry during server initialization.
+//! Tools Module
+//!
+//! This module contains all MCP tool implementations. Each tool is implemented
+//! as a separate module that exports a `register` function to add the tool to
+//! the registry during server initialization.
+//!
+//! The `register_tool!` and `submit_tool_plugin!` macros from `macros.rs` are
+//! available to every tool module declared after them.
 
-pub mod echo;
+#[macro_use]
+mod macros;
 
+pub mod calc;
+pub mod echo;
+pub mod fetch;
+pub mod kv;
+pub mod lines;
+pub mod list_dir;
+pub mod long_task;
+pub mod pixel;
+pub mod read_file;
+pub mod read_files;
+pub mod render_template;
+pub mod session_counter;
+pub mod sleep;
+pub mod stats;
@@ -0,0 +1,50 @@
+//! Pixel Tool Implementation
+//!
+//! Returns a 1x1 PNG image. It demonstrates binary tool results: the image is
+//! returned as a base64 `image` content block, or, on request, as an embedded
+//! `resource` block.
+
+use crate::core::server::{ContentBlock, EmbeddedResource, ToolError, ToolOutput, ToolRegistry};
+use serde_json::Value;
+
+/// A complete 1x1 PNG image file.
+const PIXEL_PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4,
+    0x89, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0x64, 0x60, 0xf8, 0x5f,
+    0x0f, 0x00, 0x02, 0x87, 0x01, 0x80, 0xeb, 0x47, 0xba, 0x92, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45,
+    0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+/// Register the pixel tool with the tool registry.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tool will be registered
+pub fn register(registry: &mut ToolRegistry) {
+    register_tool! {
+        registry,
+        name: "pixel",
+        description: "Return a 1x1 PNG image.",
+        properties: {
+            "embed": {
+                "type": "boolean",
+                "description": "Return the PNG as an embedded resource instead of an image"
+            },
+        },
+        handler: |args: Value| -> Result<ToolOutput, ToolError> {
+            let embed = args.get("embed")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            
+            let block = if embed {
+                ContentBlock::Resource {
+                    resource: EmbeddedResource::blob("pixel://1x1.png", "image/png", PIXEL_PNG),
+                }
+            } else {
+                ContentBlock::image(PIXEL_PNG, "image/png")
+            };
+            
+            Ok(ToolOutput::text("A 1x1 PNG image").with_content(block))
+        },
+    }
+}
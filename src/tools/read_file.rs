@@ -0,0 +1,128 @@
+//! Read File Tool Implementation
+//!
+//! Reads a UTF-8 text file from a sandboxed root directory and returns its
+//! contents as a text block. It demonstrates safe filesystem access: paths are
+//! resolved relative to the root set by `MCP_FILE_ROOT`, and any path that
+//! escapes the root, whether through `..`, an absolute path or a symlink, is
+//! rejected as invalid arguments. Without `MCP_FILE_ROOT` every call fails.
+
+use crate::core::server::{CallContext, MCPTool, ToolError, ToolHandler, ToolOutput, ToolRegistry};
+use crate::core::utils;
+use std::path::{Component, Path, PathBuf};
+
+/// Largest file the tool will return, in bytes.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Register the read_file tool with the tool registry.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tool will be registered
+pub fn register(registry: &mut ToolRegistry) {
+    let tool = MCPTool {
+        name: "read_file".to_string(),
+        description: "Read a text file from the server's file root directory.".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the file, relative to the file root"
+                }
+            },
+            "required": ["path"]
+        }),
+        output_schema: None,
+        cacheable: false,
+    };
+    
+    let handler: ToolHandler = Box::new(|call: CallContext| -> Result<ToolOutput, ToolError> {
+        let args = call.arguments;
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidArguments("Missing required parameter: path".to_string()))?;
+        
+        let root = file_root("read_file")?;
+        Ok(ToolOutput::text(read_in_root(&root, path)?))
+    });
+    
+    registry.register(tool, handler);
+}
+
+/// Get the canonical file root set by `MCP_FILE_ROOT`.
+///
+/// The root is read per call so it can be changed without re-registering.
+///
+/// # Arguments
+/// * `tool_name` - Name of the calling tool, for the error when no root is set
+pub(crate) fn file_root(tool_name: &str) -> Result<PathBuf, ToolError> {
+    let root = utils::get_env_var("MCP_FILE_ROOT", "");
+    if root.is_empty() {
+        return Err(ToolError::ExecutionFailed(format!("{} is disabled: MCP_FILE_ROOT is not set", tool_name)));
+    }
+    Path::new(&root)
+        .canonicalize()
+        .map_err(|e| ToolError::ExecutionFailed(format!("Invalid MCP_FILE_ROOT: {}", e)))
+}
+
+/// Read a UTF-8 text file inside `root`, refusing directories and files over
+/// `MAX_FILE_BYTES`.
+///
+/// # Arguments
+/// * `root` - Canonical root directory
+/// * `path` - Path from the tool arguments, relative to the root
+pub(crate) fn read_in_root(root: &Path, path: &str) -> Result<String, ToolError> {
+    let file = resolve_in_root(root, path)?;
+    
+    // Refuse directories and oversized files before reading anything
+    let metadata = std::fs::metadata(&file)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Cannot read {}: {}", path, e)))?;
+    if !metadata.is_file() {
+        return Err(ToolError::InvalidArguments(format!("Not a file: {}", path)));
+    }
+    if metadata.len() > MAX_FILE_BYTES {
+        return Err(ToolError::InvalidArguments(format!(
+            "File is too large: {} ({} bytes, at most {})",
+            path,
+            metadata.len(),
+            MAX_FILE_BYTES
+        )));
+    }
+    
+    std::fs::read_to_string(&file)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Cannot read {}: {}", path, e)))
+}
+
+/// Resolve a client-supplied path to an existing file inside `root`.
+///
+/// The path is first checked lexically, so a path that escapes the root is
+/// rejected without touching the filesystem (and without revealing whether
+/// the target exists). It is then canonicalized and checked again, which
+/// catches symlinks that point outside the root.
+///
+/// # Arguments
+/// * `root` - Canonical root directory
+/// * `path` - Path from the tool arguments, relative to the root
+pub(crate) fn resolve_in_root(root: &Path, path: &str) -> Result<PathBuf, ToolError> {
+    let escapes = || ToolError::InvalidArguments(format!("Path escapes the file root: {}", path));
+    
+    // Lexical check: only plain components, and `..` may not climb above the root
+    let mut depth = 0usize;
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => depth = depth.checked_sub(1).ok_or_else(escapes)?,
+            Component::RootDir | Component::Prefix(_) => return Err(escapes()),
+        }
+    }
+    
+    // Canonical check: resolves symlinks, and fails for missing files
+    let file = root.join(path).canonicalize().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => ToolError::InvalidArguments(format!("File not found: {}", path)),
+        _ => ToolError::ExecutionFailed(format!("Cannot read {}: {}", path, e)),
+    })?;
+    if !file.starts_with(root) {
+        return Err(escapes());
+    }
+    Ok(file)
+}
@@ -0,0 +1,74 @@
+//! Read Files Tool Implementation
+//!
+//! Reads several text files from the sandboxed file root in one call, with the
+//! same rules as `read_file`. It demonstrates partial results: every file that
+//! can be read is returned as its own text block, in the order requested,
+//! while files that cannot be read are reported as warnings in the result's
+//! `_meta.warnings` instead of failing the whole call. The call only fails if
+//! no file could be read.
+
+use crate::core::server::{CallContext, ContentBlock, MCPTool, ToolError, ToolHandler, ToolOutput, ToolRegistry};
+use crate::tools::read_file;
+
+/// Most files a single call may read.
+const MAX_FILES: usize = 50;
+
+/// Register the read_files tool with the tool registry.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tool will be registered
+pub fn register(registry: &mut ToolRegistry) {
+    let tool = MCPTool {
+        name: "read_files".to_string(),
+        description: "Read several text files from the server's file root directory, skipping unreadable ones.".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "paths": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Paths of the files, relative to the file root (at most 50)"
+                }
+            },
+            "required": ["paths"]
+        }),
+        output_schema: None,
+        cacheable: false,
+    };
+    
+    let handler: ToolHandler = Box::new(|call: CallContext| -> Result<ToolOutput, ToolError> {
+        let args = call.arguments;
+        let paths: Vec<&str> = args.get("paths")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| ToolError::InvalidArguments("Missing required parameter: paths".to_string()))?
+            .iter()
+            .map(|path| path.as_str().ok_or_else(|| ToolError::InvalidArguments("paths must be strings".to_string())))
+            .collect::<Result<_, _>>()?;
+        if paths.is_empty() || paths.len() > MAX_FILES {
+            return Err(ToolError::InvalidArguments(format!("paths must list between 1 and {} files", MAX_FILES)));
+        }
+        
+        let root = read_file::file_root("read_files")?;
+        let mut output = ToolOutput::default();
+        let mut read = 0;
+        for path in paths {
+            match read_file::read_in_root(&root, path) {
+                Ok(contents) => {
+                    output = output.with_content(ContentBlock::Text { text: contents });
+                    read += 1;
+                }
+                // The error messages name the path, so they serve as warnings as they are
+                Err(ToolError::InvalidArguments(message) | ToolError::ExecutionFailed(message)) => {
+                    output = output.with_warning(message);
+                }
+            }
+        }
+        
+        if read == 0 {
+            return Err(ToolError::ExecutionFailed(format!("No file could be read: {}", output.warnings.join("; "))));
+        }
+        Ok(output)
+    });
+    
+    registry.register(tool, handler);
+}
@@ -0,0 +1,112 @@
+//! Render Template Tool Implementation
+//!
+//! Renders a Handlebars template with a JSON data object and returns the
+//! resulting text. Rendering is strict: a template that references a
+//! variable missing from the data is rejected rather than rendered with a
+//! blank, and templates that do not compile are rejected too, both as invalid
+//! arguments. Output is plain text, so values are not HTML-escaped.
+//!
+//! Templates are limited to `MAX_TEMPLATE_BYTES` and the rendered output to
+//! `MAX_OUTPUT_BYTES`; rendering stops as soon as the output would exceed it,
+//! so a small template that expands to a huge result cannot exhaust memory.
+
+use crate::core::server::{ToolError, ToolOutput, ToolRegistry};
+use handlebars::{Handlebars, RenderErrorReason, Template};
+use serde_json::Value;
+use std::io::Write;
+
+/// Largest template accepted, in bytes.
+const MAX_TEMPLATE_BYTES: usize = 64 * 1024;
+
+/// Largest rendered output returned, in bytes.
+const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Name of the template within the per-call registry.
+const TEMPLATE_NAME: &str = "template";
+
+/// Register the render_template tool with the tool registry.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tool will be registered
+pub fn register(registry: &mut ToolRegistry) {
+    register_tool! {
+        registry,
+        name: "render_template",
+        description: "Render a Handlebars template with a JSON data object.",
+        properties: {
+            "template": {
+                "type": "string",
+                "description": "Handlebars template, e.g. \"Hello, {{name}}!\""
+            } required,
+            "data": {
+                "type": "object",
+                "description": "Values referenced by the template (default: no values)"
+            },
+        },
+        cacheable: true,
+        handler: |args: Value| -> Result<ToolOutput, ToolError> {
+            let template = args.get("template")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ToolError::InvalidArguments("Missing required parameter: template".to_string()))?;
+            let data = args.get("data").cloned().unwrap_or_else(|| serde_json::json!({}));
+            render(template, &data).map(ToolOutput::text)
+        },
+    }
+}
+
+/// Render `template` with `data`, enforcing the size limits.
+fn render(template: &str, data: &Value) -> Result<String, ToolError> {
+    if template.len() > MAX_TEMPLATE_BYTES {
+        return Err(ToolError::InvalidArguments(format!(
+            "template is larger than {} bytes",
+            MAX_TEMPLATE_BYTES
+        )));
+    }
+    let compiled = Template::compile(template)
+        .map_err(|e| ToolError::InvalidArguments(format!("Invalid template: {}", e)))?;
+
+    let mut handlebars = Handlebars::new();
+    handlebars.set_strict_mode(true);
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.register_template(TEMPLATE_NAME, compiled);
+
+    let mut output = CappedOutput::default();
+    match handlebars.render_to_write(TEMPLATE_NAME, data, &mut output) {
+        Ok(()) => String::from_utf8(output.bytes)
+            .map_err(|e| ToolError::ExecutionFailed(format!("Rendered output is not UTF-8: {}", e))),
+        Err(_) if output.overflowed => Err(ToolError::ExecutionFailed(format!(
+            "Rendered output is larger than {} bytes",
+            MAX_OUTPUT_BYTES
+        ))),
+        Err(e) => match e.reason() {
+            RenderErrorReason::MissingVariable(Some(path)) => {
+                Err(ToolError::InvalidArguments(format!("Missing variable in data: {}", path)))
+            }
+            _ => Err(ToolError::InvalidArguments(format!("Cannot render template: {}", e))),
+        },
+    }
+}
+
+/// Output buffer that refuses to grow beyond `MAX_OUTPUT_BYTES`.
+#[derive(Default)]
+struct CappedOutput {
+    /// Output rendered so far
+    bytes: Vec<u8>,
+    /// Whether a write was refused for exceeding the limit
+    overflowed: bool,
+}
+
+impl Write for CappedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.bytes.len() + buf.len() > MAX_OUTPUT_BYTES {
+            self.overflowed = true;
+            return Err(std::io::Error::other("output size limit exceeded"));
+        }
+        self.bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
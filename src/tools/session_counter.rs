@@ -0,0 +1,47 @@
+//! Session Counter Tool Implementation
+//!
+//! Counts how many times it has been called in the caller's session. It
+//! demonstrates per-session tool state keyed by the `session_id` of the call:
+//! each STDIO or Unix socket connection, SSE stream and HTTP session
+//! (`Mcp-Session-Id`) has its own count, while other plain HTTP requests are
+//! stateless and always count 1.
+//!
+//! Counts of the most recently used sessions are kept in memory, bounded so
+//! that closed sessions do not accumulate.
+
+use crate::core::server::{CallContext, ToolError, ToolRegistry};
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, OnceLock};
+
+/// Most sessions whose counts are remembered.
+const MAX_SESSIONS: usize = 10_000;
+
+/// Call counts by session id.
+static COUNTS: OnceLock<Mutex<LruCache<String, u64>>> = OnceLock::new();
+
+/// Register the session_counter tool with the tool registry.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tool will be registered
+pub fn register(registry: &mut ToolRegistry) {
+    register_tool! {
+        registry,
+        name: "session_counter",
+        description: "Count the calls made to this tool in the current session.",
+        properties: {},
+        handler: |call: CallContext| -> Result<serde_json::Value, ToolError> {
+            let session_id = call.session_id
+                .ok_or_else(|| ToolError::ExecutionFailed("no client session".to_string()))?;
+
+            let counts = COUNTS.get_or_init(|| {
+                Mutex::new(LruCache::new(NonZeroUsize::new(MAX_SESSIONS).expect("MAX_SESSIONS is not zero")))
+            });
+            let mut counts = counts.lock().unwrap_or_else(|e| e.into_inner());
+            let count = counts.get_or_insert_mut(session_id.clone(), || 0);
+            *count += 1;
+
+            Ok(serde_json::json!({ "session_id": session_id, "count": *count }))
+        },
+    }
+}
@@ -0,0 +1,65 @@
+//! Sleep Tool Implementation
+//!
+//! Waits for the requested number of seconds and then reports how long it slept.
+//! It demonstrates async tool handlers, which wait without occupying a thread,
+//! and is useful for exercising tool call timeouts (`TOOL_TIMEOUT_SECS`) and
+//! cancellation (`notifications/cancelled`).
+
+use crate::core::logging::{self, LogLevel};
+use crate::core::server::{AsyncToolHandler, CallContext, MCPTool, ToolError, ToolRegistry};
+use crate::core::utils;
+use std::time::Duration;
+
+/// Longest sleep a single call may request, in seconds.
+const MAX_SLEEP_SECS: f64 = 300.0;
+
+/// Register the sleep tool with the tool registry.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tool will be registered
+pub fn register(registry: &mut ToolRegistry) {
+    let tool = MCPTool {
+        name: "sleep".to_string(),
+        description: "Wait for a number of seconds before returning.".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "seconds": {
+                    "type": "number",
+                    "description": "How long to sleep, in seconds (at most 300)"
+                }
+            },
+            "required": ["seconds"]
+        }),
+        output_schema: None,
+        cacheable: false,
+    };
+    
+    let handler: AsyncToolHandler = Box::new(|call: CallContext| {
+        Box::pin(async move {
+            let seconds = call.arguments.get("seconds")
+                .and_then(|v| v.as_f64())
+                .ok_or_else(|| ToolError::InvalidArguments("Missing required parameter: seconds".to_string()))?;
+            if !(0.0..=MAX_SLEEP_SECS).contains(&seconds) {
+                return Err(ToolError::InvalidArguments(format!(
+                    "seconds must be between 0 and {}",
+                    MAX_SLEEP_SECS
+                )));
+            }
+            
+            logging::log_to_client(LogLevel::Debug, format!("Sleeping for {} seconds", seconds));
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs_f64(seconds)) => {
+                    Ok(serde_json::json!({ "result": format!("Slept for {} seconds", seconds) }).into())
+                }
+                // Stop early if the client cancels the call
+                _ = call.cancellation.cancelled() => {
+                    utils::log_stderr!("sleep cancelled after less than {} seconds", seconds);
+                    Err(ToolError::ExecutionFailed("Cancelled".to_string()))
+                }
+            }
+        })
+    });
+    
+    registry.register_async(tool, handler);
+}
@@ -0,0 +1,66 @@
+//! Stats Tool Implementation
+//!
+//! Computes summary statistics (count, sum, mean, min, max) for a list of
+//! numbers. It demonstrates structured tool results: the tool declares an
+//! output schema, so tools/call returns the statistics both as a text block
+//! and as machine-readable `structuredContent` validated against that schema.
+
+use crate::core::server::{ToolError, ToolRegistry};
+use serde_json::Value;
+
+/// Register the stats tool with the tool registry.
+///
+/// # Arguments
+/// * `registry` - Mutable reference to the tool registry where the tool will be registered
+pub fn register(registry: &mut ToolRegistry) {
+    register_tool! {
+        registry,
+        name: "stats",
+        description: "Compute count, sum, mean, min and max of a list of numbers.",
+        properties: {
+            "numbers": {
+                "type": "array",
+                "items": { "type": "number" },
+                "description": "The numbers to summarize"
+            } required,
+        },
+        output_schema: {
+            "type": "object",
+            "properties": {
+                "count": { "type": "integer" },
+                "sum": { "type": "number" },
+                "mean": { "type": "number" },
+                "min": { "type": "number" },
+                "max": { "type": "number" }
+            },
+            "required": ["count", "sum", "mean", "min", "max"]
+        },
+        cacheable: true,
+        handler: |args: Value| -> Result<Value, ToolError> {
+            let numbers: Vec<f64> = args.get("numbers")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| ToolError::InvalidArguments("Missing required parameter: numbers".to_string()))?
+                .iter()
+                .filter_map(|v| v.as_f64())
+                .collect();
+            
+            // Mean, min and max are undefined for an empty list
+            if numbers.is_empty() {
+                return Err(ToolError::InvalidArguments("numbers must not be empty".to_string()));
+            }
+            
+            let sum: f64 = numbers.iter().sum();
+            let min = numbers.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = numbers.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            
+            // The returned object becomes the call's structuredContent
+            Ok(serde_json::json!({
+                "count": numbers.len(),
+                "sum": sum,
+                "mean": sum / numbers.len() as f64,
+                "min": min,
+                "max": max
+            }))
+        },
+    }
+}
@@ -0,0 +1,68 @@
+//! Tests of the admin endpoints guarded by `MCP_ADMIN_TOKEN`.
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::TestServer;
+
+/// Admin token the test servers are started with.
+const TOKEN: &str = "admin-secret";
+
+#[tokio::test]
+async fn shutdown_requires_the_admin_token() {
+    let server = TestServer::start_with_env(&[("MCP_ADMIN_TOKEN", TOKEN)]).await;
+
+    for authorization in [None, Some("Bearer wrong")] {
+        let mut request = server.client().post(server.url("/admin/shutdown"));
+        if let Some(authorization) = authorization {
+            request = request.header("Authorization", authorization);
+        }
+        let response = request.send().await.expect("request succeeds");
+        assert_eq!(response.status(), 401, "{:?}", authorization);
+    }
+
+    // The server is still up
+    let (status, _) = server.get_json("/health").await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn shutdown_stops_accepting_connections() {
+    let server = TestServer::start_with_env(&[("MCP_ADMIN_TOKEN", TOKEN)]).await;
+
+    let response = server.client()
+        .post(server.url("/admin/shutdown"))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .expect("request succeeds");
+    assert_eq!(response.status(), 202);
+    let body: serde_json::Value = response.json().await.expect("response is JSON");
+    assert_eq!(body["status"], "shutting_down");
+
+    // Without pooling, every attempt opens a new connection
+    let client = reqwest::Client::builder()
+        .pool_max_idle_per_host(0)
+        .build()
+        .expect("client builds");
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while client.get(server.url("/health")).send().await.is_ok() {
+        assert!(Instant::now() < deadline, "server still accepts connections");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+#[tokio::test]
+async fn admin_endpoints_are_disabled_without_a_token() {
+    let server = TestServer::start().await;
+
+    let response = server.client()
+        .post(server.url("/admin/shutdown"))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .expect("request succeeds");
+
+    assert_eq!(response.status(), 404);
+}
@@ -0,0 +1,65 @@
+//! Tests of the tool execution audit log.
+
+mod common;
+
+use common::TestServer;
+use serde_json::{json, Value};
+
+/// Path of an audit log file unique to one test, removed if left over.
+fn audit_path(test: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("mcp-audit-{}-{}.jsonl", test, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// Read the records of an audit log file.
+fn read_records(path: &std::path::Path) -> Vec<Value> {
+    std::fs::read_to_string(path)
+        .expect("audit log was not written")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("audit record is not JSON"))
+        .collect()
+}
+
+#[tokio::test]
+async fn tool_calls_are_appended_to_the_audit_log() {
+    let path = audit_path("calls");
+    let server = TestServer::start_with_env(&[("MCP_AUDIT_LOG_PATH", path.to_str().unwrap())]).await;
+
+    server.call_tool("echo", json!({ "message": "hi", "api_key": "hunter2" })).await;
+    server.call_tool("read_file", json!({ "path": "does-not-exist.txt" })).await;
+
+    let records = read_records(&path);
+    assert_eq!(records.len(), 2, "unexpected records: {:?}", records);
+
+    let ok = &records[0];
+    assert_eq!(ok["tool"], "echo");
+    assert_eq!(ok["outcome"], "ok");
+    assert_eq!(ok["arguments"], json!({ "message": "hi", "api_key": "[REDACTED]" }));
+    assert!(ok["duration_ms"].is_u64());
+    assert!(ok["timestamp"].as_str().is_some_and(|t| t.ends_with('Z')), "bad timestamp: {}", ok["timestamp"]);
+    assert!(ok.get("error").is_none());
+
+    let failed = &records[1];
+    assert_eq!(failed["tool"], "read_file");
+    assert_eq!(failed["outcome"], "error");
+    assert!(failed["error"].is_string());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn audit_redaction_keys_are_configurable() {
+    let path = audit_path("redact");
+    let server = TestServer::start_with_env(&[
+        ("MCP_AUDIT_LOG_PATH", path.to_str().unwrap()),
+        ("MCP_AUDIT_REDACT_KEYS", "message"),
+    ]).await;
+
+    server.call_tool("echo", json!({ "message": "hi", "api_key": "visible" })).await;
+
+    let records = read_records(&path);
+    assert_eq!(records[0]["arguments"], json!({ "message": "[REDACTED]", "api_key": "visible" }));
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,71 @@
+//! Tests of bearer-token authentication (`MCP_AUTH_TOKEN`).
+
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const TOKEN: &str = "s3cret-token";
+
+/// Start a server that requires `TOKEN`.
+async fn start() -> TestServer {
+    TestServer::start_with_env(&[("MCP_AUTH_TOKEN", TOKEN)]).await
+}
+
+/// POST a ping to `/mcp` with an optional `Authorization` header value.
+async fn ping(server: &TestServer, authorization: Option<&str>) -> (u16, serde_json::Value) {
+    let mut request = server.client()
+        .post(server.url("/mcp"))
+        .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" }));
+    if let Some(authorization) = authorization {
+        request = request.header("authorization", authorization);
+    }
+    let response = request.send().await.expect("request succeeds");
+    let status = response.status().as_u16();
+    (status, response.json().await.expect("response is JSON"))
+}
+
+/// Assert the 401 JSON-RPC error returned to unauthenticated requests.
+fn assert_unauthorized(status: u16, response: &serde_json::Value) {
+    assert_eq!(status, 401, "{}", response);
+    assert_eq!(response["id"], json!(null));
+    assert_eq!(response["error"]["code"], -32001);
+    assert_eq!(response["error"]["message"], "Unauthorized: missing or invalid bearer token");
+}
+
+#[tokio::test]
+async fn requests_without_a_token_are_unauthorized() {
+    let server = start().await;
+
+    let (status, response) = ping(&server, None).await;
+    assert_unauthorized(status, &response);
+}
+
+#[tokio::test]
+async fn requests_with_the_wrong_token_are_unauthorized() {
+    let server = start().await;
+
+    let (status, response) = ping(&server, Some("Bearer not-the-token")).await;
+    assert_unauthorized(status, &response);
+    let (status, response) = ping(&server, Some(TOKEN)).await;
+    assert_unauthorized(status, &response);
+}
+
+#[tokio::test]
+async fn requests_with_the_token_are_handled() {
+    let server = start().await;
+
+    let (status, response) = ping(&server, Some(&format!("Bearer {}", TOKEN))).await;
+    assert_eq!(status, 200, "{}", response);
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"], json!({}));
+}
+
+#[tokio::test]
+async fn health_needs_no_token() {
+    let server = start().await;
+
+    let (status, health) = server.get_json("/health").await;
+    assert_eq!(status, 200);
+    assert_eq!(health["status"], "ok");
+}
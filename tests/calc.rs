@@ -0,0 +1,51 @@
+//! Tests of the calc tool.
+
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+/// Call calc and parse the JSON object in its text content.
+async fn calc(server: &TestServer, operation: &str, a: f64, b: f64) -> serde_json::Value {
+    let result = server.call_tool("calc", json!({ "operation": operation, "a": a, "b": b })).await;
+    assert_eq!(result["isError"], false, "{}", result);
+    serde_json::from_str(result["content"][0]["text"].as_str().expect("text content"))
+        .expect("calc output is JSON")
+}
+
+#[tokio::test]
+async fn each_operation_computes_its_result() {
+    let server = TestServer::start().await;
+
+    assert_eq!(calc(&server, "add", 6.0, 4.0).await, json!({ "result": 10.0 }));
+    assert_eq!(calc(&server, "sub", 6.0, 4.0).await, json!({ "result": 2.0 }));
+    assert_eq!(calc(&server, "mul", 6.0, 4.0).await, json!({ "result": 24.0 }));
+    assert_eq!(calc(&server, "div", 6.0, 4.0).await, json!({ "result": 1.5 }));
+}
+
+#[tokio::test]
+async fn division_by_zero_is_invalid_arguments() {
+    let server = TestServer::start().await;
+
+    let response = server.rpc("tools/call", json!({
+        "name": "calc",
+        "arguments": { "operation": "div", "a": 1, "b": 0 }
+    })).await;
+
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(response["error"]["message"], "Invalid arguments: Division by zero");
+}
+
+#[tokio::test]
+async fn unknown_operation_is_invalid_arguments() {
+    let server = TestServer::start().await;
+
+    let response = server.rpc("tools/call", json!({
+        "name": "calc",
+        "arguments": { "operation": "pow", "a": 2, "b": 3 }
+    })).await;
+
+    assert_eq!(response["error"]["code"], -32602);
+    let message = response["error"]["message"].as_str().unwrap_or_default();
+    assert!(message.contains("operation"), "unexpected message: {}", message);
+}
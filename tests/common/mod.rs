@@ -0,0 +1,303 @@
+//! Integration Test Harness
+//!
+//! Starts the server binary in HTTP mode on a free local port and drives it
+//! over a real socket. Each test gets its own server process, configured
+//! through environment variables, which is stopped when the `TestServer` is
+//! dropped. `run_stdio` instead runs the binary in STDIO mode on a fixed
+//! input and collects what it writes.
+//!
+//! # Example
+//! ```rust,ignore
+//! mod common;
+//! use common::TestServer;
+//!
+//! #[tokio::test]
+//! async fn lists_tools() {
+//!     let server = TestServer::start().await;
+//!     let response = server.rpc("tools/list", serde_json::json!({})).await;
+//!     assert!(response["result"]["tools"].is_array());
+//! }
+//! ```
+
+#![allow(dead_code)] // Each test binary uses a different subset of the helpers
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+/// How long to wait for a server to report ready before failing the test.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A server process listening on a local port.
+pub struct TestServer {
+    /// The running server binary
+    child: Child,
+    /// Base URL of the server, without a trailing slash
+    base_url: String,
+    /// HTTP client shared by every request to this server
+    client: reqwest::Client,
+    /// Source of JSON-RPC request ids
+    next_id: AtomicU64,
+}
+
+impl TestServer {
+    /// Start a server with the default configuration.
+    pub async fn start() -> Self {
+        Self::start_with_env(&[]).await
+    }
+
+    /// Start a server with extra environment variables.
+    ///
+    /// The server always runs in HTTP mode on 127.0.0.1 with the example tools
+    /// registered; `vars` can set any other configuration, e.g.
+    /// `[("MCP_AUTH_TOKEN", "secret")]`.
+    ///
+    /// Panics if the server does not become ready within 10 seconds.
+    ///
+    /// # Arguments
+    /// * `vars` - Environment variables for the server process
+    pub async fn start_with_env(vars: &[(&str, &str)]) -> Self {
+        let mut server = Self::spawn(vars);
+        server.wait_for("/readyz").await;
+        server
+    }
+
+    /// Start a server and wait only until it is live (`/health` responds).
+    ///
+    /// Use this for servers expected to report not ready, e.g. with an
+    /// invalid configuration file.
+    ///
+    /// # Arguments
+    /// * `vars` - Environment variables for the server process
+    pub async fn start_live_with_env(vars: &[(&str, &str)]) -> Self {
+        let mut server = Self::spawn(vars);
+        server.wait_for("/health").await;
+        server
+    }
+
+    /// Start a server that serves HTTPS with a PEM certificate and key.
+    ///
+    /// The harness's client trusts the certificate, which must be valid for
+    /// the IP address 127.0.0.1.
+    ///
+    /// # Arguments
+    /// * `cert_path` - Path to the certificate, passed as `TLS_CERT_PATH`
+    /// * `key_path` - Path to the private key, passed as `TLS_KEY_PATH`
+    pub async fn start_tls(cert_path: &str, key_path: &str) -> Self {
+        let cert = std::fs::read(cert_path).expect("certificate is readable");
+        let mut server = Self::spawn(&[("TLS_CERT_PATH", cert_path), ("TLS_KEY_PATH", key_path)]);
+        server.base_url = server.base_url.replacen("http://", "https://", 1);
+        server.client = reqwest::Client::builder()
+            .add_root_certificate(reqwest::Certificate::from_pem(&cert).expect("certificate is PEM"))
+            .build()
+            .expect("HTTPS client builds");
+        server.wait_for("/readyz").await;
+        server
+    }
+
+    /// Launch the server binary without waiting for it to start listening.
+    fn spawn(vars: &[(&str, &str)]) -> Self {
+        let port = free_port();
+        let child = Command::new(env!("CARGO_BIN_EXE_mcp-server"))
+            .env("MCP_TRANSPORT_MODE", "http")
+            .env("HOST", "127.0.0.1")
+            .env("PORT", port.to_string())
+            .env("WORKER_THREADS", "1")
+            // Tests may use the example tools, such as sleep
+            .env("MCP_EXAMPLE_TOOLS", "1")
+            .envs(vars.iter().copied())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to start the server binary");
+
+        Self {
+            child,
+            base_url: format!("http://127.0.0.1:{}", port),
+            client: reqwest::Client::new(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Base URL of the server, e.g. `http://127.0.0.1:40123`.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Full URL of a path on the server.
+    ///
+    /// # Arguments
+    /// * `path` - Path starting with `/`, e.g. `/health`
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// HTTP client for requests the helpers do not cover.
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Send a GET request and parse the JSON response.
+    ///
+    /// # Returns
+    /// The HTTP status code and the response body.
+    pub async fn get_json(&self, path: &str) -> (u16, Value) {
+        let response = self.client.get(self.url(path)).send().await.expect("GET failed");
+        let status = response.status().as_u16();
+        (status, response.json().await.expect("response is not JSON"))
+    }
+
+    /// POST a JSON body and parse the JSON response.
+    ///
+    /// # Returns
+    /// The HTTP status code and the response body.
+    pub async fn post_json(&self, path: &str, body: &Value) -> (u16, Value) {
+        let response = self.client.post(self.url(path)).json(body).send().await.expect("POST failed");
+        let status = response.status().as_u16();
+        (status, response.json().await.expect("response is not JSON"))
+    }
+
+    /// Send a JSON-RPC request to `/mcp` and return the whole response object.
+    ///
+    /// Each request gets a fresh id, and the response is checked to carry it.
+    ///
+    /// # Arguments
+    /// * `method` - JSON-RPC method, e.g. `tools/list`
+    /// * `params` - Request params
+    pub async fn rpc(&self, method: &str, params: Value) -> Value {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params
+        });
+        let (status, response) = self.post_json("/mcp", &request).await;
+        assert_eq!(status, 200, "unexpected status for {}: {}", method, response);
+        assert_eq!(response["id"], id, "response id does not match request: {}", response);
+        response
+    }
+
+    /// Send a JSON-RPC request and return its `result`, panicking on an error response.
+    pub async fn rpc_result(&self, method: &str, params: Value) -> Value {
+        let response = self.rpc(method, params).await;
+        assert!(response.get("error").is_none(), "{} failed: {}", method, response);
+        response["result"].clone()
+    }
+
+    /// Call a tool with `tools/call` and return the call's `result`.
+    ///
+    /// # Arguments
+    /// * `name` - Tool name
+    /// * `arguments` - Tool arguments
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Value {
+        self.rpc_result("tools/call", serde_json::json!({ "name": name, "arguments": arguments })).await
+    }
+
+    /// Poll an endpoint until it responds with a success status.
+    ///
+    /// Panics if the server exits or the endpoint does not succeed within 10 seconds.
+    async fn wait_for(&mut self, path: &str) {
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        loop {
+            if let Some(status) = self.child.try_wait().expect("failed to poll the server") {
+                panic!("server exited during startup: {}", status);
+            }
+            if let Ok(response) = self.client.get(self.url(path)).send().await
+                && response.status().is_success()
+            {
+                return;
+            }
+            assert!(Instant::now() < deadline, "{} did not succeed in time", path);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// What a server run over STDIO wrote before it exited.
+pub struct StdioRun {
+    /// Everything written to stdout
+    pub stdout: String,
+    /// Everything written to stderr
+    pub stderr: String,
+}
+
+impl StdioRun {
+    /// Parse every stdout line as a JSON-RPC message.
+    pub fn messages(&self) -> Vec<Value> {
+        self.stdout
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("stdout line is not JSON"))
+            .collect()
+    }
+}
+
+/// Run the server in STDIO mode until it has read all of `input` and exited.
+///
+/// `vars` can override the transport, e.g. `[("MCP_TRANSPORT_MODE", "both")]`.
+///
+/// Panics if the server does not exit within 10 seconds of stdin closing.
+///
+/// # Arguments
+/// * `vars` - Environment variables for the server process
+/// * `input` - Bytes written to the server's stdin before it is closed
+pub fn run_stdio(vars: &[(&str, &str)], input: &str) -> StdioRun {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mcp-server"))
+        .env("MCP_TRANSPORT_MODE", "stdio")
+        .envs(vars.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start the server binary");
+
+    // Drain both pipes on their own threads so a full pipe cannot block the server
+    let drain = |mut pipe: Box<dyn Read + Send>| std::thread::spawn(move || {
+        let mut output = String::new();
+        pipe.read_to_string(&mut output).expect("output is UTF-8");
+        output
+    });
+    let stdout = drain(Box::new(child.stdout.take().expect("stdout is piped")));
+    let stderr = drain(Box::new(child.stderr.take().expect("stderr is piped")));
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin.write_all(input.as_bytes()).expect("failed to write to the server");
+    drop(stdin);
+
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    while child.try_wait().expect("failed to poll the server").is_none() {
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            panic!("server did not exit after its input ended");
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    StdioRun {
+        stdout: stdout.join().expect("stdout reader panicked"),
+        stderr: stderr.join().expect("stderr reader panicked"),
+    }
+}
+
+/// Find a local port that is free right now.
+///
+/// The port is released before the server binds it, so another process could
+/// take it in between; in practice the window is too short to matter.
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .expect("no free local port")
+}
@@ -0,0 +1,155 @@
+//! Tests of configuration file formats and environment variable interpolation.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::TestServer;
+use serde_json::json;
+
+/// Write a YAML configuration file unique to one test and return its path.
+fn write_config(test: &str, contents: &str) -> PathBuf {
+    write_config_as(test, "yaml", contents)
+}
+
+/// Write a configuration file with the given extension unique to one test and return its path.
+fn write_config_as(test: &str, extension: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("mcp-config-{}-{}.{}", test, std::process::id(), extension));
+    std::fs::write(&path, contents).expect("failed to write config file");
+    path
+}
+
+/// Echo `message` with the prefix from the configuration file.
+async fn echo(server: &TestServer, message: &str) -> String {
+    let result = server.call_tool("echo", json!({ "message": message })).await;
+    result["content"][0]["text"].as_str().unwrap_or_default().to_string()
+}
+
+#[tokio::test]
+async fn config_values_resolve_environment_variables() {
+    let config = write_config("resolved", "tools:\n  echo:\n    prefix: \"${MCP_TEST_ECHO_PREFIX}: \"\n");
+    let server = TestServer::start_with_env(&[
+        ("MCP_CONFIG_PATH", config.to_str().unwrap()),
+        ("MCP_TEST_ECHO_PREFIX", "Env"),
+    ]).await;
+
+    assert_eq!(echo(&server, "hi").await, "Env: hi");
+}
+
+#[tokio::test]
+async fn missing_variable_in_strict_mode_makes_the_server_not_ready() {
+    let config = write_config("strict", "tools:\n  echo:\n    prefix: \"${MCP_TEST_UNSET_VARIABLE}\"\n");
+    let server = TestServer::start_live_with_env(&[
+        ("MCP_CONFIG_PATH", config.to_str().unwrap()),
+        ("MCP_CONFIG_STRICT_ENV", "true"),
+    ]).await;
+
+    let (status, body) = server.get_json("/readyz").await;
+
+    assert_eq!(status, 503);
+    let reason = body["reason"].as_str().unwrap_or_default();
+    assert!(reason.contains("MCP_TEST_UNSET_VARIABLE is not set"), "unexpected reason: {}", reason);
+}
+
+#[tokio::test]
+async fn double_dollar_is_a_literal_dollar() {
+    let config = write_config("escaped", "tools:\n  echo:\n    prefix: \"$${MCP_TEST_ECHO_PREFIX} \"\n");
+    let server = TestServer::start_with_env(&[
+        ("MCP_CONFIG_PATH", config.to_str().unwrap()),
+        ("MCP_TEST_ECHO_PREFIX", "Env"),
+    ]).await;
+
+    assert_eq!(echo(&server, "hi").await, "${MCP_TEST_ECHO_PREFIX} hi");
+}
+
+#[tokio::test]
+async fn yaml_json_and_toml_configs_are_equivalent() {
+    let configs = [
+        write_config_as("format", "yaml", "tools:\n  echo:\n    prefix: \"Cfg: \"\n    output_template: \"[{result}]\"\n"),
+        write_config_as("format", "json", r#"{"tools": {"echo": {"prefix": "Cfg: ", "output_template": "[{result}]"}}}"#),
+        write_config_as("format", "toml", "[tools.echo]\nprefix = \"Cfg: \"\noutput_template = \"[{result}]\"\n"),
+    ];
+
+    for config in &configs {
+        let server = TestServer::start_with_env(&[("MCP_CONFIG_PATH", config.to_str().unwrap())]).await;
+        assert_eq!(echo(&server, "hi").await, "[Cfg: hi]", "config file {}", config.display());
+    }
+}
+
+#[tokio::test]
+async fn tool_section_overrides_default_section() {
+    let config = write_config("override", "tools:\n  _default:\n    prefix: \"Default: \"\n  echo:\n    prefix: \"Echo: \"\n");
+    let server = TestServer::start_with_env(&[("MCP_CONFIG_PATH", config.to_str().unwrap())]).await;
+
+    assert_eq!(echo(&server, "hi").await, "Echo: hi");
+}
+
+#[tokio::test]
+async fn default_section_applies_when_tool_section_is_missing() {
+    let config = write_config("default", "tools:\n  _default:\n    prefix: \"Default: \"\n");
+    let server = TestServer::start_with_env(&[("MCP_CONFIG_PATH", config.to_str().unwrap())]).await;
+
+    assert_eq!(echo(&server, "hi").await, "Default: hi");
+}
+
+#[tokio::test]
+async fn builtin_default_applies_when_no_section_sets_the_value() {
+    let config = write_config("builtin", "tools:\n  _default:\n    timeout_secs: 5\n  echo:\n    output_template: \"{result}\"\n");
+    let server = TestServer::start_with_env(&[("MCP_CONFIG_PATH", config.to_str().unwrap())]).await;
+
+    assert_eq!(echo(&server, "hi").await, "hi");
+}
+
+#[tokio::test]
+async fn echo_output_is_wrapped_by_a_configured_template() {
+    let config = write_config("template", "tools:\n  echo:\n    output_template: \"Result:\\n```\\n{result}\\n```\"\n");
+    let server = TestServer::start_with_env(&[("MCP_CONFIG_PATH", config.to_str().unwrap())]).await;
+
+    assert_eq!(echo(&server, "hi").await, "Result:\n```\nhi\n```");
+}
+
+#[tokio::test]
+async fn echo_output_is_unchanged_without_a_template() {
+    // Tests run from the repository root; its sample kmcp.yaml must not be picked up
+    let server = TestServer::start().await;
+
+    assert_eq!(echo(&server, "hi").await, "hi");
+}
+
+#[tokio::test]
+async fn echo_prefix_argument_overrides_the_configured_prefix() {
+    let config = write_config("prefix-argument", "tools:\n  echo:\n    prefix: \"Cfg: \"\n");
+    let server = TestServer::start_with_env(&[("MCP_CONFIG_PATH", config.to_str().unwrap())]).await;
+
+    let result = server.call_tool("echo", json!({ "message": "hi", "prefix": "Arg: " })).await;
+
+    assert_eq!(result["content"][0]["text"], "Arg: hi");
+}
+
+#[tokio::test]
+async fn echo_prefix_falls_back_to_the_configured_prefix() {
+    let config = write_config("prefix-config", "tools:\n  echo:\n    prefix: \"Cfg: \"\n");
+    let server = TestServer::start_with_env(&[("MCP_CONFIG_PATH", config.to_str().unwrap())]).await;
+
+    assert_eq!(echo(&server, "hi").await, "Cfg: hi");
+}
+
+#[tokio::test]
+async fn echo_has_no_prefix_without_an_argument_or_configuration() {
+    let config = write_config("prefix-none", "tools:\n  echo: {}\n");
+    let server = TestServer::start_with_env(&[("MCP_CONFIG_PATH", config.to_str().unwrap())]).await;
+
+    assert_eq!(echo(&server, "hi").await, "hi");
+}
+
+#[tokio::test]
+async fn non_string_echo_prefix_is_invalid_arguments() {
+    let server = TestServer::start().await;
+
+    let response = server.rpc("tools/call", json!({
+        "name": "echo",
+        "arguments": { "message": "hi", "prefix": 42 }
+    })).await;
+
+    assert_eq!(response["error"]["code"], -32602, "{}", response);
+}
@@ -0,0 +1,82 @@
+//! Tests of CORS headers for browser clients (`MCP_CORS_ALLOWED_ORIGINS`).
+
+mod common;
+
+use common::TestServer;
+
+/// The only origin the tests allow.
+const ALLOWED: &str = "https://app.example.com";
+
+/// POST a `ping` to `path` with a browser `Origin` header.
+async fn ping_from(server: &TestServer, path: &str, origin: &str) -> reqwest::Response {
+    server.client()
+        .post(server.url(path))
+        .header("Origin", origin)
+        .json(&serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" }))
+        .send()
+        .await
+        .expect("request succeeds")
+}
+
+#[tokio::test]
+async fn allowed_origins_get_cors_headers() {
+    let server = TestServer::start_with_env(&[("MCP_CORS_ALLOWED_ORIGINS", ALLOWED)]).await;
+
+    let response = ping_from(&server, "/mcp", ALLOWED).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["access-control-allow-origin"], ALLOWED);
+
+    let preflight = server.client()
+        .request(reqwest::Method::OPTIONS, server.url("/mcp"))
+        .header("Origin", ALLOWED)
+        .header("Access-Control-Request-Method", "POST")
+        .header("Access-Control-Request-Headers", "content-type")
+        .send()
+        .await
+        .expect("request succeeds");
+    assert_eq!(preflight.status(), 200);
+    assert_eq!(preflight.headers()["access-control-allow-origin"], ALLOWED);
+    let methods = preflight.headers()["access-control-allow-methods"].to_str().unwrap_or_default();
+    assert!(methods.contains("POST"), "{}", methods);
+}
+
+#[tokio::test]
+async fn other_origins_get_no_cors_headers() {
+    let server = TestServer::start_with_env(&[("MCP_CORS_ALLOWED_ORIGINS", ALLOWED)]).await;
+
+    let response = ping_from(&server, "/mcp", "https://evil.example.com").await;
+
+    assert!(response.headers().get("access-control-allow-origin").is_none(), "{:?}", response.headers());
+}
+
+#[tokio::test]
+async fn sse_posts_follow_the_configured_origins() {
+    let server = TestServer::start_with_env(&[("MCP_CORS_ALLOWED_ORIGINS", ALLOWED)]).await;
+
+    let allowed = ping_from(&server, "/sse", ALLOWED).await;
+    assert_eq!(allowed.status(), 200);
+    assert_eq!(allowed.headers()["access-control-allow-origin"], ALLOWED);
+
+    let other = ping_from(&server, "/sse", "https://evil.example.com").await;
+    assert!(other.headers().get("access-control-allow-origin").is_none(), "{:?}", other.headers());
+}
+
+#[tokio::test]
+async fn cors_is_off_by_default() {
+    let server = TestServer::start().await;
+
+    let response = ping_from(&server, "/mcp", ALLOWED).await;
+
+    assert_eq!(response.status(), 200);
+    assert!(response.headers().get("access-control-allow-origin").is_none(), "{:?}", response.headers());
+}
+
+#[tokio::test]
+async fn sse_sends_no_cors_headers_by_default() {
+    let server = TestServer::start().await;
+
+    let response = ping_from(&server, "/sse", ALLOWED).await;
+
+    assert_eq!(response.status(), 200);
+    assert!(response.headers().get("access-control-allow-origin").is_none(), "{:?}", response.headers());
+}
@@ -0,0 +1,59 @@
+//! Tests of the development-only debug endpoints.
+
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+/// Write a YAML configuration file unique to one test and return its path.
+fn write_config(test: &str, contents: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("mcp-debug-{}-{}.yaml", test, std::process::id()));
+    std::fs::write(&path, contents).expect("failed to write config file");
+    path
+}
+
+#[tokio::test]
+async fn debug_state_is_not_found_when_disabled() {
+    let server = TestServer::start().await;
+
+    let (status, _) = server.get_json("/debug/state").await;
+
+    assert_eq!(status, 404);
+}
+
+#[tokio::test]
+async fn debug_state_snapshots_tools_and_request_counter() {
+    let server = TestServer::start_with_env(&[("MCP_DEBUG_ENDPOINTS", "1")]).await;
+    server.rpc_result("ping", json!({})).await;
+
+    let (status, body) = server.get_json("/debug/state").await;
+
+    assert_eq!(status, 200);
+    assert!(body["server"]["name"].is_string());
+    let tools = body["tools"].as_array().expect("tools is an array");
+    assert!(tools.contains(&json!("echo")), "echo missing from {:?}", tools);
+    assert!(body["requests_total"].as_u64().unwrap_or_default() >= 1);
+    assert!(body["config"].is_object());
+    assert!(body["sessions"].is_array());
+}
+
+#[tokio::test]
+async fn debug_state_redacts_secret_config_values() {
+    let config = write_config(
+        "redacted",
+        "tools:\n  weather:\n    api_key: hunter2\n    Auth_Token: abc\n    client_secret: xyz\n    units: metric\n",
+    );
+    let server = TestServer::start_with_env(&[
+        ("MCP_DEBUG_ENDPOINTS", "1"),
+        ("MCP_CONFIG_PATH", config.to_str().unwrap()),
+    ]).await;
+
+    let (_, body) = server.get_json("/debug/state").await;
+
+    assert_eq!(body["config"]["tools"]["weather"], json!({
+        "api_key": "[REDACTED]",
+        "Auth_Token": "[REDACTED]",
+        "client_secret": "[REDACTED]",
+        "units": "metric"
+    }));
+}
@@ -0,0 +1,56 @@
+//! Tests of the fetch tool against a mock HTTP server.
+
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn fetch_returns_the_body_of_an_allowed_host() {
+    let upstream = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/hello"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("hello from upstream"))
+        .mount(&upstream)
+        .await;
+    let server = TestServer::start_with_env(&[("MCP_FETCH_ALLOWED_HOSTS", "127.0.0.1")]).await;
+
+    let result = server.call_tool("fetch", json!({ "url": format!("{}/hello", upstream.uri()) })).await;
+
+    assert_eq!(result["isError"], false);
+    assert_eq!(result["content"][0]["text"], "hello from upstream");
+}
+
+#[tokio::test]
+async fn fetch_rejects_a_host_outside_the_allowlist() {
+    let upstream = MockServer::start().await;
+    let server = TestServer::start_with_env(&[("MCP_FETCH_ALLOWED_HOSTS", "example.com")]).await;
+
+    let response = server.rpc("tools/call", json!({
+        "name": "fetch",
+        "arguments": { "url": format!("{}/hello", upstream.uri()) }
+    })).await;
+
+    assert_eq!(response["error"]["code"], -32602);
+    assert!(upstream.received_requests().await.unwrap_or_default().is_empty());
+}
+
+#[tokio::test]
+async fn fetch_rejects_an_oversized_response() {
+    let upstream = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("x".repeat(2048)))
+        .mount(&upstream)
+        .await;
+    let server = TestServer::start_with_env(&[
+        ("MCP_FETCH_ALLOWED_HOSTS", "127.0.0.1"),
+        ("MCP_FETCH_MAX_BYTES", "1024"),
+    ]).await;
+
+    let result = server.call_tool("fetch", json!({ "url": format!("{}/big", upstream.uri()) })).await;
+
+    assert_eq!(result["isError"], true);
+    assert!(result["content"][0]["text"].as_str().unwrap_or_default().contains("larger than 1024 bytes"));
+}
@@ -0,0 +1,1153 @@
+//! Baseline tests of the MCP JSON-RPC endpoint over HTTP.
+
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+#[tokio::test]
+async fn initialize_reports_server_info_and_capabilities() {
+    let server = TestServer::start_with_env(&[("SERVER_NAME", "test-server")]).await;
+
+    let result = server.rpc_result("initialize", json!({
+        "protocolVersion": "2025-06-18",
+        "capabilities": {},
+        "clientInfo": { "name": "harness", "version": "1.0" }
+    })).await;
+
+    assert_eq!(result["serverInfo"]["name"], "test-server");
+    assert!(result["protocolVersion"].is_string());
+    assert!(result["capabilities"]["tools"].is_object());
+}
+
+#[tokio::test]
+async fn capabilities_reflect_registered_features() {
+    let server = TestServer::start().await;
+
+    let result = server.rpc_result("initialize", json!({ "protocolVersion": "2025-06-18" })).await;
+
+    // Tools, resource subscriptions, logging and completions are implemented; prompts are not
+    assert_eq!(result["capabilities"], json!({
+        "tools": {},
+        "resources": { "subscribe": true },
+        "logging": {},
+        "completions": {}
+    }));
+}
+
+#[tokio::test]
+async fn tools_list_includes_builtin_tools() {
+    let server = TestServer::start().await;
+
+    let result = server.rpc_result("tools/list", json!({})).await;
+    let names: Vec<&str> = result["tools"]
+        .as_array()
+        .expect("tools is an array")
+        .iter()
+        .filter_map(|tool| tool["name"].as_str())
+        .collect();
+
+    assert!(names.contains(&"echo"), "echo missing from {:?}", names);
+    assert!(names.contains(&"calc"), "calc missing from {:?}", names);
+}
+
+#[tokio::test]
+async fn streaming_tool_output_arrives_chunked_and_concatenated() {
+    let server = TestServer::start().await;
+
+    let response = server.client()
+        .post(server.url("/mcp"))
+        .json(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": "lines", "arguments": { "count": 5, "delay_ms": 10 } }
+        }))
+        .send()
+        .await
+        .expect("request succeeds");
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["transfer-encoding"], "chunked");
+    let body: serde_json::Value = response.json().await.expect("body is JSON");
+    assert_eq!(body["id"], 1);
+    assert_eq!(body["result"]["isError"], false);
+    assert_eq!(body["result"]["content"], json!([
+        { "type": "text", "text": "line 1\nline 2\nline 3\nline 4\nline 5\n" }
+    ]));
+}
+
+#[tokio::test]
+async fn tools_describe_returns_one_tool() {
+    let server = TestServer::start().await;
+
+    let result = server.rpc_result("tools/describe", json!({ "name": "echo" })).await;
+
+    assert_eq!(result["name"], "echo");
+    assert!(result["description"].is_string());
+    assert_eq!(result["inputSchema"]["required"], json!(["message"]));
+}
+
+#[tokio::test]
+async fn tools_describe_rejects_an_unknown_tool() {
+    let server = TestServer::start().await;
+
+    let response = server.rpc("tools/describe", json!({ "name": "missing" })).await;
+
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(response["error"]["data"]["tool"], "missing");
+}
+
+#[tokio::test]
+async fn echo_returns_the_message() {
+    let server = TestServer::start().await;
+
+    let result = server.call_tool("echo", json!({ "message": "hello" })).await;
+
+    assert_eq!(result["isError"], false);
+    assert_eq!(result["content"][0]["type"], "text");
+    assert_eq!(result["content"][0]["text"], "hello");
+}
+
+#[tokio::test]
+async fn echo_result_is_wrapped_by_default() {
+    let server = TestServer::start().await;
+
+    let result = server.rpc_result("tools/call", json!({
+        "name": "echo",
+        "arguments": { "message": "hello" },
+        "_meta": { "rawResult": false }
+    })).await;
+
+    assert_eq!(result["isError"], false);
+    assert_eq!(result["content"][0]["text"], "hello");
+    assert_eq!(result["structuredContent"], json!({ "result": "hello" }));
+}
+
+#[tokio::test]
+async fn echo_result_is_unwrapped_with_raw_result() {
+    let server = TestServer::start().await;
+
+    let result = server.rpc_result("tools/call", json!({
+        "name": "echo",
+        "arguments": { "message": "hello" },
+        "_meta": { "rawResult": true }
+    })).await;
+
+    assert_eq!(result, json!({ "result": "hello" }));
+}
+
+#[tokio::test]
+async fn unknown_tool_is_a_method_not_found_error() {
+    let server = TestServer::start().await;
+
+    let response = server.rpc("tools/call", json!({ "name": "missing", "arguments": {} })).await;
+
+    assert_eq!(response["error"]["code"], -32601);
+}
+
+/// A batch of `count` ping requests with ids 1..=count.
+fn ping_batch(count: u64) -> serde_json::Value {
+    (1..=count)
+        .map(|id| json!({ "jsonrpc": "2.0", "id": id, "method": "ping" }))
+        .collect()
+}
+
+#[tokio::test]
+async fn batch_returns_one_response_per_request() {
+    let server = TestServer::start().await;
+
+    let (status, body) = server.post_json("/mcp", &ping_batch(2)).await;
+
+    assert_eq!(status, 200);
+    let ids: Vec<&serde_json::Value> = body.as_array().expect("batch response is an array")
+        .iter()
+        .map(|response| &response["id"])
+        .collect();
+    assert_eq!(ids, [&json!(1), &json!(2)]);
+}
+
+#[tokio::test]
+async fn batch_over_the_limit_is_rejected_as_a_whole() {
+    let server = TestServer::start_with_env(&[("MAX_BATCH_SIZE", "2")]).await;
+
+    let (status, body) = server.post_json("/mcp", &ping_batch(3)).await;
+
+    assert_eq!(status, 400);
+    assert!(body.is_object(), "expected a single error, got {}", body);
+    assert_eq!(body["error"]["code"], -32600);
+    assert!(body["error"]["message"].as_str().unwrap_or_default().contains("maximum of 2"));
+}
+
+#[tokio::test]
+async fn empty_batch_is_an_invalid_request() {
+    let server = TestServer::start().await;
+
+    let (status, body) = server.post_json("/mcp", &json!([])).await;
+
+    assert_eq!(status, 400);
+    assert_eq!(body["error"]["code"], -32600);
+}
+
+#[tokio::test]
+async fn get_request_lists_tools() {
+    let server = TestServer::start().await;
+
+    let (status, body) = server.get_json("/mcp?method=tools/list&id=7").await;
+
+    assert_eq!(status, 200);
+    assert_eq!(body["id"], 7);
+    assert!(body["result"]["tools"].as_array().is_some_and(|tools| !tools.is_empty()));
+}
+
+#[tokio::test]
+async fn get_request_calls_echo_when_enabled() {
+    let server = TestServer::start_with_env(&[("MCP_HTTP_GET_TOOL_CALLS", "1")]).await;
+
+    let (status, body) = server
+        .get_json("/mcp?method=tools/call&name=echo&args=%7B%22message%22%3A%22via%20get%22%7D")
+        .await;
+
+    assert_eq!(status, 200);
+    assert_eq!(body["result"]["content"][0]["text"], "via get");
+}
+
+#[tokio::test]
+async fn get_request_rejects_disallowed_methods() {
+    let server = TestServer::start().await;
+
+    for path in ["/mcp?method=tools/call&name=echo", "/mcp?method=initialize"] {
+        let (status, body) = server.get_json(path).await;
+
+        assert_eq!(status, 405, "{}", path);
+        assert_eq!(body["error"]["code"], -32600);
+    }
+}
+
+/// POST a ping request with the given `Content-Type` and return the status and body.
+async fn post_ping_as(server: &TestServer, content_type: &str) -> (u16, serde_json::Value) {
+    let response = server.client()
+        .post(server.url("/mcp"))
+        .header("Content-Type", content_type)
+        .body(r#"{"jsonrpc": "2.0", "id": 1, "method": "ping"}"#)
+        .send()
+        .await
+        .expect("request succeeds");
+    let status = response.status().as_u16();
+    (status, response.json().await.expect("body is JSON"))
+}
+
+#[tokio::test]
+async fn json_content_types_are_accepted_with_or_without_charset() {
+    let server = TestServer::start().await;
+
+    for content_type in [
+        "application/json",
+        "application/json; charset=utf-8",
+        "application/json-rpc",
+        "Application/JSON-RPC; charset=\"UTF-8\"",
+        "text/json",
+        "text/json;charset=utf-8",
+    ] {
+        let (status, body) = post_ping_as(&server, content_type).await;
+
+        assert_eq!(status, 200, "{}", content_type);
+        assert_eq!(body["result"], json!({}), "{}", content_type);
+    }
+}
+
+#[tokio::test]
+async fn other_content_types_and_charsets_are_rejected() {
+    let server = TestServer::start().await;
+
+    for content_type in ["text/plain", "application/json; charset=iso-8859-1"] {
+        let (status, body) = post_ping_as(&server, content_type).await;
+
+        assert_eq!(status, 415, "{}", content_type);
+        assert_eq!(body["error"]["code"], -32600, "{}", content_type);
+    }
+}
+
+#[tokio::test]
+async fn partial_results_carry_warnings() {
+    let root = std::env::temp_dir().join(format!("mcp-read-files-{}", std::process::id()));
+    std::fs::create_dir_all(&root).expect("failed to create file root");
+    std::fs::write(root.join("present.txt"), "hello").expect("failed to write file");
+    let server = TestServer::start_with_env(&[("MCP_FILE_ROOT", root.to_str().unwrap())]).await;
+
+    let result = server.call_tool("read_files", json!({ "paths": ["present.txt", "missing.txt"] })).await;
+
+    assert_eq!(result["isError"], false);
+    assert_eq!(result["content"], json!([{ "type": "text", "text": "hello" }]));
+    assert_eq!(result["_meta"]["warnings"], json!(["File not found: missing.txt"]));
+}
+
+#[tokio::test]
+async fn responses_identify_the_configured_instance() {
+    let server = TestServer::start_with_env(&[("INSTANCE_ID", "replica-7")]).await;
+
+    for path in ["/health", "/tools", "/no-such-route"] {
+        let response = server.client().get(server.url(path)).send().await.expect("request succeeds");
+        assert_eq!(response.headers()["x-instance-id"], "replica-7", "{}", path);
+    }
+
+    let result = server.call_tool("echo", json!({ "message": "hi" })).await;
+    assert_eq!(result["_meta"]["instanceId"], "replica-7");
+}
+
+#[tokio::test]
+async fn instance_id_defaults_to_a_generated_uuid() {
+    let server = TestServer::start().await;
+
+    let response = server.client().get(server.url("/health")).send().await.expect("request succeeds");
+    let id = response.headers()["x-instance-id"].to_str().expect("header is ASCII").to_string();
+    assert_eq!(id.len(), 36, "not a UUID: {}", id);
+
+    let result = server.call_tool("echo", json!({ "message": "hi" })).await;
+    assert_eq!(result["_meta"]["instanceId"], id.as_str());
+}
+
+#[tokio::test]
+async fn string_arguments_are_coerced_when_enabled() {
+    let server = TestServer::start_with_env(&[("MCP_COERCE_ARGS", "1")]).await;
+
+    let result = server.call_tool("calc", json!({ "operation": "add", "a": "40", "b": " 2.5" })).await;
+
+    assert_eq!(result["isError"], false);
+    assert_eq!(result["content"][0]["text"], r#"{"result":42.5}"#);
+}
+
+#[tokio::test]
+async fn uncoercible_arguments_still_fail_validation() {
+    let server = TestServer::start_with_env(&[("MCP_COERCE_ARGS", "1")]).await;
+
+    let response = server.rpc("tools/call", json!({
+        "name": "calc",
+        "arguments": { "operation": "add", "a": "forty", "b": 2 }
+    })).await;
+
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(response["error"]["data"]["errors"][0]["path"], "arguments.a");
+}
+
+#[tokio::test]
+async fn string_arguments_are_rejected_without_coercion() {
+    let server = TestServer::start().await;
+
+    let response = server.rpc("tools/call", json!({
+        "name": "calc",
+        "arguments": { "operation": "add", "a": "40", "b": 2 }
+    })).await;
+
+    assert_eq!(response["error"]["code"], -32602);
+}
+
+#[tokio::test]
+async fn read_only_mode_lists_tools_but_rejects_calls() {
+    let server = TestServer::start_with_env(&[("MCP_READONLY", "1")]).await;
+
+    let initialized = server.rpc_result("initialize", json!({ "protocolVersion": "2025-06-18" })).await;
+    assert!(initialized["capabilities"]["tools"].is_object());
+
+    let listed = server.rpc_result("tools/list", json!({})).await;
+    assert!(listed["tools"].as_array().is_some_and(|tools| !tools.is_empty()));
+
+    let response = server.rpc("tools/call", json!({ "name": "echo", "arguments": { "message": "hi" } })).await;
+    assert_eq!(response["error"]["code"], -32601);
+    assert_eq!(response["error"]["message"], "tools/call disabled in read-only mode");
+
+    let probe = server.rpc_result("capabilities/probe", json!({})).await;
+    let methods: Vec<&str> = probe["methods"]
+        .as_array()
+        .expect("methods is an array")
+        .iter()
+        .filter_map(|method| method["name"].as_str())
+        .collect();
+    assert!(methods.contains(&"tools/list") && !methods.contains(&"tools/call"), "{:?}", methods);
+}
+
+/// Unix time in milliseconds, offset by `offset_ms`.
+fn unix_ms(offset_ms: i64) -> u64 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("clock is after the epoch")
+        .as_millis() as i64;
+    (now + offset_ms) as u64
+}
+
+#[tokio::test]
+async fn calls_that_meet_their_deadline_succeed() {
+    let server = TestServer::start().await;
+
+    let response = server.client()
+        .post(server.url("/mcp"))
+        .header("X-Request-Deadline-Ms", unix_ms(10_000).to_string())
+        .json(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": "echo", "arguments": { "message": "in time" } }
+        }))
+        .send()
+        .await
+        .expect("request succeeds");
+    let body: serde_json::Value = response.json().await.expect("response is JSON");
+
+    assert!(body.get("error").is_none(), "unexpected error: {}", body);
+    assert_eq!(body["result"]["isError"], false);
+}
+
+#[tokio::test]
+async fn requests_past_their_deadline_are_not_dispatched() {
+    let server = TestServer::start().await;
+
+    let response = server.rpc("tools/call", json!({
+        "name": "echo",
+        "arguments": { "message": "too late" },
+        "_meta": { "deadlineMs": unix_ms(-1_000) }
+    })).await;
+
+    assert_eq!(response["error"]["code"], -32000);
+    assert_eq!(response["error"]["message"], "deadline exceeded");
+}
+
+#[tokio::test]
+async fn handlers_overrunning_the_deadline_are_stopped() {
+    let server = TestServer::start().await;
+    let started = std::time::Instant::now();
+
+    let response = server.rpc("tools/call", json!({
+        "name": "sleep",
+        "arguments": { "seconds": 5 },
+        "_meta": { "deadlineMs": unix_ms(300) }
+    })).await;
+
+    assert_eq!(response["error"]["code"], -32000);
+    assert_eq!(response["error"]["message"], "deadline exceeded");
+    assert!(started.elapsed() < std::time::Duration::from_secs(3), "took {:?}", started.elapsed());
+}
+
+#[tokio::test]
+async fn health_negotiates_its_response_format() {
+    let server = TestServer::start().await;
+    let get = |path: &str, accept: Option<&str>| {
+        let mut request = server.client().get(server.url(path));
+        if let Some(accept) = accept {
+            request = request.header("Accept", accept);
+        }
+        async move {
+            let response = request.send().await.expect("request succeeds");
+            let status = response.status().as_u16();
+            let content_type = response.headers().get("content-type")
+                .map(|value| value.to_str().expect("header is ASCII").to_string());
+            (status, content_type, response.text().await.expect("body is text"))
+        }
+    };
+
+    for (path, accept) in [("/health", None), ("/", None), ("/health?format=json", Some("text/plain"))] {
+        let (status, content_type, body) = get(path, accept).await;
+        assert_eq!(status, 200, "{}", path);
+        assert_eq!(content_type.as_deref(), Some("application/json"), "{}", path);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap()["status"], "ok");
+    }
+
+    for (path, accept) in [("/health?format=text", None), ("/", Some("text/plain")), ("/health", Some("text/plain, application/json;q=0.5"))] {
+        let (status, content_type, body) = get(path, accept).await;
+        assert_eq!(status, 200, "{}", path);
+        assert_eq!(content_type.as_deref(), Some("text/plain; charset=utf-8"), "{}", path);
+        assert_eq!(body, "OK");
+    }
+
+    let (status, content_type, body) = get("/health?format=empty", None).await;
+    assert_eq!(status, 204);
+    assert_eq!(content_type, None);
+    assert!(body.is_empty());
+
+    let (status, _, _) = get("/health?format=xml", None).await;
+    assert_eq!(status, 400);
+}
+
+#[tokio::test]
+async fn requests_beyond_the_inflight_limit_are_rejected_as_busy() {
+    let server = TestServer::start_with_env(&[("MAX_INFLIGHT_REQUESTS", "1")]).await;
+    let ping = json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" });
+
+    let slow = server.call_tool("sleep", json!({ "seconds": 1 }));
+    let busy = async {
+        // Let the slow call take the only slot first
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        server.post_json("/mcp", &ping).await
+    };
+    let (slow, (status, response)) = tokio::join!(slow, busy);
+
+    assert_eq!(slow["isError"], false);
+    assert_eq!(status, 503);
+    assert_eq!(response["error"]["code"], -32000);
+    assert_eq!(response["error"]["message"], "server busy");
+
+    // The slot is free again once the slow call has finished
+    let (status, response) = server.post_json("/mcp", &ping).await;
+    assert_eq!(status, 200);
+    assert_eq!(response["result"], json!({}));
+}
+
+#[tokio::test]
+async fn metrics_count_sessions_by_client() {
+    let server = TestServer::start().await;
+    let initialize = |name: &str| json!({
+        "protocolVersion": "2025-06-18",
+        "capabilities": {},
+        "clientInfo": { "name": name, "version": "1.2.0" }
+    });
+    server.rpc_result("initialize", initialize("inspector")).await;
+    server.rpc_result("initialize", initialize("inspector")).await;
+    server.rpc_result("initialize", initialize("say \"hi\"")).await;
+
+    let (status, metrics) = server.get_json("/metrics").await;
+    assert_eq!(status, 200);
+    assert_eq!(metrics["clients"], json!([
+        { "name": "inspector", "version": "1.2.0", "sessions": 2 },
+        { "name": "say \"hi\"", "version": "1.2.0", "sessions": 1 }
+    ]));
+
+    let response = server.client().get(server.url("/metrics?format=prometheus")).send().await.expect("request succeeds");
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/plain; version=0.0.4"));
+    let body = response.text().await.expect("body is text");
+    assert!(body.contains("# TYPE mcp_clients gauge\n"), "{}", body);
+    assert!(body.contains("mcp_clients{name=\"inspector\",version=\"1.2.0\"} 2\n"), "{}", body);
+    assert!(body.contains("mcp_clients{name=\"say \\\"hi\\\"\",version=\"1.2.0\"} 1\n"), "{}", body);
+    assert!(body.contains("mcp_requests_total 3\n"), "{}", body);
+}
+
+#[tokio::test]
+async fn rest_responses_are_enveloped_on_request() {
+    let server = TestServer::start_with_env(&[("SERVER_NAME", "test-server")]).await;
+
+    let (status, plain) = server.get_json("/tools").await;
+    assert_eq!(status, 200);
+    assert!(plain["tools"].is_array());
+    assert!(plain.get("data").is_none());
+
+    let (status, enveloped) = server.get_json("/tools?envelope=1").await;
+    assert_eq!(status, 200);
+    assert_eq!(enveloped["data"], plain);
+    assert_eq!(enveloped["error"], json!(null));
+    assert_eq!(enveloped["meta"]["server"], "test-server");
+    assert!(enveloped["meta"]["timestamp"].is_string());
+
+    let (status, missing) = server.get_json("/tools/no-such-tool/schema?envelope=1").await;
+    assert_eq!(status, 404);
+    assert_eq!(missing["data"], json!(null));
+    assert_eq!(missing["error"], json!({ "message": "unknown tool" }));
+}
+
+#[tokio::test]
+async fn rest_envelope_can_be_the_default() {
+    let server = TestServer::start_with_env(&[("MCP_REST_ENVELOPE", "1")]).await;
+
+    let (status, enveloped) = server.get_json("/tools").await;
+    assert_eq!(status, 200);
+    assert!(enveloped["data"]["tools"].is_array());
+    let (_, metrics) = server.get_json("/metrics").await;
+    assert_eq!(metrics["data"]["status"], "ok");
+
+    // The query overrides the default, and JSON-RPC responses are never wrapped
+    let (_, plain) = server.get_json("/tools?envelope=0").await;
+    assert!(plain["tools"].is_array());
+    let result = server.rpc_result("tools/list", json!({})).await;
+    assert!(result["tools"].is_array());
+}
+
+#[tokio::test]
+async fn metrics_include_call_duration_histograms() {
+    let server = TestServer::start().await;
+    for _ in 0..3 {
+        server.call_tool("sleep", json!({ "seconds": 0.2 })).await;
+    }
+
+    let response = server.client().get(server.url("/metrics?format=prometheus")).send().await.expect("request succeeds");
+    let body = response.text().await.expect("body is text");
+    let value = |series: &str| -> f64 {
+        let line = body.lines()
+            .find(|line| line.starts_with(series))
+            .unwrap_or_else(|| panic!("no {} in:\n{}", series, body));
+        line[series.len()..].trim().parse().expect("sample is a number")
+    };
+    assert!(body.contains("# TYPE mcp_tool_duration_seconds histogram\n"), "{}", body);
+    assert_eq!(value("mcp_tool_duration_seconds_count{tool=\"sleep\"}"), 3.0);
+    let sum = value("mcp_tool_duration_seconds_sum{tool=\"sleep\"}");
+    assert!((0.6..1.5).contains(&sum), "sum of three 0.2s sleeps is {}", sum);
+    assert_eq!(value("mcp_tool_duration_seconds_bucket{tool=\"sleep\",le=\"0.1\"}"), 0.0);
+    assert_eq!(value("mcp_tool_duration_seconds_bucket{tool=\"sleep\",le=\"+Inf\"}"), 3.0);
+    assert_eq!(value("mcp_request_duration_seconds_count{method=\"tools/call\"}"), 3.0);
+}
+
+#[tokio::test]
+async fn requests_without_a_method_are_invalid_requests() {
+    let server = TestServer::start().await;
+
+    let (status, response) = server.post_json("/mcp", &json!({ "jsonrpc": "2.0", "id": 9, "params": {} })).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["id"], 9);
+    assert_eq!(response["error"]["code"], -32600);
+    assert_eq!(response["error"]["message"], "Invalid Request: missing method");
+
+    let response = server.client()
+        .post(server.url("/mcp"))
+        .header("content-type", "application/json")
+        .body(r#"{"jsonrpc": "2.0", "id": 9, "method": "#)
+        .send()
+        .await
+        .expect("request succeeds");
+    assert_eq!(response.status(), 400);
+    let response: serde_json::Value = response.json().await.expect("response is JSON");
+    assert_eq!(response["id"], json!(null));
+    assert_eq!(response["error"]["code"], -32700);
+}
+
+#[tokio::test]
+async fn non_object_arguments_are_rejected() {
+    let server = TestServer::start().await;
+
+    // `lines` streams its output over HTTP, so both call paths are covered
+    for tool in ["echo", "lines"] {
+        for arguments in [json!("hello"), json!(["hello"])] {
+            let response = server.rpc("tools/call", json!({ "name": tool, "arguments": arguments })).await;
+            assert_eq!(response["error"]["code"], -32602, "{}", response);
+            assert_eq!(response["error"]["message"], "Invalid params: arguments must be an object");
+        }
+    }
+}
+
+#[tokio::test]
+async fn well_known_manifest_describes_the_server() {
+    let server = TestServer::start_with_env(&[("SERVER_NAME", "test-server")]).await;
+
+    let (status, manifest) = server.get_json("/.well-known/mcp.json").await;
+    assert_eq!(status, 200);
+    assert_eq!(manifest["name"], "test-server");
+    assert!(manifest["version"].is_string());
+    assert!(manifest["capabilities"]["tools"].is_object(), "{}", manifest);
+
+    // Tools and the protocol version match what a connected client would see
+    let initialized = server.rpc_result("initialize", json!({
+        "protocolVersion": manifest["protocolVersion"],
+        "capabilities": {},
+        "clientInfo": { "name": "test", "version": "1.0.0" }
+    })).await;
+    assert_eq!(initialized["protocolVersion"], manifest["protocolVersion"]);
+    let echo = manifest["tools"].as_array().unwrap().iter().find(|tool| tool["name"] == "echo");
+    assert!(echo.is_some_and(|echo| echo["inputSchema"]["properties"]["message"].is_object()), "{}", manifest);
+}
+
+/// Gzip `body` as a client would before sending it with `Content-Encoding: gzip`.
+fn gzip(body: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(body).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// POST an encoded body to `/mcp` and return the status and body.
+async fn post_encoded(server: &TestServer, encoding: &str, body: Vec<u8>) -> (u16, serde_json::Value) {
+    let response = server.client()
+        .post(server.url("/mcp"))
+        .header("Content-Type", "application/json")
+        .header("Content-Encoding", encoding)
+        .body(body)
+        .send()
+        .await
+        .expect("request succeeds");
+    let status = response.status().as_u16();
+    (status, response.json().await.expect("body is JSON"))
+}
+
+#[tokio::test]
+async fn compressed_request_bodies_are_decoded() {
+    let server = TestServer::start().await;
+    let request = serde_json::to_vec(&json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-06-18",
+            "capabilities": {},
+            "clientInfo": { "name": "test", "version": "1.0.0" }
+        }
+    })).unwrap();
+
+    let (status, body) = post_encoded(&server, "gzip", gzip(&request)).await;
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(body["id"], 1);
+    assert!(body["result"]["serverInfo"]["name"].is_string(), "{}", body);
+
+    let (status, body) = post_encoded(&server, "zstd", zstd::encode_all(&request[..], 0).unwrap()).await;
+    assert_eq!(status, 200, "{}", body);
+    assert!(body["result"]["serverInfo"]["name"].is_string(), "{}", body);
+
+    // Encodings that are not supported are refused rather than parsed as JSON
+    let (status, body) = post_encoded(&server, "br", request).await;
+    assert_eq!(status, 415);
+    assert_eq!(body["error"]["code"], -32600);
+}
+
+#[tokio::test]
+async fn compressed_bodies_expanding_past_the_limit_are_rejected() {
+    let server = TestServer::start_with_env(&[("MAX_REQUEST_BYTES", "4096")]).await;
+
+    // A ping padded with a megabyte of whitespace compresses to a few kilobytes at most
+    let mut request = br#"{"jsonrpc": "2.0", "id": 1, "method": "ping"}"#.to_vec();
+    request.extend(std::iter::repeat_n(b' ', 1024 * 1024));
+    let compressed = gzip(&request);
+    assert!(compressed.len() < 4096, "compressed to {} bytes", compressed.len());
+
+    let (status, body) = post_encoded(&server, "gzip", compressed).await;
+    assert_eq!(status, 413);
+    assert_eq!(body["error"]["code"], -32600);
+    assert_eq!(body["error"]["message"], "Request body exceeds maximum size of 4096 bytes");
+
+    let (status, _) = post_encoded(&server, "gzip", gzip(br#"{"jsonrpc": "2.0", "id": 1, "method": "ping"}"#)).await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn server_stats_count_requests_by_method_and_tool() {
+    let server = TestServer::start().await;
+    server.call_tool("echo", json!({ "message": "one" })).await;
+    server.call_tool("echo", json!({ "message": "two" })).await;
+    server.rpc_result("ping", json!({})).await;
+
+    let stats = server.rpc_result("server/stats", json!({})).await;
+    assert_eq!(stats["requests_total"], 3, "{}", stats);
+    assert_eq!(stats["per_method"]["tools/call"]["count"], 2, "{}", stats);
+    assert_eq!(stats["per_method"]["ping"]["count"], 1, "{}", stats);
+    assert_eq!(stats["per_tool"]["echo"]["count"], 2, "{}", stats);
+    assert!(stats["per_tool"]["echo"]["total_secs"].is_f64(), "{}", stats);
+    assert!(stats["uptime_secs"].is_u64(), "{}", stats);
+
+    // The stats request is counted once it completes
+    let stats = server.rpc_result("server/stats", json!({})).await;
+    assert_eq!(stats["requests_total"], 4, "{}", stats);
+    assert_eq!(stats["per_method"]["server/stats"]["count"], 1, "{}", stats);
+}
+
+#[tokio::test]
+async fn bodies_over_the_size_limit_are_invalid_requests() {
+    let server = TestServer::start_with_env(&[("MAX_REQUEST_BYTES", "1024")]).await;
+
+    // A valid request padded to one byte over the limit
+    let mut body = json!({ "jsonrpc": "2.0", "id": 1, "method": "ping", "params": { "padding": "" } });
+    let padding = 1025 - body.to_string().len();
+    body["params"]["padding"] = json!("x".repeat(padding));
+    assert_eq!(body.to_string().len(), 1025);
+
+    let (status, response) = server.post_json("/mcp", &body).await;
+    assert_eq!(status, 413);
+    assert_eq!(response["jsonrpc"], "2.0");
+    assert_eq!(response["id"], json!(null));
+    assert_eq!(response["error"]["code"], -32600);
+    assert_eq!(response["error"]["message"], "Request body exceeds maximum size of 1024 bytes");
+
+    // A body at the limit is accepted
+    body["params"]["padding"] = json!("x".repeat(padding - 1));
+    let (status, response) = server.post_json("/mcp", &body).await;
+    assert_eq!(status, 200, "{}", response);
+    assert_eq!(response["id"], 1);
+}
+
+#[tokio::test]
+async fn calls_running_past_the_tool_timeout_fail() {
+    let server = TestServer::start_with_env(&[("TOOL_TIMEOUT_SECS", "1")]).await;
+    let started = std::time::Instant::now();
+
+    let response = server.rpc("tools/call", json!({ "name": "sleep", "arguments": { "seconds": 5 } })).await;
+    assert_eq!(response["error"]["code"], -32000);
+    assert_eq!(response["error"]["message"], "tool execution timed out");
+    assert!(started.elapsed() < std::time::Duration::from_secs(3), "took {:?}", started.elapsed());
+
+    // The server keeps handling calls afterwards
+    let result = server.call_tool("echo", json!({ "message": "still here" })).await;
+    assert_eq!(result["content"][0]["text"], "still here");
+}
+
+#[tokio::test]
+async fn example_tools_are_opt_in() {
+    let server = TestServer::start_with_env(&[("MCP_EXAMPLE_TOOLS", "0")]).await;
+
+    let tools = server.rpc_result("tools/list", json!({})).await;
+    let names: Vec<&str> = tools["tools"].as_array().unwrap().iter()
+        .filter_map(|tool| tool["name"].as_str())
+        .collect();
+    assert!(names.contains(&"echo"), "{:?}", names);
+    assert!(!names.contains(&"sleep"), "{:?}", names);
+    assert!(!names.contains(&"long_task"), "{:?}", names);
+    assert!(!names.contains(&"lines"), "{:?}", names);
+    assert!(!names.contains(&"read_files"), "{:?}", names);
+    assert!(!names.contains(&"session_counter"), "{:?}", names);
+}
+
+#[tokio::test]
+async fn malformed_bodies_get_json_rpc_errors() {
+    let server = TestServer::start().await;
+
+    // Not JSON at all: a parse error, still in JSON-RPC form
+    let response = server.client()
+        .post(server.url("/mcp"))
+        .header("content-type", "application/json")
+        .body(r#"{"jsonrpc": "2.0", "id": 3, "method": "ping",}"#)
+        .send()
+        .await
+        .expect("request succeeds");
+    assert_eq!(response.status(), 400);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let response: serde_json::Value = response.json().await.expect("response is JSON");
+    assert_eq!(response["jsonrpc"], "2.0");
+    assert_eq!(response["id"], json!(null));
+    assert_eq!(response["error"]["code"], -32700);
+    let message = response["error"]["message"].as_str().unwrap_or_default();
+    assert!(message.starts_with("Parse error"), "unexpected message: {}", message);
+
+    // JSON of the wrong shape: an invalid request that keeps its id
+    for body in [
+        json!({ "jsonrpc": "2.0", "id": 4, "method": 42 }),
+        json!({ "jsonrpc": "1.0", "id": 4, "method": "ping" }),
+    ] {
+        let (status, response) = server.post_json("/mcp", &body).await;
+        assert_eq!(status, 400, "{}", body);
+        assert_eq!(response["id"], 4);
+        assert_eq!(response["error"]["code"], -32600, "{}", body);
+    }
+}
+
+#[tokio::test]
+async fn cancelling_over_plain_http_does_not_reach_other_requests() {
+    let server = TestServer::start().await;
+
+    let call = server.rpc("tools/call", json!({ "name": "sleep", "arguments": { "seconds": 0.5 } }));
+    let cancel = async {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let response = server.client()
+            .post(server.url("/mcp"))
+            .json(&json!({ "jsonrpc": "2.0", "method": "notifications/cancelled", "params": { "requestId": 1 } }))
+            .send()
+            .await
+            .expect("request succeeds");
+        (response.status().as_u16(), response.text().await.expect("body is readable"))
+    };
+    let (response, (status, body)) = tokio::join!(call, cancel);
+
+    // The notification is acknowledged, but the call it names still completes
+    assert_eq!(status, 202);
+    assert_eq!(body, "");
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["content"][0]["text"], r#"{"result":"Slept for 0.5 seconds"}"#);
+}
+
+#[tokio::test]
+async fn ping_returns_an_empty_result_without_counting_tool_calls() {
+    let server = TestServer::start().await;
+
+    for _ in 0..3 {
+        assert_eq!(server.rpc_result("ping", json!({})).await, json!({}));
+    }
+
+    let stats = server.rpc_result("server/stats", json!({})).await;
+    assert_eq!(stats["per_method"]["ping"]["count"], 3);
+    assert_eq!(stats["per_tool"], json!({}));
+}
+
+#[tokio::test]
+async fn ids_and_jsonrpc_versions_are_validated() {
+    let server = TestServer::start().await;
+
+    let (status, response) = server.post_json("/mcp", &json!({ "jsonrpc": "2.0", "id": { "n": 1 }, "method": "ping" })).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["id"], json!(null));
+    assert_eq!(response["error"]["code"], -32600);
+    assert_eq!(response["error"]["message"], "Invalid Request: id must be a string, number or null");
+
+    let (status, response) = server.post_json("/mcp", &json!({ "jsonrpc": "2.1", "id": "a", "method": "ping" })).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["id"], "a");
+    assert_eq!(response["error"]["code"], -32600);
+
+    let (status, response) = server.post_json("/mcp", &json!({ "jsonrpc": "2.0", "id": "b", "method": "ping" })).await;
+    assert_eq!(status, 200);
+    assert_eq!(response, json!({ "jsonrpc": "2.0", "id": "b", "result": {} }));
+}
+
+#[tokio::test]
+async fn disabled_tools_are_neither_listed_nor_callable() {
+    let server = TestServer::start_with_env(&[("DISABLED_TOOLS", "calc, stats")]).await;
+
+    let tools = server.rpc_result("tools/list", json!({})).await;
+    let names: Vec<&str> = tools["tools"].as_array().unwrap().iter()
+        .filter_map(|tool| tool["name"].as_str())
+        .collect();
+    assert!(!names.contains(&"calc") && !names.contains(&"stats"), "{:?}", names);
+    assert!(names.contains(&"echo"), "{:?}", names);
+
+    let disabled = server.rpc("tools/call", json!({ "name": "calc", "arguments": { "operation": "add", "a": 1, "b": 2 } })).await;
+    let unknown = server.rpc("tools/call", json!({ "name": "missing", "arguments": {} })).await;
+    assert_eq!(disabled["error"]["code"], unknown["error"]["code"]);
+    assert_eq!(disabled["error"]["code"], -32601);
+}
+
+#[tokio::test]
+async fn validate_only_checks_arguments_without_running_the_tool() {
+    let server = TestServer::start().await;
+
+    let valid = server.rpc_result("tools/call", json!({
+        "name": "echo",
+        "arguments": { "message": "hi" },
+        "validateOnly": true
+    })).await;
+    assert_eq!(valid["valid"], true, "{}", valid);
+    assert!(valid.get("content").is_none(), "{}", valid);
+
+    let invalid = server.rpc("tools/call", json!({
+        "name": "echo",
+        "arguments": { "message": 42 },
+        "validateOnly": true
+    })).await;
+    assert_eq!(invalid["error"]["code"], -32602, "{}", invalid);
+
+    // Neither call reached the tool
+    let stats = server.rpc_result("server/stats", json!({})).await;
+    assert!(stats["per_tool"].get("echo").is_none(), "{}", stats);
+}
+
+#[tokio::test]
+async fn tools_list_pages_follow_the_cursor_to_the_end() {
+    let server = TestServer::start_with_env(&[("TOOLS_PAGE_SIZE", "2")]).await;
+    // The same tools in one page, from a server with the default page size
+    let all_tools = TestServer::start().await.rpc_result("tools/list", json!({})).await;
+    let all_names: Vec<String> = all_tools["tools"].as_array().unwrap().iter()
+        .filter_map(|tool| tool["name"].as_str().map(str::to_string))
+        .collect();
+    assert!(all_names.len() > 4, "{:?}", all_names);
+
+    let mut names = Vec::new();
+    let mut params = json!({});
+    loop {
+        let page = server.rpc_result("tools/list", params).await;
+        let tools = page["tools"].as_array().expect("tools is an array");
+        assert!(!tools.is_empty() && tools.len() <= 2, "{}", page);
+        names.extend(tools.iter().filter_map(|tool| tool["name"].as_str().map(str::to_string)));
+        match page.get("nextCursor") {
+            Some(cursor) => params = json!({ "cursor": cursor }),
+            // The last page has no cursor
+            None => break,
+        }
+    }
+    assert_eq!(names, all_names);
+    // Pages split the name-sorted list
+    assert!(names.is_sorted(), "{:?}", names);
+
+    let response = server.rpc("tools/list", json!({ "cursor": "not-a-cursor" })).await;
+    assert_eq!(response["error"]["code"], -32602);
+}
+
+#[tokio::test]
+async fn version_reports_the_configured_name_and_build() {
+    let server = TestServer::start_with_env(&[("SERVER_NAME", "versioned"), ("SERVER_VERSION", "9.8.7")]).await;
+
+    let (status, body) = server.get_json("/version").await;
+
+    assert_eq!(status, 200);
+    assert_eq!(body["name"], "versioned");
+    assert_eq!(body["version"], "9.8.7");
+    for key in ["git_sha", "build_timestamp", "rustc_version"] {
+        assert!(body[key].as_str().is_some_and(|value| !value.is_empty()), "{}: {}", key, body);
+    }
+
+    // initialize carries the same build metadata
+    let initialize = server.rpc_result("initialize", json!({
+        "protocolVersion": "2025-06-18",
+        "capabilities": {},
+        "clientInfo": { "name": "test", "version": "1.0.0" }
+    })).await;
+    assert_eq!(initialize["serverInfo"]["buildInfo"]["git_sha"], body["git_sha"], "{}", initialize);
+}
+
+#[tokio::test]
+async fn only_responses_over_the_threshold_are_compressed() {
+    let server = TestServer::start_with_env(&[("COMPRESSION_MIN_BYTES", "1024")]).await;
+    let post = |method: &str| server.client()
+        .post(server.url("/mcp"))
+        .header("Accept-Encoding", "gzip")
+        .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method }))
+        .send();
+
+    let small = post("ping").await.expect("request succeeds");
+    assert!(small.headers().get("content-encoding").is_none(), "{:?}", small.headers());
+    assert_eq!(small.json::<serde_json::Value>().await.unwrap()["result"], json!({}));
+
+    let large = post("tools/list").await.expect("request succeeds");
+    assert_eq!(large.headers()["content-encoding"], "gzip");
+    let compressed = large.bytes().await.expect("body is readable");
+    let mut body = String::new();
+    std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(&compressed[..]), &mut body)
+        .expect("body is gzip");
+    assert!(body.len() > 1024, "{}", body);
+    let response: serde_json::Value = serde_json::from_str(&body).expect("body is JSON");
+    assert!(response["result"]["tools"].is_array(), "{}", response);
+}
+
+/// Initialize a server started with `MCP_INSTRUCTIONS` and return its `instructions`.
+async fn instructions_for(value: Option<&str>) -> serde_json::Value {
+    let vars: Vec<(&str, &str)> = value.map(|value| ("MCP_INSTRUCTIONS", value)).into_iter().collect();
+    let server = TestServer::start_with_env(&vars).await;
+    let result = server.rpc_result("initialize", json!({
+        "protocolVersion": "2025-06-18",
+        "capabilities": {},
+        "clientInfo": { "name": "harness", "version": "1.0" }
+    })).await;
+    result.get("instructions").cloned().unwrap_or(serde_json::Value::Null)
+}
+
+#[tokio::test]
+async fn instructions_are_trimmed_and_included_when_set() {
+    assert_eq!(instructions_for(Some("  Use echo to test.\n")).await, "Use echo to test.");
+}
+
+#[tokio::test]
+async fn instructions_are_omitted_when_unset_or_blank() {
+    assert!(instructions_for(None).await.is_null());
+    assert!(instructions_for(Some("   ")).await.is_null());
+}
+
+#[tokio::test]
+async fn long_instructions_are_capped_at_4_kib() {
+    // After one ASCII byte, two-byte characters put the cap inside a character
+    let long = format!("x{}", "é".repeat(3000));
+
+    let instructions = instructions_for(Some(&long)).await;
+
+    let instructions = instructions.as_str().expect("instructions are a string");
+    assert_eq!(instructions.len(), 4095);
+    assert!(long.starts_with(instructions));
+}
+
+#[tokio::test]
+async fn deep_health_check_invokes_the_canary_tool() {
+    let server = TestServer::start().await;
+
+    let (status, body) = server.get_json("/health?deep=true").await;
+
+    assert_eq!(status, 200);
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["canary"], json!({ "tool": "echo", "status": "ok" }));
+}
+
+#[tokio::test]
+async fn deep_health_check_fails_with_a_failing_canary() {
+    let server = TestServer::start_with_env(&[
+        ("HEALTH_CANARY_TOOL", "calc"),
+        ("HEALTH_CANARY_ARGS", r#"{"operation": "div", "a": 1, "b": 0}"#),
+    ]).await;
+
+    let (status, body) = server.get_json("/health?deep=true").await;
+    assert_eq!(status, 503);
+    assert_eq!(body["status"], "unhealthy");
+    assert_eq!(body["canary"]["tool"], "calc");
+    assert_eq!(body["canary"]["error"], "Invalid arguments: Division by zero");
+
+    // The plain check does not run the canary
+    let (status, _) = server.get_json("/health").await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn errors_carry_structured_data() {
+    let server = TestServer::start().await;
+
+    // Invalid params name the tool and the missing arguments
+    let response = server.rpc("tools/call", json!({ "name": "echo", "arguments": {} })).await;
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(response["error"]["data"]["tool"], "echo");
+    assert_eq!(response["error"]["data"]["missing"], json!(["message"]));
+
+    // An unknown tool lists the available ones
+    let response = server.rpc("tools/call", json!({ "name": "nope", "arguments": {} })).await;
+    assert_eq!(response["error"]["code"], -32601);
+    assert_eq!(response["error"]["data"]["tool"], "nope");
+    let available = response["error"]["data"]["available"].as_array().expect("available is an array");
+    assert!(available.contains(&json!("echo")), "{}", response);
+    assert_eq!(response["error"]["data"]["truncated"], false);
+
+    // A parse error points at the offending byte
+    let body = "{\"jsonrpc\": \"2.0\",\n\"id\": 1,,}";
+    let response: serde_json::Value = server.client()
+        .post(server.url("/mcp"))
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .expect("request succeeds")
+        .json()
+        .await
+        .expect("response is JSON");
+    assert_eq!(response["error"]["code"], -32700);
+    let location = &response["error"]["data"];
+    assert_eq!(location["line"], 2, "{}", response);
+    let offset = location["offset"].as_u64().expect("offset is a number") as usize;
+    assert_eq!(&body[offset..offset + 1], ",", "{}", response);
+}
+
+/// POST a JSON-RPC request to `/mcp`, naming `session` in the `Mcp-Session-Id` header.
+///
+/// # Returns
+/// The response, whose status and headers are left to the caller.
+async fn post_in_session(server: &TestServer, session: Option<&str>, body: serde_json::Value) -> reqwest::Response {
+    let mut request = server.client().post(server.url("/mcp")).json(&body);
+    if let Some(session) = session {
+        request = request.header("Mcp-Session-Id", session);
+    }
+    request.send().await.expect("request succeeds")
+}
+
+#[tokio::test]
+async fn http_sessions_keep_their_own_state() {
+    let server = &TestServer::start().await;
+    let initialize = json!({
+        "jsonrpc": "2.0", "id": 1, "method": "initialize",
+        "params": { "protocolVersion": "2025-06-18", "capabilities": {} }
+    });
+    let start_session = || async {
+        let response = post_in_session(server, None, initialize.clone()).await;
+        let id = response.headers()["mcp-session-id"].to_str().unwrap().to_string();
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["result"]["_meta"]["sessionId"], id.as_str());
+        id
+    };
+    let count = |session: Option<String>| async move {
+        let call = json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": { "name": "session_counter" } });
+        let response = post_in_session(server, session.as_deref(), call).await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        let text = body["result"]["content"][0]["text"].as_str().unwrap();
+        serde_json::from_str::<serde_json::Value>(text).unwrap()["count"].clone()
+    };
+
+    let first = start_session().await;
+    let second = start_session().await;
+    assert_ne!(first, second);
+    assert_eq!(count(Some(first.clone())).await, 1);
+    assert_eq!(count(Some(first.clone())).await, 2);
+    assert_eq!(count(Some(second.clone())).await, 1);
+    // Requests without the header stay stateless
+    assert_eq!(count(None).await, 1);
+    assert_eq!(count(None).await, 1);
+
+    // A deleted session is gone
+    let deleted = server.client().delete(server.url("/mcp")).header("Mcp-Session-Id", &first).send().await.unwrap();
+    assert_eq!(deleted.status(), 200);
+    let ping = json!({ "jsonrpc": "2.0", "id": 3, "method": "ping" });
+    let response = post_in_session(server, Some(&first), ping.clone()).await;
+    assert_eq!(response.status(), 404);
+    let response = post_in_session(server, Some("no-such-session"), ping).await;
+    assert_eq!(response.status(), 404);
+    assert_eq!(count(Some(second)).await, 2);
+}
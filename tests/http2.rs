@@ -0,0 +1,48 @@
+//! Tests of HTTP/2, over cleartext with `MCP_HTTP2` and over TLS.
+
+mod common;
+
+use common::TestServer;
+
+/// Self-signed certificate for 127.0.0.1 and its key, shared with the TLS tests.
+const CERT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls-cert.pem");
+const KEY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls-key.pem");
+
+/// Client that speaks HTTP/2 from the first byte (prior knowledge).
+fn h2c_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .http2_prior_knowledge()
+        .build()
+        .expect("HTTP/2 client builds")
+}
+
+#[tokio::test]
+async fn health_is_served_over_h2c_when_enabled() {
+    let server = TestServer::start_with_env(&[("MCP_HTTP2", "1")]).await;
+
+    let response = h2c_client().get(server.url("/health")).send().await.expect("request succeeds");
+
+    assert_eq!(response.version(), reqwest::Version::HTTP_2);
+    assert_eq!(response.status(), 200);
+
+    // HTTP/1.1 clients are still served
+    let response = server.client().get(server.url("/health")).send().await.expect("request succeeds");
+    assert_eq!(response.version(), reqwest::Version::HTTP_11);
+}
+
+#[tokio::test]
+async fn h2c_is_refused_by_default() {
+    let server = TestServer::start().await;
+
+    assert!(h2c_client().get(server.url("/health")).send().await.is_err());
+}
+
+#[tokio::test]
+async fn health_is_negotiated_to_http2_over_tls() {
+    let server = TestServer::start_tls(CERT, KEY).await;
+
+    let response = server.client().get(server.url("/health")).send().await.expect("request succeeds");
+
+    assert_eq!(response.version(), reqwest::Version::HTTP_2);
+    assert_eq!(response.status(), 200);
+}
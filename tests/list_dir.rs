@@ -0,0 +1,94 @@
+//! Tests of the list_dir tool against a temporary file root.
+
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+/// Create a file root unique to one test:
+/// `a.txt` (5 bytes), `sub/b.txt` (2 bytes) and `sub/deeper/c.txt` (1 byte).
+fn file_root(test: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("mcp-list-dir-{}-{}", test, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("sub/deeper")).expect("failed to create file root");
+    std::fs::write(root.join("a.txt"), "hello").expect("failed to write file");
+    std::fs::write(root.join("sub/b.txt"), "hi").expect("failed to write file");
+    std::fs::write(root.join("sub/deeper/c.txt"), "!").expect("failed to write file");
+    root
+}
+
+/// Start a server serving files from `root`.
+async fn start(root: &Path) -> TestServer {
+    TestServer::start_with_env(&[("MCP_FILE_ROOT", root.to_str().unwrap())]).await
+}
+
+#[tokio::test]
+async fn lists_the_root() {
+    let root = file_root("root");
+    let server = start(&root).await;
+
+    let result = server.call_tool("list_dir", json!({})).await;
+
+    assert_eq!(result["isError"], false);
+    assert_eq!(result["structuredContent"]["entries"], json!([
+        { "name": "a.txt", "is_dir": false, "size": 5 },
+        { "name": "sub", "is_dir": true, "size": 0 }
+    ]));
+}
+
+#[tokio::test]
+async fn lists_a_subdirectory() {
+    let root = file_root("subdir");
+    let server = start(&root).await;
+
+    let result = server.call_tool("list_dir", json!({ "path": "sub" })).await;
+
+    assert_eq!(result["structuredContent"]["entries"], json!([
+        { "name": "b.txt", "is_dir": false, "size": 2 },
+        { "name": "deeper", "is_dir": true, "size": 0 }
+    ]));
+}
+
+#[tokio::test]
+async fn recursive_listing_includes_nested_entries() {
+    let root = file_root("recursive");
+    let server = start(&root).await;
+
+    let result = server.call_tool("list_dir", json!({ "recursive": true })).await;
+
+    let names: Vec<&str> = result["structuredContent"]["entries"]
+        .as_array()
+        .expect("entries is an array")
+        .iter()
+        .filter_map(|entry| entry["name"].as_str())
+        .collect();
+    assert_eq!(names, ["a.txt", "sub", "sub/b.txt", "sub/deeper", "sub/deeper/c.txt"]);
+}
+
+#[tokio::test]
+async fn paths_escaping_the_root_are_rejected() {
+    let root = file_root("traversal");
+    let server = start(&root).await;
+
+    for path in ["..", "sub/../..", "/etc"] {
+        let response = server.rpc("tools/call", json!({ "name": "list_dir", "arguments": { "path": path } })).await;
+
+        assert_eq!(response["error"]["code"], -32602, "{}", path);
+        assert!(
+            response["error"]["message"].as_str().unwrap_or_default().contains("escapes the file root"),
+            "unexpected error for {}: {}", path, response
+        );
+    }
+}
+
+#[tokio::test]
+async fn listing_a_file_is_an_error() {
+    let root = file_root("file");
+    let server = start(&root).await;
+
+    let response = server.rpc("tools/call", json!({ "name": "list_dir", "arguments": { "path": "a.txt" } })).await;
+
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(response["error"]["message"], "Invalid arguments: Not a directory: a.txt");
+}
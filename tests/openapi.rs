@@ -0,0 +1,30 @@
+//! Tests of the OpenAPI description served at `/openapi.json`.
+
+mod common;
+
+use common::TestServer;
+
+#[tokio::test]
+async fn openapi_document_describes_the_routes_and_tools() {
+    let server = TestServer::start().await;
+
+    let response = server.client().get(server.url("/openapi.json")).send().await.expect("request succeeds");
+    assert_eq!(response.status(), 200);
+    let body = response.text().await.expect("body is text");
+    let document: serde_json::Value = serde_json::from_str(&body).expect("document is JSON");
+
+    assert!(document["openapi"].as_str().unwrap_or_default().starts_with("3.1"), "{}", document["openapi"]);
+    assert!(document["paths"]["/mcp"]["post"].is_object(), "{}", document["paths"]);
+    assert!(document["paths"]["/health"]["get"].is_object(), "{}", document["paths"]);
+
+    // Tool arguments come from the registry
+    let schemas = &document["components"]["schemas"];
+    assert_eq!(schemas["ToolArguments.echo"]["required"], serde_json::json!(["message"]));
+    let tool_names: Vec<&str> = schemas["ToolCallParams"]["oneOf"]
+        .as_array()
+        .expect("oneOf is an array")
+        .iter()
+        .filter_map(|variant| variant["properties"]["name"]["const"].as_str())
+        .collect();
+    assert!(tool_names.contains(&"echo"), "{:?}", tool_names);
+}
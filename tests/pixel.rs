@@ -0,0 +1,49 @@
+//! Tests of binary content blocks returned by the pixel tool.
+
+mod common;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use common::TestServer;
+use serde_json::json;
+
+/// The signature every PNG file starts with.
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+#[tokio::test]
+async fn image_blocks_carry_base64_png_data() {
+    let server = TestServer::start().await;
+
+    let result = server.call_tool("pixel", json!({})).await;
+    let content = result["content"].as_array().expect("content array");
+    assert_eq!(content.len(), 2, "{}", result);
+    assert_eq!(content[0], json!({ "type": "text", "text": "A 1x1 PNG image" }));
+
+    let image = &content[1];
+    assert_eq!(image["type"], "image");
+    assert_eq!(image["mimeType"], "image/png");
+    let png = BASE64.decode(image["data"].as_str().expect("base64 data")).expect("data is base64");
+    assert!(png.starts_with(PNG_SIGNATURE), "not a PNG: {:?}", png);
+}
+
+#[tokio::test]
+async fn embedded_resources_carry_a_blob() {
+    let server = TestServer::start().await;
+
+    let result = server.call_tool("pixel", json!({ "embed": true })).await;
+    let block = &result["content"][1];
+    assert_eq!(block["type"], "resource");
+    assert_eq!(block["resource"]["uri"], "pixel://1x1.png");
+    assert_eq!(block["resource"]["mimeType"], "image/png");
+    assert!(block["resource"].get("text").is_none(), "{}", block);
+    let png = BASE64.decode(block["resource"]["blob"].as_str().expect("base64 blob")).expect("blob is base64");
+    assert!(png.starts_with(PNG_SIGNATURE));
+}
+
+#[tokio::test]
+async fn pixel_is_an_example_tool() {
+    let server = TestServer::start_with_env(&[("MCP_EXAMPLE_TOOLS", "0")]).await;
+
+    let response = server.rpc("tools/call", json!({ "name": "pixel", "arguments": {} })).await;
+    assert!(response.get("error").is_some(), "{}", response);
+}
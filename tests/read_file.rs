@@ -0,0 +1,63 @@
+//! Tests of the read_file tool against a temporary file root.
+
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+/// Create a file root unique to one test holding `notes.txt` and `sub/inner.txt`.
+fn file_root(test: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("mcp-read-file-{}-{}", test, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("sub")).expect("failed to create file root");
+    std::fs::write(root.join("notes.txt"), "hello from the root").expect("failed to write file");
+    std::fs::write(root.join("sub/inner.txt"), "nested").expect("failed to write file");
+    root
+}
+
+/// Start a server serving files from `root`.
+async fn start(root: &Path) -> TestServer {
+    TestServer::start_with_env(&[("MCP_FILE_ROOT", root.to_str().unwrap())]).await
+}
+
+#[tokio::test]
+async fn reads_a_file_below_the_root() {
+    let root = file_root("valid");
+    let server = start(&root).await;
+
+    let result = server.call_tool("read_file", json!({ "path": "notes.txt" })).await;
+    assert_eq!(result["isError"], false);
+    assert_eq!(result["content"][0]["type"], "text");
+    assert_eq!(result["content"][0]["text"], "hello from the root");
+
+    let result = server.call_tool("read_file", json!({ "path": "sub/inner.txt" })).await;
+    assert_eq!(result["content"][0]["text"], "nested");
+}
+
+#[tokio::test]
+async fn paths_escaping_the_root_are_rejected() {
+    let root = file_root("traversal");
+    let server = start(&root).await;
+
+    for path in ["../../etc/passwd", "sub/../../notes.txt", "/etc/passwd"] {
+        let response = server.rpc("tools/call", json!({ "name": "read_file", "arguments": { "path": path } })).await;
+
+        assert_eq!(response["error"]["code"], -32602, "{}", path);
+        assert!(
+            response["error"]["message"].as_str().unwrap_or_default().contains("escapes the file root"),
+            "unexpected error for {}: {}", path, response
+        );
+    }
+}
+
+#[tokio::test]
+async fn missing_files_are_invalid_arguments() {
+    let root = file_root("missing");
+    let server = start(&root).await;
+
+    let response = server.rpc("tools/call", json!({ "name": "read_file", "arguments": { "path": "nope.txt" } })).await;
+
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(response["error"]["message"], "Invalid arguments: File not found: nope.txt");
+}
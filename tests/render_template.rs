@@ -0,0 +1,61 @@
+//! Tests of the render_template tool.
+
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+#[tokio::test]
+async fn renders_a_template_with_data() {
+    let server = TestServer::start().await;
+
+    let result = server.call_tool("render_template", json!({
+        "template": "Hello, {{name}}! {{#each items}}[{{this}}]{{/each}} <b>",
+        "data": { "name": "A & B", "items": [1, 2] }
+    })).await;
+
+    assert_eq!(result["isError"], false);
+    assert_eq!(result["content"][0]["text"], "Hello, A & B! [1][2] <b>");
+}
+
+#[tokio::test]
+async fn syntax_errors_are_invalid_arguments() {
+    let server = TestServer::start().await;
+
+    let response = server.rpc("tools/call", json!({
+        "name": "render_template",
+        "arguments": { "template": "Hello, {{#if name}}" }
+    })).await;
+
+    assert_eq!(response["error"]["code"], -32602);
+    let message = response["error"]["message"].as_str().unwrap();
+    assert!(message.contains("Invalid template"), "unexpected message: {}", message);
+}
+
+#[tokio::test]
+async fn missing_variables_are_invalid_arguments() {
+    let server = TestServer::start().await;
+
+    let response = server.rpc("tools/call", json!({
+        "name": "render_template",
+        "arguments": { "template": "Hello, {{name}}", "data": { "other": 1 } }
+    })).await;
+
+    assert_eq!(response["error"]["code"], -32602);
+    let message = response["error"]["message"].as_str().unwrap();
+    assert!(message.contains("Missing variable in data: name"), "unexpected message: {}", message);
+}
+
+#[tokio::test]
+async fn oversized_output_is_rejected() {
+    let server = TestServer::start().await;
+
+    // 200 copies of a 10 KB string expand to about 2 MB
+    let result = server.call_tool("render_template", json!({
+        "template": "{{#each items}}{{@root.chunk}}{{/each}}",
+        "data": { "chunk": "x".repeat(10_000), "items": vec![0; 200] }
+    })).await;
+
+    assert_eq!(result["isError"], true);
+    assert_eq!(result["content"][0]["text"], "Error: Rendered output is larger than 1048576 bytes");
+}
@@ -0,0 +1,42 @@
+//! Tests of the REST tool introspection endpoints (`GET /tools` and
+//! `GET /tools/{name}/schema`).
+
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+#[tokio::test]
+async fn tool_schema_matches_its_tools_list_entry() {
+    let server = TestServer::start().await;
+
+    let (status, schema) = server.get_json("/tools/echo/schema").await;
+    assert_eq!(status, 200);
+    assert_eq!(schema["name"], "echo");
+    assert!(schema["description"].is_string(), "{}", schema);
+    assert_eq!(schema["inputSchema"]["required"], json!(["message"]));
+
+    let listed = server.rpc_result("tools/list", json!({})).await;
+    let echo = listed["tools"].as_array().unwrap().iter()
+        .find(|tool| tool["name"] == "echo")
+        .expect("echo is listed");
+    assert_eq!(&schema, echo);
+}
+
+#[tokio::test]
+async fn unknown_tool_schema_is_not_found() {
+    let server = TestServer::start().await;
+
+    let (status, body) = server.get_json("/tools/no-such-tool/schema").await;
+    assert_eq!(status, 404);
+    assert_eq!(body, json!({ "error": "unknown tool" }));
+}
+
+#[tokio::test]
+async fn tool_list_matches_tools_list() {
+    let server = TestServer::start().await;
+
+    let (status, listed) = server.get_json("/tools").await;
+    assert_eq!(status, 200);
+    assert_eq!(listed, server.rpc_result("tools/list", json!({})).await);
+}
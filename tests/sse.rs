@@ -0,0 +1,179 @@
+//! Tests of the MCP SSE transport (`GET /sse` and `POST /messages`) and of
+//! Streamable HTTP requests (`POST /sse`).
+
+mod common;
+
+use std::time::Duration;
+
+use common::TestServer;
+use serde_json::json;
+
+/// Events read from an open `text/event-stream` response.
+struct EventStream {
+    /// The streaming response
+    response: reqwest::Response,
+    /// Received text not yet split into events
+    buffer: String,
+}
+
+impl EventStream {
+    fn new(response: reqwest::Response) -> Self {
+        Self { response, buffer: String::new() }
+    }
+
+    /// Wait for the next event, skipping keepalive comments.
+    ///
+    /// # Returns
+    /// The event name and its data.
+    async fn next_event(&mut self) -> (String, String) {
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let raw: String = self.buffer.drain(..end + 2).collect();
+                if raw.starts_with(':') {
+                    continue;
+                }
+                let field = |name: &str| raw.lines()
+                    .find_map(|line| line.strip_prefix(name))
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                return (field("event:"), field("data:"));
+            }
+            let chunk = tokio::time::timeout(Duration::from_secs(5), self.response.chunk())
+                .await
+                .expect("no event within 5 seconds")
+                .expect("event stream fails")
+                .expect("event stream ended");
+            self.buffer.push_str(std::str::from_utf8(&chunk).expect("event stream is UTF-8"));
+        }
+    }
+}
+
+#[tokio::test]
+async fn initialize_over_the_sse_transport() {
+    let server = TestServer::start().await;
+    let response = server.client().get(server.url("/sse")).send().await.expect("request succeeds");
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let mut events = EventStream::new(response);
+
+    let (event, endpoint) = events.next_event().await;
+    assert_eq!(event, "endpoint");
+    assert!(endpoint.starts_with("/messages?sessionId="), "unexpected endpoint: {}", endpoint);
+
+    let posted = server.client()
+        .post(server.url(&endpoint))
+        .json(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": { "name": "test", "version": "1.0.0" }
+            }
+        }))
+        .send()
+        .await
+        .expect("request succeeds");
+    assert_eq!(posted.status(), 202);
+
+    // The response arrives on the event stream, not in the POST's body
+    let (event, data) = events.next_event().await;
+    assert_eq!(event, "message");
+    let response: serde_json::Value = serde_json::from_str(&data).expect("message is JSON");
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["protocolVersion"], "2025-06-18");
+    assert!(response["result"]["serverInfo"]["name"].is_string(), "{}", response);
+}
+
+#[tokio::test]
+async fn messages_for_an_unknown_session_are_rejected() {
+    let server = TestServer::start().await;
+
+    let (status, body) = server.post_json(
+        "/messages?sessionId=no-such-session",
+        &json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" }),
+    ).await;
+
+    assert_eq!(status, 404);
+    assert_eq!(body["error"], "unknown session");
+}
+
+/// A `ping` request, as sent in a POST body.
+const PING: &str = r#"{"jsonrpc": "2.0", "id": 1, "method": "ping"}"#;
+
+/// POST `body` to `/sse` as `content_type`.
+///
+/// # Returns
+/// The response status and body text.
+async fn post_sse(server: &TestServer, content_type: &str, body: &str) -> (u16, String) {
+    let response = server.client()
+        .post(server.url("/sse"))
+        .header("Content-Type", content_type)
+        .body(body.to_string())
+        .send()
+        .await
+        .expect("request succeeds");
+    let status = response.status().as_u16();
+    (status, response.text().await.expect("body is text"))
+}
+
+/// The JSON-RPC response carried by the single event of a `POST /sse` reply.
+fn event_data(body: &str) -> serde_json::Value {
+    let data = body.strip_prefix("data: ").expect("reply is an SSE event").trim_end();
+    serde_json::from_str(data).expect("event data is JSON")
+}
+
+#[tokio::test]
+async fn sse_posts_check_the_content_type_like_mcp() {
+    let server = TestServer::start().await;
+
+    let (status, body) = post_sse(&server, "text/plain", PING).await;
+    assert_eq!(status, 415);
+    let error: serde_json::Value = serde_json::from_str(&body).expect("error is JSON");
+    assert_eq!(error["error"]["code"], -32600);
+
+    let (status, body) = post_sse(&server, "application/json", PING).await;
+    assert_eq!(status, 200);
+    assert_eq!(event_data(&body)["result"], json!({}));
+}
+
+#[tokio::test]
+async fn sse_posts_share_the_inflight_limit_with_mcp() {
+    let server = TestServer::start_with_env(&[("MAX_INFLIGHT_REQUESTS", "1")]).await;
+
+    let slow = server.call_tool("sleep", json!({ "seconds": 1 }));
+    let busy = async {
+        // Let the slow call over /mcp take the only slot first
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        post_sse(&server, "application/json", PING).await
+    };
+    let (slow, (status, body)) = tokio::join!(slow, busy);
+
+    assert_eq!(slow["isError"], false);
+    assert_eq!(status, 503);
+    let error: serde_json::Value = serde_json::from_str(&body).expect("error is JSON");
+    assert_eq!(error["error"]["message"], "server busy");
+}
+
+#[tokio::test]
+async fn sse_posts_answer_batches_in_one_event() {
+    let server = TestServer::start().await;
+    let batch = json!([
+        { "jsonrpc": "2.0", "id": 1, "method": "ping" },
+        { "jsonrpc": "2.0", "method": "notifications/initialized" },
+        { "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": { "name": "echo", "arguments": { "message": "hi" } } }
+    ]);
+
+    let (status, body) = post_sse(&server, "application/json", &batch.to_string()).await;
+
+    assert_eq!(status, 200);
+    let responses = event_data(&body);
+    let ids: Vec<&serde_json::Value> = responses.as_array().expect("batch response is an array")
+        .iter()
+        .map(|response| &response["id"])
+        .collect();
+    assert_eq!(ids, [&json!(1), &json!(2)]);
+    assert_eq!(responses[1]["result"]["content"][0]["text"], "hi");
+}
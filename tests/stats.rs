@@ -0,0 +1,54 @@
+//! Tests of the stats tool's structured results.
+
+mod common;
+
+use common::TestServer;
+use serde_json::{json, Value};
+
+/// Check `value` against the subset of JSON Schema used by stats' output schema:
+/// an object with required properties of type integer or number.
+fn assert_conforms(schema: &Value, value: &Value) {
+    assert_eq!(schema["type"], "object");
+    for name in schema["required"].as_array().expect("required properties") {
+        let name = name.as_str().unwrap();
+        assert!(value.get(name).is_some(), "{} is missing from {}", name, value);
+    }
+    for (name, property) in schema["properties"].as_object().expect("properties") {
+        let field = &value[name];
+        match property["type"].as_str() {
+            Some("integer") => assert!(field.is_i64() || field.is_u64(), "{} is not an integer: {}", name, field),
+            Some("number") => assert!(field.is_number(), "{} is not a number: {}", name, field),
+            other => panic!("unexpected property type {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn results_carry_text_and_structured_content() {
+    let server = TestServer::start().await;
+
+    let result = server.call_tool("stats", json!({ "numbers": [1, 2, 3, 6] })).await;
+    assert_eq!(result["isError"], false, "{}", result);
+    let structured = &result["structuredContent"];
+    assert_eq!(structured, &json!({ "count": 4, "sum": 12.0, "mean": 3.0, "min": 1.0, "max": 6.0 }));
+
+    // The text block renders the same statistics for clients that only read content
+    let text = result["content"][0]["text"].as_str().expect("text content");
+    assert_eq!(&serde_json::from_str::<Value>(text).expect("text is JSON"), structured);
+
+    // The structured content conforms to the schema advertised by tools/list
+    let tools = server.rpc_result("tools/list", json!({})).await;
+    let stats = tools["tools"].as_array().unwrap().iter()
+        .find(|tool| tool["name"] == "stats")
+        .expect("stats is listed");
+    assert_conforms(&stats["outputSchema"], structured);
+}
+
+#[tokio::test]
+async fn an_empty_list_is_invalid_arguments() {
+    let server = TestServer::start().await;
+
+    let response = server.rpc("tools/call", json!({ "name": "stats", "arguments": { "numbers": [] } })).await;
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(response["error"]["message"], "Invalid arguments: numbers must not be empty");
+}
@@ -0,0 +1,57 @@
+//! Tests of the server binary running over STDIO.
+
+mod common;
+
+use common::run_stdio;
+
+/// A `ping` request, which STDIO answers before `initialize`.
+const PING: &str = "{\"jsonrpc\": \"2.0\", \"id\": 1, \"method\": \"ping\"}\n";
+
+#[test]
+fn json_log_format_writes_a_structured_startup_record() {
+    let run = run_stdio(&[("MCP_LOG_FORMAT", "json"), ("SERVER_NAME", "json-logs")], PING);
+
+    let startup: serde_json::Value = run.stderr
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|record| record["event"] == "startup")
+        .unwrap_or_else(|| panic!("no startup record in stderr:\n{}", run.stderr));
+    assert_eq!(startup["transport"], "stdio");
+    assert_eq!(startup["name"], "json-logs");
+    assert!(startup["version"].is_string(), "{}", startup);
+    assert!(startup["max_message_bytes"].is_u64(), "{}", startup);
+
+    // Every stderr line is a JSON record, and stdout still carries the response
+    for line in run.stderr.lines() {
+        assert!(serde_json::from_str::<serde_json::Value>(line).is_ok(), "not JSON: {}", line);
+    }
+    assert_eq!(run.messages()[0]["result"], serde_json::json!({}));
+}
+
+#[test]
+fn pretty_log_goes_to_stderr_while_stdout_stays_compact() {
+    let run = run_stdio(&[("MCP_PRETTY_LOG", "true")], PING);
+
+    // One compact JSON-RPC message per stdout line
+    assert_eq!(run.stdout, "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n");
+
+    assert!(run.stderr.contains("--- request ---\n{\n  \"id\": 1,"), "{}", run.stderr);
+    assert!(run.stderr.contains("--- response ---\n{\n"), "{}", run.stderr);
+}
+
+#[test]
+fn both_mode_keeps_serving_stdio_when_http_cannot_bind() {
+    // Hold the port so the HTTP server fails to bind it
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").expect("no free local port");
+    let port = taken.local_addr().expect("listener has an address").port().to_string();
+
+    let run = run_stdio(&[
+        ("MCP_TRANSPORT_MODE", "both"),
+        ("HOST", "127.0.0.1"),
+        ("PORT", &port),
+        ("WORKER_THREADS", "1"),
+    ], PING);
+
+    assert!(run.stderr.contains("continuing with STDIO only"), "{}", run.stderr);
+    assert_eq!(run.messages()[0]["result"], serde_json::json!({}));
+}
@@ -0,0 +1,41 @@
+//! Tests of HTTPS termination (`TLS_CERT_PATH` and `TLS_KEY_PATH`).
+//!
+//! `tests/fixtures/tls-cert.pem` is a self-signed certificate for 127.0.0.1,
+//! generated with:
+//!
+//! ```text
+//! openssl req -x509 -newkey ec -pkeyopt ec_paramgen_curve:prime256v1 -nodes -days 36500 \
+//!     -subj "/CN=localhost" -addext "subjectAltName=IP:127.0.0.1,DNS:localhost" \
+//!     -addext "basicConstraints=critical,CA:FALSE" -addext "extendedKeyUsage=serverAuth" \
+//!     -keyout tests/fixtures/tls-key.pem -out tests/fixtures/tls-cert.pem
+//! ```
+
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const CERT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls-cert.pem");
+const KEY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls-key.pem");
+
+#[tokio::test]
+async fn health_is_served_over_https() {
+    let server = TestServer::start_tls(CERT, KEY).await;
+    assert!(server.base_url().starts_with("https://"), "{}", server.base_url());
+
+    let (status, health) = server.get_json("/health").await;
+    assert_eq!(status, 200);
+    assert_eq!(health["status"], "ok");
+
+    let result = server.call_tool("echo", json!({ "message": "secure" })).await;
+    assert_eq!(result["content"][0]["text"], "secure");
+}
+
+#[tokio::test]
+async fn plain_http_is_refused_when_tls_is_enabled() {
+    let server = TestServer::start_tls(CERT, KEY).await;
+
+    let plain = server.base_url().replacen("https://", "http://", 1);
+    let response = reqwest::get(format!("{}/health", plain)).await;
+    assert!(response.is_err(), "plain HTTP was answered: {:?}", response);
+}